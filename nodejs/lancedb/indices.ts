@@ -56,8 +56,9 @@ export interface IvfPqOptions {
    * (vectors are grouped in partitions with similar vectors according to this
    * distance type) and to calculate a subvector's code during quantization.
    *
-   * The distance type used to train an index MUST match the distance type used
-   * to search the index.  Failure to do so will yield inaccurate results.
+   * The index can only be used by queries with the same distance type.  A query
+   * that requests a different distance type falls back to a (much slower) flat
+   * search.
    *
    * The following distance types are available:
    *
@@ -294,9 +294,10 @@ export class VectorQuery extends QueryBase<NativeVectorQuery, VectorQuery> {
    * use.  See @see {@link IvfPqOptions.distanceType} for more details on the different
    * distance metrics available.
    *
-   * Note: if there is a vector index then the distance type used MUST match the distance
-   * type used to train the vector index.  If this is not done then the results will be
-   * invalid.
+   * Note: if there is a vector index that was trained with a different distance type
+   * then the index cannot be used and the query falls back to a flat search that
+   * computes the exact distance to every vector.  The results are correct but the
+   * search can be much slower than an indexed search on a large table.
    *
    * By default "l2" is used.
    */
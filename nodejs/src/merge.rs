@@ -42,6 +42,7 @@ impl NativeMergeInsertBuilder {
         this.inner
             .execute(data)
             .await
+            .map(|_| ())
             .map_err(|e| napi::Error::from_reason(format!("Failed to execute merge insert: {}", e)))
     }
 }
@@ -129,7 +129,8 @@ impl Table {
                 "Failed to delete rows in table {}: predicate={}",
                 self.name, e
             ))
-        })
+        })?;
+        Ok(())
     }
 
     #[napi]
@@ -164,7 +165,8 @@ impl Table {
         for (column_name, value) in columns {
             op = op.column(column_name, value);
         }
-        op.execute().await.default_error()
+        op.execute().await.default_error()?;
+        Ok(())
     }
 
     #[napi]
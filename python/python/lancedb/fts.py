@@ -13,8 +13,11 @@
 
 """Full text search index using tantivy-py"""
 
+import json
 import os
-from typing import List, Tuple
+import re
+from dataclasses import dataclass
+from typing import List, Optional, Tuple
 
 import pyarrow as pa
 
@@ -27,12 +30,150 @@ except ImportError:
 
 from .table import LanceTable
 
+# The BM25 parameters tantivy scores with
+BM25_K1 = 1.2
+BM25_B = 0.75
+# With other BM25 parameters this many times the limit of the top results are
+# scored again
+BM25_RESCORE_FACTOR = 10
+
+# Prefix of the names of the tokenizers configured with FtsOptions
+_TOKENIZER_PREFIX = "lancedb"
+_TOKENIZER_PATTERN = re.compile(
+    rf"^{_TOKENIZER_PREFIX}_(?P<language>[a-z]+)"
+    r"(?P<stem>_stem)?(?P<stop>_stop)?(?:_ngram(?P<min>\d+)_(?P<max>\d+))?$"
+)
+
+
+@dataclass(frozen=True)
+class FtsOptions:
+    """
+    Options for the tokenizer of a full text search index
+
+    The text is split into words, lowercased and words longer than 40 bytes
+    are dropped.  The same tokenizer is used for the queries, the options are
+    stored in the name of the tokenizer in the index metadata so an index
+    that is opened again tokenizes queries in the same way.
+
+    Parameters
+    ----------
+    language : str, default "english"
+        The language used for stemming and stopwords, for example "english"
+        or "french". For available languages see:
+        https://docs.rs/tantivy/latest/tantivy/tokenizer/enum.Language.html
+    stem : bool, default False
+        Reduce words to their stem, so that "running" matches "run".
+    remove_stopwords : bool, default False
+        Drop common words of the language such as "the" or "and".
+    ngram : tuple of (int, int), optional
+        If set, the text is split into ngrams of between ``min`` and ``max``
+        characters instead of words.  This allows matching parts of words.
+    """
+
+    language: str = "english"
+    stem: bool = False
+    remove_stopwords: bool = False
+    ngram: Optional[Tuple[int, int]] = None
+
+    def __post_init__(self):
+        if not re.fullmatch("[a-z]+", self.language):
+            raise ValueError(f"Invalid language {self.language!r}")
+        if self.ngram is not None:
+            min_gram, max_gram = self.ngram
+            if min_gram < 1 or max_gram < min_gram:
+                raise ValueError(f"Invalid ngram range {self.ngram}")
+
+    @property
+    def tokenizer_name(self) -> str:
+        """The name the tokenizer is registered with in the index"""
+        name = f"{_TOKENIZER_PREFIX}_{self.language}"
+        if self.stem:
+            name += "_stem"
+        if self.remove_stopwords:
+            name += "_stop"
+        if self.ngram is not None:
+            name += f"_ngram{self.ngram[0]}_{self.ngram[1]}"
+        return name
+
+    @classmethod
+    def from_tokenizer_name(cls, name: str) -> Optional["FtsOptions"]:
+        """Parse the options from a tokenizer name, None if it is not one of ours"""
+        match = _TOKENIZER_PATTERN.match(name)
+        if match is None:
+            return None
+        ngram = None
+        if match["min"] is not None:
+            ngram = (int(match["min"]), int(match["max"]))
+        return cls(
+            language=match["language"],
+            stem=match["stem"] is not None,
+            remove_stopwords=match["stop"] is not None,
+            ngram=ngram,
+        )
+
+    def build(self) -> "tantivy.TextAnalyzer":
+        """Build the tantivy text analyzer for these options"""
+        if self.ngram is not None:
+            tokenizer = tantivy.Tokenizer.ngram(
+                min_gram=self.ngram[0], max_gram=self.ngram[1]
+            )
+        else:
+            tokenizer = tantivy.Tokenizer.simple()
+        builder = (
+            tantivy.TextAnalyzerBuilder(tokenizer)
+            .filter(tantivy.Filter.remove_long(40))
+            .filter(tantivy.Filter.lowercase())
+        )
+        if self.remove_stopwords:
+            builder = builder.filter(tantivy.Filter.stopword(self.language))
+        if self.stem:
+            builder = builder.filter(tantivy.Filter.stemmer(self.language))
+        return builder.build()
+
+
+def _register_tokenizers(index: tantivy.Index, tokenizer_names) -> None:
+    for name in set(tokenizer_names):
+        options = FtsOptions.from_tokenizer_name(name)
+        if options is not None:
+            index.register_tokenizer(name, options.build())
+
+
+def open_index(index_path: str) -> tantivy.Index:
+    """
+    Open an existing index
+
+    The tokenizers configured with :class:`FtsOptions` are registered so that
+    queries are tokenized in the same way as the indexed text.
+
+    Parameters
+    ----------
+    index_path : str
+        Path to the index directory
+
+    Returns
+    -------
+    index : tantivy.Index
+        The index object
+    """
+    index = tantivy.Index.open(index_path)
+    with open(os.path.join(index_path, "meta.json")) as f:
+        fields = json.load(f)["schema"]
+    tokenizer_names = [
+        field["options"]["indexing"]["tokenizer"]
+        for field in fields
+        if field.get("type") == "text"
+        and (field.get("options") or {}).get("indexing")
+    ]
+    _register_tokenizers(index, tokenizer_names)
+    return index
+
 
 def create_index(
     index_path: str,
     text_fields: List[str],
     ordering_fields: List[str] = None,
     tokenizer_name: str = "default",
+    fts_options: Optional[FtsOptions] = None,
 ) -> tantivy.Index:
     """
     Create a new Index (not populated)
@@ -47,6 +188,9 @@ def create_index(
         List of unsigned type fields to order by at search time
     tokenizer_name : str, default "default"
         The tokenizer to use
+    fts_options : FtsOptions, optional
+        Configure the tokenizer instead of using one of the built in
+        tokenizers. Cannot be combined with ``tokenizer_name``.
 
     Returns
     -------
@@ -55,6 +199,10 @@ def create_index(
     """
     if ordering_fields is None:
         ordering_fields = []
+    if fts_options is not None:
+        if tokenizer_name != "default":
+            raise ValueError("Cannot set both tokenizer_name and fts_options")
+        tokenizer_name = fts_options.tokenizer_name
     # Declaring our schema.
     schema_builder = tantivy.SchemaBuilder()
     # special field that we'll populate with row_id
@@ -68,6 +216,7 @@ def create_index(
     schema = schema_builder.build()
     os.makedirs(index_path, exist_ok=True)
     index = tantivy.Index(schema, path=index_path)
+    _register_tokenizers(index, [tokenizer_name])
     return index
 
 
@@ -99,8 +248,10 @@ def populate_index(
     """
     if ordering_fields is None:
         ordering_fields = []
-    # first check the fields exist and are string or large string type
+    # first check the fields exist and are string, large string, binary or
+    # large binary type. Binary fields are expected to hold UTF-8 text.
     nested = []
+    binary = set()
 
     for name in fields:
         try:
@@ -109,8 +260,10 @@ def populate_index(
             f = resolve_path(table.schema, name)
             nested.append(name)
 
-        if not pa.types.is_string(f.type) and not pa.types.is_large_string(f.type):
-            raise TypeError(f"Field {name} is not a string type")
+        if pa.types.is_binary(f.type) or pa.types.is_large_binary(f.type):
+            binary.add(name)
+        elif not pa.types.is_string(f.type) and not pa.types.is_large_string(f.type):
+            raise TypeError(f"Field {name} is not a string or binary type")
 
     # create a tantivy writer
     writer = index.writer(heap_size=writer_heap_size)
@@ -131,6 +284,13 @@ def populate_index(
             doc = tantivy.Document()
             for name in fields:
                 value = b[name][i].as_py()
+                if value is not None and name in binary:
+                    try:
+                        value = value.decode("utf-8")
+                    except UnicodeDecodeError as e:
+                        raise ValueError(
+                            f"Field {name} contains invalid UTF-8 at row {row_id}"
+                        ) from e
                 if value is not None:
                     doc.add_text(name, value)
             for name in ordering_fields:
@@ -170,12 +330,119 @@ def resolve_path(schema, field_name: str) -> pa.Field:
     return field
 
 
+def rewrite_query(query: str) -> str:
+    """
+    Rewrite the parts of a query that tantivy cannot parse
+
+    ``NOT term`` is rewritten to tantivy's ``-term``, except inside quoted
+    phrases.
+
+    Parameters
+    ----------
+    query : str
+        The query string
+
+    Returns
+    -------
+    str
+        The query in tantivy's syntax
+    """
+    parts = re.split(r'("[^"]*")', query)
+    return "".join(
+        part if part.startswith('"') else re.sub(r"(?<![\w-])NOT\s+", "-", part)
+        for part in parts
+    )
+
+
+def _explanation_details(explanation: dict) -> dict:
+    details = {}
+    for detail in explanation.get("details", []):
+        # e.g. "k1, term saturation parameter"
+        name = detail["description"].split(",")[0]
+        details[name] = detail["value"]
+    return details
+
+
+def rescore_bm25(explanation: dict, k1: float, b: float) -> float:
+    """
+    Recompute the score of a document with other BM25 parameters
+
+    Parameters
+    ----------
+    explanation : dict
+        The explanation of the score of the document, as returned by
+        ``tantivy.Query.explain(...).to_json()``
+    k1 : float
+        The term frequency saturation parameter
+    b : float
+        The length normalization parameter
+
+    Returns
+    -------
+    float
+        The score of the document
+
+    Raises
+    ------
+    NotImplementedError
+        If the explanation has a phrase query, whose score is not explained
+        term by term
+    ValueError
+        If the explanation of a term does not have the term frequency, the
+        field length and the average field length
+    """
+    score = explanation["value"]
+    # Each term is scored with
+    # idf * (k1 + 1) * freq / (freq + k1 * (1 - b + b * dl / avgdl))
+    # and the terms of a query are summed, so replace the score of each
+    # term with the score computed with the new parameters
+    stack = [explanation]
+    while stack:
+        node = stack.pop()
+        description = node["description"]
+        if description.startswith("Phrase"):
+            raise NotImplementedError(
+                "phrase queries cannot be scored with other BM25 parameters"
+            )
+        if not description.startswith("TermQuery"):
+            stack.extend(node.get("details", []))
+            continue
+        tf_explanation = next(
+            (
+                detail
+                for detail in node.get("details", [])
+                if detail["description"].startswith("freq /")
+            ),
+            None,
+        )
+        tf = _explanation_details(tf_explanation or {})
+        if not {"freq", "dl", "avgdl"} <= tf.keys():
+            raise ValueError(f"unexpected explanation of a term score: {node}")
+        old_k1 = tf.get("k1", BM25_K1)
+        norm = k1 * (1 - b + b * tf["dl"] / tf["avgdl"])
+        new_tf = tf["freq"] / (tf["freq"] + norm)
+        new_value = (
+            node["value"]
+            * (k1 + 1)
+            * new_tf
+            / ((old_k1 + 1) * tf_explanation["value"])
+        )
+        score += new_value - node["value"]
+    return score
+
+
 def search_index(
-    index: tantivy.Index, query: str, limit: int = 10, ordering_field=None
+    index: tantivy.Index,
+    query: str,
+    limit: int = 10,
+    ordering_field=None,
+    bm25_params: Optional[Tuple[float, float]] = None,
 ) -> Tuple[Tuple[int], Tuple[float]]:
     """
     Search an index for a query
 
+    See :class:`lancedb.query.LanceFtsQueryBuilder` for the query syntax.
+
     Parameters
     ----------
     index : tantivy.Index
@@ -184,6 +451,13 @@ def search_index(
         The query string
     limit : int
         The maximum number of results to return
+    ordering_field : str, optional
+        Order the results by this field instead of by score
+    bm25_params : (float, float), optional
+        The ``k1`` and ``b`` BM25 parameters, by default 1.2 and 0.75.
+        tantivy always scores with the defaults, so with other parameters
+        the top ``limit * BM25_RESCORE_FACTOR`` results are scored again.
+        Phrase queries are not supported.
 
     Returns
     -------
@@ -191,20 +465,43 @@ def search_index(
         A tuple of two tuples, the first containing the document ids
         and the second containing the scores
     """
+    rescore = bm25_params is not None and tuple(bm25_params) != (BM25_K1, BM25_B)
+    if rescore and '"' in query:
+        raise NotImplementedError(
+            "phrase queries cannot be scored with other BM25 parameters"
+        )
     searcher = index.searcher()
-    query = index.parse_query(query)
+    query = index.parse_query(rewrite_query(query))
     # get top results
     if ordering_field:
         results = searcher.search(query, limit, order_by_field=ordering_field)
+    elif rescore:
+        # The top results with the new parameters are assumed to be among
+        # the top results with the default parameters
+        results = searcher.search(query, limit * BM25_RESCORE_FACTOR)
     else:
         results = searcher.search(query, limit)
     if results.count == 0:
         return tuple(), tuple()
+    hits = results.hits
+    if rescore:
+        k1, b = bm25_params
+        hits = [
+            (
+                rescore_bm25(
+                    json.loads(query.explain(searcher, doc_address).to_json()), k1, b
+                ),
+                doc_address,
+            )
+            for _, doc_address in hits
+        ]
+        if not ordering_field:
+            hits = sorted(hits, key=lambda hit: hit[0], reverse=True)[:limit]
     return tuple(
         zip(
             *[
                 (searcher.doc(doc_address)["doc_id"][0], score)
-                for score, doc_address in results.hits
+                for score, doc_address in hits
             ]
         )
     )
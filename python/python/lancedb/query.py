@@ -628,7 +628,22 @@ class LanceVectorQueryBuilder(LanceQueryBuilder):
 
 
 class LanceFtsQueryBuilder(LanceQueryBuilder):
-    """A builder for full text search for LanceDB."""
+    """A builder for full text search for LanceDB.
+
+    The query is parsed with tantivy's query parser:
+
+    - ``machine learning`` matches documents with either term, documents
+      with both are scored higher.
+    - ``"machine learning"`` is a phrase, the terms must be adjacent and in
+      that order.
+    - ``rust AND (async OR tokio)`` combines terms with boolean operators,
+      ``AND`` takes precedence over ``OR``.
+    - ``rust NOT python`` or ``rust -python`` excludes the documents that
+      contain ``python``, ``+rust`` requires the term.
+    - ``title:rust`` only searches the ``title`` column when the index has
+      several columns. Without a column the terms are searched for in every
+      indexed column.
+    """
 
     def __init__(self, table: "Table", query: str, ordering_field_name: str = None):
         super().__init__(table)
@@ -636,6 +651,7 @@ class LanceFtsQueryBuilder(LanceQueryBuilder):
         self._phrase_query = False
         self.ordering_field_name = ordering_field_name
         self._reranker = None
+        self._bm25_params = None
 
     def phrase_query(self, phrase_query: bool = True) -> LanceFtsQueryBuilder:
         """Set whether to use phrase query.
@@ -654,15 +670,46 @@ class LanceFtsQueryBuilder(LanceQueryBuilder):
         self._phrase_query = phrase_query
         return self
 
+    def bm25_params(self, k1: float = 1.2, b: float = 0.75) -> LanceFtsQueryBuilder:
+        """Set the parameters of the BM25 scoring.
+
+        The index is searched with tantivy, which always scores with the
+        default parameters and does not allow them to be configured. With
+        other parameters the top ``10 * limit`` results are scored again from
+        the explanation of their tantivy scores, so a document outside of
+        those is not returned even if it would score higher. Phrase queries
+        cannot be scored again and raise a ``NotImplementedError``.
+
+        Parameters
+        ----------
+        k1: float, default 1.2
+            How quickly the score saturates as a term occurs more often in a
+            document. 0 ignores the term frequency.
+        b: float, default 0.75
+            How much the score is normalized by the length of the document,
+            from 0 (not normalized) to 1 (fully normalized).
+
+        Returns
+        -------
+        LanceFtsQueryBuilder
+            The LanceFtsQueryBuilder object.
+        """
+        if k1 < 0:
+            raise ValueError(f"k1 must be non-negative, got {k1}")
+        if not 0 <= b <= 1:
+            raise ValueError(f"b must be between 0 and 1, got {b}")
+        self._bm25_params = (k1, b)
+        return self
+
     def to_arrow(self) -> pa.Table:
         try:
-            import tantivy
+            import tantivy  # noqa: F401
         except ImportError:
             raise ImportError(
                 "Please install tantivy-py `pip install tantivy` to use the full text search feature."  # noqa: E501
             )
 
-        from .fts import search_index
+        from .fts import open_index, search_index
 
         # get the index path
         index_path = self._table._get_fts_index_path()
@@ -682,14 +729,18 @@ class LanceFtsQueryBuilder(LanceQueryBuilder):
                 "create the fts index."
             )
         # open the index
-        index = tantivy.Index.open(index_path)
+        index = open_index(index_path)
         # get the scores and doc ids
         query = self._query
         if self._phrase_query:
             query = query.replace('"', "'")
             query = f'"{query}"'
         row_ids, scores = search_index(
-            index, query, self._limit, ordering_field=self.ordering_field_name
+            index,
+            query,
+            self._limit,
+            ordering_field=self.ordering_field_name,
+            bm25_params=self._bm25_params,
         )
         if len(row_ids) == 0:
             empty_schema = pa.schema([pa.field("score", pa.float32())])
@@ -1338,9 +1389,10 @@ class AsyncVectorQuery(AsyncQueryBase):
         to use.  See @see {@link IvfPqOptions.distanceType} for more details on the
         different distance metrics available.
 
-        Note: if there is a vector index then the distance type used MUST match the
-        distance type used to train the vector index.  If this is not done then the
-        results will be invalid.
+        Note: if there is a vector index that was trained with a different distance
+        type then the index cannot be used and the query falls back to a flat search
+        that computes the exact distance to every vector.  The results are correct but
+        the search can be much slower than an indexed search on a large table.
 
         By default "l2" is used.
         """
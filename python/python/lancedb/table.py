@@ -60,6 +60,7 @@ if TYPE_CHECKING:
 
     from ._lancedb import Table as LanceDBTable, OptimizeStats
     from .db import LanceDBConnection
+    from .fts import FtsOptions
     from .index import BTree, IndexConfig, IvfPq
 
 
@@ -1172,6 +1173,7 @@ class LanceTable(Table):
         replace: bool = False,
         writer_heap_size: Optional[int] = 1024 * 1024 * 1024,
         tokenizer_name: str = "default",
+        fts_options: Optional["FtsOptions"] = None,
     ):
         """Create a full-text search index on the table.
 
@@ -1181,7 +1183,8 @@ class LanceTable(Table):
         Parameters
         ----------
         field_names: str or list of str
-            The name(s) of the field to index.
+            The name(s) of the field to index. The fields must be string or
+            binary columns. Binary columns must contain valid UTF-8 text.
         replace: bool, default False
             If True, replace the existing index if it exists. Note that this is
             not yet an atomic operation; the index will be temporarily
@@ -1194,6 +1197,10 @@ class LanceTable(Table):
             The tokenizer to use for the index. Can be "raw", "default" or the 2 letter
             language code followed by "_stem". So for english it would be "en_stem".
             For available languages see: https://docs.rs/tantivy/latest/tantivy/tokenizer/enum.Language.html
+        fts_options: FtsOptions, optional
+            Configure stemming, stopword removal and ngrams for the tokenizer
+            instead of using ``tokenizer_name``. The options are stored with the
+            index and also apply to the queries. See :class:`lancedb.fts.FtsOptions`.
         """
         from .fts import create_index, populate_index
 
@@ -1220,6 +1227,7 @@ class LanceTable(Table):
             field_names,
             ordering_fields=ordering_field_names,
             tokenizer_name=tokenizer_name,
+            fts_options=fts_options,
         )
         populate_index(
             index,
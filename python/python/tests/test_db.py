@@ -361,18 +361,18 @@ async def test_create_exist_ok_async(tmp_path):
     tbl3 = await db.create_table("test", schema=schema, exist_ok=True)
     assert await tbl3.schema() == schema
 
-    # Migration: When creating a table, but the table already exists, but
+    # When creating a table, but the table already exists, but
     # the schema is different, it should raise an error.
-    # bad_schema = pa.schema(
-    #     [
-    #         pa.field("vector", pa.list_(pa.float32(), list_size=2)),
-    #         pa.field("item", pa.utf8()),
-    #         pa.field("price", pa.float64()),
-    #         pa.field("extra", pa.float32()),
-    #     ]
-    # )
-    # with pytest.raises(ValueError):
-    #     await db.create_table("test", schema=bad_schema, exist_ok=True)
+    bad_schema = pa.schema(
+        [
+            pa.field("vector", pa.list_(pa.float32(), list_size=2)),
+            pa.field("item", pa.utf8()),
+            pa.field("price", pa.float64()),
+            pa.field("extra", pa.float32()),
+        ]
+    )
+    with pytest.raises(ValueError):
+        await db.create_table("test", schema=bad_schema, exist_ok=True)
 
 
 def test_open_table_sync(tmp_path):
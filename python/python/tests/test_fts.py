@@ -17,6 +17,7 @@ from unittest import mock
 import lancedb as ldb
 import numpy as np
 import pandas as pd
+import pyarrow as pa
 import pytest
 
 pytest.importorskip("lancedb.fts")
@@ -246,3 +247,203 @@ def test_syntax(table):
     table.search('the cats OR dogs were not really "pets" at all').phrase_query().limit(
         10
     ).to_list()
+
+
+def test_create_index_binary_column(tmp_path):
+    db = ldb.connect(tmp_path)
+    data = pa.table(
+        {
+            "id": [0, 1, 2],
+            "text": pa.array(
+                [b"puppy runs merrily", b"car hits", b"puppy jumps"], pa.binary()
+            ),
+        }
+    )
+    table = db.create_table("binary", data=data)
+    table.create_fts_index("text")
+    rs = table.search("puppy").limit(10).to_list()
+    assert sorted(r["id"] for r in rs) == [0, 2]
+
+    data = pa.table(
+        {
+            "id": [0, 1],
+            "text": pa.array([b"car hits", b"\xff\xfe"], pa.large_binary()),
+        }
+    )
+    table = db.create_table("invalid_utf8", data=data)
+    with pytest.raises(ValueError, match="invalid UTF-8 at row 1"):
+        table.create_fts_index("text")
+
+
+def test_fts_options(tmp_path):
+    from lancedb.fts import FtsOptions
+
+    db = ldb.connect(tmp_path)
+    data = pa.table(
+        {
+            "id": [0, 1, 2],
+            "text": ["I like to run", "the cat sat", "a quick ride"],
+        }
+    )
+    table = db.create_table("stem", data=data)
+
+    # Without stemming "running" does not match "run"
+    table.create_fts_index("text")
+    assert table.search("running").limit(10).to_list() == []
+
+    options = FtsOptions(stem=True, remove_stopwords=True)
+    table.create_fts_index("text", replace=True, fts_options=options)
+    rs = table.search("running").limit(10).to_list()
+    assert [r["id"] for r in rs] == [0]
+    # Stopwords are not indexed or searched for
+    assert table.search("the").limit(10).to_list() == []
+
+    assert FtsOptions.from_tokenizer_name(options.tokenizer_name) == options
+    ngram = FtsOptions(language="french", ngram=(2, 4))
+    assert ngram.tokenizer_name == "lancedb_french_ngram2_4"
+    assert FtsOptions.from_tokenizer_name(ngram.tokenizer_name) == ngram
+    assert FtsOptions.from_tokenizer_name("en_stem") is None
+
+    with pytest.raises(ValueError):
+        table.create_fts_index(
+            "text", replace=True, tokenizer_name="en_stem", fts_options=options
+        )
+    with pytest.raises(ValueError):
+        FtsOptions(ngram=(3, 2))
+
+
+def test_fts_options_ngram(tmp_path):
+    from lancedb.fts import FtsOptions
+
+    db = ldb.connect(tmp_path)
+    data = pa.table({"id": [0, 1], "text": ["lancedb", "tantivy"]})
+    table = db.create_table("ngram", data=data)
+    table.create_fts_index("text", fts_options=FtsOptions(ngram=(3, 3)))
+    rs = table.search("nce").limit(10).to_list()
+    assert [r["id"] for r in rs] == [0]
+
+
+def test_phrase_and_boolean_queries(tmp_path):
+    db = ldb.connect(tmp_path)
+    data = pa.table(
+        {
+            "id": [0, 1, 2, 3, 4],
+            "title": ["ml", "tools", "rust", "rust", "rust"],
+            "body": [
+                "machine learning is fun",
+                "learning about machine tools",
+                "rust with tokio",
+                "rust with async std",
+                "rust python bindings",
+            ],
+        }
+    )
+    table = db.create_table("queries", data=data)
+    table.create_fts_index(["title", "body"])
+
+    def search(query):
+        return sorted(r["id"] for r in table.search(query).limit(10).to_list())
+
+    assert search("machine learning") == [0, 1]
+    # The words of a phrase must be adjacent
+    assert search('"machine learning"') == [0]
+    assert search("rust AND (async OR tokio)") == [2, 3]
+    assert search("rust NOT python") == [2, 3]
+    assert search("rust -python") == [2, 3]
+    # NOT is only an operator outside of phrases
+    assert search('"NOT python"') == []
+    assert search("title:tools") == [1]
+    assert search("body:tools") == [1]
+    assert search("title:learning") == []
+
+
+def test_rewrite_query():
+    from lancedb.fts import rewrite_query
+
+    assert rewrite_query("rust NOT python") == "rust -python"
+    assert rewrite_query("(a OR b) AND NOT  c") == "(a OR b) AND -c"
+    assert rewrite_query('"this is NOT rewritten" NOT b') == (
+        '"this is NOT rewritten" -b'
+    )
+    assert rewrite_query("NOTHING") == "NOTHING"
+
+
+def test_bm25_params(tmp_path):
+    db = ldb.connect(tmp_path)
+    # A long document with the term twice and a short one with it once
+    data = pa.table(
+        {
+            "id": [0, 1],
+            "text": ["rust rust " + " ".join(["filler"] * 20), "rust"],
+        }
+    )
+    table = db.create_table("bm25", data=data)
+    table.create_fts_index("text")
+
+    def ids(query):
+        return [r["id"] for r in query.limit(10).to_list()]
+
+    # By default the short document ranks first because of its length
+    assert ids(table.search("rust")) == [1, 0]
+    assert ids(table.search("rust").bm25_params(1.2, 0.75)) == [1, 0]
+    # Without length normalization the term frequency decides
+    assert ids(table.search("rust").bm25_params(b=0.0)) == [0, 1]
+    # Without term frequency every document with the term scores the same
+    scores = [r["score"] for r in table.search("rust").bm25_params(k1=0).to_list()]
+    assert scores[0] == pytest.approx(scores[1])
+    # Only the top results are scored again
+    assert ids(table.search("rust").bm25_params(b=0.0).limit(1)) == [0]
+
+    with pytest.raises(NotImplementedError, match="phrase"):
+        table.search("rust filler").phrase_query().bm25_params(b=0.0).to_list()
+    with pytest.raises(NotImplementedError, match="phrase"):
+        table.search('"rust filler"').bm25_params(b=0.0).to_list()
+    with pytest.raises(ValueError):
+        table.search("rust").bm25_params(b=2.0)
+
+
+def test_rescore_bm25():
+    from lancedb.fts import BM25_B, BM25_K1, rescore_bm25
+
+    def term(freq, dl, avgdl, idf=1.0):
+        tf = freq / (freq + BM25_K1 * (1 - BM25_B + BM25_B * dl / avgdl))
+        return {
+            "value": idf * (BM25_K1 + 1) * tf,
+            "description": "TermQuery, product of...",
+            "details": [
+                {"value": BM25_K1 + 1, "description": "(K1+1)"},
+                {"value": idf, "description": "idf, computed as ..."},
+                {
+                    "value": tf,
+                    "description": "freq / (freq + k1 * (1 - b + b * dl / avgdl))",
+                    "details": [
+                        {"value": freq, "description": "freq, occurrences"},
+                        {"value": BM25_K1, "description": "k1, saturation"},
+                        {"value": BM25_B, "description": "b, normalization"},
+                        {"value": dl, "description": "dl, length of field"},
+                        {"value": avgdl, "description": "avgdl, average length"},
+                    ],
+                },
+            ],
+        }
+
+    single = term(2, 22, 11.5)
+    assert rescore_bm25(single, BM25_K1, BM25_B) == pytest.approx(single["value"])
+    assert rescore_bm25(single, 1.2, 0.0) == pytest.approx(2.2 * 2 / 3.2)
+
+    terms = [term(2, 22, 11.5), term(1, 22, 11.5, idf=2.0)]
+    boolean = {
+        "value": sum(t["value"] for t in terms),
+        "description": "BooleanClause. Sum of ...",
+        "details": terms,
+    }
+    expected = 2.2 * 2 / 3.2 + 2.0 * 2.2 * 1 / 2.2
+    assert rescore_bm25(boolean, 1.2, 0.0) == pytest.approx(expected)
+
+    phrase = {"value": 1.0, "description": "PhraseScorer", "details": []}
+    with pytest.raises(NotImplementedError):
+        rescore_bm25(phrase, 1.2, 0.0)
+    # An explanation in another format is an error, not a wrong score
+    unknown = {"value": 1.0, "description": "TermQuery, product of...", "details": []}
+    with pytest.raises(ValueError):
+        rescore_bm25(unknown, 1.2, 0.0)
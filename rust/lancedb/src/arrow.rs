@@ -12,11 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{pin::Pin, sync::Arc};
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 pub use arrow_schema;
+use arrow_schema::ArrowError;
 use futures::{Stream, StreamExt};
 
+#[cfg(feature = "serde")]
+use crate::serde_arrow_convertors;
+
 #[cfg(feature = "polars")]
 use {crate::polars_arrow_convertors, polars::frame::ArrowChunk, polars::prelude::DataFrame};
 
@@ -129,8 +139,76 @@ impl<S: Stream<Item = Result<arrow_array::RecordBatch>>> SimpleRecordBatchStream
         Self { schema, stream }
     }
 }
+
+/// A RecordBatchReader that pulls batches from a [`SendableRecordBatchStream`]
+///
+/// Each call to `next` blocks on the stream using the runtime that created the reader.
+/// Lance consumes the data for a write on a blocking thread, so this is safe to pass to
+/// a write, but the reader must not be iterated from an async task.
+pub(crate) struct StreamRecordBatchReader {
+    stream: SendableRecordBatchStream,
+    handle: tokio::runtime::Handle,
+    num_rows: Arc<AtomicU64>,
+}
+
+impl StreamRecordBatchReader {
+    /// Must be called from within a tokio runtime
+    pub(crate) fn new(stream: SendableRecordBatchStream) -> Self {
+        Self {
+            stream,
+            handle: tokio::runtime::Handle::current(),
+            num_rows: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// A counter of the number of rows read so far
+    pub(crate) fn num_rows(&self) -> Arc<AtomicU64> {
+        self.num_rows.clone()
+    }
+}
+
+impl Iterator for StreamRecordBatchReader {
+    type Item = std::result::Result<arrow_array::RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.handle.block_on(self.stream.next())?;
+        if let Ok(batch) = &batch {
+            self.num_rows
+                .fetch_add(batch.num_rows() as u64, Ordering::Relaxed);
+        }
+        Some(batch.map_err(|err| ArrowError::ExternalError(Box::new(err))))
+    }
+}
+
+impl arrow_array::RecordBatchReader for StreamRecordBatchReader {
+    fn schema(&self) -> Arc<arrow_schema::Schema> {
+        self.stream.schema()
+    }
+}
 #[cfg(feature = "polars")]
 /// An iterator of record batches formed from a Polars DataFrame.
+///
+/// This is how a DataFrame is passed to [`crate::Connection::create_table`] or
+/// [`crate::Table::add`].  `IntoArrow` cannot be implemented for `DataFrame`
+/// itself because it would conflict with the implementation for every
+/// `RecordBatchReader`.  A `LazyFrame` must be collected into a `DataFrame`
+/// first.
+///
+/// The columns keep their order and the buffers are shared through the Arrow C
+/// data interface.  Strings are converted to `LargeUtf8` and binary to
+/// `LargeBinary`.
+///
+/// ```
+/// # use lancedb::arrow::PolarsDataFrameRecordBatchReader;
+/// # use polars::prelude::df;
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// # let tmpdir = tempfile::tempdir().unwrap();
+/// # let db = lancedb::connect(tmpdir.path().to_str().unwrap()).execute().await.unwrap();
+/// let df = df!("id" => [1, 2, 3], "text" => ["a", "b", "c"]).unwrap();
+/// let reader = PolarsDataFrameRecordBatchReader::new(df).unwrap();
+/// db.create_table("my_table", reader).execute().await.unwrap();
+/// # });
+/// ```
 pub struct PolarsDataFrameRecordBatchReader {
     chunks: std::vec::IntoIter<ArrowChunk>,
     arrow_schema: Arc<arrow_schema::Schema>,
@@ -185,6 +263,108 @@ impl arrow_array::RecordBatchReader for PolarsDataFrameRecordBatchReader {
     }
 }
 
+/// An iterator of record batches formed from serde-serializable records.
+///
+/// This is how a `Vec` of structs that derive `Serialize` is passed to
+/// [`crate::Connection::create_table`] or [`crate::Table::add`].  Like
+/// `PolarsDataFrameRecordBatchReader` it exists because `IntoArrow` cannot be
+/// implemented for `Vec<T>` without conflicting with the implementation for every
+/// `RecordBatchReader`.
+///
+/// The schema is inferred from the first record, values that are `None` in the first
+/// record are filled in from the records that follow.  Each field of the struct becomes
+/// a nullable column: integers, floats, booleans and strings map to the matching Arrow
+/// types, sequences to lists, nested structs to struct columns and unit enum variants to
+/// strings.  Maps and enum variants that carry data are not supported.
+///
+/// Sequences become variable length lists.  Vector fields, which must be fixed size
+/// lists, are marked with [`serde_vector`]:
+///
+/// ```ignore
+/// #[derive(Serialize)]
+/// struct Item {
+///     id: i32,
+///     #[serde(with = "lancedb::arrow::serde_vector")]
+///     vector: Vec<f32>,
+/// }
+///
+/// let items = vec![Item { id: 1, vector: vec![0.1, 0.2] }];
+/// db.create_table("items", SerdeRecordBatchReader::new(items)?).execute().await?;
+/// ```
+#[cfg(feature = "serde")]
+pub struct SerdeRecordBatchReader {
+    batch: Option<arrow_array::RecordBatch>,
+    schema: Arc<arrow_schema::Schema>,
+}
+
+#[cfg(feature = "serde")]
+impl SerdeRecordBatchReader {
+    /// Converts the records into Arrow
+    ///
+    /// Returns an error if there are no records (the schema cannot be inferred) or if a
+    /// record cannot be converted.
+    pub fn new<T: serde::Serialize>(records: impl IntoIterator<Item = T>) -> Result<Self> {
+        let records = records.into_iter().collect::<Vec<_>>();
+        let batch = serde_arrow_convertors::records_to_batch(&records)?;
+        Ok(Self {
+            schema: batch.schema(),
+            batch: Some(batch),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Iterator for SerdeRecordBatchReader {
+    type Item = std::result::Result<arrow_array::RecordBatch, arrow_schema::ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.batch.take().map(Ok)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl arrow_array::RecordBatchReader for SerdeRecordBatchReader {
+    fn schema(&self) -> Arc<arrow_schema::Schema> {
+        self.schema.clone()
+    }
+}
+
+/// Serializes a vector field as a fixed size list
+///
+/// Use it with `#[serde(with = "lancedb::arrow::serde_vector")]` on a `Vec<f32>` (or any
+/// other sequence of numbers) field of a record passed to [`SerdeRecordBatchReader`].
+/// Every record must have a vector of the same length.  Other serializers see the vector
+/// as a plain sequence.
+#[cfg(feature = "serde")]
+pub mod serde_vector {
+    use serde::ser::SerializeTupleStruct;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::serde_arrow_convertors::VECTOR_MARKER;
+
+    pub fn serialize<V, T, S>(vector: &V, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        V: AsRef<[T]>,
+        T: Serialize,
+        S: Serializer,
+    {
+        let vector = vector.as_ref();
+        let mut state = serializer.serialize_tuple_struct(VECTOR_MARKER, vector.len())?;
+        for value in vector {
+            state.serialize_field(value)?;
+        }
+        state.end()
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::deserialize(deserializer)
+    }
+}
+
 /// A trait for converting the result of a LanceDB query into a Polars DataFrame with aligned
 /// chunks. The resulting Polars DataFrame will have aligned chunks, but the series's
 /// chunks are not guaranteed to be contiguous.
@@ -216,8 +396,13 @@ mod tests {
     use crate::arrow::{
         IntoArrow, IntoPolars, PolarsDataFrameRecordBatchReader, SimpleRecordBatchStream,
     };
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int32Type;
+    use futures::TryStreamExt;
     use polars::prelude::{DataFrame, NamedFrom, Series};
 
+    use crate::query::{ExecutableQuery, QueryBase};
+
     fn get_record_batch_reader_from_polars() -> Box<dyn arrow_array::RecordBatchReader + Send> {
         let mut string_series = Series::new("string", &["ab"]);
         let mut int_series = Series::new("int", &[1]);
@@ -295,4 +480,50 @@ mod tests {
             ]
         );
     }
+
+    #[tokio::test]
+    async fn create_table_from_polars() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let db = crate::connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let df = DataFrame::new(vec![
+            Series::new("text", &["a", "b", "c"]),
+            Series::new("id", &[1, 2, 3]),
+        ])
+        .unwrap();
+        let table = db
+            .create_table("polars", PolarsDataFrameRecordBatchReader::new(df).unwrap())
+            .execute()
+            .await
+            .unwrap();
+
+        let schema = table.schema().await.unwrap();
+        assert_eq!(
+            schema
+                .fields
+                .iter()
+                .map(|field| (field.name().as_str(), field.data_type()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("text", &arrow_schema::DataType::LargeUtf8),
+                ("id", &arrow_schema::DataType::Int32),
+            ]
+        );
+        let batches = table
+            .query()
+            .only_if("id > 1")
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let ids = batches
+            .iter()
+            .flat_map(|batch| batch["id"].as_primitive::<Int32Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![2, 3]);
+    }
 }
@@ -14,16 +14,18 @@
 
 //! LanceDB Database
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::create_dir_all;
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use arrow_array::{RecordBatchIterator, RecordBatchReader};
-use arrow_schema::SchemaRef;
-use lance::dataset::{ReadParams, WriteMode};
+use arrow_schema::{DataType, Schema, SchemaRef};
+use futures::{StreamExt, TryStreamExt};
+use lance::dataset::{Dataset, ReadParams, WriteMode};
 use lance::io::{ObjectStore, ObjectStoreParams, WrappingObjectStore};
-use object_store::{aws::AwsCredential, local::LocalFileSystem};
+use object_store::{aws::AwsCredential, local::LocalFileSystem, DynObjectStore};
 use snafu::prelude::*;
 
 use crate::arrow::IntoArrow;
@@ -31,8 +33,10 @@ use crate::embeddings::{
     EmbeddingDefinition, EmbeddingFunction, EmbeddingRegistry, MemoryRegistry, WithEmbeddings,
 };
 use crate::error::{CreateDirSnafu, Error, InvalidTableNameSnafu, Result};
+use crate::io::http::{is_http_url, new_http_store, HttpCommitHandler};
+use crate::io::memory::SharedMemoryStoreWrapper;
 use crate::io::object_store::MirroringObjectStoreWrapper;
-use crate::table::{NativeTable, TableDefinition, WriteOptions};
+use crate::table::{NativeTable, TableDefinition, TableStats, WriteOptions};
 use crate::utils::validate_table_name;
 use crate::Table;
 
@@ -51,6 +55,10 @@ pub enum CreateTableMode {
     /// If the table already exists, it is opened.  Any provided data is
     /// ignored.  The function will be passed an OpenTableBuilder to customize
     /// how the table is opened
+    ///
+    /// The schema of the existing table must have the same columns, with the
+    /// same data types, as the provided data (the order of the columns and their
+    /// nullability may differ).  If it does not then an error is returned.
     ExistOk(TableBuilderCallback),
     /// If the table already exists, it is overwritten
     Overwrite,
@@ -70,6 +78,38 @@ impl Default for CreateTableMode {
     }
 }
 
+/// Checks that an existing table (opened with [`CreateTableMode::ExistOk`]) has
+/// the same columns as the data that would have been used to create it
+fn check_existing_schema(name: &str, existing: &Schema, provided: &Schema) -> Result<()> {
+    let describe = |schema: &Schema| {
+        schema
+            .fields()
+            .iter()
+            .map(|field| format!("{}: {}", field.name(), field.data_type()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let matches = existing.fields().len() == provided.fields().len()
+        && provided.fields().iter().all(|field| {
+            existing
+                .field_with_name(field.name())
+                .map(|existing| existing.data_type() == field.data_type())
+                .unwrap_or(false)
+        });
+    if matches {
+        Ok(())
+    } else {
+        Err(Error::Schema {
+            message: format!(
+                "table '{}' already exists with a different schema, the existing columns are [{}] but the provided columns are [{}]",
+                name,
+                describe(existing),
+                describe(provided)
+            ),
+        })
+    }
+}
+
 /// Describes what happens when a vector either contains NaN or
 /// does not have enough values
 #[derive(Clone, Debug, Default)]
@@ -85,11 +125,63 @@ enum BadVectorHandling {
     Fill(f32),
 }
 
+/// Options for [`Connection::drop_all_tables`]
+#[derive(Clone, Debug, Default)]
+pub struct DropAllOptions {
+    /// Must be set to true, otherwise no tables are dropped and an error is returned
+    ///
+    /// This guards against accidentally deleting every table in the database.
+    pub confirm: bool,
+}
+
+/// Statistics about every table in a database
+///
+/// See [`Connection::database_stats`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DatabaseStats {
+    /// The number of tables in the database
+    pub num_tables: usize,
+    /// The number of rows in all of the tables, not counting deleted rows
+    pub num_rows: usize,
+    /// The total size of the data files of the current version of the tables, in bytes
+    pub data_bytes: u64,
+    /// The total size of the index files of the current version of the tables, in bytes
+    pub index_bytes: u64,
+}
+
+/// Counters of the cache of opened tables
+///
+/// See [`Connection::table_cache_stats`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableCacheStats {
+    /// The number of times a table was opened from the cache without reading a manifest
+    pub hits: u64,
+    /// The number of times the cache read the manifest of a table, because the table
+    /// was not cached or because it has a newer version than the cached one
+    pub refreshes: u64,
+}
+
+impl DatabaseStats {
+    /// The total size of the data and index files, in bytes
+    pub fn total_bytes(&self) -> u64 {
+        self.data_bytes + self.index_bytes
+    }
+
+    fn add_table(mut self, stats: &TableStats) -> Self {
+        self.num_tables += 1;
+        self.num_rows += stats.num_rows;
+        self.data_bytes += stats.data_bytes;
+        self.index_bytes += stats.index_bytes;
+        self
+    }
+}
+
 /// A builder for configuring a [`Connection::table_names`] operation
 pub struct TableNamesBuilder {
     parent: Arc<dyn ConnectionInternal>,
     pub(crate) start_after: Option<String>,
     pub(crate) limit: Option<u32>,
+    pub(crate) prefix: Option<String>,
 }
 
 impl TableNamesBuilder {
@@ -98,6 +190,7 @@ impl TableNamesBuilder {
             parent,
             start_after: None,
             limit: None,
+            prefix: None,
         }
     }
 
@@ -117,6 +210,15 @@ impl TableNamesBuilder {
         self
     }
 
+    /// If present, only return names that start with the supplied prefix
+    ///
+    /// The prefix is applied before `start_after` and `limit` and so it can be
+    /// combined with them to paginate through the matching names.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
     /// Execute the table names operation
     pub async fn execute(self) -> Result<Vec<String>> {
         self.parent.clone().table_names(self).await
@@ -298,12 +400,16 @@ impl<const HAS_DATA: bool, T: IntoArrow> CreateTableBuilder<HAS_DATA, T> {
     }
 }
 
+const DEFAULT_INDEX_CACHE_SIZE: u32 = 256;
+
 #[derive(Clone, Debug)]
 pub struct OpenTableBuilder {
     parent: Arc<dyn ConnectionInternal>,
     name: String,
     index_cache_size: u32,
     lance_read_params: Option<ReadParams>,
+    read_consistency_interval: Option<std::time::Duration>,
+    lazy_indices: bool,
 }
 
 impl OpenTableBuilder {
@@ -311,8 +417,10 @@ impl OpenTableBuilder {
         Self {
             parent,
             name,
-            index_cache_size: 256,
+            index_cache_size: DEFAULT_INDEX_CACHE_SIZE,
             lance_read_params: None,
+            read_consistency_interval: None,
+            lazy_indices: true,
         }
     }
 
@@ -382,6 +490,34 @@ impl OpenTableBuilder {
         self
     }
 
+    /// The interval at which this table checks for updates from other processes
+    ///
+    /// This overrides [`ConnectBuilder::read_consistency_interval`] for this
+    /// table only.  For example, a connection can use eventual consistency while
+    /// a table that must always be fresh uses `Duration::ZERO`, which checks for
+    /// a newer version before every read.
+    pub fn read_consistency_interval(
+        mut self,
+        read_consistency_interval: std::time::Duration,
+    ) -> Self {
+        self.read_consistency_interval = Some(read_consistency_interval);
+        self
+    }
+
+    /// Whether to defer loading the indices until they are used
+    ///
+    /// The default is true.  Opening a table only reads the latest manifest
+    /// and the metadata of an index is loaded into the index cache the first
+    /// time a query or an index operation needs it.  This keeps opening a table
+    /// with many indices fast, for example for a writer that never queries.
+    ///
+    /// If false then the metadata of every index is loaded when the table is
+    /// opened, so the first query does not pay for it.
+    pub fn lazy_indices(mut self, lazy_indices: bool) -> Self {
+        self.lazy_indices = lazy_indices;
+        self
+    }
+
     /// Open the table
     pub async fn execute(self) -> Result<Table> {
         self.parent.clone().do_open_table(self).await
@@ -401,20 +537,79 @@ pub(crate) trait ConnectionInternal:
     ) -> Result<Table>;
     async fn do_open_table(&self, options: OpenTableBuilder) -> Result<Table>;
     async fn drop_table(&self, name: &str) -> Result<()>;
+    async fn rename_table(&self, old_name: &str, new_name: &str) -> Result<()>;
+    async fn clone_table(&self, source: &str, dest: &str) -> Result<()>;
     async fn drop_db(&self) -> Result<()>;
 
+    /// Forget the cached table, if there is one
+    fn invalidate_table_cache(&self, _name: &str) {}
+
+    /// The counters of the cache of opened tables
+    fn table_cache_stats(&self) -> TableCacheStats {
+        TableCacheStats::default()
+    }
+
+    /// The storage options used to access files outside of the database, such as
+    /// the Parquet files read by [`Connection::create_table_from_parquet`]
+    #[cfg(feature = "parquet")]
+    fn storage_options(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    /// Combine the [`TableStats`] of the given tables
+    async fn database_stats(self: Arc<Self>, names: Vec<String>) -> Result<DatabaseStats>;
+
+    /// Drop the given tables, returning the number of tables dropped
+    async fn drop_tables(&self, names: Vec<String>) -> Result<usize> {
+        for name in &names {
+            self.drop_table(name).await?;
+        }
+        Ok(names.len())
+    }
+
     async fn do_create_empty_table(
         &self,
         options: CreateTableBuilder<false, NoData>,
     ) -> Result<Table> {
-        let batches = Box::new(RecordBatchIterator::new(
-            vec![],
-            options.table_definition.clone().unwrap().schema.clone(),
-        ));
+        let schema = options.table_definition.clone().unwrap().schema.clone();
+        validate_empty_table_schema(&schema)?;
+        let batches = Box::new(RecordBatchIterator::new(vec![], schema));
         self.do_create_table(options, batches).await
     }
 }
 
+/// Check the schema of a table created without data
+///
+/// Without data the schema is the only definition of the table, so catch the
+/// mistakes that would otherwise only fail on the first write.
+fn validate_empty_table_schema(schema: &Schema) -> Result<()> {
+    if schema.fields().is_empty() {
+        return Err(Error::Schema {
+            message: "the schema of a table must have at least one field".to_string(),
+        });
+    }
+    let mut names = HashSet::new();
+    for field in schema.fields() {
+        if !names.insert(field.name()) {
+            return Err(Error::Schema {
+                message: format!("the schema has more than one field named {}", field.name()),
+            });
+        }
+        if let DataType::FixedSizeList(_, dim) = field.data_type() {
+            if *dim <= 0 {
+                return Err(Error::Schema {
+                    message: format!(
+                        "the fixed size list field {} must have a positive size, got {}",
+                        field.name(),
+                        dim
+                    ),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 /// A connection to LanceDB
 #[derive(Clone)]
 pub struct Connection {
@@ -439,12 +634,19 @@ impl Connection {
     /// The names will be returned in lexicographical order (ascending)
     ///
     /// The parameters `page_token` and `limit` can be used to paginate the results
+    ///
+    /// For a database served over HTTP(S) the server must support WebDAV `PROPFIND`
+    /// requests to list the tables.
     pub fn table_names(&self) -> TableNamesBuilder {
         TableNamesBuilder::new(self.internal.clone())
     }
 
     /// Create a new table from data
     ///
+    /// `Utf8View` and `BinaryView` columns are not supported by the storage format
+    /// and are stored as `Utf8` and `Binary` columns instead.  Queries against the
+    /// table will return the `Utf8` and `Binary` types.
+    ///
     /// # Parameters
     ///
     /// * `name` - The name of the table
@@ -459,6 +661,15 @@ impl Connection {
 
     /// Create an empty table with a given schema
     ///
+    /// The table has no rows and the schema, including the metadata of the schema
+    /// and of its fields (e.g. the definition of an embedding function), is
+    /// stored as given.  Vector columns are `FixedSizeList` fields, for example
+    /// `FixedSizeList(Float32, 128)`.  Data can be added later with
+    /// [`Table::add`] and must match the schema.
+    ///
+    /// An error is returned if the schema has no fields, has two fields with the same
+    /// name or has a fixed size list field of size zero.
+    ///
     /// # Parameters
     ///
     /// * `name` - The name of the table
@@ -471,6 +682,44 @@ impl Connection {
         CreateTableBuilder::<false, NoData>::new(self.internal.clone(), name.into(), schema)
     }
 
+    /// Create a new table from Parquet files
+    ///
+    /// `path` is a URI (or local path) that points at a single Parquet file, at a
+    /// directory, in which case all of the `.parquet` files under it are read, or
+    /// that is a glob pattern such as `s3://bucket/export/part-*.parquet`.  In a
+    /// pattern `*` and `?` match within one path segment, `**` matches any number
+    /// of directories and `[...]` matches one of a set of characters (e.g. `[0-9]`, or
+    /// `[!0-9]` for any other character).  The files are read with the storage options
+    /// of the connection and must all have the same schema.
+    ///
+    /// The files are streamed into the new table, they are never read to memory as a
+    /// whole.  Columns keep their Arrow types, which are stored in the Parquet files
+    /// written by Arrow (e.g. by pyarrow or polars).  A vector column must be a
+    /// `FixedSizeList`, a plain Parquet list is read as a variable size `List`.
+    ///
+    /// The returned builder is used to set the other options of the new table.
+    ///
+    /// ```ignore
+    /// let table = db
+    ///     .create_table_from_parquet("items", "/data/items/*.parquet")
+    ///     .await?
+    ///     .mode(CreateTableMode::Overwrite)
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "parquet")]
+    pub async fn create_table_from_parquet(
+        &self,
+        name: impl Into<String>,
+        path: &str,
+    ) -> Result<CreateTableBuilder<true, crate::arrow::BoxedRecordBatchReader>> {
+        let stream =
+            crate::io::parquet::read_parquet(path, &self.internal.storage_options()).await?;
+        let data: crate::arrow::BoxedRecordBatchReader =
+            Box::new(crate::arrow::StreamRecordBatchReader::new(stream));
+        Ok(self.create_table(name, data))
+    }
+
     /// Open an existing table in the database
     ///
     /// # Arguments
@@ -490,6 +739,105 @@ impl Connection {
         self.internal.drop_table(name.as_ref()).await
     }
 
+    /// Rename a table in the database
+    ///
+    /// The table keeps its version history and indices.  Returns
+    /// [`Error::TableNotFound`] if there is no table named `old_name` and
+    /// [`Error::TableAlreadyExists`] if there is already a table named `new_name`.
+    ///
+    /// On a local file system the table directory is renamed, which is atomic.
+    /// Object stores (e.g. S3) cannot rename directories and so the files of the
+    /// table are copied to the new name and then removed from the old name.  This
+    /// is not atomic and should not be run while the table is being written to.
+    ///
+    /// Any [`Table`] opened with the old name must be reopened with the new name.
+    ///
+    /// # Arguments
+    /// * `old_name` - The current name of the table
+    /// * `new_name` - The new name of the table
+    pub async fn rename_table(
+        &self,
+        old_name: impl AsRef<str>,
+        new_name: impl AsRef<str>,
+    ) -> Result<()> {
+        self.internal
+            .rename_table(old_name.as_ref(), new_name.as_ref())
+            .await
+    }
+
+    /// Create a copy of a table that can be modified independently of the original
+    ///
+    /// The copy has the same version history and indices as `source`.  Writes to
+    /// either table after the clone do not affect the other.  Returns
+    /// [`Error::TableNotFound`] if there is no table named `source` and
+    /// [`Error::TableAlreadyExists`] if there is already a table named `dest`.
+    ///
+    /// On a local file system the clone is cheap: the data files, deletion files
+    /// and indices are never modified once written, so they are hard linked and
+    /// only the manifests are copied.  Object stores (e.g. S3) cannot link files
+    /// and so every file of the table is copied, which takes time proportional
+    /// to the size of the table.
+    ///
+    /// The clone is not atomic and should not be run while `source` is being
+    /// written to.
+    ///
+    /// # Arguments
+    /// * `source` - The name of the table to clone
+    /// * `dest` - The name of the new table
+    pub async fn clone_table(&self, source: impl AsRef<str>, dest: impl AsRef<str>) -> Result<()> {
+        self.internal
+            .clone_table(source.as_ref(), dest.as_ref())
+            .await
+    }
+
+    /// Drop every table in the database
+    ///
+    /// Returns the number of tables that were dropped.
+    ///
+    /// This is a destructive operation and so `options.confirm` must be set to
+    /// true.  If it is not then [`Error::InvalidInput`] is returned and nothing
+    /// is dropped.
+    pub async fn drop_all_tables(&self, options: DropAllOptions) -> Result<usize> {
+        if !options.confirm {
+            return Err(Error::InvalidInput {
+                message: "drop_all_tables requires DropAllOptions { confirm: true }".to_string(),
+            });
+        }
+        let names = self.all_table_names().await?;
+        self.internal.drop_tables(names).await
+    }
+
+    /// Get statistics about every table in the database
+    ///
+    /// This returns the number of tables and the total number of rows and bytes
+    /// of the tables, for example for a dashboard.  The [`Table::stats`] of the
+    /// tables are collected concurrently, and each table is opened to read its
+    /// latest manifest, so this is slower for databases with many tables.
+    ///
+    /// Tables that are dropped while the statistics are collected are skipped.
+    /// This is not yet supported by LanceDB Cloud.
+    pub async fn database_stats(&self) -> Result<DatabaseStats> {
+        let names = self.all_table_names().await?;
+        self.internal.clone().database_stats(names).await
+    }
+
+    /// The names of every table, reading as many pages of names as needed
+    async fn all_table_names(&self) -> Result<Vec<String>> {
+        let mut names: Vec<String> = Vec::new();
+        loop {
+            let mut builder = self.table_names();
+            if let Some(last) = names.last() {
+                builder = builder.start_after(last.clone());
+            }
+            let page = builder.execute().await?;
+            if page.is_empty() {
+                break;
+            }
+            names.extend(page);
+        }
+        Ok(names)
+    }
+
     /// Drop the database
     ///
     /// This is the same as dropping all of the tables
@@ -497,6 +845,24 @@ impl Connection {
         self.internal.drop_db().await
     }
 
+    /// Remove the table from the cache of opened tables
+    ///
+    /// The next [`Self::open_table`] call for the table opens it again from scratch.
+    /// This does nothing if the table is not cached, or if the cache is disabled (see
+    /// [`ConnectBuilder::table_cache_size`]).
+    pub fn invalidate_table_cache(&self, name: impl AsRef<str>) {
+        self.internal.invalidate_table_cache(name.as_ref())
+    }
+
+    /// The number of tables opened from the cache and the number of manifests the
+    /// cache had to read
+    ///
+    /// The counters are zero if the cache is disabled (see
+    /// [`ConnectBuilder::table_cache_size`]).
+    pub fn table_cache_stats(&self) -> TableCacheStats {
+        self.internal.table_cache_stats()
+    }
+
     /// Get the in-memory embedding registry.
     /// It's important to note that the embedding registry is not persisted across connections.
     /// So if a table contains embeddings, you will need to make sure that you are using a connection that has the same embedding functions registered
@@ -505,6 +871,67 @@ impl Connection {
     }
 }
 
+/// How requests to LanceDB Cloud are retried when they fail
+///
+/// Requests are retried when the server is overloaded (429), when it is
+/// unavailable (502, 503 or 504) and when the connection fails.  Requests that
+/// modify the database, such as creating or dropping a table, may already have
+/// been applied when the server responds with a 5xx error and so they are only
+/// retried when the server is overloaded or the connection could not be made.
+///
+/// The delay before the first retry is `initial_backoff` and it doubles with
+/// each retry, up to `max_backoff`.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// The maximum number of times a request is retried, 0 disables retries
+    pub max_retries: u32,
+    /// The delay before the first retry
+    pub initial_backoff: std::time::Duration,
+    /// The maximum delay between two retries
+    pub max_backoff: std::time::Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: std::time::Duration::from_millis(500),
+            max_backoff: std::time::Duration::from_secs(10),
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl RetryConfig {
+    /// The delay before the given retry, the first retry is 0
+    pub(crate) fn backoff(&self, retry: u32) -> std::time::Duration {
+        self.initial_backoff
+            .saturating_mul(2_u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+}
+
+/// A token used to authenticate with LanceDB Cloud, see [`TokenProvider`]
+#[derive(Debug, Clone)]
+pub struct AuthToken {
+    /// The token, sent in the `Authorization` header as a bearer token
+    pub token: String,
+    /// When the token expires, or None if it does not expire
+    pub expires_at: Option<std::time::SystemTime>,
+}
+
+/// Provides short-lived tokens to authenticate with LanceDB Cloud
+///
+/// The token is requested before the first request and then again whenever it
+/// is about to expire, so that a connection can outlive any single token.  If
+/// the server rejects a token (401) then a new token is requested and the
+/// request is sent once more.
+#[async_trait::async_trait]
+pub trait TokenProvider: std::fmt::Debug + Send + Sync {
+    /// Get a new token
+    async fn token(&self) -> Result<AuthToken>;
+}
+
 #[derive(Debug)]
 pub struct ConnectBuilder {
     /// Database URI
@@ -514,9 +941,16 @@ pub struct ConnectBuilder {
     /// - `/path/to/database` - local database on file system.
     /// - `s3://bucket/path/to/database` or `gs://bucket/path/to/database` - database on cloud object store
     /// - `db://dbname` - LanceDB Cloud
+    /// - `memory://` - database held in memory, useful for tests.  Each connection
+    ///   has its own database, which is dropped with the connection and its tables.
+    /// - `https://host/path/to/database` - read-only database served over HTTP(S).  Files
+    ///   are read with range requests, so tables can be opened from any static file
+    ///   server.  Listing the tables (e.g. `table_names`) or the versions of a table
+    ///   needs a server that supports WebDAV `PROPFIND` requests.  Any write returns
+    ///   an error.
     uri: String,
 
-    /// LanceDB Cloud API key, required if using Lance Cloud
+    /// LanceDB Cloud API key, required if using Lance Cloud without a token provider
     api_key: Option<String>,
     /// LanceDB Cloud region, required if using Lance Cloud
     region: Option<String>,
@@ -537,6 +971,22 @@ pub struct ConnectBuilder {
     /// always consistent.
     read_consistency_interval: Option<std::time::Duration>,
     embedding_registry: Option<Arc<dyn EmbeddingRegistry>>,
+
+    /// If true, every operation that would modify the database fails with
+    /// [`Error::ReadOnly`].
+    read_only: bool,
+
+    /// The maximum number of opened tables to keep for reuse by `open_table`
+    table_cache_size: usize,
+
+    /// How requests to LanceDB Cloud are retried
+    retry_config: RetryConfig,
+
+    /// Headers sent with every request to LanceDB Cloud
+    headers: HashMap<String, String>,
+
+    /// Provides the tokens used to authenticate with LanceDB Cloud
+    token_provider: Option<Arc<dyn TokenProvider>>,
 }
 
 impl ConnectBuilder {
@@ -550,6 +1000,11 @@ impl ConnectBuilder {
             read_consistency_interval: None,
             storage_options: HashMap::new(),
             embedding_registry: None,
+            read_only: false,
+            table_cache_size: 0,
+            retry_config: RetryConfig::default(),
+            headers: HashMap::new(),
+            token_provider: None,
         }
     }
 
@@ -599,6 +1054,51 @@ impl ConnectBuilder {
     /// Set multiple options for the storage layer.
     ///
     /// See available options at <https://lancedb.github.io/lancedb/guides/storage/>
+    ///
+    /// The options are passed to every table opened or created with the connection.
+    /// For example, an S3 compatible store such as MinIO can be used with:
+    ///
+    /// ```no_run
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let db = lancedb::connect("s3://bucket/path")
+    ///     .storage_options([
+    ///         ("endpoint", "http://localhost:9000"),
+    ///         ("region", "us-east-1"),
+    ///         ("allow_http", "true"),
+    ///         ("aws_access_key_id", "minioadmin"),
+    ///         ("aws_secret_access_key", "minioadmin"),
+    ///     ])
+    ///     .execute()
+    ///     .await
+    ///     .unwrap();
+    /// # });
+    /// ```
+    ///
+    /// The scheme of the URI selects the object store: `s3://` for S3, `gs://` for
+    /// Google Cloud Storage and `az://` for Azure Blob Storage.  For example, to use
+    /// a GCS service account or an Azure storage account key:
+    ///
+    /// ```no_run
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let db = lancedb::connect("gs://bucket/path")
+    ///     .storage_options([("google_service_account", "/path/to/service-account.json")])
+    ///     .execute()
+    ///     .await
+    ///     .unwrap();
+    /// let db = lancedb::connect("az://container/path")
+    ///     .storage_options([
+    ///         ("azure_storage_account_name", "account"),
+    ///         ("azure_storage_account_key", "key"),
+    ///     ])
+    ///     .execute()
+    ///     .await
+    ///     .unwrap();
+    /// # });
+    /// ```
+    ///
+    /// Options that are not set are read from the environment (e.g. `AWS_ENDPOINT`,
+    /// `GOOGLE_SERVICE_ACCOUNT` or `AZURE_STORAGE_ACCOUNT_NAME`).  Options set here
+    /// take precedence over the environment.
     pub fn storage_options(
         mut self,
         pairs: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
@@ -632,20 +1132,99 @@ impl ConnectBuilder {
         self
     }
 
+    /// Open the database in read-only mode.  This only affects LanceDB OSS.
+    ///
+    /// Creating, dropping or renaming tables, and every operation that would
+    /// write to a table opened from this connection, such as `add`, `delete`
+    /// or `create_index`, fails with [`Error::ReadOnly`] before anything is
+    /// written.  Queries are not affected.
+    ///
+    /// This is a guard against accidental writes, for example in a process that
+    /// only serves queries.  It is not a permission system: other connections,
+    /// and other processes, can still write to the database.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Keep up to `table_cache_size` opened tables for reuse.  This only affects
+    /// LanceDB OSS.
+    ///
+    /// Opening a table reads its latest manifest.  With a cache, calling
+    /// [`Connection::open_table`] again for a table that was opened recently only
+    /// checks the number of the latest version.  If the table was not modified
+    /// since, the cached manifest (and the index metadata loaded with it) is reused,
+    /// otherwise the new manifest is read and cached.  This helps when tables are
+    /// opened for every request.  The least recently opened table is evicted when
+    /// the cache is full.  The default is 0, which disables the cache.
+    ///
+    /// Every call returns its own handle, checking out a version of one handle
+    /// does not affect the others.  See [`Connection::table_cache_stats`] for counters
+    /// of the cache.
+    ///
+    /// Tables opened with custom read parameters, storage options or a
+    /// consistency interval are not cached.  Dropping, renaming or overwriting a
+    /// table through the connection removes it from the cache.
+    pub fn table_cache_size(mut self, table_cache_size: usize) -> Self {
+        self.table_cache_size = table_cache_size;
+        self
+    }
+
+    /// Set how failed requests to LanceDB Cloud are retried.  This only affects
+    /// LanceDB Cloud.
+    ///
+    /// See [`RetryConfig`] for which requests are retried.  The default is to
+    /// retry up to 3 times.
+    pub fn retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = retry_config;
+        self
+    }
+
+    /// Set a header that is sent with every request to LanceDB Cloud.  This
+    /// only affects LanceDB Cloud.
+    ///
+    /// This can be called multiple times, a header set twice keeps the last value.
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    /// Authenticate with tokens from the given [`TokenProvider`] instead of an
+    /// api key.  This only affects LanceDB Cloud.
+    ///
+    /// This is useful for deployments that use short-lived tokens, the tokens are
+    /// refreshed without creating a new connection.
+    pub fn token_provider(mut self, token_provider: Arc<dyn TokenProvider>) -> Self {
+        self.token_provider = Some(token_provider);
+        self
+    }
+
     #[cfg(feature = "remote")]
     fn execute_remote(self) -> Result<Connection> {
+        if self.read_only {
+            return Err(Error::NotSupported {
+                message: "read-only connections are not supported by LanceDb Cloud".to_string(),
+            });
+        }
         let region = self.region.ok_or_else(|| Error::InvalidInput {
             message: "A region is required when connecting to LanceDb Cloud".to_string(),
         })?;
-        let api_key = self.api_key.ok_or_else(|| Error::InvalidInput {
-            message: "An api_key is required when connecting to LanceDb Cloud".to_string(),
-        })?;
+        if self.api_key.is_none() && self.token_provider.is_none() {
+            return Err(Error::InvalidInput {
+                message:
+                    "An api_key or a token_provider is required when connecting to LanceDb Cloud"
+                        .to_string(),
+            });
+        }
         warn!("The rust implementation of the remote client is not yet ready for use.");
         let internal = Arc::new(crate::remote::db::RemoteDatabase::try_new(
             &self.uri,
-            &api_key,
+            self.api_key.as_deref(),
             &region,
             self.host_override,
+            self.retry_config,
+            self.headers,
+            self.token_provider,
         )?);
         Ok(Connection {
             internal,
@@ -685,6 +1264,80 @@ pub fn connect(uri: &str) -> ConnectBuilder {
     ConnectBuilder::new(uri)
 }
 
+/// The datasets of the tables opened by a [`Database`], kept for reuse
+///
+/// Each entry is the dataset of a table at the version that was the latest when it
+/// was cached.  Every caller gets its own handle for the dataset.  The most recently
+/// used table is at the back of `entries`.  The cache holds a handful of tables so a
+/// linear search is fine.
+#[derive(Debug)]
+struct TableCache {
+    capacity: usize,
+    entries: Mutex<VecDeque<(String, Dataset)>>,
+    hits: AtomicU64,
+    refreshes: AtomicU64,
+}
+
+impl TableCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            hits: AtomicU64::new(0),
+            refreshes: AtomicU64::new(0),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    fn get(&self, name: &str) -> Option<Dataset> {
+        let mut entries = self.entries.lock().unwrap();
+        let idx = entries.iter().position(|(cached, _)| cached == name)?;
+        let entry = entries.remove(idx).unwrap();
+        let dataset = entry.1.clone();
+        entries.push_back(entry);
+        Some(dataset)
+    }
+
+    /// Cache a dataset whose manifest was just read
+    fn insert(&self, name: &str, dataset: Dataset) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.refreshes.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|(cached, _)| cached != name);
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back((name.to_string(), dataset));
+    }
+
+    fn record_hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> TableCacheStats {
+        TableCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            refreshes: self.refreshes.load(Ordering::Relaxed),
+        }
+    }
+
+    fn remove(&self, name: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|(cached, _)| cached != name);
+    }
+
+    fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
 #[derive(Debug)]
 struct Database {
     object_store: ObjectStore,
@@ -701,6 +1354,13 @@ struct Database {
     // Storage options to be inherited by tables created from this connection
     storage_options: HashMap<String, String>,
     embedding_registry: Arc<dyn EmbeddingRegistry>,
+
+    read_only: bool,
+
+    // The store used to read tables, if the database is served over HTTP(S)
+    http_store: Option<Arc<DynObjectStore>>,
+
+    table_cache: TableCache,
 }
 
 impl std::fmt::Display for Database {
@@ -722,8 +1382,16 @@ impl std::fmt::Display for Database {
 }
 
 const LANCE_EXTENSION: &str = "lance";
+
+/// The maximum number of files copied at once when renaming or cloning a table
+const COPY_CONCURRENCY: usize = 8;
+/// The maximum number of tables opened at once to collect the database stats
+const STATS_CONCURRENCY: usize = 8;
+/// The directories of a table holding files that are never modified once written
+const IMMUTABLE_TABLE_DIRS: [&str; 3] = ["data", "_deletions", "_indices"];
 const ENGINE: &str = "engine";
 const MIRRORED_STORE: &str = "mirroredStore";
+const MEMORY_SCHEME: &str = "memory";
 
 /// A connection to LanceDB
 impl Database {
@@ -738,6 +1406,8 @@ impl Database {
                     uri,
                     options.read_consistency_interval,
                     options.embedding_registry.clone(),
+                    options.read_only,
+                    options.table_cache_size,
                 )
                 .await
             }
@@ -775,6 +1445,23 @@ impl Database {
                 // will add a trailing '?' to the url
                 url.set_query(None);
 
+                let memory_store = if url.scheme() == MEMORY_SCHEME {
+                    if mirrored_store.is_some() {
+                        return Err(Error::NotSupported {
+                            message: "mirrored store is not supported for in-memory databases"
+                                .into(),
+                        });
+                    }
+                    // Table URIs are joined onto the path so make sure it has one
+                    if url.path().is_empty() {
+                        url.set_path("/");
+                    }
+                    let wrapper = SharedMemoryStoreWrapper::default();
+                    Some(Arc::new(wrapper) as Arc<dyn WrappingObjectStore>)
+                } else {
+                    None
+                };
+
                 let table_base_uri = if let Some(store) = engine {
                     static WARN_ONCE: std::sync::Once = std::sync::Once::new();
                     WARN_ONCE.call_once(|| {
@@ -790,12 +1477,25 @@ impl Database {
                 let plain_uri = url.to_string();
 
                 let storage_options = options.storage_options.clone();
-                let os_params = ObjectStoreParams {
-                    storage_options: Some(storage_options.clone()),
-                    ..Default::default()
+                let http_store = if is_http_url(&url) {
+                    Some(new_http_store(&url, &storage_options)?)
+                } else {
+                    None
+                };
+                let (object_store, base_path) = match &http_store {
+                    Some(store) => (
+                        ObjectStore::new(store.clone(), url.clone(), None, None),
+                        object_store::path::Path::from(url.path()),
+                    ),
+                    None => {
+                        let os_params = ObjectStoreParams {
+                            storage_options: Some(storage_options.clone()),
+                            object_store_wrapper: memory_store.clone(),
+                            ..Default::default()
+                        };
+                        ObjectStore::from_uri_and_params(&plain_uri, &os_params).await?
+                    }
                 };
-                let (object_store, base_path) =
-                    ObjectStore::from_uri_and_params(&plain_uri, &os_params).await?;
                 if object_store.is_local() {
                     Self::try_create_dir(&plain_uri).context(CreateDirSnafu { path: plain_uri })?;
                 }
@@ -806,7 +1506,7 @@ impl Database {
                         let wrapper = MirroringObjectStoreWrapper::new(mirrored_store);
                         Some(Arc::new(wrapper) as Arc<dyn WrappingObjectStore>)
                     }
-                    None => None,
+                    None => memory_store,
                 };
 
                 let embedding_registry = options
@@ -822,6 +1522,10 @@ impl Database {
                     read_consistency_interval: options.read_consistency_interval,
                     storage_options,
                     embedding_registry,
+                    // Databases served over HTTP can only be read
+                    read_only: options.read_only || http_store.is_some(),
+                    http_store,
+                    table_cache: TableCache::new(options.table_cache_size),
                 })
             }
             Err(_) => {
@@ -829,6 +1533,8 @@ impl Database {
                     uri,
                     options.read_consistency_interval,
                     options.embedding_registry.clone(),
+                    options.read_only,
+                    options.table_cache_size,
                 )
                 .await
             }
@@ -839,6 +1545,8 @@ impl Database {
         path: &str,
         read_consistency_interval: Option<std::time::Duration>,
         embedding_registry: Option<Arc<dyn EmbeddingRegistry>>,
+        read_only: bool,
+        table_cache_size: usize,
     ) -> Result<Self> {
         let (object_store, base_path) = ObjectStore::from_uri(path).await?;
         if object_store.is_local() {
@@ -857,6 +1565,9 @@ impl Database {
             read_consistency_interval,
             storage_options: HashMap::new(),
             embedding_registry,
+            read_only,
+            http_store: None,
+            table_cache: TableCache::new(table_cache_size),
         })
     }
 
@@ -869,12 +1580,123 @@ impl Database {
         Ok(())
     }
 
+    /// Fail with [`Error::ReadOnly`] if the connection is read-only
+    fn ensure_writable(&self, operation: &str) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly {
+                message: format!("cannot {} in a read-only database", operation),
+            });
+        }
+        Ok(())
+    }
+
+    /// The path of a directory of the database on the local file system
+    ///
+    /// The connection URI may be a `file://` URL or an unexpanded path so this is
+    /// derived from the object store path instead.
+    fn local_path(&self, dir: &str) -> Result<PathBuf> {
+        Ok(LocalFileSystem::new().path_to_filesystem(&self.base_path.child(dir))?)
+    }
+
+    /// Copy every file of the table directory `from_dir` to `to_dir`
+    ///
+    /// If `hard_link` is true then the immutable files of the table (data files,
+    /// deletion files and indices) are hard linked instead of copied.  Only the
+    /// manifests, which are replaced when the table is written to, are copied.
+    /// This is only possible on a local file system.
+    async fn copy_table_files(&self, from_dir: &str, to_dir: &str, hard_link: bool) -> Result<()> {
+        let from_path = self.base_path.child(from_dir);
+        let to_path = self.base_path.child(to_dir);
+        let (from_dir_path, to_dir_path) = if hard_link {
+            (self.local_path(from_dir)?, self.local_path(to_dir)?)
+        } else {
+            Default::default()
+        };
+        let store = self.object_store.inner.clone();
+        store
+            .list(Some(&from_path))
+            .map_err(Error::from)
+            .try_for_each_concurrent(COPY_CONCURRENCY, |meta| {
+                let store = store.clone();
+                let parts = meta
+                    .location
+                    .prefix_match(&from_path)
+                    .map(|parts| parts.collect::<Vec<_>>())
+                    .unwrap_or_default();
+                let to = parts
+                    .iter()
+                    .fold(to_path.clone(), |path, part| path.child(part.clone()));
+                let link = hard_link
+                    && parts
+                        .first()
+                        .map_or(false, |dir| IMMUTABLE_TABLE_DIRS.contains(&dir.as_ref()));
+                let link_paths = link.then(|| {
+                    let relative = parts.iter().map(|part| part.as_ref());
+                    (
+                        relative
+                            .clone()
+                            .fold(from_dir_path.clone(), |p, part| p.join(part)),
+                        relative.fold(to_dir_path.clone(), |p, part| p.join(part)),
+                    )
+                });
+                async move {
+                    if let Some((from, to)) = link_paths {
+                        if let Some(parent) = to.parent() {
+                            create_dir_all(parent).context(CreateDirSnafu {
+                                path: parent.display().to_string(),
+                            })?;
+                        }
+                        std::fs::hard_link(&from, &to).map_err(|err| Error::Runtime {
+                            message: format!(
+                                "failed to link {} to {}: {}",
+                                from.display(),
+                                to.display(),
+                                err
+                            ),
+                        })?;
+                    } else {
+                        store.copy(&meta.location, &to).await?;
+                    }
+                    Ok(())
+                }
+            })
+            .await
+    }
+
+    /// Bring a dataset from the table cache up to the latest version of the table
+    ///
+    /// Only the number of the latest version is read if the table was not modified.
+    async fn refresh_cached_dataset(&self, name: &str, dataset: Dataset) -> Result<Dataset> {
+        let latest_version = dataset.latest_version_id().await?;
+        if latest_version == dataset.version().version {
+            self.table_cache.record_hit();
+            return Ok(dataset);
+        }
+        let dataset = dataset.checkout_version(latest_version).await?;
+        self.table_cache.insert(name, dataset.clone());
+        Ok(dataset)
+    }
+
     /// Get the URI of a table in the database.
     fn table_uri(&self, name: &str) -> Result<String> {
         validate_table_name(name)?;
 
+        let table_dir = format!("{}.{}", name, LANCE_FILE_EXTENSION);
+        // Lance only opens a `file://` URL if the table already exists, so local
+        // tables are always referred to by their path
+        if self.object_store.is_local() {
+            return Ok(self
+                .local_path(&table_dir)?
+                .to_str()
+                .context(InvalidTableNameSnafu {
+                    name,
+                    reason: "Name is not valid URL",
+                })?
+                .to_string());
+        }
+
         let path = Path::new(&self.uri);
-        let table_uri = path.join(format!("{}.{}", name, LANCE_FILE_EXTENSION));
+        let table_uri = path.join(table_dir);
 
         let mut uri = table_uri
             .as_path()
@@ -897,6 +1719,11 @@ impl Database {
 
 #[async_trait::async_trait]
 impl ConnectionInternal for Database {
+    #[cfg(feature = "parquet")]
+    fn storage_options(&self) -> HashMap<String, String> {
+        self.storage_options.clone()
+    }
+
     fn embedding_registry(&self) -> &dyn EmbeddingRegistry {
         self.embedding_registry.as_ref()
     }
@@ -915,6 +1742,13 @@ impl ConnectionInternal for Database {
                 is_lance.unwrap_or(false)
             })
             .filter_map(|p| p.file_stem().and_then(|s| s.to_str().map(String::from)))
+            .filter(|name| {
+                options
+                    .prefix
+                    .as_ref()
+                    .map(|prefix| name.starts_with(prefix.as_str()))
+                    .unwrap_or(true)
+            })
             .collect::<Vec<String>>();
         f.sort();
         if let Some(start_after) = options.start_after {
@@ -935,7 +1769,11 @@ impl ConnectionInternal for Database {
         mut options: CreateTableBuilder<false, NoData>,
         data: Box<dyn RecordBatchReader + Send>,
     ) -> Result<Table> {
+        self.ensure_writable("create a table")?;
         let table_uri = self.table_uri(&options.name)?;
+        if matches!(&options.mode, CreateTableMode::Overwrite) {
+            self.table_cache.remove(&options.name);
+        }
         let embedding_registry = self.embedding_registry.clone();
         // Inherit storage options from the connection
         let storage_options = options
@@ -957,6 +1795,8 @@ impl ConnectionInternal for Database {
             Box::new(WithEmbeddings::new(data, options.embeddings))
         };
 
+        let schema = data.schema();
+
         let mut write_params = options.write_options.lance_write_params.unwrap_or_default();
         if matches!(&options.mode, CreateTableMode::Overwrite) {
             write_params.mode = WriteMode::Overwrite;
@@ -982,7 +1822,10 @@ impl ConnectionInternal for Database {
                 CreateTableMode::ExistOk(callback) => {
                     let builder = OpenTableBuilder::new(options.parent, options.name);
                     let builder = (callback)(builder);
-                    builder.execute().await
+                    let table = builder.execute().await?;
+                    let existing = table.schema().await?;
+                    check_existing_schema(&name, &existing, &schema)?;
+                    Ok(table)
                 }
                 CreateTableMode::Overwrite => unreachable!(),
             },
@@ -993,6 +1836,41 @@ impl ConnectionInternal for Database {
     async fn do_open_table(&self, mut options: OpenTableBuilder) -> Result<Table> {
         let table_uri = self.table_uri(&options.name)?;
 
+        // Only tables opened with the default parameters are shared
+        let cacheable = self.table_cache.is_enabled()
+            && options.lance_read_params.is_none()
+            && options.read_consistency_interval.is_none()
+            && options.index_cache_size == DEFAULT_INDEX_CACHE_SIZE;
+        if cacheable {
+            if let Some(dataset) = self.table_cache.get(&options.name) {
+                match self.refresh_cached_dataset(&options.name, dataset).await {
+                    Ok(dataset) => {
+                        let native_table = Arc::new(
+                            NativeTable::from_dataset(
+                                dataset,
+                                &table_uri,
+                                &options.name,
+                                self.store_wrapper.clone(),
+                                self.storage_options.clone(),
+                                self.read_consistency_interval,
+                            )
+                            .with_read_only(self.read_only),
+                        );
+                        if !options.lazy_indices {
+                            native_table.load_indices().await?;
+                        }
+                        return Ok(Table::new_with_embedding_registry(
+                            native_table,
+                            self.embedding_registry.clone(),
+                        ));
+                    }
+                    // The table may have been dropped by another process, open it
+                    // again to report that
+                    Err(_) => self.table_cache.remove(&options.name),
+                }
+            }
+        }
+
         // Inherit storage options from the connection
         let storage_options = options
             .lance_read_params
@@ -1013,10 +1891,24 @@ impl ConnectionInternal for Database {
         // If we have a user provided ReadParams use that
         // If we don't then start with the default ReadParams and customize it with
         // the options from the OpenTableBuilder
-        let read_params = options.lance_read_params.unwrap_or_else(|| ReadParams {
+        let mut read_params = options.lance_read_params.unwrap_or_else(|| ReadParams {
             index_cache_size: options.index_cache_size as usize,
             ..Default::default()
         });
+        if let Some(store) = &self.http_store {
+            let table_url = url::Url::parse(&table_uri).map_err(|err| Error::InvalidInput {
+                message: format!("invalid table URI {}: {}", table_uri, err),
+            })?;
+            read_params
+                .store_options
+                .get_or_insert_with(Default::default)
+                .object_store = Some((store.clone(), table_url));
+            // Lance needs a commit handler for a custom store, this one finds the
+            // latest version without listing the table
+            read_params
+                .commit_handler
+                .get_or_insert_with(|| Arc::new(HttpCommitHandler));
+        }
 
         let native_table = Arc::new(
             NativeTable::open_with_params(
@@ -1024,14 +1916,29 @@ impl ConnectionInternal for Database {
                 &options.name,
                 self.store_wrapper.clone(),
                 Some(read_params),
-                self.read_consistency_interval,
+                options
+                    .read_consistency_interval
+                    .or(self.read_consistency_interval),
             )
-            .await?,
+            .await?
+            .with_read_only(self.read_only),
         );
-        Ok(Table::new(native_table))
+        if !options.lazy_indices {
+            native_table.load_indices().await?;
+        }
+        if cacheable {
+            let dataset = native_table.dataset.get().await?.clone();
+            self.table_cache.insert(&options.name, dataset);
+        }
+        Ok(Table::new_with_embedding_registry(
+            native_table,
+            self.embedding_registry.clone(),
+        ))
     }
 
     async fn drop_table(&self, name: &str) -> Result<()> {
+        self.ensure_writable("drop a table")?;
+        self.table_cache.remove(name);
         let dir_name = format!("{}.{}", name, LANCE_EXTENSION);
         let full_path = self.base_path.child(dir_name.clone());
         self.object_store
@@ -1048,7 +1955,98 @@ impl ConnectionInternal for Database {
         Ok(())
     }
 
-    async fn drop_db(&self) -> Result<()> {
+    async fn rename_table(&self, old_name: &str, new_name: &str) -> Result<()> {
+        self.ensure_writable("rename a table")?;
+        self.table_cache.remove(old_name);
+        validate_table_name(new_name)?;
+        let old_dir = format!("{}.{}", old_name, LANCE_EXTENSION);
+        let new_dir = format!("{}.{}", new_name, LANCE_EXTENSION);
+        let existing = self.object_store.read_dir(self.base_path.clone()).await?;
+        if !existing.contains(&old_dir) {
+            return Err(Error::TableNotFound {
+                name: old_name.to_owned(),
+            });
+        }
+        if existing.contains(&new_dir) {
+            return Err(Error::TableAlreadyExists {
+                name: new_name.to_owned(),
+            });
+        }
+
+        if self.object_store.is_local() {
+            std::fs::rename(self.local_path(&old_dir)?, self.local_path(&new_dir)?).map_err(
+                |err| Error::Runtime {
+                    message: format!(
+                        "failed to rename table {} to {}: {}",
+                        old_name, new_name, err
+                    ),
+                },
+            )?;
+            return Ok(());
+        }
+
+        self.copy_table_files(&old_dir, &new_dir, false).await?;
+        self.object_store
+            .remove_dir_all(self.base_path.child(old_dir))
+            .await?;
+        Ok(())
+    }
+
+    async fn clone_table(&self, source: &str, dest: &str) -> Result<()> {
+        self.ensure_writable("clone a table")?;
+        validate_table_name(dest)?;
+        let source_dir = format!("{}.{}", source, LANCE_EXTENSION);
+        let dest_dir = format!("{}.{}", dest, LANCE_EXTENSION);
+        let existing = self.object_store.read_dir(self.base_path.clone()).await?;
+        if !existing.contains(&source_dir) {
+            return Err(Error::TableNotFound {
+                name: source.to_owned(),
+            });
+        }
+        if existing.contains(&dest_dir) {
+            return Err(Error::TableAlreadyExists {
+                name: dest.to_owned(),
+            });
+        }
+        let hard_link = self.object_store.is_local();
+        self.copy_table_files(&source_dir, &dest_dir, hard_link)
+            .await
+    }
+
+    fn invalidate_table_cache(&self, name: &str) {
+        self.table_cache.remove(name);
+    }
+
+    fn table_cache_stats(&self) -> TableCacheStats {
+        self.table_cache.stats()
+    }
+
+    async fn database_stats(self: Arc<Self>, names: Vec<String>) -> Result<DatabaseStats> {
+        futures::stream::iter(names)
+            .map(|name| {
+                let parent = self.clone();
+                async move {
+                    let options = OpenTableBuilder::new(parent.clone(), name);
+                    match parent.do_open_table(options).await {
+                        Ok(table) => table.stats().await.map(Some),
+                        Err(Error::TableNotFound { .. }) => Ok(None),
+                        Err(err) => Err(err),
+                    }
+                }
+            })
+            .buffer_unordered(STATS_CONCURRENCY)
+            .try_fold(DatabaseStats::default(), |db_stats, stats| async move {
+                Ok(match stats {
+                    Some(stats) => db_stats.add_table(&stats),
+                    None => db_stats,
+                })
+            })
+            .await
+    }
+
+    async fn drop_db(&self) -> Result<()> {
+        self.ensure_writable("drop the database")?;
+        self.table_cache.clear();
         self.object_store
             .remove_dir_all(self.base_path.clone())
             .await?;
@@ -1058,12 +2056,15 @@ impl ConnectionInternal for Database {
 
 #[cfg(test)]
 mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float32Type, Int32Type};
+    use arrow_array::{FixedSizeListArray, Int32Array, RecordBatch, StringArray};
     use arrow_schema::{DataType, Field, Schema};
     use futures::TryStreamExt;
-    use lance_testing::datagen::{BatchGenerator, IncrementingInt32};
+    use lance_testing::datagen::{BatchGenerator, IncrementingInt32, RandomVector};
     use tempfile::tempdir;
 
-    use crate::query::{ExecutableQuery, QueryExecutionOptions};
+    use crate::query::{ExecutableQuery, QueryBase, QueryExecutionOptions};
 
     use super::*;
 
@@ -1139,9 +2140,334 @@ mod tests {
         assert_eq!(tables, names[..7]);
     }
 
+    #[tokio::test]
+    async fn test_table_names_start_after_boundaries() {
+        let tmp_dir = tempdir().unwrap();
+        let names = ["table_1", "table_2", "table_3"];
+        for name in names {
+            create_dir_all(tmp_dir.path().join(format!("{}.lance", name))).unwrap();
+        }
+
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+
+        let tables = db
+            .table_names()
+            .start_after(names[2])
+            .execute()
+            .await
+            .unwrap();
+        assert!(tables.is_empty());
+
+        let tables = db.table_names().start_after("").execute().await.unwrap();
+        assert_eq!(tables, names);
+    }
+
+    #[tokio::test]
+    async fn test_table_names_prefix() {
+        let tmp_dir = tempdir().unwrap();
+        let names = ["alpha_1", "alpha_2", "alpha_3", "beta_1", "beta_2"];
+        for name in names {
+            create_dir_all(tmp_dir.path().join(format!("{}.lance", name))).unwrap();
+        }
+
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+
+        let tables = db.table_names().prefix("alpha").execute().await.unwrap();
+        assert_eq!(tables, names[..3]);
+
+        let tables = db.table_names().prefix("gamma").execute().await.unwrap();
+        assert!(tables.is_empty());
+
+        // The prefix is applied before start_after / limit
+        let tables = db
+            .table_names()
+            .prefix("alpha")
+            .start_after("alpha_1")
+            .limit(1)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(tables, ["alpha_2"]);
+
+        let tables = db
+            .table_names()
+            .prefix("beta")
+            .start_after("alpha_3")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(tables, names[3..]);
+    }
+
+    /// Serves an in-memory S3 compatible store over HTTP and records each request as
+    /// `METHOD path authorization`
+    ///
+    /// Only the requests made by lance are supported: simple and multipart uploads,
+    /// copies, deletes, (range) reads and listings.
+    fn mock_s3() -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        use std::collections::BTreeMap;
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = requests.clone();
+        std::thread::spawn(move || {
+            let mut objects = BTreeMap::<String, Vec<u8>>::new();
+            let mut uploads = HashMap::<String, BTreeMap<usize, Vec<u8>>>::new();
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let target = parts.next().unwrap_or_default().to_string();
+                let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+                let key = path.trim_start_matches('/').to_string();
+                let query = url::form_urlencoded::parse(query.as_bytes())
+                    .into_owned()
+                    .collect::<HashMap<_, _>>();
+                let mut headers = HashMap::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    let (name, value) = header.split_once(':').unwrap();
+                    headers.insert(name.to_lowercase(), value.trim().to_string());
+                }
+                let mut body = Vec::new();
+                let content_length = headers
+                    .get("content-length")
+                    .map_or(0, |length| length.parse().unwrap());
+                reader
+                    .by_ref()
+                    .take(content_length)
+                    .read_to_end(&mut body)
+                    .unwrap();
+                log.lock().unwrap().push(format!(
+                    "{} {} {}",
+                    method,
+                    path,
+                    headers.get("authorization").cloned().unwrap_or_default()
+                ));
+
+                let etag = "ETag: \"etag\"\r\nLast-Modified: Mon, 01 Jan 2024 00:00:00 GMT\r\n";
+                let (status, headers, body) = match method.as_str() {
+                    "GET" if query.contains_key("list-type") => {
+                        let bucket = format!("{}/", key.trim_end_matches('/'));
+                        let prefix = query.get("prefix").cloned().unwrap_or_default();
+                        let delimiter = query.get("delimiter").cloned();
+                        let start_after = query.get("start-after").cloned().unwrap_or_default();
+                        let mut xml = "<ListBucketResult>".to_string();
+                        let mut common_prefixes = Vec::<String>::new();
+                        for (name, data) in objects.iter() {
+                            let Some(name) = name.strip_prefix(&bucket) else {
+                                continue;
+                            };
+                            let Some(rest) = name.strip_prefix(&prefix) else {
+                                continue;
+                            };
+                            if name <= start_after.as_str() {
+                                continue;
+                            }
+                            match delimiter.as_deref().and_then(|d| rest.split_once(d)) {
+                                Some((dir, _)) => {
+                                    let common_prefix = format!("{}{}/", prefix, dir);
+                                    if !common_prefixes.contains(&common_prefix) {
+                                        common_prefixes.push(common_prefix);
+                                    }
+                                }
+                                None => xml.push_str(&format!(
+                                    "<Contents><Key>{}</Key><Size>{}</Size>\
+                                     <LastModified>2024-01-01T00:00:00.000Z</LastModified>\
+                                     </Contents>",
+                                    name,
+                                    data.len()
+                                )),
+                            }
+                        }
+                        for common_prefix in common_prefixes {
+                            xml.push_str(&format!(
+                                "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+                                common_prefix
+                            ));
+                        }
+                        xml.push_str("</ListBucketResult>");
+                        ("200 OK", String::new(), xml.into_bytes())
+                    }
+                    "GET" | "HEAD" => match objects.get(&key) {
+                        Some(data) => {
+                            let len = data.len();
+                            let range = headers.get("range");
+                            match range.and_then(|r| r.strip_prefix("bytes=")) {
+                                Some(range) => {
+                                    let (start, end) = range.split_once('-').unwrap();
+                                    let start = start.parse::<usize>().unwrap_or(0);
+                                    let end = end.parse::<usize>().map_or(len, |end| end + 1);
+                                    let end = end.min(len);
+                                    let headers = format!(
+                                        "{}Content-Range: bytes {}-{}/{}\r\n",
+                                        etag,
+                                        start,
+                                        end - 1,
+                                        len
+                                    );
+                                    ("206 Partial Content", headers, data[start..end].to_vec())
+                                }
+                                None => ("200 OK", etag.to_string(), data.clone()),
+                            }
+                        }
+                        None => ("404 Not Found", String::new(), Vec::new()),
+                    },
+                    "PUT" if query.contains_key("uploadId") => {
+                        let part = query["partNumber"].parse().unwrap();
+                        uploads
+                            .entry(query["uploadId"].clone())
+                            .or_default()
+                            .insert(part, body);
+                        ("200 OK", etag.to_string(), Vec::new())
+                    }
+                    "PUT" => {
+                        let data = match headers.get("x-amz-copy-source") {
+                            Some(source) => objects[source.trim_start_matches('/')].clone(),
+                            None => body,
+                        };
+                        objects.insert(key, data);
+                        let xml = "<CopyObjectResult></CopyObjectResult>";
+                        ("200 OK", etag.to_string(), xml.as_bytes().to_vec())
+                    }
+                    "POST" if query.contains_key("uploads") => {
+                        let upload_id = uploads.len().to_string();
+                        uploads.insert(upload_id.clone(), BTreeMap::new());
+                        let xml = format!(
+                            "<InitiateMultipartUploadResult><UploadId>{}</UploadId>\
+                             </InitiateMultipartUploadResult>",
+                            upload_id
+                        );
+                        ("200 OK", String::new(), xml.into_bytes())
+                    }
+                    "POST" if query.contains_key("uploadId") => {
+                        let parts = uploads.remove(&query["uploadId"]).unwrap();
+                        objects.insert(key, parts.into_values().flatten().collect());
+                        let xml = "<CompleteMultipartUploadResult><ETag>\"etag\"</ETag>\
+                                   </CompleteMultipartUploadResult>";
+                        ("200 OK", String::new(), xml.as_bytes().to_vec())
+                    }
+                    "DELETE" => {
+                        objects.remove(&key);
+                        ("204 No Content", String::new(), Vec::new())
+                    }
+                    _ => ("400 Bad Request", String::new(), Vec::new()),
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    headers,
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).unwrap();
+                if method != "HEAD" {
+                    stream.write_all(&body).unwrap();
+                }
+            }
+        });
+        (url, requests)
+    }
+
     #[tokio::test]
     async fn test_connect_s3() {
-        // let db = Database::connect("s3://bucket/path/to/database").await.unwrap();
+        // An S3 compatible store (e.g. MinIO) is configured with the storage options
+        let (url, requests) = mock_s3();
+        let db = connect("s3://bucket/path/db")
+            .storage_options([
+                ("endpoint", url.as_str()),
+                ("region", "eu-west-1"),
+                ("allow_http", "true"),
+                ("aws_access_key_id", "my-key-id"),
+                ("aws_secret_access_key", "my-secret-key"),
+            ])
+            .execute()
+            .await
+            .unwrap();
+        db.create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(db.table_names().execute().await.unwrap(), vec!["test"]);
+        let tbl = db.open_table("test").execute().await.unwrap();
+        assert_eq!(tbl.count_rows(None).await.unwrap(), 20000);
+        tbl.add(make_data()).execute().await.unwrap();
+        assert_eq!(tbl.count_rows(None).await.unwrap(), 40000);
+
+        // The requests use path style addressing and are signed with the credentials
+        // and the region from the options
+        let requests = requests.lock().unwrap().clone();
+        assert!(!requests.is_empty());
+        for request in &requests {
+            let (_, request) = request.split_once(' ').unwrap();
+            assert!(request.starts_with("/bucket"), "{}", request);
+            assert!(
+                request.contains("Credential=my-key-id/") && request.contains("/eu-west-1/s3/"),
+                "{}",
+                request
+            );
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn test_export_parquet_storage_options() {
+        let (url, requests) = mock_s3();
+        let db = connect("s3://bucket/db")
+            .storage_options([
+                ("endpoint", url.as_str()),
+                ("region", "eu-west-1"),
+                ("allow_http", "true"),
+                ("aws_access_key_id", "my-key-id"),
+                ("aws_secret_access_key", "my-secret-key"),
+            ])
+            .execute()
+            .await
+            .unwrap();
+        let tbl = db
+            .create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+
+        // The options of the connection are used by default
+        requests.lock().unwrap().clear();
+        let num_rows = tbl
+            .export_parquet("s3://bucket/export/all.parquet", Default::default())
+            .await
+            .unwrap();
+        assert_eq!(num_rows, 20000);
+        let log = std::mem::take(&mut *requests.lock().unwrap());
+        assert!(log.iter().any(|r| r.contains("/bucket/export/all.parquet")));
+        assert!(log.iter().all(|r| r.contains("Credential=my-key-id/")));
+
+        // An explicit option wins, the missing ones are still inherited
+        let options = crate::io::parquet::ParquetWriteOptions {
+            storage_options: HashMap::from([(
+                "aws_access_key_id".to_string(),
+                "other-key-id".to_string(),
+            )]),
+            ..Default::default()
+        };
+        tbl.export_parquet("s3://bucket/export/other.parquet", options)
+            .await
+            .unwrap();
+        let log = std::mem::take(&mut *requests.lock().unwrap());
+        assert!(log
+            .iter()
+            .any(|r| r.contains("/bucket/export/other.parquet")
+                && r.contains("Credential=other-key-id/")
+                && r.contains("/eu-west-1/s3/")));
     }
 
     #[tokio::test]
@@ -1169,6 +2495,229 @@ mod tests {
         assert_eq!(tables, vec!["table1".to_owned()]);
     }
 
+    #[tokio::test]
+    async fn test_table_cache() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).table_cache_size(2).execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        for name in ["a", "b", "c"] {
+            db.create_empty_table(name, schema.clone())
+                .execute()
+                .await
+                .unwrap();
+        }
+        let open = |name: &'static str| db.open_table(name).execute();
+        let stats = |hits, refreshes| TableCacheStats { hits, refreshes };
+
+        let a = open("a").await.unwrap();
+        assert_eq!(db.table_cache_stats(), stats(0, 1));
+        open("a").await.unwrap();
+        assert_eq!(db.table_cache_stats(), stats(1, 1));
+
+        // Writes through one handle are seen by the tables opened later
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![1]))])
+            .unwrap();
+        a.add(Box::new(RecordBatchIterator::new(
+            vec![Ok(batch)],
+            schema.clone(),
+        )))
+        .execute()
+        .await
+        .unwrap();
+        let a2 = open("a").await.unwrap();
+        assert_eq!(db.table_cache_stats(), stats(1, 2));
+        assert_eq!(a2.count_rows(None).await.unwrap(), 1);
+        assert_eq!(a2.version().await.unwrap(), 2);
+
+        // Every caller has its own handle, checking out a version only affects that one
+        a.checkout(1).await.unwrap();
+        assert_eq!(a2.version().await.unwrap(), 2);
+        let a3 = open("a").await.unwrap();
+        assert_eq!(db.table_cache_stats(), stats(2, 2));
+        assert_eq!(a3.version().await.unwrap(), 2);
+        assert_eq!(a3.count_rows(None).await.unwrap(), 1);
+        a.checkout_latest().await.unwrap();
+
+        // Tables opened with custom parameters are not cached
+        db.open_table("a")
+            .index_cache_size(16)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(db.table_cache_stats(), stats(2, 2));
+
+        // The least recently opened table is evicted
+        open("b").await.unwrap();
+        open("c").await.unwrap();
+        assert_eq!(db.table_cache_stats(), stats(2, 4));
+        open("a").await.unwrap();
+        assert_eq!(db.table_cache_stats(), stats(2, 5));
+        open("c").await.unwrap();
+        assert_eq!(db.table_cache_stats(), stats(3, 5));
+
+        db.invalidate_table_cache("c");
+        open("c").await.unwrap();
+        assert_eq!(db.table_cache_stats(), stats(3, 6));
+
+        // Dropping the table removes it from the cache
+        db.drop_table("a").await.unwrap();
+        assert!(matches!(
+            open("a").await,
+            Err(crate::Error::TableNotFound { .. })
+        ));
+
+        // The cache is disabled by default
+        let db = connect(uri).execute().await.unwrap();
+        db.open_table("c").execute().await.unwrap();
+        db.open_table("c").execute().await.unwrap();
+        assert_eq!(db.table_cache_stats(), TableCacheStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_database_stats() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        assert_eq!(db.database_stats().await.unwrap(), DatabaseStats::default());
+
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        let make_data = |num_rows: i32| {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(0..num_rows))],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let small = db
+            .create_table("small", make_data(10))
+            .execute()
+            .await
+            .unwrap();
+        let large = db
+            .create_table("large", make_data(1000))
+            .execute()
+            .await
+            .unwrap();
+        large.delete("x < 100").await.unwrap();
+        large
+            .create_index(&["x"], crate::index::Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        let small_stats = small.stats().await.unwrap();
+        let large_stats = large.stats().await.unwrap();
+        let stats = db.database_stats().await.unwrap();
+        assert_eq!(stats.num_tables, 2);
+        assert_eq!(stats.num_rows, 910);
+        assert_eq!(
+            stats.data_bytes,
+            small_stats.data_bytes + large_stats.data_bytes
+        );
+        assert!(stats.data_bytes > 0);
+        assert_eq!(stats.index_bytes, large_stats.index_bytes);
+        assert!(stats.index_bytes > 0);
+        assert_eq!(stats.total_bytes(), stats.data_bytes + stats.index_bytes);
+    }
+
+    #[tokio::test]
+    async fn test_create_empty_table_with_vectors() {
+        let tmp_dir = tempdir().unwrap();
+        let db = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+
+        let vector_type =
+            DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), 4);
+        let metadata = HashMap::from([(
+            "embedding_function".to_string(),
+            r#"{"name": "openai", "source_column": "text"}"#.to_string(),
+        )]);
+        let schema = Arc::new(
+            Schema::new(vec![
+                Field::new("id", DataType::Int32, false),
+                Field::new("text", DataType::Utf8, true),
+                Field::new("vector", vector_type.clone(), true).with_metadata(metadata.clone()),
+            ])
+            .with_metadata(HashMap::from([("owner".to_string(), "me".to_string())])),
+        );
+        let table = db
+            .create_empty_table("vectors", schema.clone())
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 0);
+        let stored = table.schema().await.unwrap();
+        assert_eq!(
+            stored.field_with_name("vector").unwrap().metadata(),
+            &metadata
+        );
+        assert_eq!(stored.metadata().get("owner").unwrap(), "me");
+
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(
+                    FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                        vec![
+                            Some(vec![Some(1.0); 4]),
+                            Some(vec![Some(0.0), Some(1.0), Some(0.0), Some(1.0)]),
+                        ],
+                        4,
+                    ),
+                ),
+            ],
+        )
+        .unwrap();
+        table
+            .add(RecordBatchIterator::new(vec![Ok(batch)], schema.clone()))
+            .execute()
+            .await
+            .unwrap();
+        let table = db.open_table("vectors").execute().await.unwrap();
+        let batches = table
+            .query()
+            .nearest_to(&[0.0, 1.0, 0.0, 1.0])
+            .unwrap()
+            .limit(1)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches[0]["id"].as_primitive::<Int32Type>().values(), &[2]);
+
+        let err = db
+            .create_empty_table("no_fields", Arc::new(Schema::empty()))
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::Schema { .. }), "{}", err);
+        let duplicate = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("id", DataType::Utf8, false),
+        ]));
+        let err = db
+            .create_empty_table("duplicate", duplicate)
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("more than one field named id"),
+            "{}",
+            err
+        );
+        assert_eq!(db.table_names().execute().await.unwrap(), vec!["vectors"]);
+    }
+
     fn make_data() -> impl RecordBatchReader + Send + 'static {
         let id = Box::new(IncrementingInt32::new().named("id".to_string()));
         BatchGenerator::new().col(id).batches(10, 2000)
@@ -1241,6 +2790,221 @@ mod tests {
         assert_eq!(tables.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_rename_table() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+
+        let make_batches = || {
+            BatchGenerator::new()
+                .col(Box::new(IncrementingInt32::new().named("i".to_string())))
+                .batch(10)
+        };
+        let table = db
+            .create_table("old", make_batches())
+            .execute()
+            .await
+            .unwrap();
+        table.add(make_batches()).execute().await.unwrap();
+        table
+            .create_index(&["i"], crate::index::Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+        let version = table.version().await.unwrap();
+        db.create_table("other", make_batches())
+            .execute()
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            db.rename_table("missing", "new").await,
+            Err(crate::Error::TableNotFound { .. })
+        ));
+        assert!(matches!(
+            db.rename_table("old", "other").await,
+            Err(crate::Error::TableAlreadyExists { .. })
+        ));
+
+        db.rename_table("old", "new").await.unwrap();
+        assert_eq!(
+            db.table_names().execute().await.unwrap(),
+            vec!["new".to_string(), "other".to_string()]
+        );
+        assert!(matches!(
+            db.open_table("old").execute().await,
+            Err(crate::Error::TableNotFound { .. })
+        ));
+
+        let table = db.open_table("new").execute().await.unwrap();
+        assert_eq!(table.version().await.unwrap(), version);
+        assert_eq!(table.count_rows(None).await.unwrap(), 20);
+        let indices = table.list_indices().await.unwrap();
+        assert_eq!(indices.len(), 1);
+        assert_eq!(indices[0].columns, vec!["i".to_string()]);
+
+        table.checkout(1).await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_rename_table_file_uri() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = url::Url::from_directory_path(tmp_dir.path()).unwrap();
+        let db = connect(uri.as_str()).execute().await.unwrap();
+
+        let batches = BatchGenerator::new()
+            .col(Box::new(IncrementingInt32::new().named("i".to_string())))
+            .batch(10);
+        db.create_table("old", batches).execute().await.unwrap();
+
+        db.rename_table("old", "new").await.unwrap();
+        assert_eq!(
+            db.table_names().execute().await.unwrap(),
+            vec!["new".to_string()]
+        );
+        assert!(tmp_dir.path().join("new.lance").is_dir());
+        assert!(!tmp_dir.path().join("old.lance").exists());
+        let table = db.open_table("new").execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_clone_table() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+
+        let make_batches = || {
+            BatchGenerator::new()
+                .col(Box::new(IncrementingInt32::new().named("i".to_string())))
+                .batch(10)
+        };
+        let source = db
+            .create_table("source", make_batches())
+            .execute()
+            .await
+            .unwrap();
+        source.delete("i < 2").await.unwrap();
+        source
+            .create_index(&["i"], crate::index::Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+        let version = source.version().await.unwrap();
+
+        assert!(matches!(
+            db.clone_table("missing", "clone").await,
+            Err(crate::Error::TableNotFound { .. })
+        ));
+        assert!(matches!(
+            db.clone_table("source", "source").await,
+            Err(crate::Error::TableAlreadyExists { .. })
+        ));
+
+        db.clone_table("source", "clone").await.unwrap();
+        assert_eq!(
+            db.table_names().execute().await.unwrap(),
+            vec!["clone".to_string(), "source".to_string()]
+        );
+        let clone = db.open_table("clone").execute().await.unwrap();
+        assert_eq!(clone.version().await.unwrap(), version);
+        assert_eq!(clone.count_rows(None).await.unwrap(), 8);
+        assert_eq!(clone.list_indices().await.unwrap().len(), 1);
+
+        // Mutating the clone does not change the source
+        clone.add(make_batches()).execute().await.unwrap();
+        clone.add(make_batches()).execute().await.unwrap();
+        assert_eq!(clone.count_rows(None).await.unwrap(), 28);
+        let source = db.open_table("source").execute().await.unwrap();
+        assert_eq!(source.version().await.unwrap(), version);
+        assert_eq!(source.count_rows(None).await.unwrap(), 8);
+
+        // Nor does mutating the source change the clone
+        source.add(make_batches()).execute().await.unwrap();
+        assert_eq!(source.count_rows(None).await.unwrap(), 18);
+        let clone = db.open_table("clone").execute().await.unwrap();
+        assert_eq!(clone.count_rows(None).await.unwrap(), 28);
+
+        // The clone survives dropping the source
+        db.drop_table("source").await.unwrap();
+        assert_eq!(clone.count_rows(None).await.unwrap(), 28);
+        clone.checkout(1).await.unwrap();
+        assert_eq!(clone.count_rows(None).await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_clone_table_file_uri() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = url::Url::from_directory_path(tmp_dir.path()).unwrap();
+        let db = connect(uri.as_str()).execute().await.unwrap();
+
+        let batches = BatchGenerator::new()
+            .col(Box::new(IncrementingInt32::new().named("i".to_string())))
+            .batch(10);
+        let source = db.create_table("source", batches).execute().await.unwrap();
+
+        db.clone_table("source", "clone").await.unwrap();
+        assert_eq!(
+            db.table_names().execute().await.unwrap(),
+            vec!["clone".to_string(), "source".to_string()]
+        );
+        let clone = db.open_table("clone").execute().await.unwrap();
+        assert_eq!(clone.count_rows(None).await.unwrap(), 10);
+
+        // The data files are hard linked into the clone
+        let data_files = |table: &str| {
+            std::fs::read_dir(tmp_dir.path().join(table).join("data"))
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect::<Vec<_>>()
+        };
+        let source_files = data_files("source.lance");
+        assert_eq!(source_files.len(), 1);
+        let clone_files = data_files("clone.lance");
+        assert_eq!(clone_files.len(), 1);
+        assert_eq!(source_files[0].file_name(), clone_files[0].file_name());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            assert_eq!(
+                std::fs::metadata(&source_files[0]).unwrap().ino(),
+                std::fs::metadata(&clone_files[0]).unwrap().ino()
+            );
+        }
+
+        source.delete("i < 5").await.unwrap();
+        assert_eq!(clone.count_rows(None).await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_drop_all_tables() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        for name in ["table1", "table2", "table3"] {
+            db.create_empty_table(name, schema.clone())
+                .execute()
+                .await
+                .unwrap();
+        }
+
+        assert!(matches!(
+            db.drop_all_tables(DropAllOptions::default()).await,
+            Err(crate::Error::InvalidInput { .. }),
+        ));
+        assert_eq!(db.table_names().execute().await.unwrap().len(), 3);
+
+        let dropped = db
+            .drop_all_tables(DropAllOptions { confirm: true })
+            .await
+            .unwrap();
+        assert_eq!(dropped, 3);
+        assert_eq!(db.table_names().execute().await.unwrap().len(), 0);
+    }
+
     #[tokio::test]
     async fn test_create_table_already_exists() {
         let tmp_dir = tempdir().unwrap();
@@ -1266,6 +3030,16 @@ mod tests {
             .execute()
             .await
             .is_err());
+        // ExistOk fails if the existing table has a different schema
+        let err = db
+            .create_empty_table("test", other_schema.clone())
+            .mode(CreateTableMode::exist_ok(|builder| builder))
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::Schema { .. }), "{}", err);
+        assert!(err.to_string().contains("[x: Int32]"), "{}", err);
         let overwritten = db
             .create_empty_table("test", other_schema.clone())
             .mode(CreateTableMode::Overwrite)
@@ -1274,4 +3048,432 @@ mod tests {
             .unwrap();
         assert_eq!(other_schema, overwritten.schema().await.unwrap());
     }
+
+    /// Removes the environment variables starting with `prefix`, the object store
+    /// builders read their configuration from them before the storage options
+    fn clear_env(prefix: &str) {
+        for (key, _) in std::env::vars_os() {
+            if key.to_str().is_some_and(|key| key.starts_with(prefix)) {
+                std::env::remove_var(key);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_gcs() {
+        clear_env("GOOGLE_");
+        // The scheme selects the object store, no requests are made until a
+        // table is opened or created
+        let builder = ConnectBuilder::new("gs://my-bucket/path/db");
+        let db = Database::connect_with_options(&builder).await.unwrap();
+        assert_eq!(
+            db.object_store.inner.to_string(),
+            "PatchedGoogleCloudStorage(GoogleCloudStorage(my-bucket))"
+        );
+        assert_eq!(db.base_path.to_string(), "path/db");
+
+        // The storage options are passed to the object store
+        let builder = ConnectBuilder::new("gs://my-bucket/path/db")
+            .storage_option("google_service_account", "/does/not/exist.json");
+        let err = Database::connect_with_options(&builder)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("/does/not/exist.json"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_connect_azure() {
+        clear_env("AZURE_");
+        let builder = ConnectBuilder::new("az://my-container/path/db").storage_options([
+            ("azure_storage_account_name", "myaccount"),
+            ("azure_storage_account_key", "YWNjb3VudGtleQ=="),
+        ]);
+        let db = Database::connect_with_options(&builder).await.unwrap();
+        assert_eq!(
+            db.object_store.inner.to_string(),
+            "MicrosoftAzure { account: myaccount, container: my-container }"
+        );
+        assert_eq!(db.base_path.to_string(), "path/db");
+        // The options are inherited by the tables
+        assert_eq!(
+            db.storage_options.get("azure_storage_account_name"),
+            Some(&"myaccount".to_string())
+        );
+
+        // The account is required
+        let builder = ConnectBuilder::new("az://my-container/path/db");
+        assert!(Database::connect_with_options(&builder).await.is_err());
+    }
+
+    /// Serves the files in `root` over HTTP, with range requests and `PROPFIND`
+    /// listings, and records each request as `METHOD path [range]`
+    fn serve_dir(root: std::path::PathBuf) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        use std::io::{BufRead, BufReader, Write};
+
+        fn list(dir: &Path, recursive: bool, out: &mut Vec<std::path::PathBuf>) {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() && recursive {
+                    list(&path, recursive, out);
+                } else {
+                    out.push(path);
+                }
+            }
+        }
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+                let (mut range, mut depth) = (None, None);
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    let (name, value) = header.split_once(':').unwrap();
+                    match name.to_lowercase().as_str() {
+                        "range" => range = Some(value.trim().to_string()),
+                        "depth" => depth = Some(value.trim().to_string()),
+                        _ => {}
+                    }
+                }
+                log.lock().unwrap().push(format!(
+                    "{} {} {}",
+                    method,
+                    path,
+                    range.clone().unwrap_or_default()
+                ));
+
+                let file = root.join(path.trim_start_matches('/'));
+                let (status, headers, body) = if method == "PROPFIND" && file.is_dir() {
+                    let mut paths = Vec::new();
+                    list(&file, depth.as_deref() != Some("1"), &mut paths);
+                    let mut xml = r#"<?xml version="1.0"?><multistatus xmlns="DAV:">"#.to_string();
+                    for path in paths {
+                        let href = format!("/{}", path.strip_prefix(&root).unwrap().display());
+                        let prop = if path.is_dir() {
+                            "<resourcetype><collection/></resourcetype>".to_string()
+                        } else {
+                            format!(
+                                "<resourcetype/><getcontentlength>{}</getcontentlength>",
+                                path.metadata().unwrap().len()
+                            )
+                        };
+                        xml.push_str(&format!(
+                            "<response><href>{}</href><propstat><prop>\
+                             <getlastmodified>Mon, 01 Jan 2024 00:00:00 GMT</getlastmodified>\
+                             {}</prop><status>HTTP/1.1 200 OK</status></propstat></response>",
+                            href, prop
+                        ));
+                    }
+                    xml.push_str("</multistatus>");
+                    ("207 Multi-Status", String::new(), xml.into_bytes())
+                } else if (method == "GET" || method == "HEAD") && file.is_file() {
+                    let data = std::fs::read(&file).unwrap();
+                    let len = data.len();
+                    match range.as_deref().and_then(|r| r.strip_prefix("bytes=")) {
+                        Some(range) => {
+                            let (start, end) = range.split_once('-').unwrap();
+                            let (start, end) = match (start.parse::<usize>(), end.parse::<usize>())
+                            {
+                                (Ok(start), Ok(end)) => (start, (end + 1).min(len)),
+                                (Ok(start), Err(_)) => (start, len),
+                                (Err(_), Ok(suffix)) => (len - suffix.min(len), len),
+                                _ => (0, len),
+                            };
+                            let headers =
+                                format!("Content-Range: bytes {}-{}/{}\r\n", start, end - 1, len);
+                            ("206 Partial Content", headers, data[start..end].to_vec())
+                        }
+                        None => ("200 OK", String::new(), data),
+                    }
+                } else {
+                    ("404 Not Found", String::new(), Vec::new())
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    headers,
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).unwrap();
+                if method != "HEAD" {
+                    stream.write_all(&body).unwrap();
+                }
+            }
+        });
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_connect_http() {
+        let tmp_dir = tempdir().unwrap();
+        let db_dir = tmp_dir.path().join("db");
+        let db = connect(db_dir.to_str().unwrap()).execute().await.unwrap();
+        db.create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+
+        let (url, requests) = serve_dir(tmp_dir.path().to_path_buf());
+        let db = connect(&format!("{}/db", url)).execute().await.unwrap();
+        assert_eq!(db.table_names().execute().await.unwrap(), vec!["test"]);
+        requests.lock().unwrap().clear();
+        let tbl = db.open_table("test").execute().await.unwrap();
+        assert_eq!(tbl.count_rows(None).await.unwrap(), 20000);
+        assert_eq!(
+            tbl.count_rows(Some("id < 100".to_string())).await.unwrap(),
+            100
+        );
+
+        // Only listing the tables needs PROPFIND, the latest version of the table
+        // is found from its _latest.manifest
+        let requests = requests.lock().unwrap().clone();
+        assert!(
+            requests.iter().all(|r| !r.starts_with("PROPFIND ")),
+            "{:?}",
+            requests
+        );
+        assert!(
+            requests
+                .iter()
+                .any(|r| r.starts_with("GET ") && r.contains("/_latest.manifest")),
+            "{:?}",
+            requests
+        );
+
+        // The data files are read with range requests
+        let data_reads = requests
+            .iter()
+            .filter(|r| r.starts_with("GET ") && r.contains("/data/"))
+            .collect::<Vec<_>>();
+        assert!(!data_reads.is_empty(), "{:?}", requests);
+        assert!(
+            data_reads.iter().all(|r| r.contains("bytes=")),
+            "{:?}",
+            requests
+        );
+
+        // The database is read-only
+        let err = tbl.add(make_data()).execute().await.unwrap_err();
+        assert!(matches!(err, Error::ReadOnly { .. }), "{}", err);
+        let err = db
+            .create_table("other", make_data())
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::ReadOnly { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        db.create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+
+        let db = connect(uri).read_only(true).execute().await.unwrap();
+        let tbl = db.open_table("test").execute().await.unwrap();
+
+        let err = tbl.add(make_data()).execute().await.unwrap_err();
+        assert!(matches!(err, crate::Error::ReadOnly { .. }), "{}", err);
+        let err = tbl
+            .create_index(&["id"], crate::index::Index::Auto)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::ReadOnly { .. }), "{}", err);
+        assert!(matches!(
+            tbl.delete("id > 0").await,
+            Err(crate::Error::ReadOnly { .. })
+        ));
+        assert!(matches!(
+            db.create_table("other", make_data()).execute().await,
+            Err(crate::Error::ReadOnly { .. })
+        ));
+        assert!(matches!(
+            db.drop_table("test").await,
+            Err(crate::Error::ReadOnly { .. })
+        ));
+
+        // Nothing was written and reads still work
+        assert_eq!(tbl.version().await.unwrap(), 1);
+        assert!(tbl.list_indices().await.unwrap().is_empty());
+        let batches = tbl
+            .query()
+            .only_if("id < 10")
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+        assert_eq!(db.table_names().execute().await.unwrap(), vec!["test"]);
+    }
+
+    #[tokio::test]
+    async fn test_table_read_consistency_interval() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let writer = connect(uri).execute().await.unwrap();
+        let written = writer
+            .create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+
+        let reader = connect(uri)
+            .read_consistency_interval(std::time::Duration::from_secs(3600))
+            .execute()
+            .await
+            .unwrap();
+        let stale = reader.open_table("test").execute().await.unwrap();
+        let latest = reader
+            .open_table("test")
+            .read_consistency_interval(std::time::Duration::ZERO)
+            .execute()
+            .await
+            .unwrap();
+        let eventual = reader
+            .open_table("test")
+            .read_consistency_interval(std::time::Duration::from_secs(2))
+            .execute()
+            .await
+            .unwrap();
+        let rows = stale.count_rows(None).await.unwrap();
+        assert_eq!(latest.count_rows(None).await.unwrap(), rows);
+        assert_eq!(eventual.count_rows(None).await.unwrap(), rows);
+
+        written.add(make_data()).execute().await.unwrap();
+        assert_eq!(latest.count_rows(None).await.unwrap(), rows * 2);
+        assert_eq!(eventual.count_rows(None).await.unwrap(), rows);
+        assert_eq!(stale.count_rows(None).await.unwrap(), rows);
+
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        assert_eq!(eventual.count_rows(None).await.unwrap(), rows * 2);
+        // The connection's interval has not elapsed yet
+        assert_eq!(stale.count_rows(None).await.unwrap(), rows);
+    }
+
+    #[tokio::test]
+    async fn test_lazy_indices() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let vector = Box::new(RandomVector::new().named("vector".to_string()));
+        let id = Box::new(IncrementingInt32::new().named("id".to_string()));
+        let tbl = db
+            .create_table(
+                "test",
+                BatchGenerator::new().col(vector).col(id).batches(4, 256),
+            )
+            .execute()
+            .await
+            .unwrap();
+        tbl.create_index(&["vector"], crate::index::Index::Auto)
+            .execute()
+            .await
+            .unwrap();
+        tbl.create_index(&["id"], crate::index::Index::Auto)
+            .execute()
+            .await
+            .unwrap();
+
+        for lazy_indices in [true, false] {
+            let tbl = db
+                .open_table("test")
+                .lazy_indices(lazy_indices)
+                .execute()
+                .await
+                .unwrap();
+            let query = tbl
+                .query()
+                .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+                .unwrap()
+                .only_if("id < 100")
+                .limit(5);
+            let plan = query.explain_plan(false).await.unwrap();
+            assert!(plan.contains("ANNSubIndex"), "{}", plan);
+            assert!(plan.contains("ScalarIndexQuery"), "{}", plan);
+            let batches = query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+            assert_eq!(tbl.list_indices().await.unwrap().len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory() {
+        let db = connect("memory://").execute().await.unwrap();
+        let vector = Box::new(RandomVector::new().named("vector".to_string()));
+        let id = Box::new(IncrementingInt32::new().named("id".to_string()));
+        let tbl = db
+            .create_table(
+                "test",
+                BatchGenerator::new().col(vector).col(id).batches(4, 256),
+            )
+            .execute()
+            .await
+            .unwrap();
+        tbl.create_index(&["vector"], crate::index::Index::Auto)
+            .execute()
+            .await
+            .unwrap();
+        tbl.create_index(&["id"], crate::index::Index::Auto)
+            .execute()
+            .await
+            .unwrap();
+
+        // The table can be opened again through the connection
+        assert_eq!(db.table_names().execute().await.unwrap(), vec!["test"]);
+        let tbl = db.open_table("test").execute().await.unwrap();
+        assert_eq!(tbl.list_indices().await.unwrap().len(), 2);
+        let batches = tbl
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .only_if("id < 100")
+            .limit(5)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+
+        // Other connections have their own database
+        let other = connect("memory://").execute().await.unwrap();
+        assert!(other.table_names().execute().await.unwrap().is_empty());
+        assert!(matches!(
+            other.open_table("test").execute().await,
+            Err(crate::Error::TableNotFound { .. })
+        ));
+
+        db.drop_table("test").await.unwrap();
+        assert!(db.table_names().execute().await.unwrap().is_empty());
+        assert!(!Path::new("memory:").exists());
+    }
 }
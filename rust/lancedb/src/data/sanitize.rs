@@ -14,14 +14,17 @@
 
 use std::{iter::repeat_with, sync::Arc};
 
+use arrow::buffer::NullBuffer;
+use arrow::compute::filter_record_batch;
 use arrow_array::{
     cast::AsArray,
     types::{Float16Type, Float32Type, Float64Type, Int32Type, Int64Type},
-    Array, ArrowNumericType, FixedSizeListArray, PrimitiveArray, RecordBatch, RecordBatchIterator,
-    RecordBatchReader,
+    Array, ArrayRef, ArrowNumericType, BinaryArray, BinaryViewArray, BooleanArray,
+    FixedSizeListArray, Float64Array, PrimitiveArray, RecordBatch, RecordBatchIterator,
+    RecordBatchReader, StringArray, StringViewArray,
 };
 use arrow_cast::{can_cast_types, cast};
-use arrow_schema::{ArrowError, DataType, Field, Schema};
+use arrow_schema::{ArrowError, DataType, Field, FieldRef, Schema};
 use half::f16;
 use lance::arrow::{DataTypeExt, FixedSizeListArrayExt};
 use log::warn;
@@ -188,6 +191,264 @@ pub fn coerce_schema(
     Ok(Box::new(RecordBatchIterator::new(batches, schema)))
 }
 
+fn view_to_offsets_type(data_type: &DataType) -> Option<DataType> {
+    match data_type {
+        DataType::Utf8View => Some(DataType::Utf8),
+        DataType::BinaryView => Some(DataType::Binary),
+        _ => None,
+    }
+}
+
+fn cast_view_array(array: &Arc<dyn Array>) -> Arc<dyn Array> {
+    match array.data_type() {
+        DataType::Utf8View => {
+            let array = array.as_any().downcast_ref::<StringViewArray>().unwrap();
+            Arc::new(array.iter().collect::<StringArray>())
+        }
+        DataType::BinaryView => {
+            let array = array.as_any().downcast_ref::<BinaryViewArray>().unwrap();
+            Arc::new(array.iter().collect::<BinaryArray>())
+        }
+        _ => array.clone(),
+    }
+}
+
+/// Convert any top level `Utf8View` and `BinaryView` columns to `Utf8` and `Binary`.
+///
+/// Lance cannot store the view types yet, so they are written as the equivalent
+/// offset based types.  The values are unchanged but scans will return `Utf8` and
+/// `Binary` columns.  Readers without view columns are returned unchanged.
+pub fn coerce_view_types(
+    reader: impl RecordBatchReader + Send + 'static,
+) -> Box<dyn RecordBatchReader + Send> {
+    let schema = reader.schema();
+    if !schema
+        .fields()
+        .iter()
+        .any(|f| view_to_offsets_type(f.data_type()).is_some())
+    {
+        return Box::new(reader);
+    }
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|f| match view_to_offsets_type(f.data_type()) {
+            Some(data_type) => Arc::new(f.as_ref().clone().with_data_type(data_type)),
+            None => f.clone(),
+        })
+        .collect::<Vec<_>>();
+    let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+    let s = schema.clone();
+    let batches = reader.map(move |batch| {
+        let columns = batch?.columns().iter().map(cast_view_array).collect();
+        RecordBatch::try_new(s.clone(), columns)
+    });
+    Box::new(RecordBatchIterator::new(batches, schema))
+}
+
+/// What to do with a vector that has NaN, infinite or null values, or the wrong
+/// number of values, when adding data to a table
+///
+/// See [`crate::table::AddDataBuilder::on_bad_vector`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BadVectorMode {
+    /// Fail the write
+    Error,
+    /// Skip the rows with a bad vector
+    Drop,
+    /// Replace the bad values of a vector with the given value
+    ///
+    /// A vector with the wrong number of values is replaced by a vector that
+    /// has the given value in every position.
+    Fill(f32),
+}
+
+/// The values of each row of a list column, flattened to f64
+struct FlatVectors {
+    values: Float64Array,
+    /// The range of `values` of each row, None for a null row
+    rows: Vec<Option<(usize, usize)>>,
+}
+
+fn flatten_vectors(array: &ArrayRef) -> std::result::Result<Option<FlatVectors>, ArrowError> {
+    let (values, rows) = match array.data_type() {
+        DataType::FixedSizeList(_, dim) => {
+            let list = array.as_fixed_size_list();
+            let dim = *dim as usize;
+            let rows = (0..list.len())
+                .map(|i| list.is_valid(i).then(|| (i * dim, (i + 1) * dim)))
+                .collect::<Vec<_>>();
+            let values = list.values().slice(list.offset() * dim, list.len() * dim);
+            (values, rows)
+        }
+        DataType::List(_) => {
+            let list = array.as_list::<i32>();
+            let offsets = list.offsets();
+            let first = offsets[0] as usize;
+            let rows = (0..list.len())
+                .map(|i| {
+                    list.is_valid(i)
+                        .then(|| (offsets[i] as usize - first, offsets[i + 1] as usize - first))
+                })
+                .collect();
+            let last = offsets[list.len()] as usize;
+            (list.values().slice(first, last - first), rows)
+        }
+        DataType::LargeList(_) => {
+            let list = array.as_list::<i64>();
+            let offsets = list.offsets();
+            let first = offsets[0] as usize;
+            let rows = (0..list.len())
+                .map(|i| {
+                    list.is_valid(i)
+                        .then(|| (offsets[i] as usize - first, offsets[i + 1] as usize - first))
+                })
+                .collect();
+            let last = offsets[list.len()] as usize;
+            (list.values().slice(first, last - first), rows)
+        }
+        _ => return Ok(None),
+    };
+    if !values.data_type().is_numeric() {
+        return Ok(None);
+    }
+    let values = cast(&values, &DataType::Float64)?
+        .as_primitive::<Float64Type>()
+        .clone();
+    Ok(Some(FlatVectors { values, rows }))
+}
+
+/// Check the vectors of one column and return the rows to keep and the new column
+fn handle_bad_vector_column(
+    array: &ArrayRef,
+    field: &Field,
+    target: &Field,
+    mode: BadVectorMode,
+    keep: &mut [bool],
+) -> std::result::Result<ArrayRef, ArrowError> {
+    let DataType::FixedSizeList(item, dim) = target.data_type() else {
+        unreachable!("only fixed size list columns are checked")
+    };
+    let dim = *dim as usize;
+    let Some(vectors) = flatten_vectors(array)? else {
+        // Not a list of numbers, leave it for the write to reject
+        return Ok(array.clone());
+    };
+
+    let mut values = Vec::with_capacity(vectors.rows.len() * dim);
+    for (row, range) in vectors.rows.iter().enumerate() {
+        let Some((start, end)) = *range else {
+            values.extend(std::iter::repeat(0.0).take(dim));
+            continue;
+        };
+        let vector = vectors.values.slice(start, end - start);
+        let problem = if vector.len() != dim {
+            Some(format!("has {} values instead of {}", vector.len(), dim))
+        } else if vector
+            .iter()
+            .any(|v| !v.map(f64::is_finite).unwrap_or(false))
+        {
+            Some("has NaN, infinite or null values".to_string())
+        } else {
+            None
+        };
+        match (problem, mode) {
+            (None, _) => values.extend(vector.values().iter()),
+            (Some(problem), BadVectorMode::Error) => {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "the vector in row {} of column {} {}",
+                    row,
+                    field.name(),
+                    problem
+                )));
+            }
+            (Some(_), BadVectorMode::Drop) => {
+                keep[row] = false;
+                values.extend(std::iter::repeat(0.0).take(dim));
+            }
+            (Some(_), BadVectorMode::Fill(fill)) => {
+                if vector.len() == dim {
+                    values.extend(vector.iter().map(|v| match v {
+                        Some(v) if v.is_finite() => v,
+                        _ => fill as f64,
+                    }));
+                } else {
+                    values.extend(std::iter::repeat(fill as f64).take(dim));
+                }
+            }
+        }
+    }
+
+    let values = cast(&Float64Array::from(values), item.data_type())?;
+    let nulls = NullBuffer::from(vectors.rows.iter().map(Option::is_some).collect::<Vec<_>>());
+    Ok(Arc::new(FixedSizeListArray::try_new(
+        item.clone(),
+        dim as i32,
+        values,
+        Some(nulls).filter(|nulls| nulls.null_count() > 0),
+    )?))
+}
+
+/// Check the vector columns of the new data with the given [BadVectorMode]
+///
+/// The vector columns are the fixed size list columns of `schema`, the schema of
+/// the table.  The new data may have these columns as fixed size lists of any
+/// size or as variable size lists, and they are converted to the type of the
+/// column in `schema`.  Other columns are not changed.
+pub fn handle_bad_vectors(
+    reader: impl RecordBatchReader + Send + 'static,
+    schema: &Schema,
+    mode: BadVectorMode,
+) -> Result<Box<dyn RecordBatchReader + Send>> {
+    let input_schema = reader.schema();
+    let mut targets: Vec<(usize, FieldRef)> = Vec::new();
+    let fields = input_schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| match schema.field_with_name(field.name()) {
+            Ok(target) if matches!(target.data_type(), DataType::FixedSizeList(item, _) if item.data_type().is_floating()) =>
+            {
+                let target =
+                    Arc::new(field.as_ref().clone().with_data_type(target.data_type().clone()));
+                targets.push((idx, target.clone()));
+                target
+            }
+            _ => field.clone(),
+        })
+        .collect::<Vec<_>>();
+    if targets.is_empty() {
+        return Ok(Box::new(reader));
+    }
+    let output_schema = Arc::new(Schema::new_with_metadata(
+        fields,
+        input_schema.metadata().clone(),
+    ));
+
+    let s = output_schema.clone();
+    let batches = reader.map(move |batch| {
+        let batch = batch?;
+        let mut keep = vec![true; batch.num_rows()];
+        let mut columns = batch.columns().to_vec();
+        for (idx, target) in &targets {
+            columns[*idx] = handle_bad_vector_column(
+                &columns[*idx],
+                batch.schema().field(*idx),
+                target,
+                mode,
+                &mut keep,
+            )?;
+        }
+        let batch = RecordBatch::try_new(s.clone(), columns)?;
+        if keep.iter().all(|keep| *keep) {
+            Ok(batch)
+        } else {
+            filter_record_batch(&batch, &BooleanArray::from(keep))
+        }
+    });
+    Ok(Box::new(RecordBatchIterator::new(batches, output_schema)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
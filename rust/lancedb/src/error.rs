@@ -26,11 +26,19 @@ pub enum Error {
     InvalidInput { message: String },
     #[snafu(display("Table '{name}' was not found"))]
     TableNotFound { name: String },
+    #[snafu(display("Index '{name}' was not found"))]
+    IndexNotFound { name: String },
+    #[snafu(display(
+        "More than one vector column found, please specify which column to query with `column`: {candidates:?}"
+    ))]
+    AmbiguousVectorColumn { candidates: Vec<String> },
     #[snafu(display("Embedding function '{name}' was not found. : {reason}"))]
     EmbeddingFunctionNotFound { name: String, reason: String },
 
     #[snafu(display("Table '{name}' already exists"))]
     TableAlreadyExists { name: String },
+    #[snafu(display("Read-only connection: {message}"))]
+    ReadOnly { message: String },
     #[snafu(display("Unable to created lance dataset at {path}: {source}"))]
     CreateDir {
         path: String,
@@ -40,6 +48,8 @@ pub enum Error {
     Schema { message: String },
     #[snafu(display("Runtime error: {message}"))]
     Runtime { message: String },
+    #[snafu(display("Timeout: {message}"))]
+    Timeout { message: String },
 
     // 3rd party / external errors
     #[snafu(display("object_store error: {source}"))]
@@ -76,6 +86,15 @@ impl From<lance::Error> for Error {
     }
 }
 
+impl From<datafusion_common::DataFusionError> for Error {
+    fn from(source: datafusion_common::DataFusionError) -> Self {
+        Self::Other {
+            message: "Error in DataFusion execution plan.".to_string(),
+            source: Some(Box::new(source)),
+        }
+    }
+}
+
 impl From<object_store::Error> for Error {
     fn from(source: object_store::Error) -> Self {
         Self::ObjectStore { source }
@@ -125,3 +144,13 @@ impl From<polars::prelude::PolarsError> for Error {
         }
     }
 }
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(source: parquet::errors::ParquetError) -> Self {
+        Self::Other {
+            message: "Error reading or writing Parquet file.".to_string(),
+            source: Some(Box::new(source)),
+        }
+    }
+}
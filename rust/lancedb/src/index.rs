@@ -89,6 +89,13 @@ pub struct IndexConfig {
     /// Currently this is always a Vec of size 1.  In the future there may
     /// be more columns to represent composite indices.
     pub columns: Vec<String>,
+    /// The number of rows covered by the index
+    ///
+    /// Rows added after the index was created are not indexed until the index
+    /// is optimized (see [`crate::table::OptimizeAction::Index`]).
+    pub num_indexed_rows: usize,
+    /// The version of the table when the index was last created or updated
+    pub dataset_version: u64,
 }
 
 #[skip_serializing_none]
@@ -96,12 +103,34 @@ pub struct IndexConfig {
 pub struct IndexMetadata {
     pub metric_type: Option<String>,
     pub index_type: Option<String>,
+    /// The number of IVF partitions (only set for IVF based vector indices)
+    pub num_partitions: Option<usize>,
+    /// The index used to search each IVF partition (only set for IVF based vector indices)
+    pub sub_index: Option<SubIndexMetadata>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Deserialize)]
+pub struct SubIndexMetadata {
+    pub index_type: Option<String>,
+    /// The number of sub vectors (only set for PQ)
+    pub num_sub_vectors: Option<u32>,
+    /// The number of bits each sub vector is quantized to (only set for PQ)
+    pub nbits: Option<u32>,
 }
 
+/// Statistics about an index, see [`crate::Table::index_stats`]
 #[skip_serializing_none]
 #[derive(Debug, Deserialize)]
 pub struct IndexStatistics {
+    /// The number of rows covered by the index
     pub num_indexed_rows: usize,
+    /// The number of rows that are not covered by the index
+    ///
+    /// Rows added after the index was built are not indexed.  Searches still
+    /// find these rows, by scanning them, but this gets slower as the number of
+    /// unindexed rows grows.  Optimizing the index (see
+    /// [`crate::table::OptimizeAction::Index`]) adds them to the index.
     pub num_unindexed_rows: usize,
     pub index_type: Option<String>,
     pub indices: Vec<IndexMetadata>,
@@ -22,6 +22,10 @@
 /// This index is good for scalar columns with mostly distinct values and does best when
 /// the query is highly selective.
 ///
+/// The btree index can also be used for equality and `IN` filters on low cardinality
+/// columns, such as a category with a few distinct values.  A bitmap index, which is
+/// better suited to these columns, is not available yet.
+///
 /// The btree index does not currently have any parameters though parameters such as the
 /// block size may be added in the future.
 #[derive(Default, Debug, Clone)]
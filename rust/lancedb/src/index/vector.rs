@@ -21,7 +21,7 @@ use std::cmp::max;
 
 use lance::table::format::{Index, Manifest};
 
-use crate::DistanceType;
+use crate::{DistanceType, Error, Result};
 
 pub struct VectorIndex {
     pub columns: Vec<String>,
@@ -54,8 +54,8 @@ macro_rules! impl_distance_type_setter {
         /// grouped in partitions with similar vectors according to this distance type) and to
         /// calculate a subvector's code during quantization.
         ///
-        /// The metric type used to train an index MUST match the metric type used to search the
-        /// index.  Failure to do so will yield inaccurate results.
+        /// The index can only be used by queries with the same distance type.  A query that
+        /// requests a different distance type falls back to a (much slower) flat search.
         pub fn distance_type(mut self, distance_type: DistanceType) -> Self {
             self.distance_type = distance_type;
             self
@@ -128,10 +128,23 @@ macro_rules! impl_pq_params_setter {
         ///
         /// If the dimension is not visible by 8 then we use 1 subvector.  This is not ideal and
         /// will likely result in poor performance.
+        ///
+        /// The dimension of the vector must be divisible by the number of sub-vectors.  If it
+        /// is not then an error will be returned when the index is created.
         pub fn num_sub_vectors(mut self, num_sub_vectors: u32) -> Self {
             self.num_sub_vectors = Some(num_sub_vectors);
             self
         }
+
+        /// Number of bits used to encode each sub-vector.
+        ///
+        /// Each sub-vector is replaced by the index of its closest centroid.  There are
+        /// 2^num_bits centroids per sub-vector.  Fewer bits give a smaller index but less
+        /// accurate distances.  The value must be between 1 and 8.  The default is 8.
+        pub fn num_bits(mut self, num_bits: u32) -> Self {
+            self.num_bits = num_bits;
+            self
+        }
     };
 }
 
@@ -189,6 +202,7 @@ pub struct IvfPqIndexBuilder {
 
     // PQ
     pub(crate) num_sub_vectors: Option<u32>,
+    pub(crate) num_bits: u32,
 }
 
 impl Default for IvfPqIndexBuilder {
@@ -197,6 +211,7 @@ impl Default for IvfPqIndexBuilder {
             distance_type: DistanceType::L2,
             num_partitions: None,
             num_sub_vectors: None,
+            num_bits: 8,
             sample_rate: 256,
             max_iterations: 50,
         }
@@ -229,6 +244,34 @@ pub(crate) fn suggested_num_sub_vectors(dim: u32) -> u32 {
     }
 }
 
+/// Check that the IVF and PQ parameters are valid for vectors of the given dimension
+pub(crate) fn validate_ivf_pq_params(
+    dim: u32,
+    num_partitions: u32,
+    num_sub_vectors: u32,
+    num_bits: u32,
+) -> Result<()> {
+    if num_partitions == 0 {
+        return Err(Error::InvalidInput {
+            message: "num_partitions must be greater than 0".to_string(),
+        });
+    }
+    if num_sub_vectors == 0 || dim % num_sub_vectors != 0 {
+        return Err(Error::InvalidInput {
+            message: format!(
+                "the vector dimension ({}) must be divisible by num_sub_vectors ({})",
+                dim, num_sub_vectors
+            ),
+        });
+    }
+    if !(1..=8).contains(&num_bits) {
+        return Err(Error::InvalidInput {
+            message: format!("num_bits must be between 1 and 8 but was {}", num_bits),
+        });
+    }
+    Ok(())
+}
+
 /// Builder for an IVF HNSW PQ index.
 ///
 /// This index is a combination of IVF and HNSW.
@@ -251,6 +294,7 @@ pub struct IvfHnswPqIndexBuilder {
 
     // PQ
     pub(crate) num_sub_vectors: Option<u32>,
+    pub(crate) num_bits: u32,
 }
 
 impl Default for IvfHnswPqIndexBuilder {
@@ -259,6 +303,7 @@ impl Default for IvfHnswPqIndexBuilder {
             distance_type: DistanceType::L2,
             num_partitions: None,
             num_sub_vectors: None,
+            num_bits: 8,
             sample_rate: 256,
             max_iterations: 50,
             m: 20,
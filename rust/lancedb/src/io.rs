@@ -1 +1,5 @@
+pub mod http;
+pub mod memory;
 pub mod object_store;
+#[cfg(feature = "parquet")]
+pub mod parquet;
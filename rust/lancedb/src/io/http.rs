@@ -0,0 +1,129 @@
+// Copyright 2024 LanceDB Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A read-only object store for databases served over HTTP(S)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use lance::io::ObjectStore as LanceObjectStore;
+use lance_table::format::{Index, Manifest};
+use lance_table::io::commit::{
+    latest_manifest_path, manifest_path, CommitError, CommitHandler, ManifestLocation,
+    ManifestWriter,
+};
+use lance_table::io::manifest::read_manifest;
+use object_store::http::HttpBuilder;
+use object_store::path::Path;
+use object_store::{ClientConfigKey, ObjectStore};
+use snafu::{location, Location};
+use url::Url;
+
+use crate::error::Result;
+
+/// Returns true if the URL refers to a database served over HTTP(S)
+pub fn is_http_url(url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https")
+}
+
+/// Creates an object store that reads from the server of `url`
+///
+/// Lance does not support `http://` and `https://` URIs and so this store is given to
+/// lance as a custom object store.  The store is created for the origin of the URL (the
+/// path is part of the object paths).  Objects are read with `GET` requests for byte
+/// ranges, so only the parts of the files needed by a query are downloaded.  Directories
+/// are listed with WebDAV `PROPFIND` requests, which are only needed to list the tables
+/// of the database and the versions of a table.  Opening a table does not list anything
+/// when it uses [`HttpCommitHandler`].
+///
+/// Storage options that are HTTP client options (e.g. `timeout` or `user_agent`) are
+/// applied to the client, any other options are ignored.
+pub fn new_http_store(
+    url: &Url,
+    storage_options: &HashMap<String, String>,
+) -> Result<Arc<dyn ObjectStore>> {
+    let mut builder = HttpBuilder::new()
+        .with_url(url.origin().ascii_serialization())
+        .with_config(
+            ClientConfigKey::AllowHttp,
+            (url.scheme() == "http").to_string(),
+        );
+    for (key, value) in storage_options {
+        if let Ok(key) = key.parse::<ClientConfigKey>() {
+            builder = builder.with_config(key, value);
+        }
+    }
+    Ok(Arc::new(builder.build()?))
+}
+
+/// The commit handler for tables served over HTTP(S)
+///
+/// The default handlers find the latest version of a table by listing its `_versions`
+/// directory, which most HTTP servers do not support.  Every commit also copies the new
+/// manifest to `_latest.manifest`, so this handler reads the version from that file
+/// with plain `GET` requests instead.  The tables are read-only and commits fail.
+#[derive(Debug)]
+pub struct HttpCommitHandler;
+
+#[async_trait::async_trait]
+impl CommitHandler for HttpCommitHandler {
+    async fn resolve_latest_location(
+        &self,
+        base_path: &Path,
+        object_store: &LanceObjectStore,
+    ) -> lance::Result<ManifestLocation> {
+        let manifest = read_manifest(object_store, &latest_manifest_path(base_path)).await?;
+        Ok(ManifestLocation {
+            version: manifest.version,
+            path: manifest_path(base_path, manifest.version),
+            size: None,
+        })
+    }
+
+    async fn resolve_latest_version(
+        &self,
+        base_path: &Path,
+        object_store: &LanceObjectStore,
+    ) -> std::result::Result<Path, lance::Error> {
+        Ok(self
+            .resolve_latest_location(base_path, object_store)
+            .await?
+            .path)
+    }
+
+    async fn resolve_latest_version_id(
+        &self,
+        base_path: &Path,
+        object_store: &LanceObjectStore,
+    ) -> lance::Result<u64> {
+        Ok(self
+            .resolve_latest_location(base_path, object_store)
+            .await?
+            .version)
+    }
+
+    async fn commit(
+        &self,
+        _manifest: &mut Manifest,
+        _indices: Option<Vec<Index>>,
+        _base_path: &Path,
+        _object_store: &dyn ObjectStore,
+        _manifest_writer: ManifestWriter,
+    ) -> std::result::Result<(), CommitError> {
+        Err(CommitError::OtherError(lance::Error::NotSupported {
+            source: "tables served over HTTP(S) are read-only".into(),
+            location: location!(),
+        }))
+    }
+}
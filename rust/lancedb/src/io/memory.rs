@@ -0,0 +1,37 @@
+// Copyright 2024 LanceDB Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An in-memory object store that is shared by every table of a connection
+
+use std::sync::Arc;
+
+use lance::io::WrappingObjectStore;
+use object_store::{memory::InMemory, ObjectStore};
+
+/// Replaces the object store of every dataset with the same in-memory store
+///
+/// Lance creates a new, empty, in-memory store each time a `memory://` URI is
+/// opened, so a table written through one store cannot be opened again.  A
+/// connection to a `memory://` database shares a single store, which lives as
+/// long as the connection and the tables opened from it.
+#[derive(Debug, Default)]
+pub struct SharedMemoryStoreWrapper {
+    store: Arc<InMemory>,
+}
+
+impl WrappingObjectStore for SharedMemoryStoreWrapper {
+    fn wrap(&self, _original: Arc<dyn ObjectStore>) -> Arc<dyn ObjectStore> {
+        self.store.clone()
+    }
+}
@@ -0,0 +1,462 @@
+// Copyright 2024 LanceDB Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reading and writing Parquet files on an object store
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::{StreamExt, TryStreamExt};
+use lance::io::{ObjectStore, ObjectStoreParams};
+use object_store::path::Path;
+use object_store::ObjectMeta;
+use parquet::arrow::async_reader::{ParquetObjectReader, ParquetRecordBatchStreamBuilder};
+use parquet::arrow::AsyncArrowWriter;
+use parquet::file::properties::WriterProperties;
+
+pub use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+
+use crate::arrow::{SendableRecordBatchStream, SimpleRecordBatchStream};
+use crate::error::{Error, Result};
+
+const PARQUET_EXTENSION: &str = "parquet";
+
+/// Options for writing Parquet files
+///
+/// Used by [`crate::Table::export_parquet`] and
+/// [`crate::query::ExecutableQuery::write_parquet`].
+#[derive(Clone, Debug, Default)]
+pub struct ParquetWriteOptions {
+    /// The maximum number of rows in each row group
+    ///
+    /// Readers can skip whole row groups using their statistics, and decode them in
+    /// parallel, but each row group must fit in the memory of the writer.  The
+    /// default is 1,048,576 rows.
+    pub max_row_group_size: Option<usize>,
+    /// The compression of the column chunks, the default is uncompressed
+    pub compression: Option<Compression>,
+    /// Options to configure the object store the file is written to, e.g. the
+    /// credentials of an S3 bucket
+    ///
+    /// An option that is not set here is taken from the storage options of the
+    /// table, like [`crate::Connection::create_table_from_parquet`] reads with the
+    /// storage options of the connection.
+    pub storage_options: HashMap<String, String>,
+}
+
+/// Opens a stream of the rows of the Parquet files at `uri`
+///
+/// `uri` is a file, a directory (all of the `.parquet` files under it are read) or a
+/// glob pattern, where `*` and `?` match within a path segment, `**` matches any
+/// number of segments and `[...]` matches a set of characters.  The files are read one after the other in the order of their
+/// paths and must all have the same schema.
+pub(crate) async fn read_parquet(
+    uri: &str,
+    storage_options: &HashMap<String, String>,
+) -> Result<SendableRecordBatchStream> {
+    let (base_uri, pattern) = split_glob(uri);
+    let params = ObjectStoreParams {
+        storage_options: Some(storage_options.clone()),
+        ..Default::default()
+    };
+    let (store, base_path) = ObjectStore::from_uri_and_params(base_uri, &params).await?;
+    let store = store.inner;
+
+    let mut files = match &pattern {
+        Some(pattern) => {
+            let pattern = glob_pattern(pattern)?;
+            list(&store, &base_path)
+                .await?
+                .into_iter()
+                .filter(|meta| glob_matches(&pattern, &base_path, &meta.location))
+                .collect()
+        }
+        None => match store.head(&base_path).await {
+            Ok(meta) => vec![meta],
+            Err(object_store::Error::NotFound { .. }) => list(&store, &base_path)
+                .await?
+                .into_iter()
+                .filter(|meta| meta.location.extension() == Some(PARQUET_EXTENSION))
+                .collect(),
+            Err(err) => return Err(err.into()),
+        },
+    };
+    if files.is_empty() {
+        return Err(Error::InvalidInput {
+            message: format!("no Parquet files found at {}", uri),
+        });
+    }
+    files.sort_by(|a, b| a.location.cmp(&b.location));
+
+    let mut builders = Vec::with_capacity(files.len());
+    for meta in files {
+        let location = meta.location.clone();
+        let reader = ParquetObjectReader::new(store.clone(), meta);
+        builders.push((
+            location,
+            ParquetRecordBatchStreamBuilder::new(reader).await?,
+        ));
+    }
+    let schema = builders[0].1.schema().clone();
+    for (location, builder) in &builders[1..] {
+        if builder.schema().fields() != schema.fields() {
+            return Err(Error::Schema {
+                message: format!(
+                    "the schema of {} does not match the schema of {}: {:?} != {:?}",
+                    location,
+                    builders[0].0,
+                    builder.schema(),
+                    schema
+                ),
+            });
+        }
+    }
+
+    let streams = builders
+        .into_iter()
+        .map(|(_, builder)| builder.build().map_err(Error::from))
+        .collect::<Result<Vec<_>>>()?;
+    let stream = futures::stream::iter(streams)
+        .flatten()
+        .map_err(Error::from);
+    Ok(Box::pin(SimpleRecordBatchStream::new(stream, schema)))
+}
+
+/// Writes the rows of `stream` to a single Parquet file at `uri`
+///
+/// An existing file is replaced.  Returns the number of rows written.
+pub(crate) async fn write_parquet(
+    mut stream: SendableRecordBatchStream,
+    uri: &str,
+    options: &ParquetWriteOptions,
+) -> Result<usize> {
+    let params = ObjectStoreParams {
+        storage_options: Some(options.storage_options.clone()),
+        ..Default::default()
+    };
+    let (store, path) = ObjectStore::from_uri_and_params(uri, &params).await?;
+    let mut properties = WriterProperties::builder();
+    if let Some(max_row_group_size) = options.max_row_group_size {
+        properties = properties.set_max_row_group_size(max_row_group_size);
+    }
+    if let Some(compression) = options.compression {
+        properties = properties.set_compression(compression);
+    }
+
+    let (multipart_id, sink) = store.inner.put_multipart(&path).await?;
+    let write = async {
+        let mut writer =
+            AsyncArrowWriter::try_new(sink, stream.schema(), Some(properties.build()))?;
+        let mut num_rows = 0;
+        while let Some(batch) = stream.try_next().await? {
+            num_rows += batch.num_rows();
+            writer.write(&batch).await?;
+        }
+        writer.close().await?;
+        Ok(num_rows)
+    };
+    let result = write.await;
+    if result.is_err() {
+        // Don't leave the parts that were uploaded behind
+        let _ = store.inner.abort_multipart(&path, &multipart_id).await;
+    }
+    result
+}
+
+async fn list(
+    store: &Arc<dyn object_store::ObjectStore>,
+    prefix: &Path,
+) -> Result<Vec<ObjectMeta>> {
+    Ok(store.list(Some(prefix)).try_collect().await?)
+}
+
+/// Splits `uri` into the directory before the first segment with a glob character and
+/// the rest of the pattern
+fn split_glob(uri: &str) -> (&str, Option<&str>) {
+    let Some(first_glob) = uri.find(['*', '?', '[']) else {
+        return (uri, None);
+    };
+    match uri[..first_glob].rfind('/') {
+        Some(slash) => (&uri[..slash], Some(&uri[slash + 1..])),
+        None => (".", Some(uri)),
+    }
+}
+
+/// Parses the glob `pattern`, matched against object paths relative to the directory
+/// the pattern starts in
+///
+/// `*` and `?` do not match `/` while `**` matches any number of directories (including
+/// none).  `[...]` matches one of the characters (or ranges, e.g. `[0-9]`) and `[!...]`
+/// any other character.
+fn glob_pattern(pattern: &str) -> Result<glob::Pattern> {
+    glob::Pattern::new(pattern).map_err(|err| Error::InvalidInput {
+        message: format!("invalid glob pattern {}: {}", pattern, err),
+    })
+}
+
+const GLOB_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Whether the object at `location` under `base` matches the glob `pattern`
+fn glob_matches(pattern: &glob::Pattern, base: &Path, location: &Path) -> bool {
+    match location.prefix_match(base) {
+        Some(parts) => {
+            let relative = parts
+                .map(|part| part.as_ref().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            pattern.matches_with(&relative, GLOB_OPTIONS)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float32Type, Int32Type};
+    use arrow_array::{ArrayRef, FixedSizeListArray, Int32Array, RecordBatch};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::arrow::ArrowWriter;
+
+    use super::*;
+    use crate::connect;
+    use crate::query::{ExecutableQuery, QueryBase, Select};
+
+    fn write_parquet(path: &std::path::Path, ids: std::ops::Range<i32>) {
+        let vectors = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            ids.clone().map(|i| Some(vec![Some(i as f32), Some(0.0)])),
+            2,
+        );
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from_iter_values(ids)) as ArrayRef,
+            ),
+            ("vector", Arc::new(vectors) as ArrayRef),
+        ])
+        .unwrap();
+        let file = std::fs::File::create(path).unwrap();
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_table_from_parquet() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_dir = tmp_dir.path().join("data");
+        std::fs::create_dir_all(data_dir.join("more")).unwrap();
+        write_parquet(&data_dir.join("part-0.parquet"), 0..10);
+        write_parquet(&data_dir.join("part-1.parquet"), 10..20);
+        write_parquet(&data_dir.join("more").join("part-2.parquet"), 20..30);
+        std::fs::write(data_dir.join("_SUCCESS"), "").unwrap();
+        let db = connect(tmp_dir.path().join("db").to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+
+        let from_file = db
+            .create_table_from_parquet("file", data_dir.join("part-1.parquet").to_str().unwrap())
+            .await
+            .unwrap()
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(from_file.count_rows(None).await.unwrap(), 10);
+        // The vector column keeps its type and can be searched
+        assert_eq!(
+            from_file.schema().await.unwrap().field(1).data_type(),
+            &arrow_schema::DataType::FixedSizeList(
+                Arc::new(arrow_schema::Field::new(
+                    "item",
+                    arrow_schema::DataType::Float32,
+                    true
+                )),
+                2
+            )
+        );
+        let batch = from_file
+            .query()
+            .nearest_to(&[12.2, 0.0])
+            .unwrap()
+            .limit(1)
+            .execute()
+            .await
+            .unwrap()
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch["id"].as_primitive::<Int32Type>().values(), &[12]);
+
+        let from_dir = db
+            .create_table_from_parquet("dir", data_dir.to_str().unwrap())
+            .await
+            .unwrap()
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(from_dir.count_rows(None).await.unwrap(), 30);
+
+        let glob = format!("{}/part-*.parquet", data_dir.to_str().unwrap());
+        let from_glob = db
+            .create_table_from_parquet("glob", &glob)
+            .await
+            .unwrap()
+            .execute()
+            .await
+            .unwrap();
+        let ids = from_glob
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .iter()
+            .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, (0..20).collect::<Vec<_>>());
+
+        let missing = format!("{}/*.csv", data_dir.to_str().unwrap());
+        let err = db
+            .create_table_from_parquet("missing", &missing)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_glob() {
+        assert_eq!(split_glob("/data/files"), ("/data/files", None));
+        assert_eq!(
+            split_glob("s3://bucket/data/part-*.parquet"),
+            ("s3://bucket/data", Some("part-*.parquet"))
+        );
+        assert_eq!(
+            split_glob("/data/**/*.parquet"),
+            ("/data", Some("**/*.parquet"))
+        );
+
+        let base = Path::from("data");
+        let matches = |pattern: &str, location: &str| {
+            glob_matches(
+                &glob_pattern(pattern).unwrap(),
+                &base,
+                &Path::from(location),
+            )
+        };
+        assert!(matches("part-?.parquet", "data/part-1.parquet"));
+        assert!(!matches("part-?.parquet", "data/part-10.parquet"));
+        assert!(!matches("part-?.parquet", "data/sub/part-1.parquet"));
+        assert!(!matches("part-?.parquet", "other/part-1.parquet"));
+
+        assert!(matches("**/*.parquet", "data/a.parquet"));
+        assert!(matches("**/*.parquet", "data/x/y/a.parquet"));
+        assert!(!matches("**/*.parquet", "data/a.parquet.crc"));
+
+        assert!(matches("part-[0-9].parquet", "data/part-7.parquet"));
+        assert!(!matches("part-[0-9].parquet", "data/part-a.parquet"));
+        assert!(matches("part-[!0].parquet", "data/part-1.parquet"));
+        assert!(!matches("part-[!0].parquet", "data/part-0.parquet"));
+        assert!(glob_pattern("part-[.parquet").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_parquet() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let input = tmp_dir.path().join("input.parquet");
+        write_parquet(&input, 0..100);
+        let db = connect(tmp_dir.path().join("db").to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let table = db
+            .create_table_from_parquet("t", input.to_str().unwrap())
+            .await
+            .unwrap()
+            .execute()
+            .await
+            .unwrap();
+
+        let output = tmp_dir.path().join("out").join("query.parquet");
+        let options = ParquetWriteOptions {
+            max_row_group_size: Some(8),
+            compression: Some(Compression::ZSTD(ZstdLevel::default())),
+            ..Default::default()
+        };
+        let num_rows = table
+            .query()
+            .only_if("id >= 70")
+            .select(Select::columns(&["id"]))
+            .write_parquet(output.to_str().unwrap(), options)
+            .await
+            .unwrap();
+        assert_eq!(num_rows, 30);
+
+        let builder =
+            ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&output).unwrap())
+                .unwrap();
+        let metadata = builder.metadata().clone();
+        assert_eq!(metadata.file_metadata().num_rows(), 30);
+        assert_eq!(metadata.num_row_groups(), 4);
+        assert!(matches!(
+            metadata.row_group(0).column(0).compression(),
+            Compression::ZSTD(_)
+        ));
+        assert_eq!(
+            builder.schema().as_ref(),
+            &arrow_schema::Schema::new(vec![arrow_schema::Field::new(
+                "id",
+                arrow_schema::DataType::Int32,
+                false
+            )])
+        );
+        let ids = builder
+            .build()
+            .unwrap()
+            .map(|batch| {
+                batch.unwrap()["id"]
+                    .as_primitive::<Int32Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        assert_eq!(ids, (70..100).collect::<Vec<_>>());
+
+        // The whole table, which can be imported again
+        let output = tmp_dir.path().join("table.parquet");
+        let num_rows = table
+            .export_parquet(output.to_str().unwrap(), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(num_rows, 100);
+        let copy = db
+            .create_table_from_parquet("copy", output.to_str().unwrap())
+            .await
+            .unwrap()
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(copy.schema().await.unwrap(), table.schema().await.unwrap());
+        assert_eq!(copy.count_rows(None).await.unwrap(), 100);
+    }
+}
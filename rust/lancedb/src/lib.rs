@@ -43,6 +43,12 @@
 //!
 //! - `remote` - Enable remote client to connect to LanceDB cloud.  This is not yet fully implemented
 //!              and should not be enabled.
+//! - `serde` - Create tables from structs that implement `Serialize` with
+//!             `arrow::SerdeRecordBatchReader`.
+//! - `parquet` - Create tables from Parquet files with
+//!               `Connection::create_table_from_parquet` and export tables and
+//!               query results with `Table::export_parquet` and
+//!               `ExecutableQuery::write_parquet`.
 //!
 //! ### Quick Start
 //!
@@ -58,8 +64,9 @@
 //! LanceDB accepts the different form of database path:
 //!
 //! - `/path/to/database` - local database on file system.
-//! - `s3://bucket/path/to/database` or `gs://bucket/path/to/database` - database on cloud object store
+//! - `s3://bucket/path/to/database`, `gs://bucket/path/to/database` or `az://container/path/to/database` - database on cloud object store
 //! - `db://dbname` - Lance Cloud
+//! - `https://host/path/to/database` - read-only database served over HTTP(S)
 //!
 //! You can also use [`ConnectOptions`] to configure the connection to the database.
 //!
@@ -204,6 +211,9 @@ mod polars_arrow_convertors;
 pub mod query;
 #[cfg(feature = "remote")]
 pub(crate) mod remote;
+pub mod rerankers;
+#[cfg(feature = "serde")]
+mod serde_arrow_convertors;
 pub mod table;
 pub mod utils;
 
@@ -233,13 +243,30 @@ pub enum DistanceType {
     /// Note: the cosine distance is undefined when one (or both) of the vectors
     /// are all zeros (there is no direction).  These vectors are invalid and may
     /// never be returned from a vector search.
+    ///
+    /// Note: vector indices trained with cosine distance normalize the stored
+    /// vectors when the index is built, and the query vector is normalized once
+    /// per query, so an indexed search does not recompute the norms of the stored
+    /// vectors.  A flat search (when there is no index, or for rows that have not
+    /// been indexed yet) computes the norms on the fly.
     Cosine,
     /// Dot product. Dot distance is the dot product of two vectors. Dot
     /// distance has a range of (-∞, ∞). If the vectors are normalized (i.e. their
     /// L2 norm is 1), then dot distance is equivalent to the cosine distance.
+    ///
+    /// The vectors are not normalized.  This is used for maximum inner product
+    /// search, where a larger dot product is a better match.  So that results are
+    /// still sorted from smallest to largest distance the `_distance` column is
+    /// `1 - dot(query, vector)`, which means the largest dot products come first
+    /// and `limit`, `nprobes`, and `refine_factor` behave as they do for the other
+    /// distance types.
     Dot,
     /// Hamming distance. Hamming distance is a distance metric that measures
     /// the number of positions at which the corresponding elements are different.
+    ///
+    /// This is used to search binary vectors (fixed size lists of u8), where it is
+    /// the number of bits that differ.  It cannot be used with floating point vectors
+    /// or to train a vector index.
     Hamming,
 }
 
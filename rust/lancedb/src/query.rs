@@ -13,21 +13,34 @@
 // limitations under the License.
 
 use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use arrow_array::{make_array, Array, Float16Array, Float32Array, Float64Array};
+use arrow_array::cast::AsArray;
+use arrow_array::{
+    make_array, Array, Float16Array, Float32Array, Float64Array, StringArray, UInt8Array,
+};
 use arrow_schema::DataType;
-use datafusion_physical_plan::ExecutionPlan;
+use datafusion_physical_plan::display::DisplayableExecutionPlan;
+use datafusion_physical_plan::expressions::Column;
+use datafusion_physical_plan::projection::ProjectionExec;
+use datafusion_physical_plan::{displayable, ExecutionPlan, PhysicalExpr};
+use futures::{Stream, StreamExt, TryStreamExt};
 use half::f16;
 use lance::dataset::scanner::DatasetRecordBatchStream;
 use lance_datafusion::exec::execute_plan;
+use lance_index::vector::DIST_COL;
 
-use crate::arrow::SendableRecordBatchStream;
+use crate::arrow::{RecordBatchStream, SendableRecordBatchStream, SimpleRecordBatchStream};
+use crate::embeddings::EmbeddingRegistry;
 use crate::error::{Error, Result};
-use crate::table::TableInternal;
+use crate::table::{ColumnKind, TableInternal};
 use crate::DistanceType;
 
 pub(crate) const DEFAULT_TOP_K: usize = 10;
+pub(crate) const DEFAULT_NPROBES: usize = 20;
 
 /// Which columns should be retrieved from the database
 #[derive(Debug, Clone)]
@@ -37,6 +50,9 @@ pub enum Select {
     /// Warning: This will always be slower than selecting only the columns you need.
     All,
     /// Select the provided columns
+    ///
+    /// A field of a struct column can be selected with a dotted path, such as
+    /// `metadata.score`.  It is returned as a top level column with that name.
     Columns(Vec<String>),
     /// Advanced selection which allows for dynamic column calculations
     ///
@@ -45,6 +61,10 @@ pub enum Select {
     ///
     /// See [`Query::select`] for more details and examples
     Dynamic(Vec<(String, String)>),
+    /// Select every column of the table except the provided columns
+    ///
+    /// See [`QueryBase::select_except`] for more details
+    Except(Vec<String>),
 }
 
 impl Select {
@@ -67,6 +87,14 @@ impl Select {
                 .collect(),
         )
     }
+
+    /// Create a selection of every column except the given columns
+    ///
+    /// This method is a convenience method for creating a [`Select::Except`] variant
+    /// from either Vec<&str> or Vec<String>
+    pub fn except(columns: &[impl AsRef<str>]) -> Self {
+        Self::Except(columns.iter().map(|c| c.as_ref().to_string()).collect())
+    }
 }
 
 /// A trait for converting a type to a query vector
@@ -260,6 +288,42 @@ impl IntoQueryVector for &[f64] {
     }
 }
 
+/// Slices of u8 are treated as binary vectors
+///
+/// Binary vectors are always searched with the hamming distance and so the input
+/// is never converted to a floating point type.
+impl IntoQueryVector for &[u8] {
+    fn to_query_vector(
+        self,
+        _data_type: &DataType,
+        _embedding_model_label: &str,
+    ) -> Result<Arc<dyn Array>> {
+        Ok(Arc::new(UInt8Array::from(self.to_vec())))
+    }
+}
+
+impl<const N: usize> IntoQueryVector for &[u8; N] {
+    fn to_query_vector(
+        self,
+        data_type: &DataType,
+        embedding_model_label: &str,
+    ) -> Result<Arc<dyn Array>> {
+        self.as_slice()
+            .to_query_vector(data_type, embedding_model_label)
+    }
+}
+
+impl IntoQueryVector for Vec<u8> {
+    fn to_query_vector(
+        self,
+        data_type: &DataType,
+        embedding_model_label: &str,
+    ) -> Result<Arc<dyn Array>> {
+        self.as_slice()
+            .to_query_vector(data_type, embedding_model_label)
+    }
+}
+
 impl<const N: usize> IntoQueryVector for &[f16; N] {
     fn to_query_vector(
         self,
@@ -337,6 +401,20 @@ pub trait QueryBase {
     /// it will default to 10.
     fn limit(self, limit: usize) -> Self;
 
+    /// Skip the first `offset` rows of the results.
+    ///
+    /// This can be combined with [`Self::limit`] to page through a large set of
+    /// results.  For example, `offset(100).limit(50)` will return rows 100-149.
+    ///
+    /// For a vector search the offset is applied after the results have been sorted
+    /// by distance.  The search will find the nearest `offset + limit` rows and then
+    /// discard the first `offset` of them.  This means paging is stable as long as the
+    /// table (and any vector index) does not change between pages.
+    ///
+    /// If the offset is greater than the number of rows that would have been returned
+    /// then the query will return no results (this is not an error).
+    fn offset(self, offset: usize) -> Self;
+
     /// Only return rows which match the filter.
     ///
     /// The filter should be supplied as an SQL query string.  For example:
@@ -347,8 +425,47 @@ pub trait QueryBase {
     /// x > 5 OR y = 'test'
     /// ```
     ///
+    /// Strings can be matched with `LIKE`, `NOT LIKE`, and the case insensitive
+    /// `ILIKE`.  A backslash can be used to match a literal `%` or `_` (the `ESCAPE`
+    /// clause is not supported).  Regular expressions can be matched with the
+    /// `regexp_match` function, which returns null if there is no match:
+    ///
+    /// ```ignore
+    /// name LIKE 'prod-%'
+    /// name ILIKE '%-API'
+    /// regexp_match(name, '^(prod|dev)-api$') IS NOT NULL
+    /// ```
+    ///
+    /// List columns can be filtered with `array_has`, `array_has_any` and
+    /// `array_has_all`.  These filters are not accelerated by an index.
+    ///
+    /// ```ignore
+    /// array_has(tags, 'rust')
+    /// array_has_any(tags, ['rust', 'python'])
+    /// ```
+    ///
+    /// Ranges can be written with `BETWEEN`, which is inclusive on both ends:
+    ///
+    /// ```ignore
+    /// timestamp BETWEEN 100 AND 200
+    /// ```
+    ///
+    /// Numbers compared with a decimal column are treated as exact decimals, so
+    /// the comparison does not lose precision:
+    ///
+    /// ```ignore
+    /// amount > 100.50
+    /// ```
+    ///
+    /// The fields of struct columns are referred to with a dotted path:
+    ///
+    /// ```ignore
+    /// metadata.score > 0.5
+    /// ```
+    ///
     /// Filtering performance can often be improved by creating a scalar index
-    /// on the filter column(s).
+    /// on the filter column(s).  For vector searches the scalar index is used to
+    /// find the matching rows before the search when prefiltering (the default).
     fn only_if(self, filter: impl AsRef<str>) -> Self;
 
     /// Return only the specified columns.
@@ -373,7 +490,73 @@ pub trait QueryBase {
     ///
     /// Columns will always be returned in the order given, even if that order is different than
     /// the order used when adding the data.
+    ///
+    /// A plain column can be renamed by selecting it under a new name, e.g. `("key", "id")`.
+    ///
+    /// In a vector search the expressions may also reference the `_distance` column, e.g.
+    /// `&[("dist_sq", "_distance * _distance"), ("id", "id")]`.  These expressions are
+    /// evaluated on the search results, after the distances are calculated.  The `_distance`
+    /// column is still returned after the selected columns.
     fn select(self, selection: Select) -> Self;
+
+    /// Return every column except the specified columns.
+    ///
+    /// This is a shortcut for `select(Select::except(columns))`.  The columns to return
+    /// are the top level columns of the table schema, in the order of the schema, without
+    /// the excluded columns.  This is easier than listing the columns to keep when a table
+    /// has many columns and only a few are not needed.
+    ///
+    /// A vector search still returns the `_distance` column unless it is excluded too.
+    ///
+    /// The query fails if one of the columns does not exist or if every column is excluded.
+    fn select_except(self, columns: &[&str]) -> Self;
+
+    /// Whether to return the row id of each row in a `_rowid` column.
+    ///
+    /// The `_rowid` column is a `UInt64` column appended to the results.  Row ids can be
+    /// used to identify rows when joining the results with data from another system.
+    ///
+    /// Row ids are only stable within a single version of the table.  Operations that
+    /// rewrite data, such as [`crate::Table::optimize`] (compaction) or
+    /// [`crate::Table::update`], can change the row id of a row.
+    ///
+    /// By default the row id is not returned.
+    fn with_row_id(self, with_row_id: bool) -> Self;
+
+    /// Whether to return the address of each row in a `_rowaddr` column.
+    ///
+    /// The `_rowaddr` column is a `UInt64` column appended to the results (after
+    /// `_rowid`, if it was requested).  The address is where the row is stored:
+    /// the upper 32 bits are the id of the fragment that holds the row and the lower
+    /// 32 bits are the offset of the row within the fragment, counting deleted rows.
+    /// For example, the fragment id is `address >> 32` and the offset is
+    /// `address as u32`.  The address can be used to send follow up requests, such as
+    /// [`crate::Table::take`], to the worker that owns the fragment.
+    ///
+    /// Tables currently use the row address as the row id and so the two columns have
+    /// the same values.  This is not guaranteed in the future, use the `_rowaddr`
+    /// column when the physical location of the row is needed.  Like row ids, row
+    /// addresses change when data is rewritten (e.g. by compaction).
+    ///
+    /// By default the row address is not returned.
+    fn with_row_address(self, with_row_address: bool) -> Self;
+
+    /// Fail the query with [`Error::Timeout`] if it takes longer than `timeout`
+    ///
+    /// The timeout covers both planning the query and reading every batch of
+    /// the results, and starts when the query is executed.  If the deadline passes
+    /// while the results are being read then the stream returns the error and the
+    /// underlying scan is dropped, which cancels any IO that is still in flight.
+    ///
+    /// By default there is no timeout.
+    fn timeout(self, timeout: Duration) -> Self;
+
+    /// Set the maximum number of rows in each batch of the results
+    ///
+    /// This takes precedence over [`QueryExecutionOptions::max_batch_length`].
+    /// Batches may be smaller, for example the last batch of each fragment of
+    /// the table.  By default the maximum is 1024.
+    fn batch_size(self, batch_size: usize) -> Self;
 }
 
 pub trait HasQuery {
@@ -386,6 +569,11 @@ impl<T: HasQuery> QueryBase for T {
         self
     }
 
+    fn offset(mut self, offset: usize) -> Self {
+        self.mut_query().offset = Some(offset);
+        self
+    }
+
     fn only_if(mut self, filter: impl AsRef<str>) -> Self {
         self.mut_query().filter = Some(filter.as_ref().to_string());
         self
@@ -395,10 +583,35 @@ impl<T: HasQuery> QueryBase for T {
         self.mut_query().select = select;
         self
     }
+
+    fn select_except(self, columns: &[&str]) -> Self {
+        self.select(Select::except(columns))
+    }
+
+    fn with_row_id(mut self, with_row_id: bool) -> Self {
+        self.mut_query().with_row_id = with_row_id;
+        self
+    }
+
+    fn with_row_address(mut self, with_row_address: bool) -> Self {
+        self.mut_query().with_row_address = with_row_address;
+        self
+    }
+
+    fn timeout(mut self, timeout: Duration) -> Self {
+        self.mut_query().timeout = Some(timeout);
+        self
+    }
+
+    fn batch_size(mut self, batch_size: usize) -> Self {
+        self.mut_query().batch_size = Some(batch_size);
+        self
+    }
 }
 
 /// Options for controlling the execution of a query
 #[non_exhaustive]
+#[derive(Debug, Clone)]
 pub struct QueryExecutionOptions {
     /// The maximum number of rows that will be contained in a single
     /// `RecordBatch` delivered by the query.
@@ -465,6 +678,196 @@ pub trait ExecutableQuery {
         &self,
         options: QueryExecutionOptions,
     ) -> impl Future<Output = Result<SendableRecordBatchStream>> + Send;
+
+    /// Return the physical plan of the query as text
+    ///
+    /// The query is planned but not executed.  The plan shows how the data will be
+    /// searched, for example whether a filter is answered by a scalar index
+    /// (`MaterializeIndex`) or by scanning the table (`LanceScan`).
+    ///
+    /// If `verbose` is true then each node is also annotated with its estimated
+    /// statistics (e.g. the number of rows).
+    fn explain_plan(&self, verbose: bool) -> impl Future<Output = Result<String>> + Send;
+
+    /// Execute the query and return its physical plan annotated with runtime metrics
+    ///
+    /// This is similar to `EXPLAIN ANALYZE` in SQL databases.  The query is run to
+    /// completion (the results are discarded) and each node of the plan is printed
+    /// with the metrics it collected, such as the number of rows it produced and the
+    /// time spent computing them.  The total number of rows and the elapsed time are
+    /// printed at the end.
+    ///
+    /// Not every node collects metrics.  Nodes that don't are printed with their
+    /// parameters only, e.g. the `ANNIvfPartition` node shows the number of IVF
+    /// partitions that were probed (`nprobes`).
+    fn analyze_plan(&self) -> impl Future<Output = Result<String>> + Send;
+
+    /// Execute the query and collect the results into a Polars DataFrame
+    ///
+    /// The DataFrame has the schema of the results, including the `_distance`
+    /// column of a vector query.  Each result batch is a chunk of the DataFrame.
+    #[cfg(feature = "polars")]
+    fn execute_into_polars(
+        &self,
+    ) -> impl Future<Output = Result<polars::prelude::DataFrame>> + Send {
+        let execute = self.execute();
+        async move {
+            use crate::arrow::IntoPolars;
+            execute.await?.into_polars().await
+        }
+    }
+
+    /// Execute the query and write the results to a Parquet file
+    ///
+    /// `path` is a URI (or local path) on any of the supported object stores, an
+    /// existing file is replaced.  The file has the schema of the results, including
+    /// the `_distance` column of a vector query.  The results are streamed into the
+    /// file, so they do not need to fit in memory, but each row group does.
+    ///
+    /// The file is written with the storage options of `options`, an option that is
+    /// not set there is taken from the storage options of the table (and so of the
+    /// connection).
+    ///
+    /// Returns the number of rows written.
+    #[cfg(feature = "parquet")]
+    fn write_parquet(
+        &self,
+        path: &str,
+        options: crate::io::parquet::ParquetWriteOptions,
+    ) -> impl Future<Output = Result<usize>> + Send;
+}
+
+/// Writes the results of a query on `table` to a Parquet file
+///
+/// The storage options that are not set in `options` are taken from the table.
+#[cfg(feature = "parquet")]
+async fn write_query_parquet(
+    table: &dyn TableInternal,
+    stream: SendableRecordBatchStream,
+    path: &str,
+    mut options: crate::io::parquet::ParquetWriteOptions,
+) -> Result<usize> {
+    if let Some(table) = table.as_native() {
+        for (key, value) in table.storage_options.iter() {
+            if !options.storage_options.contains_key(key) {
+                options.storage_options.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    crate::io::parquet::write_parquet(stream, path, &options).await
+}
+
+fn format_plan(plan: Arc<dyn ExecutionPlan>, verbose: bool) -> String {
+    displayable(plan.as_ref())
+        .set_show_statistics(verbose)
+        .indent(verbose)
+        .to_string()
+}
+
+fn timeout_error(timeout: Duration) -> Error {
+    Error::Timeout {
+        message: format!("query did not finish within {:?}", timeout),
+    }
+}
+
+/// Run `execute` and read the resulting stream with a deadline of `timeout`
+async fn execute_with_timeout(
+    timeout: Option<Duration>,
+    execute: impl Future<Output = Result<SendableRecordBatchStream>>,
+) -> Result<SendableRecordBatchStream> {
+    let Some(timeout) = timeout else {
+        return execute.await;
+    };
+    let deadline = tokio::time::Instant::now() + timeout;
+    let stream = tokio::time::timeout_at(deadline, execute)
+        .await
+        .map_err(|_| timeout_error(timeout))??;
+    Ok(Box::pin(TimeoutStream {
+        schema: stream.schema(),
+        deadline: tokio::time::sleep_until(deadline),
+        stream: Some(stream),
+        timeout,
+    }))
+}
+
+/// Split the batches of `stream` that have more than `batch_size` rows
+///
+/// The scan already honors the batch size but some plan nodes, such as the sort
+/// of a vector search, emit their results as a single batch.
+fn split_batches(
+    stream: SendableRecordBatchStream,
+    batch_size: Option<usize>,
+) -> SendableRecordBatchStream {
+    let Some(batch_size) = batch_size else {
+        return stream;
+    };
+    let schema = stream.schema();
+    let stream = stream.flat_map(move |batch| {
+        let batches = match batch {
+            Ok(batch) => (0..batch.num_rows())
+                .step_by(batch_size)
+                .map(|offset| Ok(batch.slice(offset, batch_size.min(batch.num_rows() - offset))))
+                .collect(),
+            Err(err) => vec![Err(err)],
+        };
+        futures::stream::iter(batches)
+    });
+    Box::pin(SimpleRecordBatchStream::new(stream, schema))
+}
+
+/// A stream that fails with [`Error::Timeout`] once the deadline passes
+///
+/// The inner stream is dropped as soon as the deadline passes so the scan
+/// feeding it is cancelled, even if the caller keeps the stream around.
+#[pin_project::pin_project]
+struct TimeoutStream {
+    schema: Arc<arrow_schema::Schema>,
+    #[pin]
+    deadline: tokio::time::Sleep,
+    stream: Option<SendableRecordBatchStream>,
+    timeout: Duration,
+}
+
+impl Stream for TimeoutStream {
+    type Item = Result<arrow_array::RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let Some(stream) = this.stream.as_mut() else {
+            return Poll::Ready(None);
+        };
+        if this.deadline.poll(cx).is_ready() {
+            this.stream.take();
+            return Poll::Ready(Some(Err(timeout_error(*this.timeout))));
+        }
+        let next = stream.as_mut().poll_next(cx);
+        if matches!(next, Poll::Ready(None)) {
+            this.stream.take();
+        }
+        next
+    }
+}
+
+impl RecordBatchStream for TimeoutStream {
+    fn schema(&self) -> Arc<arrow_schema::Schema> {
+        self.schema.clone()
+    }
+}
+
+async fn analyze_plan(plan: Arc<dyn ExecutionPlan>) -> Result<String> {
+    let start = Instant::now();
+    let mut stream = execute_plan(plan.clone(), Default::default())?;
+    let mut num_rows = 0;
+    while let Some(batch) = stream.try_next().await? {
+        num_rows += batch.num_rows();
+    }
+    let elapsed = start.elapsed();
+    Ok(format!(
+        "{}Total rows: {}, elapsed: {:?}",
+        DisplayableExecutionPlan::with_metrics(plan.as_ref()).indent(true),
+        num_rows,
+        elapsed
+    ))
 }
 
 /// A builder for LanceDB queries.
@@ -482,27 +885,63 @@ pub trait ExecutableQuery {
 #[derive(Debug, Clone)]
 pub struct Query {
     parent: Arc<dyn TableInternal>,
+    /// The registry used to embed the text of [`Self::nearest_to_text`] queries.
+    pub(crate) embedding_registry: Option<Arc<dyn EmbeddingRegistry>>,
 
     /// limit the number of rows to return.
     pub(crate) limit: Option<usize>,
+    /// skip this many rows before returning results.
+    pub(crate) offset: Option<usize>,
     /// Apply filter to the returned rows.
     pub(crate) filter: Option<String>,
     /// Select column projection.
     pub(crate) select: Select,
+    /// Whether to include the `_rowid` column in the results.
+    pub(crate) with_row_id: bool,
+    /// Whether to include the `_rowaddr` column in the results.
+    pub(crate) with_row_address: bool,
+    /// Fail the query if it does not finish within this duration.
+    pub(crate) timeout: Option<Duration>,
+    /// Only scan the fragments with these ids.
+    pub(crate) fragments: Option<Vec<u64>>,
+    /// The maximum number of rows in each batch of the results.
+    pub(crate) batch_size: Option<usize>,
 }
 
 impl Query {
     pub(crate) fn new(parent: Arc<dyn TableInternal>) -> Self {
         Self {
             parent,
+            embedding_registry: None,
             limit: None,
+            offset: None,
             filter: None,
             select: Select::All,
+            with_row_id: false,
+            with_row_address: false,
+            timeout: None,
+            fragments: None,
+            batch_size: None,
         }
     }
 
-    /// Helper method to convert the query to a VectorQuery with a `query_vector`
-    /// of None.  This retrofits to some existing inner paths that work with a
+    /// Only scan the fragments with the given ids
+    ///
+    /// A table is stored as a list of fragments, each of which holds a subset of
+    /// the rows.  The fragments can be split between workers, each of which scans
+    /// its own fragments, to read the whole table in parallel.  The ids of the
+    /// fragments are reported by [`crate::Table::stats`].
+    ///
+    /// The fragments are scanned in the given order.  The query fails if one of
+    /// the fragments does not exist in the checked out version of the table.
+    /// This cannot be combined with a vector search.
+    pub fn with_fragments(mut self, fragment_ids: &[u64]) -> Self {
+        self.fragments = Some(fragment_ids.to_vec());
+        self
+    }
+
+    /// Helper method to convert the query to a VectorQuery without any query
+    /// vectors.  This retrofits to some existing inner paths that work with a
     /// single query object for both vector and plain queries.
     pub(crate) fn into_vector(self) -> VectorQuery {
         VectorQuery::new(self)
@@ -539,15 +978,71 @@ impl Query {
     /// are various search parameters that will let you fine tune your recall
     /// accuracy vs search latency.
     ///
+    /// The vector column may store float16, float32 or float64 values.  The query
+    /// vector can be given in any of these types (e.g. `&[f32]` or `&[f16]`) and
+    /// does not need to match the column.  Distances are returned (and, for float16
+    /// vectors, accumulated) as 32 bit floats, so using float16 vectors halves the
+    /// storage without overflowing the distance calculation.
+    ///
+    /// A binary query vector (e.g. `&[u8]`) searches a binary vector column (a
+    /// fixed size list of u8) using [`DistanceType::Hamming`].  Binary vectors cannot
+    /// be indexed and so this is always a flat search.
+    ///
+    /// A binary query vector is never interpreted as the product quantization (PQ)
+    /// codes of an IVF PQ index and searching a float vector column with one fails.
+    /// For the L2 and cosine distances the PQ codes of a vector encode its offset
+    /// from the centroid of its IVF partition, so the codes alone do not identify
+    /// a vector.  Search with the original (or decoded) float vector instead.
+    ///
     /// # Arguments
     ///
     /// * `vector` - The vector that will be used for search.
     pub fn nearest_to(self, vector: impl IntoQueryVector) -> Result<VectorQuery> {
         let mut vector_query = self.into_vector();
         let query_vector = vector.to_query_vector(&DataType::Float32, "default")?;
-        vector_query.query_vector = Some(query_vector);
+        vector_query.query_vector.push(query_vector);
         Ok(vector_query)
     }
+
+    /// Find the nearest vectors to the embedding of `text`
+    ///
+    /// The text is embedded with the embedding function that populates the vector
+    /// column (see [`crate::connection::CreateTableBuilder::add_embedding`]), using
+    /// [`crate::embeddings::EmbeddingFunction::compute_query_embeddings`], and then
+    /// the vector search is the same as [`Self::nearest_to`].  This saves embedding
+    /// the text yourself, e.g. to retrieve the documents for a prompt.
+    ///
+    /// The function must be registered on the connection the table was opened from.
+    /// If the table has more than one embedding column then use
+    /// [`VectorQuery::column`] to pick one.  The text is embedded when the query is
+    /// run, and an error is returned if no embedding function is configured for the
+    /// column or if it is not in the registry.
+    pub fn nearest_to_text(self, text: impl Into<String>) -> VectorQuery {
+        let mut vector_query = self.into_vector();
+        vector_query.query_text = Some(text.into());
+        vector_query
+    }
+
+    /// Find the nearest vectors to each of the given query vectors.
+    ///
+    /// This is the same as calling [`Self::nearest_to`] with the first vector and
+    /// then calling [`VectorQuery::add_query_vector`] with each of the remaining
+    /// vectors.  See [`VectorQuery::add_query_vector`] for details on how the
+    /// results are returned.
+    ///
+    /// An error will be returned if no vectors are provided.
+    pub fn nearest_to_many<V: IntoQueryVector>(
+        self,
+        vectors: impl IntoIterator<Item = V>,
+    ) -> Result<VectorQuery> {
+        let mut vectors = vectors.into_iter();
+        let first = vectors.next().ok_or_else(|| Error::InvalidInput {
+            message: "at least one query vector must be provided".to_string(),
+        })?;
+        vectors.try_fold(self.nearest_to(first)?, |query, vector| {
+            query.add_query_vector(vector)
+        })
+    }
 }
 
 impl HasQuery for Query {
@@ -568,9 +1063,32 @@ impl ExecutableQuery for Query {
         &self,
         options: QueryExecutionOptions,
     ) -> Result<SendableRecordBatchStream> {
-        Ok(SendableRecordBatchStream::from(
-            self.parent.clone().plain_query(self, options).await?,
-        ))
+        execute_with_timeout(self.timeout, async {
+            let stream = SendableRecordBatchStream::from(
+                self.parent.clone().plain_query(self, options).await?,
+            );
+            Ok(split_batches(stream, self.batch_size))
+        })
+        .await
+    }
+
+    async fn explain_plan(&self, verbose: bool) -> Result<String> {
+        let plan = self.create_plan(QueryExecutionOptions::default()).await?;
+        Ok(format_plan(plan, verbose))
+    }
+
+    async fn analyze_plan(&self) -> Result<String> {
+        let plan = self.create_plan(QueryExecutionOptions::default()).await?;
+        analyze_plan(plan).await
+    }
+
+    #[cfg(feature = "parquet")]
+    async fn write_parquet(
+        &self,
+        path: &str,
+        options: crate::io::parquet::ParquetWriteOptions,
+    ) -> Result<usize> {
+        write_query_parquet(self.parent.as_ref(), self.execute().await?, path, options).await
     }
 }
 
@@ -590,14 +1108,24 @@ pub struct VectorQuery {
     // the column based on the dataset's schema.
     pub(crate) column: Option<String>,
     // IVF PQ - ANN search.
-    pub(crate) query_vector: Option<Arc<dyn Array>>,
-    pub(crate) nprobes: usize,
+    pub(crate) query_vector: Vec<Arc<dyn Array>>,
+    pub(crate) nprobes: Option<usize>,
     pub(crate) refine_factor: Option<u32>,
+    // HNSW - the number of candidates to consider during the graph search
+    pub(crate) ef: Option<u32>,
     pub(crate) distance_type: Option<DistanceType>,
     /// Default is true. Set to false to enforce a brute force search.
     pub(crate) use_index: bool,
     /// Apply filter before ANN search/
     pub(crate) prefilter: bool,
+    /// Only search the rows covered by the vector index
+    pub(crate) fast_search: bool,
+    /// The name of the distance column in the results, or None to drop it
+    pub(crate) distance_column_name: Option<String>,
+    /// Only return results whose distance is within these (lower, upper) bounds
+    pub(crate) distance_range: (Option<f32>, Option<f32>),
+    /// Text to embed as the query vector when the query is run
+    pub(crate) query_text: Option<String>,
 }
 
 impl VectorQuery {
@@ -605,12 +1133,17 @@ impl VectorQuery {
         Self {
             base,
             column: None,
-            query_vector: None,
-            nprobes: 20,
+            query_vector: Vec::new(),
+            nprobes: None,
             refine_factor: None,
+            ef: None,
             distance_type: None,
             use_index: true,
             prefilter: true,
+            fast_search: false,
+            distance_column_name: Some(DIST_COL.to_string()),
+            distance_range: (None, None),
+            query_text: None,
         }
     }
 
@@ -619,13 +1152,37 @@ impl VectorQuery {
     /// This controls which column is compared to the query vector supplied in
     /// the call to [`Query::nearest_to`]
     ///
-    /// This parameter must be specified if the table has more than one column
-    /// whose data type is a fixed-size-list of floats.
+    /// If this is not set then the column is the only fixed-size-list of floats
+    /// column whose dimension matches the query vector.  This parameter must be
+    /// specified if the table has more than one such column, otherwise the query
+    /// fails with [`crate::Error::AmbiguousVectorColumn`] listing the candidates.
+    ///
+    /// Each vector column can have its own index (see [`crate::Table::create_index`]),
+    /// and the search uses the index of the chosen column.  The query fails if the
+    /// dimension of the query vector does not match the column.
     pub fn column(mut self, column: &str) -> Self {
         self.column = Some(column.to_string());
         self
     }
 
+    /// Add another query vector to the search
+    ///
+    /// Multiple query vectors can be used to batch several searches into a single
+    /// query.  Each query vector is searched independently and the [`QueryBase::limit`]
+    /// (and [`QueryBase::offset`]) apply to each query vector, not to the results as
+    /// a whole.  All other parameters (filter, nprobes, etc.) are shared by every
+    /// search.  The searches all run against the same version of the table and so they
+    /// also share any cached index data.
+    ///
+    /// When there is more than one query vector the results will contain an additional
+    /// `query_index` column (a u32) with the position of the query vector that produced
+    /// each row.  The rows for different query vectors may be interleaved in the output.
+    pub fn add_query_vector(mut self, vector: impl IntoQueryVector) -> Result<Self> {
+        let query_vector = vector.to_query_vector(&DataType::Float32, "default")?;
+        self.query_vector.push(query_vector);
+        Ok(self)
+    }
+
     /// Set the number of partitions to search (probe)
     ///
     /// This argument is only used when the vector column has an IVF PQ index.
@@ -646,8 +1203,32 @@ impl VectorQuery {
     /// For best results we recommend tuning this parameter with a benchmark against
     /// your actual data to find the smallest possible value that will still give
     /// you the desired recall.
+    ///
+    /// If the column has an IVF index then the query will fail at execution time if
+    /// this value is 0 or is larger than the number of partitions in the index.  When
+    /// this method is not called all partitions are searched if the index has fewer
+    /// than 20 partitions.
     pub fn nprobes(mut self, nprobes: usize) -> Self {
-        self.nprobes = nprobes;
+        self.nprobes = Some(nprobes);
+        self
+    }
+
+    /// Set the number of candidates to consider when searching an HNSW graph (ef)
+    ///
+    /// This argument is only used when the vector column has an IVF HNSW index
+    /// (see [`crate::index::Index::IvfHnswPq`] and [`crate::index::Index::IvfHnswSq`]).
+    /// It is ignored if the column has an IVF PQ index or no index at all.
+    ///
+    /// Each IVF partition searched has its own HNSW graph.  The search of a graph
+    /// keeps a list of the `ef` closest vectors found so far and stops when none of
+    /// their neighbors are closer.  Increasing this value will increase the recall
+    /// of your query but will also increase the latency of your query.  The default
+    /// is 1.5 times the number of results (`limit` + `offset`).
+    ///
+    /// If the column has an IVF HNSW index then the query will fail at execution
+    /// time if this value is smaller than the number of results.
+    pub fn ef(mut self, ef: u32) -> Self {
+        self.ef = Some(ef);
         self
     }
 
@@ -678,6 +1259,8 @@ impl VectorQuery {
     /// will be approximate distances based on the comparison of the quantized query vector
     /// and the quantized result vectors.  This can be considerably different than the true
     /// distance between the query vector and the actual uncompressed vector.
+    ///
+    /// A `refine_factor` of 0 is invalid and will cause the query to fail at execution time.
     pub fn refine_factor(mut self, refine_factor: u32) -> Self {
         self.refine_factor = Some(refine_factor);
         self
@@ -690,11 +1273,16 @@ impl VectorQuery {
     /// use.  See [`DistanceType`] for more details on the different distance metrics
     /// available.
     ///
-    /// Note: if there is a vector index then the distance type used MUST match the distance
-    /// type used to train the vector index.  If this is not done then the results will be
-    /// invalid.
+    /// If there is a vector index that was trained with a different distance type then the
+    /// index cannot be used.  Instead, the query falls back to a flat search which computes
+    /// the exact distance, using the requested distance type, to every vector in the column.
+    /// The results are correct but the search cost grows linearly with the number of rows,
+    /// which can be much slower than an indexed search on a large table.  The `nprobes`,
+    /// `ef` and `refine_factor` parameters have no effect in this case.
     ///
-    /// By default [`DistanceType::L2`] is used.
+    /// By default [`DistanceType::L2`] is used for floating point vectors and
+    /// [`DistanceType::Hamming`] for binary vectors.  The hamming distance can only be
+    /// used with binary vectors.
     pub fn distance_type(mut self, distance_type: DistanceType) -> Self {
         self.distance_type = Some(distance_type);
         self
@@ -718,39 +1306,263 @@ impl VectorQuery {
     /// Post filtering happens during the "refine stage" (described in more detail in
     /// [`Self::refine_factor`]).  This means that setting a higher refine factor can often
     /// help restore some of the results lost by post filtering.
+    ///
+    /// In short, prefiltering returns the nearest `limit` rows that match the filter (among
+    /// the rows reached by the search, see [`Self::nprobes`]) at the cost of evaluating the
+    /// filter on the whole table.  Post filtering only evaluates the filter on the candidates
+    /// of the search, which is cheaper, but fewer of the matching rows are found.  The more
+    /// selective the filter, the more results are lost.  Use [`Self::prefilter`] to switch
+    /// back to the default.
     pub fn postfilter(mut self) -> Self {
         self.prefilter = false;
         self
     }
 
+    /// Apply the filter before the vector search
+    ///
+    /// This is the default, see [`Self::postfilter`] for the tradeoffs between the two.
+    pub fn prefilter(mut self) -> Self {
+        self.prefilter = true;
+        self
+    }
+
     /// If this is called then any vector index is skipped
     ///
     /// An exhaustive (flat) search will be performed.  The query vector will
-    /// be compared to every vector in the table.  At high scales this can be
-    /// expensive.  However, this is often still useful.  For example, skipping
+    /// be compared to every vector in the table, using the original (not
+    /// quantized) vectors, so the results are the exact top-k nearest neighbors.
+    /// At high scales this can be expensive.  However, this is often still
+    /// useful.  For example, skipping
     /// the vector index can give you ground truth results which you can use to
     /// calculate your recall to select an appropriate value for nprobes.
     pub fn bypass_vector_index(mut self) -> Self {
         self.use_index = false;
         self
     }
+
+    /// If set to true then only rows covered by the vector index are searched
+    ///
+    /// Rows added after the vector index was created are not in the index.  By
+    /// default these rows are searched with a flat search and the results are
+    /// combined with the results of the index search.  Skipping this flat search
+    /// reduces latency when there are many unindexed rows.
+    ///
+    /// Warning: the results will not include any rows added since the index was
+    /// last created or optimized, even if they are closer to the query vector.
+    /// Use [`crate::Table::index_stats`] to see how many rows are not indexed.
+    ///
+    /// This has no effect if there is no vector index on the column.
+    pub fn fast_search(mut self, fast_search: bool) -> Self {
+        self.fast_search = fast_search;
+        self
+    }
+
+    /// Only return the results whose distance to the query vector is within a range
+    ///
+    /// The lower bound is inclusive and the upper bound is exclusive, so the results
+    /// satisfy `lower <= _distance < upper`.  `None` leaves that side of the range
+    /// open.  For example, `distance_range(None, Some(0.05))` finds near duplicates
+    /// of the query vector.
+    ///
+    /// The limit and offset apply after the range, so up to `limit` of the nearest
+    /// results within the range are returned.  With only an upper bound the search
+    /// is as fast as without a range.  A lower bound skips the nearest results, so
+    /// every candidate of the search is considered, which is slower.  With a vector
+    /// index the candidates are still limited to the probed partitions.
+    ///
+    /// An error is returned when the query is run if `lower` is not less than
+    /// `upper`.
+    pub fn distance_range(mut self, lower: Option<f32>, upper: Option<f32>) -> Self {
+        self.distance_range = (lower, upper);
+        self
+    }
+
+    /// Rename or drop the distance column of the results
+    ///
+    /// By default the distance of each result is returned in a `_distance` column.
+    /// `Some(name)` returns it in a column with the given name instead and `None`
+    /// leaves it out of the results.  The results are still sorted by distance.
+    ///
+    /// The distance is renamed or dropped after the query has been run, so the
+    /// filter and [`QueryBase::select`] expressions still refer to `_distance`.
+    pub fn distance_column_name(mut self, name: Option<&str>) -> Self {
+        self.distance_column_name = name.map(|name| name.to_string());
+        self
+    }
+
+    /// Replace the text of a [`Query::nearest_to_text`] query with its embedding
+    async fn embed_query_text(&self, text: &str) -> Result<Self> {
+        if !self.query_vector.is_empty() {
+            return Err(Error::InvalidInput {
+                message: "a text query cannot be combined with query vectors".to_string(),
+            });
+        }
+        let definition = self.base.parent.table_definition().await?;
+        let embeddings = definition
+            .schema
+            .fields()
+            .iter()
+            .zip(definition.column_definitions.iter())
+            .filter_map(|(field, column)| match &column.kind {
+                ColumnKind::Embedding(embedding) => Some((field.name(), embedding)),
+                ColumnKind::Physical => None,
+            })
+            .filter(|(name, _)| self.column.as_ref().map_or(true, |column| column == *name))
+            .collect::<Vec<_>>();
+        let (column, embedding) = match embeddings.as_slice() {
+            [embedding] => *embedding,
+            [] => {
+                return Err(Error::InvalidInput {
+                    message: match &self.column {
+                        Some(column) => format!(
+                            "cannot search column '{}' with text, no embedding function is configured for the column",
+                            column
+                        ),
+                        None => "cannot search with text, no embedding function is configured for the table".to_string(),
+                    },
+                })
+            }
+            _ => {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "the table has more than one embedding column ({}), use column() to pick the column to search",
+                        embeddings
+                            .iter()
+                            .map(|(name, _)| name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                })
+            }
+        };
+        let function = self
+            .base
+            .embedding_registry
+            .as_ref()
+            .and_then(|registry| registry.get(&embedding.embedding_name))
+            .ok_or_else(|| Error::EmbeddingFunctionNotFound {
+                name: embedding.embedding_name.clone(),
+                reason: format!(
+                    "The column `{}` is embedded with `{}` but no embedding function was found with that name within the registry.",
+                    column, embedding.embedding_name
+                ),
+            })?;
+        let embedded =
+            function.compute_query_embeddings(Arc::new(StringArray::from(vec![text])))?;
+        let vector = match embedded.data_type() {
+            DataType::FixedSizeList(_, _) if embedded.len() == 1 => {
+                embedded.as_fixed_size_list().value(0)
+            }
+            DataType::List(_) if embedded.len() == 1 => embedded.as_list::<i32>().value(0),
+            _ => {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "the embedding function `{}` returned {} values of type {} for the query text, expected a single vector",
+                        embedding.embedding_name,
+                        embedded.len(),
+                        embedded.data_type()
+                    ),
+                })
+            }
+        };
+        let mut query = self.clone();
+        query.query_text = None;
+        query.column = Some(column.clone());
+        query
+            .query_vector
+            .push(vector.to_query_vector(&DataType::Float32, &embedding.embedding_name)?);
+        Ok(query)
+    }
+
+    /// Apply [`Self::distance_column_name`] to the plan of the search
+    fn rename_distance_column(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if self.distance_column_name.as_deref() == Some(DIST_COL) {
+            return Ok(plan);
+        }
+        let schema = plan.schema();
+        if let Some(name) = &self.distance_column_name {
+            if schema.column_with_name(name).is_some() {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "cannot name the distance column {} as the results already contain a column with that name",
+                        name
+                    ),
+                });
+            }
+        }
+        let projection = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, field)| {
+                let name = if field.name() == DIST_COL {
+                    self.distance_column_name.clone()?
+                } else {
+                    field.name().clone()
+                };
+                let expr: Arc<dyn PhysicalExpr> = Arc::new(Column::new(field.name(), idx));
+                Some((expr, name))
+            })
+            .collect();
+        Ok(Arc::new(ProjectionExec::try_new(projection, plan)?))
+    }
 }
 
 impl ExecutableQuery for VectorQuery {
     async fn create_plan(&self, options: QueryExecutionOptions) -> Result<Arc<dyn ExecutionPlan>> {
-        self.base.parent.clone().create_plan(self, options).await
+        let plan = match &self.query_text {
+            Some(text) => {
+                let query = self.embed_query_text(text).await?;
+                self.base
+                    .parent
+                    .clone()
+                    .create_plan(&query, options)
+                    .await?
+            }
+            None => self.base.parent.clone().create_plan(self, options).await?,
+        };
+        self.rename_distance_column(plan)
     }
 
     async fn execute_with_options(
         &self,
         options: QueryExecutionOptions,
     ) -> Result<SendableRecordBatchStream> {
-        Ok(SendableRecordBatchStream::from(
-            DatasetRecordBatchStream::new(execute_plan(
-                self.create_plan(options).await?,
-                Default::default(),
-            )?),
-        ))
+        execute_with_timeout(self.base.timeout, async {
+            let stream = SendableRecordBatchStream::from(DatasetRecordBatchStream::new(
+                execute_plan(self.create_plan(options).await?, Default::default())?,
+            ));
+            Ok(split_batches(stream, self.base.batch_size))
+        })
+        .await
+    }
+
+    async fn explain_plan(&self, verbose: bool) -> Result<String> {
+        let plan = self.create_plan(QueryExecutionOptions::default()).await?;
+        Ok(format_plan(plan, verbose))
+    }
+
+    async fn analyze_plan(&self) -> Result<String> {
+        let plan = self.create_plan(QueryExecutionOptions::default()).await?;
+        analyze_plan(plan).await
+    }
+
+    #[cfg(feature = "parquet")]
+    async fn write_parquet(
+        &self,
+        path: &str,
+        options: crate::io::parquet::ParquetWriteOptions,
+    ) -> Result<usize> {
+        write_query_parquet(
+            self.base.parent.as_ref(),
+            self.execute().await?,
+            path,
+            options,
+        )
+        .await
     }
 }
 
@@ -762,27 +1574,31 @@ impl HasQuery for VectorQuery {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{HashMap, HashSet};
     use std::sync::Arc;
 
     use super::*;
     use arrow_array::{
-        cast::AsArray, Float32Array, Int32Array, RecordBatch, RecordBatchIterator,
-        RecordBatchReader,
+        cast::AsArray,
+        types::{Float32Type, Int32Type, UInt32Type, UInt64Type, UInt8Type},
+        ArrayRef, FixedSizeListArray, Float32Array, Int32Array, RecordBatch, RecordBatchIterator,
+        RecordBatchReader, StringArray, StructArray,
     };
-    use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema};
+    use arrow_schema::{DataType, Field as ArrowField, Fields, Schema as ArrowSchema};
     use futures::{StreamExt, TryStreamExt};
     use lance_testing::datagen::{BatchGenerator, IncrementingInt32, RandomVector};
     use tempfile::tempdir;
 
+    use crate::index::{
+        scalar::BTreeIndexBuilder,
+        vector::{IvfHnswSqIndexBuilder, IvfPqIndexBuilder},
+        Index,
+    };
     use crate::{connect, Table};
 
     #[tokio::test]
     async fn test_setters_getters() {
-        // TODO: Switch back to memory://foo after https://github.com/lancedb/lancedb/issues/1051
-        // is fixed
-        let tmp_dir = tempdir().unwrap();
-        let dataset_path = tmp_dir.path().join("test.lance");
-        let uri = dataset_path.to_str().unwrap();
+        let uri = "memory://foo";
 
         let batches = make_test_batches();
         let conn = connect(uri).execute().await.unwrap();
@@ -794,7 +1610,7 @@ mod tests {
 
         let vector = Float32Array::from_iter_values([0.1, 0.2]);
         let query = table.query().nearest_to(&[0.1, 0.2]).unwrap();
-        assert_eq!(*query.query_vector.unwrap().as_ref().as_primitive(), vector);
+        assert_eq!(*query.query_vector[0].as_ref().as_primitive(), vector);
 
         let new_vector = Float32Array::from_iter_values([9.8, 8.7]);
 
@@ -808,12 +1624,9 @@ mod tests {
             .distance_type(DistanceType::Cosine)
             .refine_factor(999);
 
-        assert_eq!(
-            *query.query_vector.unwrap().as_ref().as_primitive(),
-            new_vector
-        );
+        assert_eq!(*query.query_vector[0].as_ref().as_primitive(), new_vector);
         assert_eq!(query.base.limit.unwrap(), 100);
-        assert_eq!(query.nprobes, 1000);
+        assert_eq!(query.nprobes, Some(1000));
         assert!(query.use_index);
         assert_eq!(query.distance_type, Some(DistanceType::Cosine));
         assert_eq!(query.refine_factor, Some(999));
@@ -821,11 +1634,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_execute() {
-        // TODO: Switch back to memory://foo after https://github.com/lancedb/lancedb/issues/1051
-        // is fixed
-        let tmp_dir = tempdir().unwrap();
-        let dataset_path = tmp_dir.path().join("test.lance");
-        let uri = dataset_path.to_str().unwrap();
+        let uri = "memory://foo";
 
         let batches = make_non_empty_batches();
         let conn = connect(uri).execute().await.unwrap();
@@ -867,11 +1676,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_select_with_transform() {
-        // TODO: Switch back to memory://foo after https://github.com/lancedb/lancedb/issues/1051
-        // is fixed
-        let tmp_dir = tempdir().unwrap();
-        let dataset_path = tmp_dir.path().join("test.lance");
-        let uri = dataset_path.to_str().unwrap();
+        let uri = "memory://foo";
 
         let batches = make_non_empty_batches();
         let conn = connect(uri).execute().await.unwrap();
@@ -908,14 +1713,234 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_execute_no_vector() {
-        // TODO: Switch back to memory://foo after https://github.com/lancedb/lancedb/issues/1051
-        // is fixed
+    async fn test_select_with_distance() {
         let tmp_dir = tempdir().unwrap();
-        let dataset_path = tmp_dir.path().join("test.lance");
-        let uri = dataset_path.to_str().unwrap();
+        let table = make_test_table(&tmp_dir).await;
 
-        // test that it's ok to not specify a query vector (just filter / limit)
+        let batches = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .limit(5)
+            .select(Select::dynamic(&[
+                ("dist_sq", "_distance * _distance"),
+                ("key", "id"),
+            ]))
+            .with_row_id(true)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        let names = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["dist_sq", "key", "_distance", "_rowid"]);
+        assert_eq!(batch.num_rows(), 5);
+
+        let dist_sq = batch["dist_sq"].as_primitive::<Float32Type>();
+        let distance = batch["_distance"].as_primitive::<Float32Type>();
+        for (dist_sq, distance) in dist_sq.values().iter().zip(distance.values()) {
+            assert_eq!(*dist_sq, distance * distance);
+        }
+        // The rows are still sorted by distance
+        assert!(distance.values().windows(2).all(|w| w[0] <= w[1]));
+        // The ids are 0..512 so they match the row ids of the single fragment
+        let key = batch["key"].as_primitive::<Int32Type>();
+        let row_id = batch["_rowid"].as_primitive::<UInt64Type>();
+        for (key, row_id) in key.values().iter().zip(row_id.values()) {
+            assert_eq!(*key as u64, *row_id);
+        }
+
+        // Only the distance is selected
+        let batches = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .limit(5)
+            .select(Select::dynamic(&[("score", "1.0 - _distance")]))
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let schema = batches[0].schema();
+        let names = schema.fields().iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["score", "_distance"]);
+
+        // The results of several query vectors can still be matched to their vector
+        let query_vectors = [[0.1, 0.2, 0.3, 0.4], [0.9, 0.8, 0.7, 0.6]];
+        let batches = table
+            .query()
+            .nearest_to_many(query_vectors.iter().map(|v| v.as_slice()))
+            .unwrap()
+            .limit(5)
+            .select(Select::dynamic(&[
+                ("score", "1.0 - _distance"),
+                ("id", "id"),
+            ]))
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        let names = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["score", "id", "_distance", "query_index"]);
+        for (query_index, query_vector) in query_vectors.iter().enumerate() {
+            let expected = table
+                .query()
+                .nearest_to(query_vector.as_slice())
+                .unwrap()
+                .limit(5)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>();
+            let indices = batch["query_index"].as_primitive::<UInt32Type>();
+            let ids = batch["id"].as_primitive::<Int32Type>();
+            let ids = indices
+                .values()
+                .iter()
+                .zip(ids.values())
+                .filter(|(idx, _)| **idx == query_index as u32)
+                .map(|(_, id)| *id)
+                .collect::<Vec<_>>();
+            assert_eq!(ids, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_distance_column_name() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let query = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .limit(10)
+            .select(Select::columns(&["id"]));
+        let collect = |query: VectorQuery| async move {
+            let batches = query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap()
+        };
+        let names = |batch: &RecordBatch| {
+            batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .collect::<Vec<_>>()
+        };
+
+        let default = collect(query.clone()).await;
+        assert_eq!(names(&default), vec!["id", "_distance"]);
+
+        let renamed = collect(query.clone().distance_column_name(Some("score"))).await;
+        assert_eq!(names(&renamed), vec!["id", "score"]);
+        assert_eq!(renamed["score"].as_ref(), default["_distance"].as_ref());
+
+        // Dropping the distance does not change the order of the results
+        let dropped = collect(query.clone().distance_column_name(None)).await;
+        assert_eq!(names(&dropped), vec!["id"]);
+        assert_eq!(dropped["id"].as_ref(), default["id"].as_ref());
+
+        let result = query.distance_column_name(Some("id")).execute().await;
+        assert!(matches!(result, Err(Error::InvalidInput { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_distance_range() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let query = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .select(Select::columns(&["id"]));
+        let distances = |query: VectorQuery| async move {
+            let batches = query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            batches
+                .iter()
+                .flat_map(|batch| {
+                    batch[DIST_COL]
+                        .as_primitive::<Float32Type>()
+                        .values()
+                        .to_vec()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let all = distances(query.clone().limit(512)).await;
+        let (lower, upper) = (all[3], all[20]);
+        let count_in_range = all.iter().filter(|d| **d >= lower && **d < upper).count();
+
+        // The limit applies to the results in the range
+        let in_range = distances(query.clone().distance_range(Some(lower), Some(upper))).await;
+        assert_eq!(in_range.len(), 10);
+        assert!(in_range.iter().all(|d| *d >= lower && *d < upper));
+        assert_eq!(in_range, all[3..13]);
+
+        let in_range = distances(
+            query
+                .clone()
+                .limit(512)
+                .distance_range(Some(lower), Some(upper)),
+        )
+        .await;
+        assert_eq!(in_range.len(), count_in_range);
+
+        let in_range = distances(
+            query
+                .clone()
+                .limit(5)
+                .offset(2)
+                .distance_range(Some(lower), None),
+        )
+        .await;
+        assert_eq!(in_range, all[5..10]);
+
+        let below = distances(query.clone().limit(512).distance_range(None, Some(upper))).await;
+        assert!(below.iter().all(|d| *d < upper));
+        assert_eq!(below, all[..below.len()]);
+
+        let result = query
+            .distance_range(Some(upper), Some(lower))
+            .execute()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidInput { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_execute_no_vector() {
+        let uri = "memory://foo";
+
+        // test that it's ok to not specify a query vector (just filter / limit)
         let batches = make_non_empty_batches();
         let conn = connect(uri).execute().await.unwrap();
         let table = conn
@@ -998,59 +2023,2151 @@ mod tests {
         }
     }
 
-    fn assert_plan_exists(plan: &Arc<dyn ExecutionPlan>, name: &str) -> bool {
-        if plan.name() == name {
-            return true;
+    #[tokio::test]
+    async fn test_batch_size() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let batches = BatchGenerator::new()
+            .col(Box::new(IncrementingInt32::new().named("id".to_string())))
+            .col(Box::new(RandomVector::new().named("vector".to_string())))
+            .batch(1000);
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+
+        let batch_sizes =
+            |batches: Vec<RecordBatch>| batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>();
+        let plain = table
+            .query()
+            .batch_size(100)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let vector = table
+            .query()
+            .nearest_to(&[0.5; 4])
+            .unwrap()
+            .limit(1000)
+            .batch_size(100)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        for sizes in [batch_sizes(plain), batch_sizes(vector)] {
+            assert_eq!(sizes.iter().sum::<usize>(), 1000);
+            assert!(sizes.iter().all(|size| *size <= 100), "{:?}", sizes);
         }
-        plan.children()
+
+        // The batch size takes precedence over the execution options
+        let batches = table
+            .query()
+            .batch_size(100)
+            .execute_with_options(QueryExecutionOptions {
+                max_batch_length: 500,
+            })
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert!(batch_sizes(batches).iter().all(|size| *size <= 100));
+
+        let err = table.query().batch_size(0).execute().await.err().unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_offset() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+
+        let batches = table
+            .query()
+            .offset(100)
+            .limit(50)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let ids = batches
             .iter()
-            .any(|child| assert_plan_exists(child, name))
+            .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, (100..150).collect::<Vec<_>>());
+
+        // An offset past the end of the table is not an error
+        let batches = table
+            .query()
+            .offset(1000)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
     }
 
     #[tokio::test]
-    async fn test_create_execute_plan() {
+    async fn test_offset_vector_query() {
         let tmp_dir = tempdir().unwrap();
         let table = make_test_table(&tmp_dir).await;
-        let plan = table
+
+        let query_ids = |offset: usize, limit: usize| {
+            let table = table.clone();
+            async move {
+                let batches = table
+                    .query()
+                    .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+                    .unwrap()
+                    .offset(offset)
+                    .limit(limit)
+                    .execute()
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap();
+                batches
+                    .iter()
+                    .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        // The offset should be applied after sorting by distance
+        let all = query_ids(0, 10).await;
+        assert_eq!(all.len(), 10);
+        let page = query_ids(5, 5).await;
+        assert_eq!(page, all[5..]);
+
+        let past_end = query_ids(1000, 10).await;
+        assert!(past_end.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_with_row_address() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        table.add(make_non_empty_batches()).execute().await.unwrap();
+        let fragment_ids = table.stats().await.unwrap().fragment_ids;
+        assert_eq!(fragment_ids.len(), 2);
+
+        let batches = table
             .query()
-            .nearest_to(vec![0.1, 0.2, 0.3, 0.4])
+            .only_if("id % 100 = 0")
+            .select(Select::columns(&["id"]))
+            .with_row_address(true)
+            .execute()
+            .await
             .unwrap()
-            .create_plan(QueryExecutionOptions::default())
+            .try_collect::<Vec<_>>()
             .await
             .unwrap();
-        assert_plan_exists(&plan, "KNNFlatSearch");
-        assert_plan_exists(&plan, "ProjectionExec");
+        let mut fragments_seen = HashSet::new();
+        for batch in &batches {
+            assert_eq!(batch.num_columns(), 2);
+            assert!(batch.column_by_name("_rowid").is_none());
+            let ids = batch["id"].as_primitive::<Int32Type>();
+            let addresses = batch["_rowaddr"].as_primitive::<UInt64Type>();
+            for (id, address) in ids.values().iter().zip(addresses.values()) {
+                // The upper 32 bits are the fragment id and the lower the offset
+                let fragment_id = address >> 32;
+                assert!(fragment_ids.contains(&fragment_id));
+                assert!((*address as u32) < 512);
+                fragments_seen.insert(fragment_id);
+
+                let row = table.take(&[*address], None).await.unwrap();
+                assert_eq!(row["id"].as_primitive::<Int32Type>().value(0), *id);
+            }
+        }
+        assert_eq!(fragments_seen.len(), 2);
+
+        // The address survives vector searches and projections of the distance
+        for select in [Select::All, Select::dynamic(&[("score", "_distance * 2")])] {
+            let batches = table
+                .query()
+                .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+                .unwrap()
+                .select(select)
+                .with_row_id(true)
+                .with_row_address(true)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            let schema = batches[0].schema();
+            let names = schema
+                .fields()
+                .iter()
+                .map(|f| f.name().as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(&names[names.len() - 2..], &["_rowid", "_rowaddr"]);
+        }
     }
 
     #[tokio::test]
-    async fn query_base_methods_on_vector_query() {
-        // Make sure VectorQuery can be used as a QueryBase
+    async fn test_with_row_id() {
         let tmp_dir = tempdir().unwrap();
         let table = make_test_table(&tmp_dir).await;
 
-        let mut results = table
-            .vector_search(&[1.0, 2.0, 3.0, 4.0])
+        let batches = table
+            .query()
+            .with_row_id(true)
+            .execute()
+            .await
             .unwrap()
-            .limit(1)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let row_ids = batches
+            .iter()
+            .flat_map(|b| b["_rowid"].as_primitive::<UInt64Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(row_ids.len(), 512);
+        assert!(row_ids.windows(2).all(|w| w[0] < w[1]));
+
+        // The row id survives filters and vector searches
+        let filtered = table
+            .query()
+            .only_if("id >= 100")
+            .select(Select::columns(&["id"]))
+            .with_row_id(true)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        for batch in &filtered {
+            assert!(batch.column_by_name("_rowid").is_some());
+        }
+        let batches = table
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .with_row_id(true)
             .execute()
             .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
             .unwrap();
+        for batch in &batches {
+            assert!(batch.column_by_name("_rowid").is_some());
+        }
 
-        let first_batch = results.next().await.unwrap().unwrap();
-        assert_eq!(first_batch.num_rows(), 1);
-        assert!(results.next().await.is_none());
+        // It is not returned by default
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert!(batches[0].column_by_name("_rowid").is_none());
+    }
 
-        // query with wrong vector dimension
-        let error_result = table
-            .vector_search(&[1.0, 2.0, 3.0])
+    #[tokio::test]
+    async fn test_hamming_distance() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new(
+                "vector",
+                DataType::FixedSizeList(
+                    Arc::new(ArrowField::new("item", DataType::UInt8, true)),
+                    2,
+                ),
+                true,
+            ),
+        ]));
+        // Distances to [0, 0] are 16, 0, 2 and 1 bits
+        let vectors = FixedSizeListArray::from_iter_primitive::<UInt8Type, _, _>(
+            [[0xFF, 0xFF], [0x00, 0x00], [0x03, 0x00], [0x00, 0x80]]
+                .into_iter()
+                .map(|v| Some(v.map(Some))),
+            2,
+        );
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..4)),
+                Arc::new(vectors),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table("binary", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+
+        let search = |query: VectorQuery| async move {
+            let batches = query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            let ids = batches
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>();
+            let distances = batches
+                .iter()
+                .flat_map(|b| {
+                    b["_distance"]
+                        .as_primitive::<Float32Type>()
+                        .values()
+                        .to_vec()
+                })
+                .collect::<Vec<_>>();
+            (ids, distances)
+        };
+
+        let query = table.query().nearest_to(&[0u8, 0u8]).unwrap();
+        let (ids, distances) = search(query.clone()).await;
+        assert_eq!(ids, vec![1, 3, 2, 0]);
+        assert_eq!(distances, vec![0.0, 1.0, 2.0, 16.0]);
+
+        let (ids, _) = search(query.clone().limit(2)).await;
+        assert_eq!(ids, vec![1, 3]);
+        let (ids, _) = search(query.clone().only_if("id != 1").limit(2)).await;
+        assert_eq!(ids, vec![3, 2]);
+        let (ids, _) = search(
+            query
+                .clone()
+                .distance_type(DistanceType::Hamming)
+                .select(Select::columns(&["id"])),
+        )
+        .await;
+        assert_eq!(ids, vec![1, 3, 2, 0]);
+
+        assert!(query
+            .distance_type(DistanceType::L2)
+            .execute()
+            .await
+            .is_err());
+        assert!(table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(IvfPqIndexBuilder::default().distance_type(DistanceType::Hamming))
+            )
+            .execute()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pq_codes_query_vector() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let batches = BatchGenerator::new()
+            .col(Box::new(IncrementingInt32::new().named("id".to_string())))
+            .col(Box::new(
+                RandomVector::new()
+                    .vec_width(16)
+                    .named("vector".to_string()),
+            ))
+            .batch(512);
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .num_partitions(2)
+                        .num_sub_vectors(4),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // One 8 bit code for each of the 4 sub vectors
+        let codes = [3u8, 17, 250, 0];
+        let err = table
+            .query()
+            .nearest_to(&codes)
             .unwrap()
-            .limit(1)
+            .column("vector")
+            .nprobes(2)
             .execute()
-            .await;
-        assert!(error_result
+            .await
             .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("Product quantization codes cannot be used as a query vector"),
+            "{}",
+            err
+        );
+        let err = table
+            .query()
+            .nearest_to(&codes)
             .unwrap()
-            .to_string()
-            .contains("No vector column found to match with the query vector dimension: 3"));
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains(
+                "No binary vector column found to match with the query vector dimension: 4"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dot_distance() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new(
+                "vector",
+                DataType::FixedSizeList(
+                    Arc::new(ArrowField::new("item", DataType::Float32, true)),
+                    2,
+                ),
+                true,
+            ),
+        ]));
+        // Dot products with [1, 0] are 0.9, 5, 3 and -1 but the L2 order is 0, 3, 2, 1
+        let vectors = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            [[0.9, 0.0], [5.0, 5.0], [3.0, -1.0], [-1.0, 0.0]]
+                .into_iter()
+                .map(|v| Some(v.map(Some))),
+            2,
+        );
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..4)),
+                Arc::new(vectors),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table("dot", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+
+        let search = |query: VectorQuery| async move {
+            let batch = query
+                .execute()
+                .await
+                .unwrap()
+                .next()
+                .await
+                .unwrap()
+                .unwrap();
+            let ids = batch["id"].as_primitive::<Int32Type>().values().to_vec();
+            let distances = batch["_distance"]
+                .as_primitive::<Float32Type>()
+                .values()
+                .to_vec();
+            (ids, distances)
+        };
+
+        let query = table.query().nearest_to(&[1.0, 0.0]).unwrap();
+        let (ids, _) = search(query.clone()).await;
+        assert_eq!(ids, vec![0, 3, 2, 1]);
+
+        let query = query.distance_type(DistanceType::Dot);
+        let (ids, distances) = search(query.clone()).await;
+        assert_eq!(ids, vec![1, 2, 0, 3]);
+        for (distance, expected) in distances.iter().zip([-4.0, -2.0, 0.1, 2.0]) {
+            assert!((distance - expected).abs() < 1e-6, "{:?}", distances);
+        }
+        let (ids, _) = search(query.limit(2)).await;
+        assert_eq!(ids, vec![1, 2]);
+
+        // An index trained with the dot distance returns the largest dot products
+        // first, when every partition is probed and refined it matches a flat search
+        let dim = 8;
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new(
+                "vector",
+                DataType::FixedSizeList(
+                    Arc::new(ArrowField::new("item", DataType::Float32, true)),
+                    dim,
+                ),
+                true,
+            ),
+        ]));
+        let vectors = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            (0..512).map(|i| {
+                Some(
+                    (0..dim)
+                        .map(|j| Some(((i * dim + j) as f32 * 0.731).sin() * (i % 5 + 1) as f32))
+                        .collect::<Vec<_>>(),
+                )
+            }),
+            dim,
+        );
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..512)),
+                Arc::new(vectors),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table(
+                "dot_indexed",
+                RecordBatchIterator::new(vec![Ok(batch)], schema),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let query_vector = [1.0, -0.5, 0.25, 2.0, 0.0, -1.0, 0.5, 1.0];
+        let flat = search(
+            table
+                .query()
+                .nearest_to(&query_vector)
+                .unwrap()
+                .distance_type(DistanceType::Dot)
+                .limit(10),
+        )
+        .await;
+
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .distance_type(DistanceType::Dot)
+                        .num_partitions(2)
+                        .num_sub_vectors(2),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let indexed = search(
+            table
+                .query()
+                .nearest_to(&query_vector)
+                .unwrap()
+                .distance_type(DistanceType::Dot)
+                .limit(10)
+                .nprobes(2)
+                .refine_factor(100),
+        )
+        .await;
+        assert_eq!(indexed.0, flat.0);
+        assert!(indexed.1.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[tokio::test]
+    async fn test_distance_type_overrides_index() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let dim = 8;
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new(
+                "vector",
+                DataType::FixedSizeList(
+                    Arc::new(ArrowField::new("item", DataType::Float32, true)),
+                    dim,
+                ),
+                true,
+            ),
+        ]));
+        // Vectors with very different magnitudes so the cosine and L2 orders differ
+        let rows = (0..512)
+            .map(|i| {
+                (0..dim)
+                    .map(|j| ((i * dim + j) as f32 * 0.731).sin() * (i % 7 + 1) as f32)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let vectors = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            rows.iter()
+                .map(|row| Some(row.iter().copied().map(Some).collect::<Vec<_>>())),
+            dim,
+        );
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..512)),
+                Arc::new(vectors),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table(
+                "l2_indexed",
+                RecordBatchIterator::new(vec![Ok(batch)], schema),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .distance_type(DistanceType::L2)
+                        .num_partitions(2)
+                        .num_sub_vectors(2),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let query_vector = [1.0, -0.5, 0.25, 2.0, 0.0, -1.0, 0.5, 1.0];
+        let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let mut expected = rows
+            .iter()
+            .enumerate()
+            .map(|(id, row)| {
+                let dot = row
+                    .iter()
+                    .zip(query_vector)
+                    .map(|(a, b)| a * b)
+                    .sum::<f32>();
+                (id as i32, 1.0 - dot / (norm(row) * norm(&query_vector)))
+            })
+            .collect::<Vec<_>>();
+        expected.sort_by(|a, b| a.1.total_cmp(&b.1));
+        expected.truncate(10);
+
+        let query = table
+            .query()
+            .nearest_to(&query_vector)
+            .unwrap()
+            .distance_type(DistanceType::Cosine)
+            .limit(10);
+        // The index was trained with L2 so it is not used
+        let plan = query.explain_plan(false).await.unwrap();
+        assert!(!plan.contains("ANNSubIndex"), "{}", plan);
+        assert!(plan.contains("KNNFlat"), "{}", plan);
+
+        let batch = query
+            .execute()
+            .await
+            .unwrap()
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        let ids = batch["id"].as_primitive::<Int32Type>().values().to_vec();
+        assert_eq!(ids, expected.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+        let distances = batch["_distance"].as_primitive::<Float32Type>().values();
+        for (distance, (_, expected)) in distances.iter().zip(&expected) {
+            assert!((distance - expected).abs() < 1e-5, "{:?}", distances);
+        }
+
+        // A query with the index's own distance type still uses the index
+        let plan = table
+            .query()
+            .nearest_to(&query_vector)
+            .unwrap()
+            .distance_type(DistanceType::L2)
+            .explain_plan(false)
+            .await
+            .unwrap();
+        assert!(plan.contains("ANNSubIndex"), "{}", plan);
+    }
+
+    #[tokio::test]
+    async fn test_struct_columns() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let metadata_fields = Fields::from(vec![
+            ArrowField::new("source", DataType::Utf8, true),
+            ArrowField::new("score", DataType::Float32, true),
+        ]);
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new("metadata", DataType::Struct(metadata_fields.clone()), true),
+        ]));
+        let metadata = StructArray::new(
+            metadata_fields,
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    (0..10).map(|i| format!("source {}", i)),
+                )),
+                Arc::new(Float32Array::from_iter_values(
+                    (0..10).map(|i| i as f32 / 10.0),
+                )),
+            ],
+            None,
+        );
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..10)),
+                Arc::new(metadata),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table("test", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+
+        let collect = |query: Query| async move {
+            let batches = query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap()
+        };
+
+        let results = collect(table.query().only_if("metadata.score > 0.55")).await;
+        assert_eq!(
+            results["id"].as_primitive::<Int32Type>().values().as_ref(),
+            &[6, 7, 8, 9]
+        );
+
+        let results = collect(
+            table
+                .query()
+                .only_if("metadata.source = 'source 3'")
+                .select(Select::columns(&["id", "metadata.score"])),
+        )
+        .await;
+        assert_eq!(results.num_columns(), 2);
+        assert_eq!(results["id"].as_primitive::<Int32Type>().value(0), 3);
+        let score = results["metadata.score"].as_primitive::<Float32Type>();
+        assert_eq!(score.values().as_ref(), &[0.3]);
+
+        let results = collect(
+            table
+                .query()
+                .only_if("metadata.source = 'source 3'")
+                .select(Select::dynamic(&[("score", "metadata.score * 2")])),
+        )
+        .await;
+        let score = results["score"].as_primitive::<Float32Type>();
+        assert_eq!(score.values().as_ref(), &[0.6]);
+
+        // The whole struct is returned when selected by name
+        let results = collect(
+            table
+                .query()
+                .only_if("metadata.score < 0.15")
+                .select(Select::columns(&["metadata"])),
+        )
+        .await;
+        let metadata = results["metadata"].as_struct();
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata["source"].as_string::<i32>().value(1), "source 1");
+    }
+
+    #[tokio::test]
+    async fn test_float16_vectors() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+
+        let dim = 16;
+        let num_rows = 512;
+        // Keep the values small so that the distances fit in a f16
+        let vector_value = |row: i32, col: i32| row as f32 / num_rows as f32 + col as f32 / 1000.0;
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new(
+                "vector",
+                DataType::FixedSizeList(
+                    Arc::new(ArrowField::new("item", DataType::Float16, true)),
+                    dim,
+                ),
+                false,
+            ),
+        ]));
+        let values = Float16Array::from_iter_values(
+            (0..num_rows * dim).map(|v| f16::from_f32(vector_value(v / dim, v % dim))),
+        );
+        let vectors = FixedSizeListArray::try_new(
+            Arc::new(ArrowField::new("item", DataType::Float16, true)),
+            dim,
+            Arc::new(values),
+            None,
+        )
+        .unwrap();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..num_rows)),
+                Arc::new(vectors),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table(
+                "f16",
+                RecordBatchIterator::new(vec![Ok(batch)], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let query_f32 = (0..dim)
+            .map(|col| vector_value(100, col))
+            .collect::<Vec<_>>();
+        let query_f16 = query_f32
+            .iter()
+            .map(|v| f16::from_f32(*v))
+            .collect::<Vec<_>>();
+
+        let nearest_id = |batches: Vec<RecordBatch>| {
+            assert_eq!(
+                batches[0].column_by_name("vector").unwrap().data_type(),
+                schema.field(1).data_type()
+            );
+            batches[0]["id"].as_primitive::<Int32Type>().value(0)
+        };
+
+        for use_index in [false, true] {
+            if use_index {
+                table
+                    .create_index(
+                        &["vector"],
+                        Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+                    )
+                    .execute()
+                    .await
+                    .unwrap();
+                let plan = table
+                    .query()
+                    .nearest_to(query_f16.as_slice())
+                    .unwrap()
+                    .nprobes(2)
+                    .explain_plan(false)
+                    .await
+                    .unwrap();
+                assert!(plan.contains("ANNSubIndex"), "{}", plan);
+            }
+            let batches = table
+                .query()
+                .nearest_to(query_f32.as_slice())
+                .unwrap()
+                .nprobes(2)
+                .refine_factor(10)
+                .limit(1)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            assert_eq!(nearest_id(batches), 100);
+
+            let batches = table
+                .query()
+                .nearest_to(query_f16.as_slice())
+                .unwrap()
+                .nprobes(2)
+                .refine_factor(10)
+                .limit(1)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            assert_eq!(nearest_id(batches), 100);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fast_search() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+
+        let dim = 16;
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new(
+                "vector",
+                DataType::FixedSizeList(
+                    Arc::new(ArrowField::new("item", DataType::Float32, true)),
+                    dim,
+                ),
+                false,
+            ),
+        ]));
+        let make_batches = |ids: std::ops::Range<i32>, value: fn(i32) -> f32| {
+            let values = Float32Array::from_iter_values(
+                ids.clone().flat_map(|id| (0..dim).map(move |_| value(id))),
+            );
+            let vectors = FixedSizeListArray::try_new(
+                Arc::new(ArrowField::new("item", DataType::Float32, true)),
+                dim,
+                Arc::new(values),
+                None,
+            )
+            .unwrap();
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from_iter_values(ids)),
+                    Arc::new(vectors),
+                ],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+
+        let table = conn
+            .create_table("test", make_batches(0..1000, |id| id as f32 / 1000.0))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+            )
+            .execute()
+            .await
+            .unwrap();
+        // The new rows are much closer to the query vector than the indexed rows
+        table
+            .add(make_batches(1000..1100, |id| id as f32))
+            .execute()
+            .await
+            .unwrap();
+
+        let nearest_id = |fast_search: bool| {
+            let table = table.clone();
+            async move {
+                let batches = table
+                    .query()
+                    .nearest_to(vec![1000.0; dim as usize])
+                    .unwrap()
+                    .nprobes(2)
+                    .fast_search(fast_search)
+                    .limit(1)
+                    .execute()
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap();
+                batches[0]["id"].as_primitive::<Int32Type>().value(0)
+            }
+        };
+
+        assert_eq!(nearest_id(false).await, 1000);
+        assert!(nearest_id(true).await < 1000);
+
+        // The flat search over the new rows is also skipped when there is a filter
+        let batches = table
+            .query()
+            .nearest_to(vec![1000.0; dim as usize])
+            .unwrap()
+            .nprobes(2)
+            .fast_search(true)
+            .only_if("id >= 500")
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let ids = batches[0]["id"].as_primitive::<Int32Type>();
+        assert!(ids.values().iter().all(|id| (500..1000).contains(id)));
+
+        // A prefilter that uses a scalar index keeps all of the indexed rows
+        let table = conn
+            .create_table("scalar", make_batches(0..1000, |id| id as f32 / 1000.0))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["id"], Index::BTree(BTreeIndexBuilder::default()))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(make_batches(1000..1100, |id| id as f32))
+            .execute()
+            .await
+            .unwrap();
+        let query = table
+            .query()
+            .nearest_to(vec![1000.0; dim as usize])
+            .unwrap()
+            .fast_search(true)
+            .only_if("id >= 500")
+            .limit(2000);
+        let plan = query.explain_plan(false).await.unwrap();
+        assert!(plan.contains("ScalarIndexQuery"), "{}", plan);
+        let mut ids = query
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .iter()
+            .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, (500..1000).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_bypass_vector_index() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let dim = 16;
+        let batches = BatchGenerator::new()
+            .col(Box::new(IncrementingInt32::new().named("id".to_string())))
+            .col(Box::new(
+                RandomVector::new()
+                    .vec_width(dim)
+                    .named("vector".to_string()),
+            ))
+            .batch(1000);
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .num_partitions(8)
+                        .num_sub_vectors(2),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // Compute the exact nearest neighbors by hand
+        let query = vec![0.5; dim as usize];
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let mut truth = batches
+            .iter()
+            .flat_map(|batch| {
+                let ids = batch["id"].as_primitive::<Int32Type>().clone();
+                let vectors = batch["vector"].as_fixed_size_list().clone();
+                (0..batch.num_rows())
+                    .map(|row| {
+                        let vector = vectors.value(row);
+                        let vector = vector.as_primitive::<Float32Type>();
+                        let distance = vector
+                            .values()
+                            .iter()
+                            .zip(query.iter())
+                            .map(|(a, b)| (a - b) * (a - b))
+                            .sum::<f32>();
+                        (ids.value(row), distance)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        truth.sort_by(|a, b| a.1.total_cmp(&b.1));
+        truth.truncate(10);
+
+        let search = table
+            .query()
+            .nearest_to(query.as_slice())
+            .unwrap()
+            .nprobes(1)
+            .limit(10);
+        let plan = search.explain_plan(false).await.unwrap();
+        assert!(plan.contains("ANNSubIndex"), "{}", plan);
+        let plan = search
+            .clone()
+            .bypass_vector_index()
+            .explain_plan(false)
+            .await
+            .unwrap();
+        assert!(plan.contains("KNNFlat"), "{}", plan);
+        assert!(!plan.contains("ANN"), "{}", plan);
+
+        let batches = search
+            .clone()
+            .bypass_vector_index()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        let ids = batch["id"].as_primitive::<Int32Type>();
+        let distances = batch["_distance"].as_primitive::<Float32Type>();
+        assert_eq!(
+            ids.values().to_vec(),
+            truth.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+        );
+        for (distance, (_, expected)) in distances.values().iter().zip(truth.iter()) {
+            assert!(
+                (distance - expected).abs() < 1e-4,
+                "{} {}",
+                distance,
+                expected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hnsw_ef() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let dim = 16;
+        let batches = BatchGenerator::new()
+            .col(Box::new(IncrementingInt32::new().named("id".to_string())))
+            .col(Box::new(
+                RandomVector::new()
+                    .vec_width(dim)
+                    .named("vector".to_string()),
+            ))
+            .batch(2000);
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+        // A sparse graph so that a narrow search misses some neighbors
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfHnswSq(
+                    IvfHnswSqIndexBuilder::default()
+                        .num_partitions(1)
+                        .num_edges(4)
+                        .ef_construction(20),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let nearest_ids = |query: VectorQuery| async move {
+            let batches = query
+                .limit(10)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            batches
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<HashSet<_>>()
+        };
+        let queries = lance_testing::datagen::generate_random_array(20 * dim as usize);
+        let mut recall = HashMap::new();
+        for query in queries.values().chunks(dim as usize) {
+            let search = table.query().nearest_to(query).unwrap().nprobes(1);
+            let truth = nearest_ids(search.clone().bypass_vector_index()).await;
+            for ef in [10, 200] {
+                let found = nearest_ids(search.clone().ef(ef)).await;
+                *recall.entry(ef).or_insert(0) += found.intersection(&truth).count();
+            }
+        }
+        assert!(recall[&200] >= recall[&10]);
+
+        let err = table
+            .query()
+            .nearest_to(&[0.5; 16])
+            .unwrap()
+            .nprobes(1)
+            .ef(5)
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains(
+                "ef must be at least the number of results (limit + offset) of 10 but was 5"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multiple_query_vectors() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+
+        let query_vectors = [[0.1, 0.2, 0.3, 0.4], [0.9, 0.8, 0.7, 0.6]];
+        let batches = table
+            .query()
+            .nearest_to_many(query_vectors.iter().map(|v| v.as_slice()))
+            .unwrap()
+            .limit(5)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        // The limit applies to each query vector
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+
+        for (query_index, query_vector) in query_vectors.iter().enumerate() {
+            let mut ids = Vec::new();
+            for batch in &batches {
+                let indices = batch["query_index"].as_primitive::<UInt32Type>();
+                let batch_ids = batch["id"].as_primitive::<Int32Type>();
+                for (idx, id) in indices.values().iter().zip(batch_ids.values()) {
+                    if *idx == query_index as u32 {
+                        ids.push(*id);
+                    }
+                }
+            }
+
+            let expected = table
+                .query()
+                .nearest_to(query_vector.as_slice())
+                .unwrap()
+                .limit(5)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>();
+            assert_eq!(ids, expected);
+        }
+
+        // A single query vector does not add a query_index column
+        let single = table
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .execute()
+            .await
+            .unwrap();
+        assert!(single.schema().column_with_name("query_index").is_none());
+
+        let no_vectors: Vec<&[f32]> = Vec::new();
+        assert!(table.query().nearest_to_many(no_vectors).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_vector_search_params() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let query = table.query().nearest_to(&[0.1, 0.2, 0.3, 0.4]).unwrap();
+
+        // The default nprobes (20) is larger than the number of partitions but it
+        // was not set explicitly so it is not rejected
+        let batches = query
+            .clone()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+
+        query.clone().nprobes(2).execute().await.unwrap();
+
+        let err = query.clone().nprobes(3).execute().await.err().unwrap();
+        assert!(
+            matches!(&err, Error::InvalidInput { message } if message.contains("between 1 and 2")),
+            "unexpected error: {}",
+            err
+        );
+        let err = query.clone().nprobes(0).execute().await.err().unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }));
+
+        // nprobes is not checked if the index is bypassed
+        query
+            .clone()
+            .nprobes(3)
+            .bypass_vector_index()
+            .execute()
+            .await
+            .unwrap();
+
+        let err = query.refine_factor(0).execute().await.err().unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_string_matching_filters() {
+        let tmp_dir = tempdir().unwrap();
+        let dataset_path = tmp_dir.path().join("test.lance");
+        let uri = dataset_path.to_str().unwrap();
+
+        let names = StringArray::from(vec![
+            "prod-api",
+            "prod-web",
+            "dev-api",
+            "PROD-db",
+            "100%_sure",
+            "100 percent",
+        ]);
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "name",
+            DataType::Utf8,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(names)]).unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table(
+                "my_table",
+                RecordBatchIterator::new(vec![Ok(batch)], schema),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let matching = |filter: &'static str| {
+            let table = table.clone();
+            async move {
+                let batches = table
+                    .query()
+                    .only_if(filter)
+                    .execute()
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap();
+                let mut names = batches
+                    .iter()
+                    .flat_map(|b| {
+                        b["name"]
+                            .as_string::<i32>()
+                            .iter()
+                            .map(|s| s.unwrap().to_string())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                names.sort();
+                names
+            }
+        };
+
+        // prefix, suffix, and infix patterns
+        assert_eq!(
+            matching("name LIKE 'prod-%'").await,
+            ["prod-api", "prod-web"]
+        );
+        assert_eq!(matching("name LIKE '%-api'").await, ["dev-api", "prod-api"]);
+        assert_eq!(
+            matching("name LIKE '%o%-%'").await,
+            ["prod-api", "prod-web"]
+        );
+        assert_eq!(
+            matching("name NOT LIKE '%-%'").await,
+            ["100 percent", "100%_sure"]
+        );
+        // ILIKE is case insensitive
+        assert_eq!(
+            matching("name ILIKE 'prod-%'").await,
+            ["PROD-db", "prod-api", "prod-web"]
+        );
+        // escaped wildcards match literally
+        assert_eq!(matching(r"name LIKE '100\%\_%'").await, ["100%_sure"]);
+        // regular expressions
+        assert_eq!(
+            matching("regexp_match(name, '^(prod|dev)-api$') IS NOT NULL").await,
+            ["dev-api", "prod-api"]
+        );
+
+        // The same filters can be used to delete rows
+        table.delete("name LIKE 'prod-%'").await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 4);
+        table
+            .delete("regexp_match(name, '^1') IS NOT NULL")
+            .await
+            .unwrap();
+        assert_eq!(matching("true").await, ["PROD-db", "dev-api"]);
+    }
+
+    fn assert_plan_exists(plan: &Arc<dyn ExecutionPlan>, name: &str) -> bool {
+        if plan.name() == name {
+            return true;
+        }
+        plan.children()
+            .iter()
+            .any(|child| assert_plan_exists(child, name))
+    }
+
+    #[tokio::test]
+    async fn test_create_execute_plan() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let plan = table
+            .query()
+            .nearest_to(vec![0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .create_plan(QueryExecutionOptions::default())
+            .await
+            .unwrap();
+        assert_plan_exists(&plan, "KNNFlatSearch");
+        assert_plan_exists(&plan, "ProjectionExec");
+    }
+
+    #[tokio::test]
+    async fn test_explain_plan() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        table
+            .create_index(&["id"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        let plan = table
+            .query()
+            .only_if("id = 10")
+            .explain_plan(false)
+            .await
+            .unwrap();
+        assert!(plan.contains("MaterializeIndex"), "{}", plan);
+
+        let plan = table
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .only_if("id = 10")
+            .explain_plan(false)
+            .await
+            .unwrap();
+        assert!(plan.contains("MaterializeIndex"), "{}", plan);
+        assert!(plan.contains("KNNFlat"), "{}", plan);
+
+        // There is no index on the vector column so it has to be scanned
+        let plan = table
+            .query()
+            .only_if("vector IS NOT NULL")
+            .explain_plan(true)
+            .await
+            .unwrap();
+        assert!(!plan.contains("MaterializeIndex"), "{}", plan);
+        assert!(plan.contains("LanceScan"), "{}", plan);
+        assert!(plan.contains("Rows="), "{}", plan);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_plan() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let analysis = table
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .nprobes(2)
+            .limit(5)
+            .analyze_plan()
+            .await
+            .unwrap();
+        assert!(analysis.contains("nprobes=2"), "{}", analysis);
+        assert!(analysis.contains("metrics=["), "{}", analysis);
+        assert!(analysis.contains("Total rows: 5"), "{}", analysis);
+    }
+
+    #[tokio::test]
+    async fn test_prefilter_with_scalar_index() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["id"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        let query = table
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .nprobes(2)
+            .only_if("id BETWEEN 100 AND 200")
+            .limit(20);
+        // The BTree index is searched to find the allowed rows before the ANN search
+        let analysis = query.analyze_plan().await.unwrap();
+        assert!(
+            analysis.contains("ScalarIndexQuery: query=AND(id >= 100,id <= 200)"),
+            "{}",
+            analysis
+        );
+        assert!(analysis.contains("ANNSubIndex"), "{}", analysis);
+        assert!(!analysis.contains("LanceScan"), "{}", analysis);
+        assert!(analysis.contains("Total rows: 20"), "{}", analysis);
+
+        let batches = query
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let ids = batches
+            .iter()
+            .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids.len(), 20);
+        assert!(ids.iter().all(|id| (100..=200).contains(id)), "{:?}", ids);
+
+        // Post filtering runs the filter on the results of the search instead
+        let analysis = query.postfilter().analyze_plan().await.unwrap();
+        assert!(!analysis.contains("ScalarIndexQuery"), "{}", analysis);
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let vector = Box::new(
+            RandomVector::new()
+                .vec_width(128)
+                .named("vector".to_string()),
+        );
+        let id = Box::new(IncrementingInt32::new().named("id".to_string()));
+        let table = conn
+            .create_table(
+                "slow",
+                BatchGenerator::new().col(vector).col(id).batches(50, 1000),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // A flat search compares the query with all 50k vectors
+        let start = Instant::now();
+        let result = async {
+            table
+                .vector_search(vec![0.5; 128])
+                .unwrap()
+                .timeout(Duration::from_millis(1))
+                .execute()
+                .await?
+                .try_collect::<Vec<_>>()
+                .await
+        }
+        .await;
+        assert!(matches!(result, Err(Error::Timeout { .. })), "{:?}", result);
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // The deadline also applies while the results are read
+        let mut results = table
+            .query()
+            .timeout(Duration::from_secs(2))
+            .execute_with_options(QueryExecutionOptions {
+                max_batch_length: 100,
+            })
+            .await
+            .unwrap();
+        results.next().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        let err = results.next().await.unwrap().unwrap_err();
+        assert!(matches!(err, Error::Timeout { .. }), "{}", err);
+        assert!(results.next().await.is_none());
+
+        // A query that finishes in time is unaffected
+        let batches = table
+            .query()
+            .only_if("id < 10")
+            .timeout(Duration::from_secs(60))
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_prefilter_vs_postfilter() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // Only 5% of the rows match the filter
+        let query = table
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .nprobes(2)
+            .only_if("id % 20 = 0")
+            .limit(10);
+        let matching_ids = |query: VectorQuery| async move {
+            let batches = query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            let ids = batches
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>();
+            assert!(ids.iter().all(|id| id % 20 == 0), "{:?}", ids);
+            ids.len()
+        };
+
+        let prefiltered = matching_ids(query.clone().postfilter().prefilter()).await;
+        assert_eq!(prefiltered, 10);
+        // Only the nearest 10 candidates are filtered
+        let postfiltered = matching_ids(query.clone().postfilter()).await;
+        assert!(postfiltered < prefiltered, "{}", postfiltered);
+        // Asking for more candidates finds more of the matching rows
+        let more_candidates = matching_ids(query.postfilter().limit(200)).await;
+        assert!(more_candidates > postfiltered, "{}", more_candidates);
+    }
+
+    #[tokio::test]
+    async fn query_base_methods_on_vector_query() {
+        // Make sure VectorQuery can be used as a QueryBase
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+
+        let mut results = table
+            .vector_search(&[1.0, 2.0, 3.0, 4.0])
+            .unwrap()
+            .limit(1)
+            .execute()
+            .await
+            .unwrap();
+
+        let first_batch = results.next().await.unwrap().unwrap();
+        assert_eq!(first_batch.num_rows(), 1);
+        assert!(results.next().await.is_none());
+
+        // query with wrong vector dimension
+        let error_result = table
+            .vector_search(&[1.0, 2.0, 3.0])
+            .unwrap()
+            .limit(1)
+            .execute()
+            .await;
+        assert!(error_result
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("No vector column found to match with the query vector dimension: 3"));
+    }
+
+    #[tokio::test]
+    async fn test_vector_column_detection() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let vectors = |dim: usize, value: f32| {
+            Arc::new(
+                FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                    (0..3).map(|i| Some(vec![Some(value + i as f32); dim])),
+                    dim as i32,
+                ),
+            ) as ArrayRef
+        };
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from_iter_values(0..3)) as ArrayRef,
+            ),
+            ("a", vectors(4, 0.0)),
+            ("b", vectors(4, 10.0)),
+            ("c", vectors(2, 0.0)),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        let table = conn
+            .create_table("vectors", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+
+        // Only one column has the dimension of the query vector
+        let batch = table
+            .query()
+            .nearest_to(&[2.0, 2.0])
+            .unwrap()
+            .limit(1)
+            .execute()
+            .await
+            .unwrap()
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch["id"].as_primitive::<Int32Type>().values(), &[2]);
+
+        let err = table
+            .query()
+            .nearest_to(&[2.0, 2.0, 2.0, 2.0])
+            .unwrap()
+            .explain_plan(false)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::AmbiguousVectorColumn { candidates } if candidates == &["a", "b"]),
+            "{}",
+            err
+        );
+
+        let batch = table
+            .query()
+            .nearest_to(&[12.0, 12.0, 12.0, 12.0])
+            .unwrap()
+            .column("b")
+            .limit(1)
+            .execute()
+            .await
+            .unwrap()
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch["id"].as_primitive::<Int32Type>().values(), &[2]);
+    }
+
+    #[tokio::test]
+    async fn test_with_fragments() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "id",
+            DataType::Int32,
+            false,
+        )]));
+        let batches = |ids: std::ops::Range<i32>| {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(ids))],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let table = conn
+            .create_table("fragments", batches(0..10))
+            .execute()
+            .await
+            .unwrap();
+        table.add(batches(10..20)).execute().await.unwrap();
+        table.add(batches(20..30)).execute().await.unwrap();
+        let fragment_ids = table.stats().await.unwrap().fragment_ids;
+        assert_eq!(fragment_ids, vec![0, 1, 2]);
+
+        let ids = |batches: Vec<RecordBatch>| {
+            batches
+                .iter()
+                .flat_map(|batch| batch["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let batches = table
+            .query()
+            .with_fragments(&[1])
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(ids(batches), (10..20).collect::<Vec<_>>());
+
+        // The fragments are scanned in the given order
+        let batches = table
+            .query()
+            .with_fragments(&[2, 0])
+            .only_if("id % 2 = 0")
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(ids(batches), vec![20, 22, 24, 26, 28, 0, 2, 4, 6, 8]);
+
+        let err = table
+            .query()
+            .with_fragments(&[3])
+            .explain_plan(false)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("fragment 3 does not exist in version 3 of the table"),
+            "{}",
+            err
+        );
+        let err = table
+            .query()
+            .with_fragments(&[0])
+            .nearest_to(&[1.0])
+            .unwrap()
+            .explain_plan(false)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("with_fragments cannot be combined with a vector search"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_select_except() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let int_column = |name: &str| Box::new(IncrementingInt32::new().named(name.to_string()));
+        let batches = BatchGenerator::new()
+            .col(Box::new(RandomVector::new().named("vector".to_string())))
+            .col(int_column("a"))
+            .col(int_column("b"))
+            .col(int_column("c"))
+            .col(int_column("d"))
+            .batch(10);
+        let table = conn
+            .create_table("except", batches)
+            .execute()
+            .await
+            .unwrap();
+
+        let names = |batch: &RecordBatch| {
+            batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| f.name().to_string())
+                .collect::<Vec<_>>()
+        };
+        let results = table
+            .query()
+            .select_except(&["vector", "c"])
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(names(&results[0]), vec!["a", "b", "d"]);
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+
+        // A vector search keeps the distance unless it is excluded
+        let query = table.query().nearest_to(&[0.1, 0.2, 0.3, 0.4]).unwrap();
+        let results = query
+            .clone()
+            .select_except(&["vector", "a"])
+            .limit(3)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(names(&results[0]), vec!["b", "c", "d", "_distance"]);
+        let results = query
+            .clone()
+            .select_except(&["vector", "_distance"])
+            .limit(3)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(names(&results[0]), vec!["a", "b", "c", "d"]);
+
+        for (excluded, message) in [
+            (vec!["e"], "cannot exclude column e, it does not exist"),
+            (vec!["_distance"], "cannot exclude column _distance"),
+            (
+                vec!["vector", "a", "b", "c", "d"],
+                "cannot exclude every column",
+            ),
+        ] {
+            let err = table
+                .query()
+                .select_except(&excluded)
+                .execute()
+                .await
+                .err()
+                .unwrap();
+            assert!(err.to_string().contains(message), "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multiple_vector_columns() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let batches = BatchGenerator::new()
+            .col(Box::new(
+                RandomVector::new()
+                    .vec_width(16)
+                    .named("text_vector".to_string()),
+            ))
+            .col(Box::new(
+                RandomVector::new()
+                    .vec_width(8)
+                    .named("image_vector".to_string()),
+            ))
+            .col(Box::new(IncrementingInt32::new().named("id".to_string())))
+            .batch(512);
+        let table = conn
+            .create_table("vectors", batches)
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(
+                &["text_vector"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .num_partitions(4)
+                        .num_sub_vectors(4),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(
+                &["image_vector"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .distance_type(DistanceType::Cosine)
+                        .num_partitions(2)
+                        .num_sub_vectors(2),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let mut indices = table
+            .list_indices()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|index| (index.name, index.columns))
+            .collect::<Vec<_>>();
+        indices.sort();
+        assert_eq!(
+            indices,
+            vec![
+                (
+                    "image_vector_idx".to_string(),
+                    vec!["image_vector".to_string()]
+                ),
+                (
+                    "text_vector_idx".to_string(),
+                    vec!["text_vector".to_string()]
+                ),
+            ]
+        );
+
+        for (column, dim) in [("text_vector", 16), ("image_vector", 8)] {
+            let query = table
+                .query()
+                .nearest_to(vec![0.5; dim])
+                .unwrap()
+                .column(column)
+                .nprobes(2)
+                .limit(5);
+            let plan = query.explain_plan(false).await.unwrap();
+            assert!(
+                plan.contains(&format!("ANNSubIndex: name={}_idx", column)),
+                "{}",
+                plan
+            );
+            let batches = query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+
+            // Without a column the column is chosen by the dimension
+            let plan = table
+                .query()
+                .nearest_to(vec![0.5; dim])
+                .unwrap()
+                .nprobes(2)
+                .explain_plan(false)
+                .await
+                .unwrap();
+            assert!(
+                plan.contains(&format!("ANNSubIndex: name={}_idx", column)),
+                "{}",
+                plan
+            );
+        }
+
+        // The dimension of the query vector must match the column
+        let err = table
+            .query()
+            .nearest_to(vec![0.5; 8])
+            .unwrap()
+            .column("text_vector")
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("query dim=8, expected vector dim=16"),
+            "{}",
+            err
+        );
+        let err = table
+            .query()
+            .nearest_to(vec![0.5; 4])
+            .unwrap()
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("No vector column found to match with the query vector dimension: 4"),
+            "{}",
+            err
+        );
+    }
+
+    #[cfg(feature = "polars")]
+    #[tokio::test]
+    async fn test_execute_into_polars() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+
+        let df = table
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .select(Select::columns(&["id"]))
+            .limit(10)
+            .execute_into_polars()
+            .await
+            .unwrap();
+        assert_eq!(df.shape(), (10, 2));
+        assert_eq!(df.get_column_names(), vec!["id", "_distance"]);
+
+        let df = table
+            .query()
+            .select(Select::columns(&["id"]))
+            .only_if("id < 5")
+            .execute_into_polars()
+            .await
+            .unwrap();
+        assert_eq!(df.shape(), (5, 1));
+        assert_eq!(df.get_column_names(), vec!["id"]);
+        let ids = df["id"]
+            .i32()
+            .unwrap()
+            .into_no_null_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+
+        // A query without results still has the columns of the results
+        let df = table
+            .query()
+            .select(Select::columns(&["id"]))
+            .only_if("id < 0")
+            .execute_into_polars()
+            .await
+            .unwrap();
+        assert_eq!(df.shape(), (0, 1));
+        assert_eq!(df.get_column_names(), vec!["id"]);
     }
 }
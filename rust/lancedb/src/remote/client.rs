@@ -12,19 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    RequestBuilder, Response,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    RequestBuilder, Response, StatusCode,
 };
 
+use crate::connection::{AuthToken, RetryConfig, TokenProvider};
 use crate::error::{Error, Result};
 
+/// A token is refreshed when it expires within this duration
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
 #[derive(Clone, Debug)]
 pub struct RestfulLanceDbClient {
     client: reqwest::Client,
     host: String,
+    retry_config: RetryConfig,
+    token_provider: Option<Arc<dyn TokenProvider>>,
+    /// The last token from the token provider
+    token: Arc<Mutex<Option<AuthToken>>>,
 }
 
 impl RestfulLanceDbClient {
@@ -33,18 +43,21 @@ impl RestfulLanceDbClient {
     }
 
     fn default_headers(
-        api_key: &str,
+        api_key: Option<&str>,
         region: &str,
         db_name: &str,
         has_host_override: bool,
+        extra_headers: &HashMap<String, String>,
     ) -> Result<HeaderMap> {
         let mut headers = HeaderMap::new();
-        headers.insert(
-            "x-api-key",
-            HeaderValue::from_str(api_key).map_err(|_| Error::Http {
-                message: "non-ascii api key provided".to_string(),
-            })?,
-        );
+        if let Some(api_key) = api_key {
+            headers.insert(
+                "x-api-key",
+                HeaderValue::from_str(api_key).map_err(|_| Error::Http {
+                    message: "non-ascii api key provided".to_string(),
+                })?,
+            );
+        }
         if region == "local" {
             let host = format!("{}.local.api.lancedb.com", db_name);
             headers.insert(
@@ -62,15 +75,27 @@ impl RestfulLanceDbClient {
                 })?,
             );
         }
+        for (key, value) in extra_headers {
+            let name = HeaderName::from_bytes(key.as_bytes()).map_err(|_| Error::Http {
+                message: format!("invalid header name '{}' provided", key),
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|_| Error::Http {
+                message: format!("non-ascii value provided for header '{}'", key),
+            })?;
+            headers.insert(name, value);
+        }
 
         Ok(headers)
     }
 
     pub fn try_new(
         db_url: &str,
-        api_key: &str,
+        api_key: Option<&str>,
         region: &str,
         host_override: Option<String>,
+        retry_config: RetryConfig,
+        headers: HashMap<String, String>,
+        token_provider: Option<Arc<dyn TokenProvider>>,
     ) -> Result<Self> {
         let parsed_url = url::Url::parse(db_url)?;
         debug_assert_eq!(parsed_url.scheme(), "db");
@@ -87,13 +112,20 @@ impl RestfulLanceDbClient {
                 region,
                 db_name,
                 host_override.is_some(),
+                &headers,
             )?)
             .build()?;
         let host = match host_override {
             Some(host_override) => host_override,
             None => format!("https://{}.{}.api.lancedb.com", db_name, region),
         };
-        Ok(Self { client, host })
+        Ok(Self {
+            client,
+            host,
+            retry_config,
+            token_provider,
+            token: Arc::new(Mutex::new(None)),
+        })
     }
 
     pub fn get(&self, uri: &str) -> RequestBuilder {
@@ -106,6 +138,104 @@ impl RestfulLanceDbClient {
         self.client.post(full_uri)
     }
 
+    /// Send a request, retrying it according to the [`RetryConfig`]
+    ///
+    /// `idempotent` requests, which can safely be applied more than once, are
+    /// retried when the server is unavailable.  Other requests are only retried
+    /// if the server did not process them: when it is overloaded or the
+    /// connection could not be made.
+    ///
+    /// If the server rejects the token of the token provider then the request is
+    /// sent once more with a new token.
+    pub async fn send(&self, request: RequestBuilder, idempotent: bool) -> Result<Response> {
+        let mut retries = 0;
+        let mut refreshed_token = false;
+        loop {
+            // A request with a streaming body cannot be cloned and so cannot be retried
+            let Some(attempt) = request.try_clone() else {
+                let response = self.authorize(request).await?.send().await?;
+                return self.check_response(response).await;
+            };
+            let can_retry = retries < self.retry_config.max_retries;
+            match self.authorize(attempt).await?.send().await {
+                // The token may have been revoked before it expired
+                Ok(response)
+                    if response.status() == StatusCode::UNAUTHORIZED
+                        && self.token_provider.is_some()
+                        && !refreshed_token =>
+                {
+                    *self.token.lock()? = None;
+                    refreshed_token = true;
+                    continue;
+                }
+                Ok(response) if can_retry && Self::should_retry(response.status(), idempotent) => {}
+                Ok(response) => {
+                    return self
+                        .check_response(response)
+                        .await
+                        .map_err(|err| Self::with_retries(err, retries))
+                }
+                Err(err) if can_retry && err.is_connect() => {}
+                Err(err) => return Err(Self::with_retries(err.into(), retries)),
+            }
+            tokio::time::sleep(self.retry_config.backoff(retries)).await;
+            retries += 1;
+        }
+    }
+
+    /// Add the token of the token provider (if any) to the request
+    async fn authorize(&self, request: RequestBuilder) -> Result<RequestBuilder> {
+        let Some(token_provider) = &self.token_provider else {
+            return Ok(request);
+        };
+        let refresh_at = SystemTime::now() + TOKEN_REFRESH_MARGIN;
+        let token = self.token.lock()?.clone().filter(|token| {
+            token
+                .expires_at
+                .map_or(true, |expires_at| expires_at > refresh_at)
+        });
+        let token = match token {
+            Some(token) => token,
+            None => {
+                let token = token_provider.token().await?;
+                *self.token.lock()? = Some(token.clone());
+                token
+            }
+        };
+        Ok(request.bearer_auth(token.token))
+    }
+
+    fn should_retry(status: StatusCode, idempotent: bool) -> bool {
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => true,
+            StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => idempotent,
+            _ => false,
+        }
+    }
+
+    /// Add the number of retries to the message of the error of the final attempt
+    fn with_retries(err: Error, retries: u32) -> Error {
+        if retries == 0 {
+            return err;
+        }
+        let with_suffix =
+            |message: String| format!("{} (failed after {} retries)", message, retries);
+        match err {
+            Error::InvalidInput { message } => Error::InvalidInput {
+                message: with_suffix(message),
+            },
+            Error::Runtime { message } => Error::Runtime {
+                message: with_suffix(message),
+            },
+            Error::Http { message } => Error::Http {
+                message: with_suffix(message),
+            },
+            err => err,
+        }
+    }
+
     async fn rsp_to_str(response: Response) -> String {
         let status = response.status();
         response.text().await.unwrap_or_else(|_| status.to_string())
@@ -126,3 +256,329 @@ impl RestfulLanceDbClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::connect;
+
+    type Headers = HashMap<String, String>;
+
+    /// Start a server that answers each request with the next of `responses`
+    ///
+    /// Returns the address of the server and the headers of each request received,
+    /// with lower case names.
+    fn mock_server(responses: Vec<(u16, &'static str)>) -> (String, Arc<Mutex<Vec<Headers>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut headers = Headers::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.insert(name.to_lowercase(), value.trim().to_string());
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let content_length = headers
+                    .get("content-length")
+                    .map_or(0, |length| length.parse().unwrap());
+                reader
+                    .by_ref()
+                    .take(content_length)
+                    .read_to_end(&mut Vec::new())
+                    .unwrap();
+                received.lock().unwrap().push(headers);
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (host, requests)
+    }
+
+    async fn connect_to(host: &str, max_retries: u32) -> crate::Connection {
+        connect("db://test")
+            .api_key("api-key")
+            .region("us-east-1")
+            .host_override(host)
+            .retry_config(RetryConfig {
+                max_retries,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+            })
+            .execute()
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = RetryConfig {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        let backoffs = (0..6)
+            .map(|retry| config.backoff(retry))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            backoffs,
+            [100, 200, 400, 800, 1000, 1000].map(Duration::from_millis)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_unavailable() {
+        let (host, requests) = mock_server(vec![
+            (503, ""),
+            (503, ""),
+            (200, r#"{"tables": ["my_table"]}"#),
+        ]);
+        let db = connect_to(&host, 3).await;
+        let names = db.table_names().execute().await.unwrap();
+        assert_eq!(names, vec!["my_table".to_string()]);
+        assert_eq!(requests.lock().unwrap().len(), 3);
+
+        // The error of the final attempt is returned with the number of retries
+        let (host, requests) = mock_server(vec![(503, "unavailable"), (503, "unavailable")]);
+        let db = connect_to(&host, 1).await;
+        let err = db.table_names().execute().await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("unavailable (failed after 1 retries)"),
+            "{}",
+            err
+        );
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_writes() {
+        // A write may have been applied when the server fails and is not retried
+        let (host, requests) = mock_server(vec![(503, "unavailable")]);
+        let db = connect_to(&host, 3).await;
+        assert!(db.drop_table("my_table").await.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        // But it was not applied when the server is overloaded
+        let (host, requests) = mock_server(vec![(429, "slow down"), (200, "")]);
+        let db = connect_to(&host, 3).await;
+        db.drop_table("my_table").await.unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        // Or when the connection could not be made
+        let host = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let db = connect_to(&host, 2).await;
+        let err = db.drop_table("my_table").await.unwrap_err();
+        assert!(
+            err.to_string().contains("(failed after 2 retries)"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_headers() {
+        let (host, requests) = mock_server(vec![(200, r#"{"tables": []}"#)]);
+        let db = connect("db://test")
+            .api_key("api-key")
+            .region("us-east-1")
+            .host_override(&host)
+            .header("x-deployment", "staging")
+            .execute()
+            .await
+            .unwrap();
+        db.table_names().execute().await.unwrap();
+        let headers = requests.lock().unwrap()[0].clone();
+        assert_eq!(headers["x-deployment"], "staging");
+        assert_eq!(headers["x-api-key"], "api-key");
+
+        let err = connect("db://test")
+            .api_key("api-key")
+            .region("us-east-1")
+            .header("bad header", "value")
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("invalid header name 'bad header'"));
+    }
+
+    /// Provides the tokens "token-1", "token-2", etc. valid for `valid_for`
+    #[derive(Debug)]
+    struct CountingTokenProvider {
+        num_calls: Mutex<usize>,
+        valid_for: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl TokenProvider for CountingTokenProvider {
+        async fn token(&self) -> Result<AuthToken> {
+            let mut num_calls = self.num_calls.lock()?;
+            *num_calls += 1;
+            Ok(AuthToken {
+                token: format!("token-{}", num_calls),
+                expires_at: Some(SystemTime::now() + self.valid_for),
+            })
+        }
+    }
+
+    async fn connect_with_tokens(
+        host: &str,
+        valid_for: Duration,
+    ) -> (crate::Connection, Arc<CountingTokenProvider>) {
+        let provider = Arc::new(CountingTokenProvider {
+            num_calls: Mutex::new(0),
+            valid_for,
+        });
+        let db = connect("db://test")
+            .region("us-east-1")
+            .host_override(host)
+            .token_provider(provider.clone())
+            .execute()
+            .await
+            .unwrap();
+        (db, provider)
+    }
+
+    #[tokio::test]
+    async fn test_token_provider() {
+        let tables = r#"{"tables": []}"#;
+        let (host, requests) = mock_server(vec![
+            (200, tables),
+            (200, tables),
+            (401, "token revoked"),
+            (200, tables),
+        ]);
+        let (db, provider) = connect_with_tokens(&host, Duration::from_secs(3600)).await;
+        db.table_names().execute().await.unwrap();
+        db.table_names().execute().await.unwrap();
+        assert_eq!(*provider.num_calls.lock().unwrap(), 1);
+
+        // A rejected token is replaced and the request is sent again
+        db.table_names().execute().await.unwrap();
+        assert_eq!(*provider.num_calls.lock().unwrap(), 2);
+        let authorization = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|headers| headers["authorization"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            authorization,
+            vec![
+                "Bearer token-1",
+                "Bearer token-1",
+                "Bearer token-1",
+                "Bearer token-2"
+            ]
+        );
+
+        // A token that is about to expire is replaced before the request
+        let (host, requests) = mock_server(vec![(200, tables), (200, tables)]);
+        let (db, provider) = connect_with_tokens(&host, Duration::from_secs(10)).await;
+        db.table_names().execute().await.unwrap();
+        db.table_names().execute().await.unwrap();
+        assert_eq!(*provider.num_calls.lock().unwrap(), 2);
+        assert_eq!(
+            requests.lock().unwrap()[1]["authorization"],
+            "Bearer token-2"
+        );
+
+        // The server rejects the new token as well
+        let (host, _) = mock_server(vec![(401, "unauthorized"), (401, "unauthorized")]);
+        let (db, provider) = connect_with_tokens(&host, Duration::from_secs(3600)).await;
+        assert!(db.table_names().execute().await.is_err());
+        assert_eq!(*provider.num_calls.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_index() {
+        let building = r#"{"indexes": [{"index_name": "vector_idx", "index_status": "building"}]}"#;
+        let ready = r#"{"indexes": [{"index_name": "vector_idx", "index_status": "ready"}]}"#;
+        let make_data = || {
+            let schema = Arc::new(arrow_schema::Schema::new(vec![arrow_schema::Field::new(
+                "x",
+                arrow_schema::DataType::Int32,
+                false,
+            )]));
+            let batch = arrow_array::RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(arrow_array::Int32Array::from(vec![1]))],
+            )
+            .unwrap();
+            arrow_array::RecordBatchIterator::new(vec![Ok(batch)], schema)
+        };
+
+        // The index is not listed until the build starts
+        let (host, requests) = mock_server(vec![
+            (200, ""),
+            (200, r#"{"indexes": []}"#),
+            (200, building),
+            (200, ready),
+        ]);
+        let db = connect_to(&host, 0).await;
+        let table = db.create_table("t", make_data()).execute().await.unwrap();
+        table
+            .wait_for_index(&["vector_idx"], Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 4);
+
+        let (host, _) = mock_server(vec![(200, ""), (200, building), (200, building)]);
+        let db = connect_to(&host, 0).await;
+        let table = db.create_table("t", make_data()).execute().await.unwrap();
+        let err = table
+            .wait_for_index(&["vector_idx"], Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_not_supported() {
+        let (host, requests) = mock_server(vec![(200, "")]);
+        let db = connect_to(&host, 0).await;
+        let schema = Arc::new(arrow_schema::Schema::new(vec![arrow_schema::Field::new(
+            "x",
+            arrow_schema::DataType::Int32,
+            false,
+        )]));
+        let table = db.create_empty_table("t", schema).execute().await.unwrap();
+
+        // These fail without sending a request
+        let errors = vec![
+            db.clone_table("t", "u").await.unwrap_err(),
+            table.list_versions().await.unwrap_err(),
+            table.restore_version(1).await.unwrap_err(),
+            table.schema_at_version(1).await.unwrap_err(),
+            table.stats().await.unwrap_err(),
+            table.take(&[0], None).await.unwrap_err(),
+            table.index_stats("x_idx").await.unwrap_err(),
+            table.drop_index("x_idx").await.unwrap_err(),
+        ];
+        for err in errors {
+            assert!(matches!(err, Error::NotSupported { .. }), "{}", err);
+        }
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+}
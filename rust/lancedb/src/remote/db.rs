@@ -12,19 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use arrow_array::RecordBatchReader;
 use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
 use reqwest::header::CONTENT_TYPE;
 use serde::Deserialize;
 use tokio::task::spawn_blocking;
 
 use crate::connection::{
-    ConnectionInternal, CreateTableBuilder, NoData, OpenTableBuilder, TableNamesBuilder,
+    ConnectionInternal, CreateTableBuilder, DatabaseStats, NoData, OpenTableBuilder, RetryConfig,
+    TableNamesBuilder, TokenProvider,
 };
 use crate::embeddings::EmbeddingRegistry;
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::Table;
 
 use super::client::RestfulLanceDbClient;
@@ -33,6 +36,9 @@ use super::util::batches_to_ipc_bytes;
 
 const ARROW_STREAM_CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
 
+/// The maximum number of concurrent requests made by `drop_tables`
+const DROP_TABLES_CONCURRENCY: usize = 8;
+
 #[derive(Deserialize)]
 struct ListTablesResponse {
     tables: Vec<String>,
@@ -46,11 +52,22 @@ pub struct RemoteDatabase {
 impl RemoteDatabase {
     pub fn try_new(
         uri: &str,
-        api_key: &str,
+        api_key: Option<&str>,
         region: &str,
         host_override: Option<String>,
+        retry_config: RetryConfig,
+        headers: HashMap<String, String>,
+        token_provider: Option<Arc<dyn TokenProvider>>,
     ) -> Result<Self> {
-        let client = RestfulLanceDbClient::try_new(uri, api_key, region, host_override)?;
+        let client = RestfulLanceDbClient::try_new(
+            uri,
+            api_key,
+            region,
+            host_override,
+            retry_config,
+            headers,
+            token_provider,
+        )?;
         Ok(Self { client })
     }
 }
@@ -71,8 +88,10 @@ impl ConnectionInternal for RemoteDatabase {
         if let Some(start_after) = options.start_after {
             req = req.query(&[("page_token", start_after)]);
         }
-        let rsp = req.send().await?;
-        let rsp = self.client.check_response(rsp).await?;
+        if let Some(prefix) = options.prefix {
+            req = req.query(&[("prefix", prefix)]);
+        }
+        let rsp = self.client.send(req, true).await?;
         Ok(rsp.json::<ListTablesResponse>().await?.tables)
     }
 
@@ -88,16 +107,14 @@ impl ConnectionInternal for RemoteDatabase {
             .await
             .unwrap()?;
 
-        let rsp = self
+        let req = self
             .client
             .post(&format!("/v1/table/{}/create/", options.name))
             .body(data_buffer)
             .header(CONTENT_TYPE, ARROW_STREAM_CONTENT_TYPE)
             // This is currently expected by LanceDb cloud but will be removed soon.
-            .header("x-request-id", "na")
-            .send()
-            .await?;
-        self.client.check_response(rsp).await?;
+            .header("x-request-id", "na");
+        self.client.send(req, false).await?;
 
         Ok(Table::new(Arc::new(RemoteTable::new(
             self.client.clone(),
@@ -109,8 +126,41 @@ impl ConnectionInternal for RemoteDatabase {
         todo!()
     }
 
-    async fn drop_table(&self, _name: &str) -> Result<()> {
-        todo!()
+    async fn drop_table(&self, name: &str) -> Result<()> {
+        let req = self.client.post(&format!("/v1/table/{}/drop/", name));
+        self.client.send(req, false).await?;
+        Ok(())
+    }
+
+    async fn rename_table(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let req = self
+            .client
+            .post(&format!("/v1/table/{}/rename/", old_name))
+            .json(&serde_json::json!({ "new_table_name": new_name }));
+        self.client.send(req, false).await?;
+        Ok(())
+    }
+
+    async fn drop_tables(&self, names: Vec<String>) -> Result<usize> {
+        let num_tables = names.len();
+        futures::stream::iter(names)
+            .map(|name| async move { self.drop_table(&name).await })
+            .buffer_unordered(DROP_TABLES_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(num_tables)
+    }
+
+    async fn clone_table(&self, _source: &str, _dest: &str) -> Result<()> {
+        Err(Error::NotSupported {
+            message: "clone_table is not supported on LanceDB Cloud".to_string(),
+        })
+    }
+
+    async fn database_stats(self: Arc<Self>, _names: Vec<String>) -> Result<DatabaseStats> {
+        Err(Error::NotSupported {
+            message: "database stats are not yet supported by LanceDB Cloud".to_string(),
+        })
     }
 
     async fn drop_db(&self) -> Result<()> {
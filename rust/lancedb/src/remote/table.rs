@@ -1,27 +1,48 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use arrow_array::RecordBatchReader;
+use arrow_array::{RecordBatch, RecordBatchReader};
 use arrow_schema::SchemaRef;
 use async_trait::async_trait;
 use datafusion_physical_plan::ExecutionPlan;
 use lance::dataset::{scanner::DatasetRecordBatchStream, ColumnAlteration, NewColumnTransform};
+use serde::Deserialize;
 
 use crate::{
     connection::NoData,
-    error::Result,
-    index::{IndexBuilder, IndexConfig},
+    error::{Error, Result},
+    index::{IndexBuilder, IndexConfig, IndexStatistics},
     query::{Query, QueryExecutionOptions, VectorQuery},
     table::{
-        merge::MergeInsertBuilder, AddDataBuilder, NativeTable, OptimizeAction, OptimizeStats,
-        TableDefinition, TableInternal, UpdateBuilder,
+        aggregate::AggregateBuilder,
+        merge::{MergeInsertBuilder, MergeInsertStats},
+        AddDataBuilder, DeleteResult, NativeTable, OptimizeAction, OptimizeStats, TableDefinition,
+        TableInternal, TableStats, UpdateBuilder, UpdateResult, Version,
     },
 };
 
 use super::client::RestfulLanceDbClient;
 
+/// The first interval between two checks of the status of the indices in
+/// `wait_for_index`, the interval doubles after each check
+const WAIT_FOR_INDEX_INITIAL_INTERVAL: Duration = Duration::from_millis(100);
+/// The longest interval between two checks of the status of the indices
+const WAIT_FOR_INDEX_MAX_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct ListIndicesResponse {
+    indexes: Vec<IndexStatus>,
+}
+
+#[derive(Deserialize)]
+struct IndexStatus {
+    index_name: String,
+    /// One of "building", "ready" or "failed"
+    index_status: String,
+}
+
 #[derive(Debug)]
 pub struct RemoteTable {
-    #[allow(dead_code)]
     client: RestfulLanceDbClient,
     name: String,
 }
@@ -30,6 +51,14 @@ impl RemoteTable {
     pub fn new(client: RestfulLanceDbClient, name: String) -> Self {
         Self { client, name }
     }
+
+    async fn index_statuses(&self) -> Result<Vec<IndexStatus>> {
+        let req = self
+            .client
+            .post(&format!("/v1/table/{}/index/list/", self.name));
+        let rsp = self.client.send(req, true).await?;
+        Ok(rsp.json::<ListIndicesResponse>().await?.indexes)
+    }
 }
 
 impl std::fmt::Display for RemoteTable {
@@ -58,15 +87,50 @@ impl TableInternal for RemoteTable {
     async fn checkout_latest(&self) -> Result<()> {
         todo!()
     }
+    async fn checkout_version(&self, _version: u64) -> Result<Arc<dyn TableInternal>> {
+        Err(Error::NotSupported {
+            message: "checkout_version is not supported on LanceDB Cloud".to_string(),
+        })
+    }
+    async fn list_versions(&self) -> Result<Vec<Version>> {
+        Err(Error::NotSupported {
+            message: "list_versions is not supported on LanceDB Cloud".to_string(),
+        })
+    }
     async fn restore(&self) -> Result<()> {
         todo!()
     }
+    async fn restore_version(&self, _version: u64) -> Result<()> {
+        Err(Error::NotSupported {
+            message: "restore_version is not supported on LanceDB Cloud".to_string(),
+        })
+    }
     async fn schema(&self) -> Result<SchemaRef> {
         todo!()
     }
+    async fn schema_at_version(&self, _version: u64) -> Result<SchemaRef> {
+        Err(Error::NotSupported {
+            message: "schema_at_version is not supported on LanceDB Cloud".to_string(),
+        })
+    }
     async fn count_rows(&self, _filter: Option<String>) -> Result<usize> {
         todo!()
     }
+    async fn stats(&self) -> Result<TableStats> {
+        Err(Error::NotSupported {
+            message: "stats is not supported on LanceDB Cloud".to_string(),
+        })
+    }
+    async fn aggregate(&self, _params: AggregateBuilder) -> Result<RecordBatch> {
+        Err(Error::NotSupported {
+            message: "aggregate is not supported on LanceDB Cloud".to_string(),
+        })
+    }
+    async fn take(&self, _row_ids: &[u64], _columns: Option<Vec<String>>) -> Result<RecordBatch> {
+        Err(Error::NotSupported {
+            message: "take is not supported on LanceDB Cloud".to_string(),
+        })
+    }
     async fn add(
         &self,
         _add: AddDataBuilder<NoData>,
@@ -88,12 +152,21 @@ impl TableInternal for RemoteTable {
     ) -> Result<DatasetRecordBatchStream> {
         todo!()
     }
-    async fn update(&self, _update: UpdateBuilder) -> Result<()> {
+    async fn update(&self, _update: UpdateBuilder) -> Result<UpdateResult> {
         todo!()
     }
-    async fn delete(&self, _predicate: &str) -> Result<()> {
+    async fn delete(&self, _predicate: &str) -> Result<DeleteResult> {
         todo!()
     }
+    async fn delete_by_keys(
+        &self,
+        _column: &str,
+        _keys: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<DeleteResult> {
+        Err(Error::NotSupported {
+            message: "delete_by_keys is not supported on LanceDB Cloud".to_string(),
+        })
+    }
     async fn create_index(&self, _index: IndexBuilder) -> Result<()> {
         todo!()
     }
@@ -101,7 +174,7 @@ impl TableInternal for RemoteTable {
         &self,
         _params: MergeInsertBuilder,
         _new_data: Box<dyn RecordBatchReader + Send>,
-    ) -> Result<()> {
+    ) -> Result<MergeInsertStats> {
         todo!()
     }
     async fn optimize(&self, _action: OptimizeAction) -> Result<OptimizeStats> {
@@ -120,9 +193,65 @@ impl TableInternal for RemoteTable {
     async fn drop_columns(&self, _columns: &[&str]) -> Result<()> {
         todo!()
     }
+    async fn merge(
+        &self,
+        _other: Box<dyn RecordBatchReader + Send>,
+        _left_on: &str,
+        _right_on: &str,
+    ) -> Result<()> {
+        Err(Error::NotSupported {
+            message: "merge is not supported on LanceDB Cloud".to_string(),
+        })
+    }
     async fn list_indices(&self) -> Result<Vec<IndexConfig>> {
         todo!()
     }
+    async fn index_stats(&self, _index_name: &str) -> Result<Option<IndexStatistics>> {
+        Err(Error::NotSupported {
+            message: "index_stats is not supported on LanceDB Cloud".to_string(),
+        })
+    }
+    async fn drop_index(&self, _name: &str) -> Result<()> {
+        Err(Error::NotSupported {
+            message: "drop_index is not supported on LanceDB Cloud".to_string(),
+        })
+    }
+    async fn wait_for_index(&self, index_names: &[&str], timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let mut interval = WAIT_FOR_INDEX_INITIAL_INTERVAL;
+        loop {
+            let statuses = self.index_statuses().await?;
+            let mut pending = Vec::new();
+            for name in index_names {
+                match statuses.iter().find(|status| status.index_name == *name) {
+                    Some(status) if status.index_status == "ready" => {}
+                    Some(status) if status.index_status == "failed" => {
+                        return Err(Error::Runtime {
+                            message: format!("building the index {} failed", name),
+                        });
+                    }
+                    // The index may not be listed until the server starts building it
+                    _ => pending.push(*name),
+                }
+            }
+            if pending.is_empty() {
+                return Ok(());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Timeout {
+                    message: format!(
+                        "the indices [{}] of table {} were not ready after {:?}",
+                        pending.join(", "),
+                        self.name,
+                        timeout
+                    ),
+                });
+            }
+            tokio::time::sleep(interval.min(deadline - now)).await;
+            interval = (interval * 2).min(WAIT_FOR_INDEX_MAX_INTERVAL);
+        }
+    }
     async fn table_definition(&self) -> Result<TableDefinition> {
         todo!()
     }
@@ -0,0 +1,160 @@
+// Copyright 2024 LanceDB Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rerankers combine the results of a vector search and a full text search
+//! into a single ranked list.
+//!
+//! Both result sets must contain a `_rowid` column, which is used to recognize
+//! rows that were found by both searches.
+pub mod linear;
+pub mod rrf;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use arrow::compute::{concat_batches, filter_record_batch, take_record_batch};
+use arrow_array::{
+    cast::AsArray, new_null_array, types::UInt64Type, BooleanArray, Float32Array, RecordBatch,
+    UInt32Array,
+};
+use arrow_schema::{DataType, Field, Schema};
+
+use crate::error::{Error, Result};
+
+pub use linear::LinearReranker;
+pub use rrf::RRFReranker;
+
+/// The name of the column the reranked score is stored in
+pub const RELEVANCE_SCORE: &str = "_relevance_score";
+
+/// The name of the row id column used to match the two result sets
+pub const ROW_ID: &str = "_rowid";
+
+/// Trait for rerankers
+///
+/// A reranker receives the results of the vector search and the full text search
+/// for the same query and returns a single batch of results, sorted from most to
+/// least relevant.
+pub trait Reranker: std::fmt::Debug + Send + Sync {
+    /// Combine the results of a vector search and a full text search
+    ///
+    /// # Arguments
+    ///
+    /// * `vector_results` - The results of the vector search, closest first
+    /// * `fts_results` - The results of the full text search, best match first
+    /// * `query` - The text of the query
+    fn rerank(
+        &self,
+        vector_results: RecordBatch,
+        fts_results: RecordBatch,
+        query: &str,
+    ) -> Result<RecordBatch>;
+}
+
+/// Sort the rows from most to least relevant and append the `_relevance_score` column
+///
+/// The sort is stable so rows with the same score keep their order.
+pub(crate) fn sort_by_relevance(batch: RecordBatch, relevance: Vec<f32>) -> Result<RecordBatch> {
+    let mut order = (0..batch.num_rows() as u32).collect::<Vec<_>>();
+    order.sort_by(|a, b| relevance[*b as usize].total_cmp(&relevance[*a as usize]));
+
+    let scores = Float32Array::from_iter_values(order.iter().map(|idx| relevance[*idx as usize]));
+    let sorted = take_record_batch(&batch, &UInt32Array::from(order))?;
+    let mut fields = sorted.schema().fields().to_vec();
+    fields.push(Arc::new(Field::new(
+        RELEVANCE_SCORE,
+        DataType::Float32,
+        false,
+    )));
+    let mut columns = sorted.columns().to_vec();
+    columns.push(Arc::new(scores));
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+/// Get the `_rowid` column of a result set
+pub(crate) fn row_ids(results: &RecordBatch) -> Result<&[u64]> {
+    let row_ids = results
+        .column_by_name(ROW_ID)
+        .ok_or_else(|| Error::InvalidInput {
+            message: format!("the results to rerank must contain a {} column", ROW_ID),
+        })?;
+    Ok(row_ids
+        .as_primitive_opt::<UInt64Type>()
+        .ok_or_else(|| Error::InvalidInput {
+            message: format!(
+                "the {} column must be UInt64 but was {}",
+                ROW_ID,
+                row_ids.data_type()
+            ),
+        })?
+        .values())
+}
+
+/// Concatenate the vector and full text search results and remove duplicate rows
+///
+/// The output contains every column of either input.  Columns that only one of the
+/// inputs has, such as the `_distance` of the vector search, are null for the rows
+/// from the other input.  When a row appears in both inputs only the first
+/// occurrence, from the vector results, is kept.
+pub fn merge_results(vector_results: RecordBatch, fts_results: RecordBatch) -> Result<RecordBatch> {
+    let mut fields = vector_results.schema().fields().to_vec();
+    for field in fts_results.schema().fields() {
+        match fields.iter().find(|f| f.name() == field.name()) {
+            Some(existing) if existing.data_type() != field.data_type() => {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "column {} is {} in the vector results but {} in the full text results",
+                        field.name(),
+                        existing.data_type(),
+                        field.data_type()
+                    ),
+                });
+            }
+            Some(_) => {}
+            None => fields.push(field.clone()),
+        }
+    }
+    // Columns missing from one input will be filled with nulls
+    let schema = Arc::new(Schema::new(
+        fields
+            .iter()
+            .map(|f| Field::new(f.name(), f.data_type().clone(), true))
+            .collect::<Vec<_>>(),
+    ));
+    let promote = |batch: &RecordBatch| {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| match batch.column_by_name(f.name()) {
+                Some(column) => column.clone(),
+                None => new_null_array(f.data_type(), batch.num_rows()),
+            })
+            .collect();
+        RecordBatch::try_new(schema.clone(), columns)
+    };
+    let combined = concat_batches(
+        &schema,
+        &[promote(&vector_results)?, promote(&fts_results)?],
+    )?;
+
+    let mut seen = HashSet::new();
+    let keep = row_ids(&combined)?
+        .iter()
+        .map(|id| Some(seen.insert(*id)))
+        .collect::<BooleanArray>();
+    Ok(filter_record_batch(&combined, &keep)?)
+}
@@ -0,0 +1,185 @@
+// Copyright 2024 LanceDB Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use arrow_array::{cast::AsArray, types::Float32Type, RecordBatch};
+use arrow_cast::cast;
+use arrow_schema::DataType;
+
+use super::{merge_results, row_ids, sort_by_relevance, Reranker};
+use crate::error::{Error, Result};
+
+/// The name of the score column of the vector search, lower is better
+pub const DISTANCE: &str = "_distance";
+
+/// The name of the score column of the full text search, higher is better
+pub const FTS_SCORE: &str = "_score";
+
+/// Reranks results with a weighted sum of the vector and full text search scores
+///
+/// The `_distance` of the vector results and the `_score` of the full text results
+/// are each min-max normalized to `[0, 1]` within their result set, with the
+/// distance inverted so that 1 is the closest row.  The relevance of a row is
+/// `vector_weight * vector + fts_weight * fts`, where a search that did not find
+/// the row contributes 0.  If every row of a result set has the same score, for
+/// example when there is a single result, they are all normalized to 1.
+///
+/// Unlike [`super::RRFReranker`] this takes the magnitude of the scores into
+/// account and not just the rank.
+#[derive(Debug, Clone)]
+pub struct LinearReranker {
+    /// The weight of the vector search score
+    pub vector_weight: f32,
+    /// The weight of the full text search score
+    pub fts_weight: f32,
+}
+
+impl Default for LinearReranker {
+    fn default() -> Self {
+        Self {
+            vector_weight: 0.7,
+            fts_weight: 0.3,
+        }
+    }
+}
+
+/// Min-max normalize the `column` of `results` by row id
+fn normalized_scores(
+    results: &RecordBatch,
+    column: &str,
+    higher_is_better: bool,
+) -> Result<HashMap<u64, f32>> {
+    let scores = results
+        .column_by_name(column)
+        .ok_or_else(|| Error::InvalidInput {
+            message: format!("the results to rerank must contain a {} column", column),
+        })?;
+    let scores = cast(scores, &DataType::Float32)?;
+    let scores = scores.as_primitive::<Float32Type>();
+
+    let valid = || scores.iter().flatten();
+    let min = valid().fold(f32::INFINITY, f32::min);
+    let max = valid().fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+
+    Ok(row_ids(results)?
+        .iter()
+        .zip(scores.iter())
+        .filter_map(|(id, score)| {
+            let score = score?;
+            let normalized = match (range > 0.0, higher_is_better) {
+                (false, _) => 1.0,
+                (true, true) => (score - min) / range,
+                (true, false) => (max - score) / range,
+            };
+            Some((*id, normalized))
+        })
+        .collect())
+}
+
+impl Reranker for LinearReranker {
+    fn rerank(
+        &self,
+        vector_results: RecordBatch,
+        fts_results: RecordBatch,
+        _query: &str,
+    ) -> Result<RecordBatch> {
+        let vector_scores = normalized_scores(&vector_results, DISTANCE, false)?;
+        let fts_scores = normalized_scores(&fts_results, FTS_SCORE, true)?;
+
+        let combined = merge_results(vector_results, fts_results)?;
+        let relevance = row_ids(&combined)?
+            .iter()
+            .map(|id| {
+                self.vector_weight * vector_scores.get(id).copied().unwrap_or_default()
+                    + self.fts_weight * fts_scores.get(id).copied().unwrap_or_default()
+            })
+            .collect();
+        sort_by_relevance(combined, relevance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{types::UInt64Type, Float32Array, UInt64Array};
+    use arrow_schema::{Field, Schema};
+
+    use super::*;
+    use crate::rerankers::RELEVANCE_SCORE;
+
+    fn results(ids: &[u64], score_column: &str, scores: &[f32]) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("_rowid", DataType::UInt64, false),
+            Field::new(score_column, DataType::Float32, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(UInt64Array::from(ids.to_vec())),
+                Arc::new(Float32Array::from(scores.to_vec())),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn rerank(reranker: LinearReranker) -> (Vec<u64>, Vec<f32>) {
+        // Row 1 is much closer than rows 2 and 3 but row 3 is the best text match
+        let vector_results = results(&[1, 2, 3], DISTANCE, &[0.0, 9.0, 10.0]);
+        let fts_results = results(&[3, 2], FTS_SCORE, &[5.0, 1.0]);
+        let reranked = reranker
+            .rerank(vector_results, fts_results, "query")
+            .unwrap();
+        let ids = reranked["_rowid"].as_primitive::<UInt64Type>();
+        let scores = reranked[RELEVANCE_SCORE].as_primitive::<Float32Type>();
+        (ids.values().to_vec(), scores.values().to_vec())
+    }
+
+    #[test]
+    fn test_linear_weights() {
+        // Normalized vector scores: 1 -> 1.0, 2 -> 0.1, 3 -> 0.0
+        // Normalized fts scores:    3 -> 1.0, 2 -> 0.0
+        let (ids, scores) = rerank(LinearReranker {
+            vector_weight: 0.8,
+            fts_weight: 0.2,
+        });
+        assert_eq!(ids, vec![1, 3, 2]);
+        for (score, expected) in scores.iter().zip([0.8, 0.2, 0.08]) {
+            assert!((score - expected).abs() < 1e-6, "{} != {}", score, expected);
+        }
+
+        let (ids, _) = rerank(LinearReranker {
+            vector_weight: 0.2,
+            fts_weight: 0.8,
+        });
+        assert_eq!(ids, vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn test_linear_single_result() {
+        let reranked = LinearReranker::default()
+            .rerank(
+                results(&[1], DISTANCE, &[0.5]),
+                results(&[2], FTS_SCORE, &[3.0]),
+                "query",
+            )
+            .unwrap();
+        let ids = reranked["_rowid"].as_primitive::<UInt64Type>();
+        assert_eq!(ids.values().as_ref(), &[1, 2]);
+        let scores = reranked[RELEVANCE_SCORE].as_primitive::<Float32Type>();
+        assert_eq!(scores.values().as_ref(), &[0.7, 0.3]);
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2024 LanceDB Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use arrow_array::RecordBatch;
+
+use super::{merge_results, row_ids, sort_by_relevance, Reranker};
+use crate::error::Result;
+
+/// Reranks results with reciprocal rank fusion
+///
+/// Each row receives a score of `1 / (rank + k)` from every result set it appears
+/// in, where `rank` is its zero based position in that result set.  The scores are
+/// summed so rows found by both searches are ranked higher.  The score is returned
+/// in a `_relevance_score` column and the rows are sorted by it, highest first.
+#[derive(Debug, Clone)]
+pub struct RRFReranker {
+    k: f32,
+}
+
+impl RRFReranker {
+    /// Create a new reranker
+    ///
+    /// `k` dampens the advantage of the top ranked rows.  The default is 60.
+    pub fn new(k: f32) -> Self {
+        Self { k }
+    }
+}
+
+impl Default for RRFReranker {
+    fn default() -> Self {
+        Self::new(60.0)
+    }
+}
+
+impl Reranker for RRFReranker {
+    fn rerank(
+        &self,
+        vector_results: RecordBatch,
+        fts_results: RecordBatch,
+        _query: &str,
+    ) -> Result<RecordBatch> {
+        let mut scores = HashMap::<u64, f32>::new();
+        for results in [&vector_results, &fts_results] {
+            for (rank, id) in row_ids(results)?.iter().enumerate() {
+                *scores.entry(*id).or_default() += 1.0 / (rank as f32 + self.k);
+            }
+        }
+
+        let combined = merge_results(vector_results, fts_results)?;
+        let relevance = row_ids(&combined)?
+            .iter()
+            .map(|id| scores[id])
+            .collect::<Vec<_>>();
+        sort_by_relevance(combined, relevance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{
+        cast::AsArray, types::UInt64Type, Array, Float32Array, StringArray, UInt64Array,
+    };
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+    use crate::rerankers::RELEVANCE_SCORE;
+    use crate::Error;
+
+    fn results(ids: &[u64], score_column: &str) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("_rowid", DataType::UInt64, false),
+            Field::new("text", DataType::Utf8, false),
+            Field::new(score_column, DataType::Float32, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(UInt64Array::from(ids.to_vec())),
+                Arc::new(StringArray::from_iter_values(
+                    ids.iter().map(|id| format!("row {}", id)),
+                )),
+                Arc::new(Float32Array::from_iter_values(
+                    (0..ids.len()).map(|i| i as f32),
+                )),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rrf() {
+        let vector_results = results(&[1, 2, 3], "_distance");
+        let fts_results = results(&[3, 4, 1], "_score");
+
+        let reranked = RRFReranker::new(1.0)
+            .rerank(vector_results, fts_results, "query")
+            .unwrap();
+
+        // With k = 1 the scores are
+        //   1: 1/1 + 1/3 = 1.333
+        //   3: 1/3 + 1/1 = 1.333
+        //   2: 1/2       = 0.5
+        //   4: 1/2       = 0.5
+        // Ties keep the order of the vector results followed by the fts results
+        let ids = reranked["_rowid"].as_primitive::<UInt64Type>().values();
+        assert_eq!(ids.as_ref(), &[1, 3, 2, 4]);
+        let scores = reranked[RELEVANCE_SCORE]
+            .as_primitive::<arrow_array::types::Float32Type>()
+            .values();
+        let expected = [4.0 / 3.0, 4.0 / 3.0, 0.5, 0.5];
+        for (score, expected) in scores.iter().zip(expected) {
+            assert!((score - expected).abs() < 1e-6, "{} != {}", score, expected);
+        }
+
+        let text = reranked["text"].as_string::<i32>();
+        assert_eq!(text.value(3), "row 4");
+        // Columns that only one search produced are null for the other rows
+        assert!(reranked["_distance"].is_null(3));
+        assert!(!reranked["_score"].is_null(3));
+        assert!(reranked["_score"].is_null(0));
+    }
+
+    #[test]
+    fn test_rrf_default_k() {
+        let reranked = RRFReranker::default()
+            .rerank(
+                results(&[10, 20], "_distance"),
+                results(&[20], "_score"),
+                "query",
+            )
+            .unwrap();
+        let ids = reranked["_rowid"].as_primitive::<UInt64Type>().values();
+        assert_eq!(ids.as_ref(), &[20, 10]);
+    }
+
+    #[test]
+    fn test_rrf_requires_row_ids() {
+        let vector_results = results(&[1], "_distance");
+        let fts_results = vector_results.project(&[1, 2]).unwrap();
+        let err = RRFReranker::default()
+            .rerank(vector_results, fts_results, "query")
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+    }
+}
@@ -0,0 +1,551 @@
+// Copyright 2024 LanceDB Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversions from serde-serializable records to Arrow
+//!
+//! The schema is inferred by serializing the records with [`SchemaInferrer`], a serializer
+//! that produces the Arrow type of a value instead of its data.  The data is then decoded
+//! by the arrow-json decoder, which can serialize serde values directly.  The decoder does
+//! not support fixed size lists and so vector columns are decoded as lists and cast
+//! afterwards.
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::json::ReaderBuilder;
+use arrow_array::RecordBatch;
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef};
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::error::{Error, Result};
+
+/// The name of the tuple struct that [`crate::arrow::serde_vector`] serializes vectors as
+pub const VECTOR_MARKER: &str = "__lancedb_vector";
+
+/// Converts the records into a single batch
+pub fn records_to_batch<T: Serialize>(records: &[T]) -> Result<RecordBatch> {
+    let schema = infer_schema(records)?;
+    let decode_schema = Arc::new(Schema::new(
+        schema
+            .fields()
+            .iter()
+            .map(|field| {
+                Field::new(
+                    field.name(),
+                    without_fixed_size_lists(field.data_type()),
+                    true,
+                )
+            })
+            .collect::<Vec<_>>(),
+    ));
+    let mut decoder = ReaderBuilder::new(decode_schema).build_decoder()?;
+    decoder.serialize(records)?;
+    let batch = decoder
+        .flush()?
+        .expect("the decoder always produces a batch for non-empty input");
+
+    let cast_options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(column, field)| {
+            if column.data_type() == field.data_type() {
+                Ok(column.clone())
+            } else {
+                cast_with_options(column, field.data_type(), &cast_options).map_err(|err| {
+                    Error::InvalidInput {
+                        message: format!("failed to convert the field {}: {}", field.name(), err),
+                    }
+                })
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// Infers the schema of the records
+///
+/// The type of each field comes from the first record.  Values that are `None` in the first
+/// record are filled in from the following records.
+fn infer_schema<T: Serialize>(records: &[T]) -> Result<SchemaRef> {
+    if records.is_empty() {
+        return Err(Error::InvalidInput {
+            message: "cannot infer a schema without any records".to_string(),
+        });
+    }
+    let mut data_type = DataType::Null;
+    for record in records {
+        let record_type = record
+            .serialize(SchemaInferrer)
+            .map_err(|err| Error::InvalidInput {
+                message: format!("cannot convert the records to arrow: {}", err),
+            })?;
+        data_type = merge(data_type, record_type);
+        if is_complete(&data_type) {
+            break;
+        }
+    }
+    match data_type {
+        DataType::Struct(fields) => Ok(Arc::new(Schema::new(fields))),
+        data_type => Err(Error::InvalidInput {
+            message: format!("the records must be structs but they are {}", data_type),
+        }),
+    }
+}
+
+/// Replaces the types that were unknown in `current` with the types from `next`
+fn merge(current: DataType, next: DataType) -> DataType {
+    match (current, next) {
+        (DataType::Null, next) => next,
+        (DataType::Struct(current), DataType::Struct(next)) => DataType::Struct(
+            current
+                .iter()
+                .map(|field| match next.find(field.name()) {
+                    Some((_, next_field)) => Arc::new(field.as_ref().clone().with_data_type(
+                        merge(field.data_type().clone(), next_field.data_type().clone()),
+                    )),
+                    None => field.clone(),
+                })
+                .collect(),
+        ),
+        (DataType::List(current), DataType::List(next)) => {
+            DataType::List(Arc::new(current.as_ref().clone().with_data_type(merge(
+                current.data_type().clone(),
+                next.data_type().clone(),
+            ))))
+        }
+        (current, _) => current,
+    }
+}
+
+/// Returns false if any of the nested types are still unknown
+fn is_complete(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Null => false,
+        DataType::Struct(fields) => fields.iter().all(|f| is_complete(f.data_type())),
+        DataType::List(field) | DataType::FixedSizeList(field, _) => is_complete(field.data_type()),
+        _ => true,
+    }
+}
+
+fn without_fixed_size_lists(data_type: &DataType) -> DataType {
+    match data_type {
+        DataType::FixedSizeList(field, _) | DataType::List(field) => DataType::List(Arc::new(
+            field
+                .as_ref()
+                .clone()
+                .with_data_type(without_fixed_size_lists(field.data_type())),
+        )),
+        DataType::Struct(fields) => DataType::Struct(
+            fields
+                .iter()
+                .map(|f| {
+                    f.as_ref()
+                        .clone()
+                        .with_data_type(without_fixed_size_lists(f.data_type()))
+                })
+                .collect(),
+        ),
+        data_type => data_type.clone(),
+    }
+}
+
+fn list_item(data_type: DataType) -> Arc<Field> {
+    Arc::new(Field::new("item", data_type, true))
+}
+
+#[derive(Debug)]
+struct InferError(String);
+
+impl Display for InferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InferError {}
+
+impl ser::Error for InferError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+fn unsupported<T>(what: &str) -> std::result::Result<T, InferError> {
+    Err(InferError(format!("{} cannot be converted to arrow", what)))
+}
+
+/// A serializer that returns the Arrow type of the value
+///
+/// `None` and `()` are [`DataType::Null`], which means the type is not known yet.
+struct SchemaInferrer;
+
+impl ser::Serializer for SchemaInferrer {
+    type Ok = DataType;
+    type Error = InferError;
+    type SerializeSeq = ListInferrer;
+    type SerializeTuple = ListInferrer;
+    type SerializeTupleStruct = ListInferrer;
+    type SerializeTupleVariant = Impossible<DataType, InferError>;
+    type SerializeMap = Impossible<DataType, InferError>;
+    type SerializeStruct = StructInferrer;
+    type SerializeStructVariant = Impossible<DataType, InferError>;
+
+    fn serialize_bool(self, _: bool) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Boolean)
+    }
+
+    fn serialize_i8(self, _: i8) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Int8)
+    }
+
+    fn serialize_i16(self, _: i16) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Int16)
+    }
+
+    fn serialize_i32(self, _: i32) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Int32)
+    }
+
+    fn serialize_i64(self, _: i64) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Int64)
+    }
+
+    fn serialize_u8(self, _: u8) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::UInt8)
+    }
+
+    fn serialize_u16(self, _: u16) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::UInt16)
+    }
+
+    fn serialize_u32(self, _: u32) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::UInt32)
+    }
+
+    fn serialize_u64(self, _: u64) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::UInt64)
+    }
+
+    fn serialize_f32(self, _: f32) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Float32)
+    }
+
+    fn serialize_f64(self, _: f64) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Float64)
+    }
+
+    fn serialize_char(self, _: char) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Utf8)
+    }
+
+    fn serialize_str(self, _: &str) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Utf8)
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> std::result::Result<DataType, InferError> {
+        unsupported("byte strings")
+    }
+
+    fn serialize_none(self) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(
+        self,
+        value: &T,
+    ) -> std::result::Result<DataType, InferError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Null)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Utf8)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> std::result::Result<DataType, InferError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> std::result::Result<DataType, InferError> {
+        unsupported("enum variants with data")
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> std::result::Result<ListInferrer, InferError> {
+        Ok(ListInferrer::new(None))
+    }
+
+    fn serialize_tuple(self, _: usize) -> std::result::Result<ListInferrer, InferError> {
+        Ok(ListInferrer::new(None))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> std::result::Result<ListInferrer, InferError> {
+        Ok(ListInferrer::new(
+            (name == VECTOR_MARKER).then_some(len as i32),
+        ))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> std::result::Result<Self::SerializeTupleVariant, InferError> {
+        unsupported("enum variants with data")
+    }
+
+    fn serialize_map(
+        self,
+        _: Option<usize>,
+    ) -> std::result::Result<Self::SerializeMap, InferError> {
+        unsupported("maps")
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> std::result::Result<StructInferrer, InferError> {
+        Ok(StructInferrer {
+            fields: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> std::result::Result<Self::SerializeStructVariant, InferError> {
+        unsupported("enum variants with data")
+    }
+}
+
+struct ListInferrer {
+    item_type: DataType,
+    /// The length of a vector, set for values serialized with [`crate::arrow::serde_vector`]
+    fixed_size: Option<i32>,
+}
+
+impl ListInferrer {
+    fn new(fixed_size: Option<i32>) -> Self {
+        Self {
+            item_type: DataType::Null,
+            fixed_size,
+        }
+    }
+
+    fn add<T: ?Sized + Serialize>(&mut self, value: &T) -> std::result::Result<(), InferError> {
+        let item_type = std::mem::replace(&mut self.item_type, DataType::Null);
+        self.item_type = merge(item_type, value.serialize(SchemaInferrer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> DataType {
+        match self.fixed_size {
+            Some(size) => DataType::FixedSizeList(list_item(self.item_type), size),
+            None => DataType::List(list_item(self.item_type)),
+        }
+    }
+}
+
+impl ser::SerializeSeq for ListInferrer {
+    type Ok = DataType;
+    type Error = InferError;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> std::result::Result<(), InferError> {
+        self.add(value)
+    }
+
+    fn end(self) -> std::result::Result<DataType, InferError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for ListInferrer {
+    type Ok = DataType;
+    type Error = InferError;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> std::result::Result<(), InferError> {
+        self.add(value)
+    }
+
+    fn end(self) -> std::result::Result<DataType, InferError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for ListInferrer {
+    type Ok = DataType;
+    type Error = InferError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> std::result::Result<(), InferError> {
+        self.add(value)
+    }
+
+    fn end(self) -> std::result::Result<DataType, InferError> {
+        Ok(self.finish())
+    }
+}
+
+struct StructInferrer {
+    fields: Vec<Field>,
+}
+
+impl ser::SerializeStruct for StructInferrer {
+    type Ok = DataType;
+    type Error = InferError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> std::result::Result<(), InferError> {
+        let data_type = value.serialize(SchemaInferrer)?;
+        self.fields.push(Field::new(key, data_type, true));
+        Ok(())
+    }
+
+    fn end(self) -> std::result::Result<DataType, InferError> {
+        Ok(DataType::Struct(Fields::from(self.fields)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int32Type;
+    use futures::TryStreamExt;
+    use serde::Serialize;
+
+    use super::*;
+    use crate::arrow::SerdeRecordBatchReader;
+    use crate::query::{ExecutableQuery, QueryBase};
+
+    #[derive(Serialize)]
+    struct Item {
+        id: i32,
+        tag: Option<String>,
+        #[serde(with = "crate::arrow::serde_vector")]
+        vector: Vec<f32>,
+    }
+
+    fn item(id: i32, tag: Option<&str>, vector: Vec<f32>) -> Item {
+        Item {
+            id,
+            tag: tag.map(String::from),
+            vector,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_table_from_serde() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let db = crate::connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let items = vec![
+            item(1, None, vec![0.0, 0.0]),
+            item(2, Some("b"), vec![1.0, 1.0]),
+            item(3, Some("c"), vec![5.0, 5.0]),
+        ];
+        let table = db
+            .create_table("serde", SerdeRecordBatchReader::new(items).unwrap())
+            .execute()
+            .await
+            .unwrap();
+
+        let schema = table.schema().await.unwrap();
+        assert_eq!(schema.field(0).data_type(), &DataType::Int32);
+        // The type of `tag` is taken from the second record
+        assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+        assert_eq!(
+            schema.field(2).data_type(),
+            &DataType::FixedSizeList(list_item(DataType::Float32), 2)
+        );
+
+        let batches = table
+            .query()
+            .nearest_to(&[4.0, 4.0])
+            .unwrap()
+            .limit(1)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let ids = batches[0]["id"].as_primitive::<Int32Type>();
+        assert_eq!(ids.values(), &[3]);
+        assert_eq!(batches[0]["tag"].as_string::<i32>().value(0), "c");
+    }
+
+    #[test]
+    fn test_serde_errors() {
+        let err = SerdeRecordBatchReader::new(Vec::<Item>::new())
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+
+        let items = vec![item(1, None, vec![0.0, 0.0]), item(2, None, vec![1.0])];
+        let err = SerdeRecordBatchReader::new(items).err().unwrap();
+        assert!(err.to_string().contains("vector"), "{}", err);
+
+        let err = SerdeRecordBatchReader::new(vec![1, 2, 3]).err().unwrap();
+        assert!(err.to_string().contains("must be structs"), "{}", err);
+    }
+}
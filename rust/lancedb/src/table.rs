@@ -14,65 +14,94 @@
 
 //! LanceDB Table APIs
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 
 use arrow::array::AsArray;
-use arrow::datatypes::Float32Type;
-use arrow_array::{RecordBatchIterator, RecordBatchReader};
+use arrow::datatypes::{Float32Type, Float64Type, UInt64Type, UInt8Type};
+use arrow::row::{RowConverter, SortField};
+use arrow_array::{new_null_array, Array, RecordBatch, RecordBatchIterator, RecordBatchReader};
 use arrow_schema::{DataType, Field, Schema, SchemaRef};
 use async_trait::async_trait;
-use datafusion_physical_plan::ExecutionPlan;
+use chrono::{DateTime, Utc};
+use datafusion_common::ScalarValue;
+use datafusion_expr::Expr;
+use datafusion_physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use datafusion_physical_plan::expressions::{Column, Literal};
+use datafusion_physical_plan::filter::FilterExec;
+use datafusion_physical_plan::limit::GlobalLimitExec;
+use datafusion_physical_plan::projection::ProjectionExec;
+use datafusion_physical_plan::repartition::RepartitionExec;
+use datafusion_physical_plan::union::UnionExec;
+use datafusion_physical_plan::{ExecutionPlan, PhysicalExpr};
+use futures::{StreamExt, TryStreamExt};
 use lance::dataset::builder::DatasetBuilder;
 use lance::dataset::cleanup::RemovalStats;
-use lance::dataset::optimize::{compact_files, CompactionMetrics, IndexRemapperOptions};
+use lance::dataset::optimize::{compact_files, IndexRemapperOptions};
 use lance::dataset::scanner::{DatasetRecordBatchStream, Scanner};
+use lance::dataset::transaction::Operation;
 pub use lance::dataset::ColumnAlteration;
 pub use lance::dataset::NewColumnTransform;
 pub use lance::dataset::ReadParams;
 use lance::dataset::{
-    Dataset, UpdateBuilder as LanceUpdateBuilder, WhenMatched, WriteMode, WriteParams,
+    BatchUDF, Dataset, UpdateBuilder as LanceUpdateBuilder, WhenMatched, WriteMode, WriteParams,
 };
 use lance::dataset::{MergeInsertBuilder as LanceMergeInsertBuilder, WhenNotMatchedBySource};
-use lance::io::WrappingObjectStore;
+use lance::dataset::{DATA_DIR, ROW_ID};
+use lance::io::exec::{ANNIvfSubIndexExec, KNNFlatExec, Planner};
+use lance::io::{ObjectStore, ObjectStoreParams, WrappingObjectStore};
+use lance_core::ROW_ADDR;
 use lance_datafusion::exec::execute_plan;
+use lance_file::reader::FileReader;
 use lance_index::vector::hnsw::builder::HnswBuildParams;
 use lance_index::vector::ivf::IvfBuildParams;
 use lance_index::vector::pq::PQBuildParams;
 use lance_index::vector::sq::builder::SQBuildParams;
+use lance_index::vector::DIST_COL;
 use lance_index::DatasetIndexExt;
 use lance_index::IndexType;
+use lance_index::{INDEX_FILE_NAME, INDEX_METADATA_SCHEMA_KEY};
+use lance_table::format::SelfDescribingFileReader;
+use lance_table::io::deletion::{read_deletion_file, write_deletion_file};
 use log::info;
 use serde::{Deserialize, Serialize};
 use snafu::whatever;
 
-use crate::arrow::IntoArrow;
+use crate::arrow::{IntoArrow, SendableRecordBatchStream, StreamRecordBatchReader};
 use crate::connection::NoData;
+use crate::data::sanitize::{coerce_view_types, handle_bad_vectors};
 use crate::embeddings::{EmbeddingDefinition, EmbeddingRegistry, MaybeEmbedded, MemoryRegistry};
 use crate::error::{Error, Result};
+use crate::index::scalar::BTreeIndexBuilder;
 use crate::index::vector::{
     IvfHnswPqIndexBuilder, IvfHnswSqIndexBuilder, IvfPqIndexBuilder, VectorIndex,
 };
 use crate::index::IndexConfig;
 use crate::index::IndexStatistics;
 use crate::index::{
-    vector::{suggested_num_partitions, suggested_num_sub_vectors},
+    vector::{suggested_num_partitions, suggested_num_sub_vectors, validate_ivf_pq_params},
     Index, IndexBuilder,
 };
 use crate::query::{
-    IntoQueryVector, Query, QueryExecutionOptions, Select, VectorQuery, DEFAULT_TOP_K,
+    IntoQueryVector, Query, QueryExecutionOptions, Select, VectorQuery, DEFAULT_NPROBES,
+    DEFAULT_TOP_K,
 };
-use crate::utils::{default_vector_column, PatchReadParam, PatchWriteParam};
+use crate::utils::{default_vector_column, rewrite_filter, PatchReadParam, PatchWriteParam};
+use crate::DistanceType;
 
+use self::aggregate::{execute_aggregate, AggregateBuilder};
 use self::dataset::DatasetConsistencyWrapper;
-use self::merge::MergeInsertBuilder;
+use self::merge::{MergeInsertBuilder, MergeInsertStats};
 
+pub mod aggregate;
 pub(crate) mod dataset;
+mod hamming;
 pub mod merge;
 
+pub use crate::data::sanitize::BadVectorMode;
 pub use chrono::Duration;
-pub use lance::dataset::optimize::CompactionOptions;
+pub use lance::dataset::optimize::{CompactionMetrics, CompactionOptions};
 pub use lance_index::optimize::OptimizeOptions;
 
 /// Defines the type of column
@@ -225,6 +254,100 @@ pub struct OptimizeStats {
     pub prune: Option<RemovalStats>,
 }
 
+/// Options for [`Table::compact_files`]
+#[derive(Clone, Debug)]
+pub struct CompactOptions {
+    /// Fragments with fewer rows than this are merged with their neighbors
+    ///
+    /// The default is 1,048,576 rows.
+    pub target_rows_per_fragment: usize,
+    /// Whether fragments with many deleted rows (more than 10%) are rewritten
+    /// without the deleted rows
+    ///
+    /// The default is true.
+    pub materialize_deletions: bool,
+}
+
+impl Default for CompactOptions {
+    fn default() -> Self {
+        let defaults = CompactionOptions::default();
+        Self {
+            target_rows_per_fragment: defaults.target_rows_per_fragment,
+            materialize_deletions: defaults.materialize_deletions,
+        }
+    }
+}
+
+impl From<CompactOptions> for CompactionOptions {
+    fn from(options: CompactOptions) -> Self {
+        Self {
+            target_rows_per_fragment: options.target_rows_per_fragment,
+            materialize_deletions: options.materialize_deletions,
+            ..Default::default()
+        }
+    }
+}
+
+/// The result of an update operation
+///
+/// See [`UpdateBuilder::execute`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdateResult {
+    /// The number of rows that matched the filter (and so were rewritten)
+    pub rows_updated: u64,
+    /// The version of the table created by the update
+    pub version: u64,
+}
+
+/// The result of a delete operation
+///
+/// See [`Table::delete`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeleteResult {
+    /// The number of rows that matched the predicate (and so were deleted)
+    pub rows_deleted: u64,
+    /// The version of the table created by the delete
+    pub version: u64,
+}
+
+/// A version of a table
+#[derive(Clone, Debug, PartialEq)]
+pub struct Version {
+    /// The version number, starting at 1 when the table is created
+    pub version: u64,
+    /// The time when the version was committed
+    pub timestamp: DateTime<Utc>,
+    /// Key-value metadata attached to the version
+    ///
+    /// This is currently always empty for local tables.  The storage format does not
+    /// yet provide a way to attach metadata to a commit, so write operations cannot
+    /// set it.
+    pub metadata: HashMap<String, String>,
+}
+
+/// A summary of the storage used by a table
+///
+/// See [`Table::stats`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableStats {
+    /// The number of rows in the table, not counting deleted rows
+    pub num_rows: usize,
+    /// The number of fragments (groups of data files) in the table
+    pub num_fragments: usize,
+    /// The ids of the fragments, in the order they are stored in the table
+    ///
+    /// See [`crate::query::Query::with_fragments`]
+    pub fragment_ids: Vec<u64>,
+    /// The number of fragments that have a deletion file
+    pub num_deletion_files: usize,
+    /// The total size of the data files of the current version, in bytes
+    ///
+    /// This includes rows that have been deleted but not yet compacted away.
+    pub data_bytes: u64,
+    /// The total size of the index files of the current version, in bytes
+    pub index_bytes: u64,
+}
+
 /// Options to use when writing data
 #[derive(Clone, Debug, Default)]
 pub struct WriteOptions {
@@ -244,6 +367,16 @@ pub enum AddDataMode {
     #[default]
     Append,
     /// The existing table will be overwritten with the new data
+    ///
+    /// The overwrite is atomic, readers will see either the old data or the new
+    /// data.  It creates a new version of the table, so the previous data can still
+    /// be read with [`Table::checkout`] until old versions are cleaned up.
+    ///
+    /// The indices of the table are trained again on the new data, with the same
+    /// distance type, number of partitions and PQ parameters (other parameters use
+    /// their defaults).  Indices on columns that are not in the new data are dropped.
+    /// If an index cannot be trained on the new data (e.g. there are too few rows)
+    /// an error is returned, the new data has been written at this point.
     Overwrite,
 }
 
@@ -254,6 +387,12 @@ pub struct AddDataBuilder<T: IntoArrow> {
     pub(crate) data: T,
     pub(crate) mode: AddDataMode,
     pub(crate) write_options: WriteOptions,
+    pub(crate) add_missing_columns: bool,
+    pub(crate) fill_values: HashMap<String, ScalarValue>,
+    pub(crate) on_bad_vector: Option<BadVectorMode>,
+    pub(crate) deduplicate_on: Option<Vec<String>>,
+    pub(crate) max_rows_per_file: Option<usize>,
+    pub(crate) max_bytes_per_file: Option<usize>,
     embedding_registry: Option<Arc<dyn EmbeddingRegistry>>,
 }
 
@@ -263,11 +402,20 @@ impl<T: IntoArrow> std::fmt::Debug for AddDataBuilder<T> {
             .field("parent", &self.parent)
             .field("mode", &self.mode)
             .field("write_options", &self.write_options)
+            .field("add_missing_columns", &self.add_missing_columns)
+            .field("fill_values", &self.fill_values)
+            .field("on_bad_vector", &self.on_bad_vector)
+            .field("deduplicate_on", &self.deduplicate_on)
+            .field("max_rows_per_file", &self.max_rows_per_file)
+            .field("max_bytes_per_file", &self.max_bytes_per_file)
             .finish()
     }
 }
 
 impl<T: IntoArrow> AddDataBuilder<T> {
+    /// Whether to append to or overwrite the existing data, see [`AddDataMode`]
+    ///
+    /// The default is [`AddDataMode::Append`].
     pub fn mode(mut self, mode: AddDataMode) -> Self {
         self.mode = mode;
         self
@@ -278,6 +426,100 @@ impl<T: IntoArrow> AddDataBuilder<T> {
         self
     }
 
+    /// Add columns of the new data that the table does not have, instead of failing
+    ///
+    /// The type of each new column is taken from the new data.  The existing rows
+    /// are backfilled with the column's [fill value](Self::fill_value), or with
+    /// nulls if there is none, in which case the column is made nullable.  Tables
+    /// written in the legacy format can only store the nulls of string and binary
+    /// columns, and no format stores the nulls of a struct, so the other columns
+    /// need a fill value.
+    ///
+    /// The columns are added in a separate version before the data is appended.
+    /// If the append fails the table keeps the new columns.
+    ///
+    /// This is ignored when the mode is [`AddDataMode::Overwrite`], which replaces
+    /// the schema of the table anyway.  The default is false.
+    pub fn add_missing_columns(mut self, add_missing_columns: bool) -> Self {
+        self.add_missing_columns = add_missing_columns;
+        self
+    }
+
+    /// The value to backfill the existing rows with when [`Self::add_missing_columns`]
+    /// adds `column` to the table
+    ///
+    /// The value is cast to the type of the column in the new data.
+    pub fn fill_value(mut self, column: impl Into<String>, value: impl Into<ScalarValue>) -> Self {
+        self.fill_values.insert(column.into(), value.into());
+        self
+    }
+
+    /// Check the vectors of the new data and handle the bad ones with `mode`
+    ///
+    /// A vector is bad if it has NaN, infinite or null values, or if it does not
+    /// have the dimension of the vector column of the table.  The vector columns are
+    /// the fixed size list columns of the current schema of the table, also when the
+    /// mode is [`AddDataMode::Overwrite`].  The vector columns
+    /// may be given as variable size lists, in which case the dimension is checked
+    /// for each row, and they are converted to the fixed size lists of the table.
+    /// Null vectors are not bad.  The vectors computed by the embedding functions
+    /// of the table are checked as well.
+    ///
+    /// By default the vectors are not checked and a wrong dimension fails the whole
+    /// write, while NaN and infinite values are stored as they are.
+    pub fn on_bad_vector(mut self, mode: BadVectorMode) -> Self {
+        self.on_bad_vector = Some(mode);
+        self
+    }
+
+    /// Skip the new rows whose key is already in the table
+    ///
+    /// The key is made of the given columns.  This is cheaper than a
+    /// [`Table::merge_insert`] when the existing rows should never be updated.
+    /// If the key is a single column with a scalar index then the index is used
+    /// to look up the keys, otherwise the whole table is scanned.
+    ///
+    /// Rows of the new data that share a key with each other but not with an
+    /// existing row are all added.  This cannot be used with
+    /// [`AddDataMode::Overwrite`].
+    ///
+    /// The rows are written with the object store and storage options the table
+    /// was opened with.  This cannot be combined with [`Self::write_options`],
+    /// [`Self::max_rows_per_file`] or [`Self::max_bytes_per_file`].
+    pub fn deduplicate_on(mut self, columns: &[&str]) -> Self {
+        self.deduplicate_on = Some(columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    /// The maximum number of rows in each data file (fragment) written
+    ///
+    /// The new data is split into as many fragments as needed.  Together with
+    /// [`Self::max_bytes_per_file`] this controls the size of the fragments, a
+    /// fragment is closed as soon as it reaches either limit.  Very small fragments
+    /// make scans slower, [`Table::optimize`] compacts them.
+    ///
+    /// This takes precedence over [`WriteParams::max_rows_per_file`].  The default
+    /// is 1,048,576 rows.  This cannot be combined with [`Self::deduplicate_on`].
+    pub fn max_rows_per_file(mut self, max_rows_per_file: usize) -> Self {
+        self.max_rows_per_file = Some(max_rows_per_file);
+        self
+    }
+
+    /// The maximum size in bytes of each data file (fragment) written
+    ///
+    /// For tables with wide rows (e.g. large vectors or long text) this is an
+    /// easier way to keep the files at a reasonable size than a row limit.  The
+    /// size is checked after each group of rows (1024 rows by default) is written,
+    /// so the limit is soft: a file is closed once it has reached the limit and
+    /// may exceed it by up to one group of rows.
+    ///
+    /// This takes precedence over [`WriteParams::max_bytes_per_file`].  The default
+    /// is 90 GB.  This cannot be combined with [`Self::deduplicate_on`].
+    pub fn max_bytes_per_file(mut self, max_bytes_per_file: usize) -> Self {
+        self.max_bytes_per_file = Some(max_bytes_per_file);
+        self
+    }
+
     pub async fn execute(self) -> Result<()> {
         let parent = self.parent.clone();
         let data = self.data.into_arrow()?;
@@ -286,6 +528,12 @@ impl<T: IntoArrow> AddDataBuilder<T> {
             mode: self.mode,
             parent: self.parent,
             write_options: self.write_options,
+            add_missing_columns: self.add_missing_columns,
+            fill_values: self.fill_values,
+            on_bad_vector: self.on_bad_vector,
+            deduplicate_on: self.deduplicate_on,
+            max_rows_per_file: self.max_rows_per_file,
+            max_bytes_per_file: self.max_bytes_per_file,
             embedding_registry: self.embedding_registry,
         };
         parent.add(without_data, data).await
@@ -323,7 +571,18 @@ impl UpdateBuilder {
     ///
     /// The `update_expr` should be an SQL expression explaining how to calculate
     /// the new value for the column.  The expression will be evaluated against the
-    /// previous row's value.
+    /// previous row's value.  The expression may reference any column of the row
+    /// (e.g. `score * 2 + bonus`) and all expressions see the values from before the
+    /// update.  A null input will generally produce a null result.
+    ///
+    /// `CASE` expressions are not supported.  To update rows differently depending on
+    /// a condition use several updates with [`Self::only_if`].
+    ///
+    /// The result is cast to the type of the column.  Instead of silently truncating
+    /// or wrapping values, an error is returned if a literal does not fit the column
+    /// (e.g. `1.5` or `3000000000` for an `Int32` column) or if any other expression
+    /// has a type that does not widen to the column's type.  Use an explicit `CAST`
+    /// if the conversion is intended.
     ///
     /// # Examples
     ///
@@ -346,7 +605,10 @@ impl UpdateBuilder {
     }
 
     /// Executes the update operation
-    pub async fn execute(self) -> Result<()> {
+    ///
+    /// Returns the number of rows that were updated and the version of the table
+    /// created by the update.  A new version is created even if no rows match the filter.
+    pub async fn execute(self) -> Result<UpdateResult> {
         if self.columns.is_empty() {
             Err(Error::InvalidInput {
                 message: "at least one column must be specified in an update operation".to_string(),
@@ -367,8 +629,13 @@ pub(crate) trait TableInternal: std::fmt::Display + std::fmt::Debug + Send + Syn
     fn name(&self) -> &str;
     /// Get the arrow [Schema] of the table.
     async fn schema(&self) -> Result<SchemaRef>;
+    /// Get the arrow [Schema] of a specific version of the table.
+    async fn schema_at_version(&self, version: u64) -> Result<SchemaRef>;
     /// Count the number of rows in this table.
     async fn count_rows(&self, filter: Option<String>) -> Result<usize>;
+    async fn stats(&self) -> Result<TableStats>;
+    async fn aggregate(&self, params: AggregateBuilder) -> Result<RecordBatch>;
+    async fn take(&self, row_ids: &[u64], columns: Option<Vec<String>>) -> Result<RecordBatch>;
     async fn create_plan(
         &self,
         query: &VectorQuery,
@@ -384,15 +651,27 @@ pub(crate) trait TableInternal: std::fmt::Display + std::fmt::Debug + Send + Syn
         add: AddDataBuilder<NoData>,
         data: Box<dyn arrow_array::RecordBatchReader + Send>,
     ) -> Result<()>;
-    async fn delete(&self, predicate: &str) -> Result<()>;
-    async fn update(&self, update: UpdateBuilder) -> Result<()>;
+    async fn delete(&self, predicate: &str) -> Result<DeleteResult>;
+    async fn delete_by_keys(
+        &self,
+        column: &str,
+        keys: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<DeleteResult>;
+    async fn update(&self, update: UpdateBuilder) -> Result<UpdateResult>;
     async fn create_index(&self, index: IndexBuilder) -> Result<()>;
     async fn list_indices(&self) -> Result<Vec<IndexConfig>>;
+    async fn index_stats(&self, index_name: &str) -> Result<Option<IndexStatistics>>;
+    async fn drop_index(&self, name: &str) -> Result<()>;
+    async fn wait_for_index(
+        &self,
+        index_names: &[&str],
+        timeout: std::time::Duration,
+    ) -> Result<()>;
     async fn merge_insert(
         &self,
         params: MergeInsertBuilder,
         new_data: Box<dyn RecordBatchReader + Send>,
-    ) -> Result<()>;
+    ) -> Result<MergeInsertStats>;
     async fn optimize(&self, action: OptimizeAction) -> Result<OptimizeStats>;
     async fn add_columns(
         &self,
@@ -401,10 +680,19 @@ pub(crate) trait TableInternal: std::fmt::Display + std::fmt::Debug + Send + Syn
     ) -> Result<()>;
     async fn alter_columns(&self, alterations: &[ColumnAlteration]) -> Result<()>;
     async fn drop_columns(&self, columns: &[&str]) -> Result<()>;
+    async fn merge(
+        &self,
+        other: Box<dyn RecordBatchReader + Send>,
+        left_on: &str,
+        right_on: &str,
+    ) -> Result<()>;
     async fn version(&self) -> Result<u64>;
     async fn checkout(&self, version: u64) -> Result<()>;
     async fn checkout_latest(&self) -> Result<()>;
+    async fn checkout_version(&self, version: u64) -> Result<Arc<dyn TableInternal>>;
+    async fn list_versions(&self) -> Result<Vec<Version>>;
     async fn restore(&self) -> Result<()>;
+    async fn restore_version(&self, version: u64) -> Result<()>;
     async fn table_definition(&self) -> Result<TableDefinition>;
 }
 
@@ -455,12 +743,38 @@ impl Table {
     }
 
     /// Get the arrow [Schema] of the table.
+    ///
+    /// Schema changes made through this table (e.g. [`Self::add_columns`]) are visible
+    /// immediately.  Changes made by other processes (or other table handles) are
+    /// subject to the connection's read consistency interval, in the same way as any
+    /// other read.  See [`crate::connection::ConnectBuilder::read_consistency_interval`]
+    /// for more details.  Use [`Self::checkout_latest`] to explicitly refresh the table.
     pub async fn schema(&self) -> Result<SchemaRef> {
         self.inner.schema().await
     }
 
+    /// Get the arrow [Schema] of a specific version of the table.
+    ///
+    /// This does not change the version that is checked out.
+    pub async fn schema_at_version(&self, version: u64) -> Result<SchemaRef> {
+        self.inner.schema_at_version(version).await
+    }
+
     /// Count the number of rows in this dataset.
     ///
+    /// If a filter is provided then only rows matching the filter are counted.  The
+    /// filter is an SQL predicate, parsed in the same way as
+    /// [`crate::query::QueryBase::only_if`]
+    /// (e.g. `"category = 'a' AND score > 0.5"`).  The filter is pushed down into the
+    /// scan and so the matching rows are never materialized.  If there is a scalar
+    /// index on the filtered column(s) then it will be used to satisfy the filter.
+    ///
+    /// Without a filter no data is read.  The count is the sum of the row counts
+    /// stored for each fragment in the manifest, minus the deleted rows recorded
+    /// with the deletion files, and so the cost does not depend on the number of
+    /// rows.  (Tables written by very old versions of lance lack these counts, for
+    /// those the fragment files are opened to read them.)
+    ///
     /// # Arguments
     ///
     /// * `filter` if present, only count rows matching the filter
@@ -468,6 +782,79 @@ impl Table {
         self.inner.count_rows(filter).await
     }
 
+    /// Write all of the rows of the table to a Parquet file
+    ///
+    /// This is a shorthand for writing the results of [`Self::query`] with
+    /// [`crate::query::ExecutableQuery::write_parquet`], use a query to export only some of the
+    /// rows or columns.  The file can be read by any Parquet reader, vector columns
+    /// are stored as fixed size lists.  Returns the number of rows written.
+    #[cfg(feature = "parquet")]
+    pub async fn export_parquet(
+        &self,
+        path: &str,
+        options: crate::io::parquet::ParquetWriteOptions,
+    ) -> Result<usize> {
+        crate::query::ExecutableQuery::write_parquet(&self.query(), path, options).await
+    }
+
+    /// Summarize the storage used by the current version of the table
+    ///
+    /// The row and fragment counts come from the manifest.  The sizes are read
+    /// from the metadata of each data and index file, no data is downloaded.  On
+    /// an object store this makes one request per file, so this can take a while
+    /// for a table with many fragments.
+    ///
+    /// Files that are only used by older versions are not counted, run
+    /// [`Self::optimize`] with [`OptimizeAction::Prune`] to remove them.
+    pub async fn stats(&self) -> Result<TableStats> {
+        self.inner.stats().await
+    }
+
+    /// Create an [`AggregateBuilder`] to compute aggregates over groups of rows
+    ///
+    /// This is the equivalent of a SQL `GROUP BY` query.  The table is scanned,
+    /// only reading the columns that are used, and the result is returned as a
+    /// single [`RecordBatch`] with one row per group.
+    ///
+    /// ```
+    /// # use lancedb::table::aggregate::{Count, Sum};
+    /// # async fn example(tbl: &lancedb::Table) -> lancedb::Result<()> {
+    /// let totals = tbl
+    ///     .aggregate()
+    ///     .group_by(&["category"])
+    ///     .agg([Count::all(), Sum::col("amount").alias("total")])
+    ///     .execute()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn aggregate(&self) -> AggregateBuilder {
+        AggregateBuilder::new(self.inner.clone())
+    }
+
+    /// Fetch rows by their row id
+    ///
+    /// The row ids are the values of the `_rowid` column returned by a query with
+    /// [`crate::query::QueryBase::with_row_id`].  This is a point lookup of each row
+    /// and does not run a search or a scan, so it can be used to fetch the full rows
+    /// (or columns that were not selected) for the results of an earlier query.
+    ///
+    /// The rows are returned in the same order as `row_ids`.  Unless the table was
+    /// created with move-stable row ids (`enable_move_stable_row_ids` in the Lance
+    /// write parameters), the row ids are the addresses of the rows and are only
+    /// valid until the table is compacted (see [`Self::optimize`]), and rows that are
+    /// updated are given a new row id.  If a row id does not refer to a row in the
+    /// current version of the table, for example because the row was deleted, then
+    /// an error is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `row_ids` - The row ids of the rows to fetch
+    /// * `columns` - The columns to fetch, if `None` then all columns are fetched
+    pub async fn take(&self, row_ids: &[u64], columns: Option<Vec<String>>) -> Result<RecordBatch> {
+        self.inner.take(row_ids, columns).await
+    }
+
     /// Insert new records into this Table
     ///
     /// # Arguments
@@ -480,10 +867,33 @@ impl Table {
             data: batches,
             mode: AddDataMode::Append,
             write_options: WriteOptions::default(),
+            add_missing_columns: false,
+            fill_values: HashMap::new(),
+            on_bad_vector: None,
+            deduplicate_on: None,
+            max_rows_per_file: None,
+            max_bytes_per_file: None,
             embedding_registry: Some(self.embedding_registry.clone()),
         }
     }
 
+    /// Insert new records into this Table from a stream of batches
+    ///
+    /// This is the same as [`Self::add`] (in append mode) except the data is pulled from
+    /// the stream as it is written instead of being provided up front.  Batches are
+    /// written to data files as they arrive, which are closed when they reach the row
+    /// limits in [`WriteParams`] (see [`AddDataBuilder::write_options`] on `add`), so
+    /// the whole stream is never held in memory.  The data is validated against the
+    /// table schema in the same way as [`Self::add`].
+    ///
+    /// Returns the number of rows written.
+    pub async fn add_stream(&self, stream: SendableRecordBatchStream) -> Result<u64> {
+        let reader = StreamRecordBatchReader::new(stream);
+        let num_rows = reader.num_rows();
+        self.add(reader).execute().await?;
+        Ok(num_rows.load(std::sync::atomic::Ordering::Relaxed))
+    }
+
     /// Update existing records in the Table
     ///
     /// An update operation can be used to adjust existing values.  Use the
@@ -504,6 +914,11 @@ impl Table {
 
     /// Delete the rows from table that match the predicate.
     ///
+    /// Returns the number of rows that were deleted and the version of the table
+    /// created by the delete.  Deleted rows are not removed from the data files (they
+    /// are recorded in deletion files until the table is compacted) but they are
+    /// counted all the same.  Rows that were already deleted are not counted.
+    ///
     /// # Arguments
     /// - `predicate` - The SQL predicate string to filter the rows to be deleted.
     ///
@@ -551,10 +966,29 @@ impl Table {
     /// tbl.delete("id > 5").await.unwrap();
     /// # });
     /// ```
-    pub async fn delete(&self, predicate: &str) -> Result<()> {
+    pub async fn delete(&self, predicate: &str) -> Result<DeleteResult> {
         self.inner.delete(predicate).await
     }
 
+    /// Delete the rows from the table whose value in `column` is one of `keys`
+    ///
+    /// `keys` must contain a column with the same name as `column`.  Any other
+    /// columns are ignored, as are null keys.  The keys must have the type of the
+    /// column, except that integer keys may be given for any integer column and
+    /// string keys for any string column.
+    ///
+    /// This avoids building one very large `IN` predicate by hand.  The keys are
+    /// held in memory and the table is scanned once (reading only `column`) to find
+    /// the matching rows.  All of the rows are deleted in a single new version of
+    /// the table, so either every key is deleted or, if an error occurs, none is.
+    ///
+    /// # Arguments
+    /// - `column` - The column to match the keys against, e.g. the primary key
+    /// - `keys` - The keys of the rows to delete
+    pub async fn delete_by_keys(&self, column: &str, keys: impl IntoArrow) -> Result<DeleteResult> {
+        self.inner.delete_by_keys(column, keys.into_arrow()?).await
+    }
+
     /// Create an index on the provided column(s).
     ///
     /// Indices are used to speed up searches and are often needed when the size of the table
@@ -581,7 +1015,17 @@ impl Table {
     /// [Table::optimize].
     ///
     /// Note: Multi-column (composite) indices are not currently supported.  However, they will
-    /// be supported in the future and the API is designed to be compatible with them.
+    /// be supported in the future and the API is designed to be compatible with them.  Until
+    /// then, to speed up filters on several columns (e.g. `tenant_id = 1 AND status = 'open'`)
+    /// create a BTree index on each of the columns.  The indices are combined when a filter
+    /// uses more than one of the indexed columns.
+    ///
+    /// Note: Partial indices (indices that only cover the rows matching some predicate) are
+    /// not currently supported.  An index always covers every row that was in the table when
+    /// it was created (or last optimized).  The underlying storage format has no place to
+    /// record an index predicate and so the query planner would have no way to know which
+    /// rows still need a flat search.  If only a subset of rows needs to be searched quickly
+    /// then consider storing that subset in a separate table.
     ///
     /// # Examples
     ///
@@ -785,7 +1229,9 @@ impl Table {
     /// # });
     /// ```
     pub fn query(&self) -> Query {
-        Query::new(self.inner.clone())
+        let mut query = Query::new(self.inner.clone());
+        query.embedding_registry = Some(self.embedding_registry.clone());
+        query
     }
 
     /// Search the table with a given query vector.
@@ -823,11 +1269,50 @@ impl Table {
     /// optimize should be run frequently.  A good rule of thumb is to run optimize if
     /// you have added or modified 100,000 or more records or run more than 20 data
     /// modification operations.
+    ///
+    /// Optimization creates a new version of the table and does not modify any existing
+    /// files.  It is safe to run while other readers are querying the table, those readers
+    /// will continue to see the version they started with.  Use [`OptimizeAction::Compact`]
+    /// (and its [`CompactionOptions`]) to control the target fragment size or to compact
+    /// without touching the indices.  The returned [`OptimizeStats`] report the number of
+    /// fragments and files that were removed and added by compaction.
     pub async fn optimize(&self, action: OptimizeAction) -> Result<OptimizeStats> {
         self.inner.optimize(action).await
     }
 
+    /// Merge small fragments into larger ones
+    ///
+    /// This only runs the compaction step of [`Self::optimize`], old versions are not
+    /// pruned and new data is not added to the indices.  Indexed fragments are never
+    /// merged with unindexed fragments, so compacting the fragments written by recent
+    /// appends leaves the indices as they are.  When indexed fragments are merged their
+    /// indices are remapped to the new row ids.
+    ///
+    /// Returns the number of fragments and files that were removed and added.
+    pub async fn compact_files(&self, options: CompactOptions) -> Result<CompactionMetrics> {
+        let stats = self
+            .inner
+            .optimize(OptimizeAction::Compact {
+                options: options.into(),
+                remap_options: None,
+            })
+            .await?;
+        Ok(stats.compaction.unwrap_or_default())
+    }
+
     /// Add new columns to the table, providing values to fill in.
+    ///
+    /// With [`NewColumnTransform::SqlExpressions`] each new column is computed from a SQL
+    /// expression, such as `"first || ' ' || last"` or `"price * quantity"`, that is
+    /// evaluated for every row of the table.  The type of the new column is inferred
+    /// from the expression.  Note that string concatenation with `||` treats nulls as
+    /// empty strings.  To handle nulls differently use functions such as `coalesce`
+    /// (e.g. `coalesce(first, last)`) or `concat_ws`, which skips nulls along with their
+    /// separator (e.g. `concat_ws(' ', first, last)`).  `CASE` expressions are not
+    /// supported.
+    ///
+    /// `read_columns` is only used by [`NewColumnTransform::BatchUDF`] and lists the
+    /// columns passed to the function.
     pub async fn add_columns(
         &self,
         transforms: NewColumnTransform,
@@ -836,16 +1321,55 @@ impl Table {
         self.inner.add_columns(transforms, read_columns).await
     }
 
-    /// Change a column's name or nullability.
+    /// Change a column's name, nullability or data type.
+    ///
+    /// Renaming a column or changing its nullability only updates the table's metadata
+    /// and does not rewrite any data.  Changing the data type with
+    /// [`ColumnAlteration::cast_to`] rewrites the column.  Only casts within the same
+    /// family of types (e.g. `Int32` to `Int64` or `Float32` to `Float16`) are allowed and
+    /// an error is returned if a value cannot be represented in the new type.  Indices on
+    /// a column whose type is changed are dropped.
+    ///
+    /// Each call creates a single new version of the table.
     pub async fn alter_columns(&self, alterations: &[ColumnAlteration]) -> Result<()> {
         self.inner.alter_columns(alterations).await
     }
 
     /// Remove columns from the table.
+    ///
+    /// This only changes the schema in a new version of the table, the other
+    /// columns and the row count are unchanged.  The data of the dropped columns
+    /// is not rewritten, it is removed from disk once the data files are
+    /// compacted and old versions are cleaned up by [`Self::optimize`].
+    ///
+    /// An error is returned if an index depends on one of the columns.  The
+    /// index must be dropped first.
     pub async fn drop_columns(&self, columns: &[&str]) -> Result<()> {
         self.inner.drop_columns(columns).await
     }
 
+    /// Add the columns of `other` to the table, matching rows with a left join
+    ///
+    /// Each row of the table is matched with the row of `other` whose `right_on`
+    /// value equals the row's `left_on` value.  Every column of `other`, except
+    /// `right_on`, is added to the table.  Rows of the table without a match get
+    /// nulls in the new columns and rows of `other` without a match are ignored.
+    /// Lance can currently only store these nulls in string, binary and list
+    /// columns, so other columns, such as numbers, must have a match for every row
+    /// of the table or an error is returned.
+    ///
+    /// Only the new columns are written, the existing data files are not
+    /// rewritten.  The merge creates a single new version of the table.
+    ///
+    /// An error is returned if `other` contains the same key more than once or
+    /// if a column of `other` already exists in the table.  `other` is read into
+    /// memory in full.
+    pub async fn merge(&self, other: impl IntoArrow, left_on: &str, right_on: &str) -> Result<()> {
+        self.inner
+            .merge(other.into_arrow()?, left_on, right_on)
+            .await
+    }
+
     /// Retrieve the version of the table
     ///
     /// LanceDb supports versioning.  Every operation that modifies the table increases
@@ -874,6 +1398,49 @@ impl Table {
         self.inner.checkout(version).await
     }
 
+    /// Checks out the version of the Table as of the given time
+    ///
+    /// This checks out the latest version that was committed at or before
+    /// `timestamp` (see [`Self::checkout`]) and returns its number.  For example,
+    /// passing midnight checks out the data as it was at midnight.
+    ///
+    /// Only the versions returned by [`Self::list_versions`] are considered.  An
+    /// error is returned if `timestamp` is before the oldest of them, e.g. because
+    /// it predates the creation of the table.
+    pub async fn checkout_at(&self, timestamp: DateTime<Utc>) -> Result<u64> {
+        let versions = self.inner.list_versions().await?;
+        let version = versions
+            .iter()
+            .filter(|version| version.timestamp <= timestamp)
+            .max_by_key(|version| version.version)
+            .ok_or_else(|| Error::InvalidInput {
+                message: format!(
+                    "there is no version of table {} at or before {}",
+                    self.name(),
+                    timestamp
+                ),
+            })?
+            .version;
+        self.inner.checkout(version).await?;
+        Ok(version)
+    }
+
+    /// Open a new, read-only handle to a specific version of the Table
+    ///
+    /// Unlike [`Self::checkout`] this does not change the current handle.  The returned
+    /// table always reads the data at `version`, ignoring any read consistency interval,
+    /// and [`Self::version`] on it returns `version`.  This is useful for reproducible
+    /// reads of an older snapshot while other handles continue to see new data.
+    ///
+    /// Any operation that modifies the returned table will fail.  Use
+    /// [`Self::checkout_latest`] on it to turn it back into a normal handle.
+    pub async fn checkout_version(&self, version: u64) -> Result<Self> {
+        Ok(Self::new_with_embedding_registry(
+            self.inner.checkout_version(version).await?,
+            self.embedding_registry.clone(),
+        ))
+    }
+
     /// Ensures the table is pointing at the latest version
     ///
     /// This can be used to manually update a table when the read_consistency_interval is None
@@ -882,6 +1449,15 @@ impl Table {
         self.inner.checkout_latest().await
     }
 
+    /// List the versions of the table that have not been cleaned up, oldest first
+    ///
+    /// Any of these versions can be passed to [`Self::checkout`] or
+    /// [`Self::checkout_version`].  Versions removed by [`Self::optimize`] with
+    /// [`OptimizeAction::Prune`] are no longer listed.
+    pub async fn list_versions(&self) -> Result<Vec<Version>> {
+        self.inner.list_versions().await
+    }
+
     /// Restore the table to the currently checked out version
     ///
     /// This operation will fail if checkout has not been called previously
@@ -896,10 +1472,210 @@ impl Table {
         self.inner.restore().await
     }
 
+    /// Restore the table to a previous version
+    ///
+    /// This is equivalent to calling [`Self::checkout`] followed by [`Self::restore`].
+    /// A new version is created whose data matches `version`.  The versions after
+    /// `version` are kept and can still be checked out.
+    ///
+    /// Once the operation concludes the table will no longer be in a checked out state,
+    /// even if it was before.  An error is returned if `version` does not exist, in
+    /// which case the table is left unchanged.
+    pub async fn restore_version(&self, version: u64) -> Result<()> {
+        self.inner.restore_version(version).await
+    }
+
     /// List all indices that have been created with [`Self::create_index`]
+    ///
+    /// Each [`IndexConfig`] reports how many rows the index covers and the table
+    /// version it was built at.  Comparing these with [`Self::count_rows`] and
+    /// [`Self::version`] shows whether an index is out of date.
     pub async fn list_indices(&self) -> Result<Vec<IndexConfig>> {
         self.inner.list_indices().await
     }
+
+    /// Get statistics about the index with the given name
+    ///
+    /// Returns `None` if there is no index with that name.
+    ///
+    /// The statistics report how many rows are covered by the index and how many
+    /// are not.  Rows added after the index was built are not indexed until the
+    /// index is optimized, so a growing number of unindexed rows is a sign that
+    /// [`Self::optimize`] should be run.
+    pub async fn index_stats(
+        &self,
+        index_name: impl AsRef<str>,
+    ) -> Result<Option<IndexStatistics>> {
+        self.inner.index_stats(index_name.as_ref()).await
+    }
+
+    /// Drop the index with the given name
+    ///
+    /// The names of the indices are reported by [`Self::list_indices`].  This creates a
+    /// new version of the table without the index, queries stop using it and the column
+    /// can be indexed again.  The other indices and the data are not changed.  Returns
+    /// [`Error::IndexNotFound`] if the table has no index with that name.
+    ///
+    /// The files of the index are still used by the older versions of the table, so they
+    /// are only removed when those versions are, see [`OptimizeAction::Prune`].
+    pub async fn drop_index(&self, name: &str) -> Result<()> {
+        self.inner.drop_index(name).await
+    }
+
+    /// Wait until the indices with the given names are ready to be used by queries
+    ///
+    /// LanceDB Cloud builds indices in the background, so [`Self::create_index`] can
+    /// return before the index is built and queries made right after do not use it.
+    /// This polls the status of the indices until all of them are built, and returns
+    /// [`Error::Timeout`] if that takes longer than `timeout`.
+    ///
+    /// LanceDB OSS builds an index before [`Self::create_index`] returns, so this only
+    /// checks that the indices exist and returns [`Error::IndexNotFound`] otherwise.
+    pub async fn wait_for_index(
+        &self,
+        index_names: &[&str],
+        timeout: std::time::Duration,
+    ) -> Result<()> {
+        self.inner.wait_for_index(index_names, timeout).await
+    }
+}
+
+/// The directory, relative to the table, that Lance stores indices in
+const INDICES_DIR: &str = "_indices";
+
+/// The maximum number of file sizes requested at once by [`Table::stats`]
+const STATS_CONCURRENCY: usize = 16;
+
+/// The column that tags the results of a search with several query vectors
+const QUERY_INDEX_COL: &str = "query_index";
+
+/// Cast the keys given to [`Table::delete_by_keys`] to the type of the key column
+fn cast_keys(
+    keys: &arrow_array::ArrayRef,
+    column: &str,
+    data_type: &DataType,
+) -> Result<arrow_array::ArrayRef> {
+    let is_string = |dt: &DataType| matches!(dt, DataType::Utf8 | DataType::LargeUtf8);
+    let key_type = keys.data_type();
+    if key_type == data_type {
+        return Ok(keys.clone());
+    }
+    if (key_type.is_integer() && data_type.is_integer())
+        || (is_string(key_type) && is_string(data_type))
+    {
+        let options = arrow_cast::CastOptions {
+            safe: false,
+            ..Default::default()
+        };
+        return Ok(arrow_cast::cast_with_options(keys, data_type, &options)?);
+    }
+    Err(Error::InvalidInput {
+        message: format!(
+            "The keys have type {} but the column {} has type {}",
+            key_type, column, data_type
+        ),
+    })
+}
+
+/// Whether every value of `from` can be cast to `to` without losing data
+fn is_widening(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    // The number of bits of an integer type, and whether it is signed
+    let int_bits = |dt: &DataType| match dt {
+        Int8 => Some((8, true)),
+        Int16 => Some((16, true)),
+        Int32 => Some((32, true)),
+        Int64 => Some((64, true)),
+        UInt8 => Some((8, false)),
+        UInt16 => Some((16, false)),
+        UInt32 => Some((32, false)),
+        UInt64 => Some((64, false)),
+        _ => None,
+    };
+    // The number of bits of the significand of a floating point type
+    let float_bits = |dt: &DataType| match dt {
+        Float16 => Some(11),
+        Float32 => Some(24),
+        Float64 => Some(53),
+        _ => None,
+    };
+    if from == to || from == &Null {
+        return true;
+    }
+    match (
+        int_bits(from),
+        int_bits(to),
+        float_bits(from),
+        float_bits(to),
+    ) {
+        (Some((from_bits, from_signed)), Some((to_bits, to_signed)), _, _) => {
+            if from_signed == to_signed {
+                from_bits <= to_bits
+            } else {
+                !from_signed && from_bits < to_bits
+            }
+        }
+        (Some((from_bits, signed)), _, _, Some(to_bits)) => {
+            from_bits - usize::from(signed) <= to_bits
+        }
+        (_, _, Some(from_bits), Some(to_bits)) => from_bits <= to_bits,
+        _ => matches!(
+            (from, to),
+            (Utf8, LargeUtf8) | (Binary, LargeBinary) | (Date32, Date64)
+        ),
+    }
+}
+
+/// Whether the values of `array` can be cast to `to` without losing data
+///
+/// Numbers must keep their value, except that floating point numbers may lose
+/// precision but not overflow.  The elements of lists follow the same rules.
+fn fits_losslessly(array: &arrow_array::ArrayRef, to: &DataType) -> bool {
+    let options = arrow_cast::CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    let Ok(cast) = arrow_cast::cast_with_options(array, to, &options) else {
+        return false;
+    };
+    let from = array.data_type();
+    let list_values = |array: &dyn Array| match array.data_type() {
+        DataType::List(_) => Some(array.as_list::<i32>().values().clone()),
+        DataType::LargeList(_) => Some(array.as_list::<i64>().values().clone()),
+        DataType::FixedSizeList(_, _) => Some(array.as_fixed_size_list().values().clone()),
+        _ => None,
+    };
+    if from.is_floating() && to.is_floating() {
+        let finite = |array: &arrow_array::ArrayRef| {
+            arrow_cast::cast(array, &DataType::Float64).map(|array| {
+                array
+                    .as_primitive::<Float64Type>()
+                    .iter()
+                    .map(|value| value.map(f64::is_finite))
+                    .collect::<Vec<_>>()
+            })
+        };
+        matches!((finite(array), finite(&cast)), (Ok(before), Ok(after)) if before == after)
+    } else if from.is_numeric() && to.is_numeric() {
+        arrow_cast::cast_with_options(&cast, from, &options)
+            .map_or(false, |round_trip| round_trip.as_ref() == array.as_ref())
+    } else if let (Some(values), Some(cast_values)) = (list_values(array), list_values(&cast)) {
+        fits_losslessly(&values, cast_values.data_type())
+    } else {
+        true
+    }
+}
+
+/// Whether a column of `data_type` keeps its nulls when written to a data file
+///
+/// Legacy files only store nulls for strings and binary.  Neither format stores
+/// the nulls of a struct itself, only those of its fields.
+fn stores_nulls(data_type: &DataType, legacy: bool) -> bool {
+    match data_type {
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Binary | DataType::LargeBinary => true,
+        DataType::Struct(_) => false,
+        _ => !legacy,
+    }
 }
 
 impl From<NativeTable> for Table {
@@ -908,6 +1684,9 @@ impl From<NativeTable> for Table {
     }
 }
 
+/// The output columns of a dynamic projection and the expressions that calculate them
+type Projection = Vec<(String, Expr)>;
+
 /// A table in a LanceDB database.
 #[derive(Debug, Clone)]
 pub struct NativeTable {
@@ -918,11 +1697,14 @@ pub struct NativeTable {
     // the object store wrapper to use on write path
     store_wrapper: Option<Arc<dyn WrappingObjectStore>>,
 
-    storage_options: HashMap<String, String>,
+    pub(crate) storage_options: HashMap<String, String>,
 
     // This comes from the connection options. We store here so we can pass down
     // to the dataset when we recreate it (for example, in checkout_latest).
     read_consistency_interval: Option<std::time::Duration>,
+
+    // If set every write to the table fails, see ConnectBuilder::read_only
+    read_only: bool,
 }
 
 impl std::fmt::Display for NativeTable {
@@ -1003,52 +1785,249 @@ impl NativeTable {
                 source => Error::Lance { source },
             })?;
 
-        let dataset = DatasetConsistencyWrapper::new_latest(dataset, read_consistency_interval);
+        Ok(Self::from_dataset(
+            dataset,
+            uri,
+            name,
+            write_store_wrapper,
+            storage_options,
+            read_consistency_interval,
+        ))
+    }
 
-        Ok(Self {
+    /// Create a handle for a dataset that was already opened
+    pub(crate) fn from_dataset(
+        dataset: Dataset,
+        uri: &str,
+        name: &str,
+        write_store_wrapper: Option<Arc<dyn WrappingObjectStore>>,
+        storage_options: HashMap<String, String>,
+        read_consistency_interval: Option<std::time::Duration>,
+    ) -> Self {
+        Self {
             name: name.to_string(),
             uri: uri.to_string(),
-            dataset,
+            dataset: DatasetConsistencyWrapper::new_latest(dataset, read_consistency_interval),
             store_wrapper: write_store_wrapper,
             storage_options,
             read_consistency_interval,
-        })
+            read_only: false,
+        }
     }
 
-    fn get_table_name(uri: &str) -> Result<String> {
-        let path = Path::new(uri);
-        let name = path
-            .file_stem()
-            .ok_or(Error::TableNotFound {
-                name: uri.to_string(),
-            })?
-            .to_str()
-            .ok_or(Error::InvalidTableName {
-                name: uri.to_string(),
-                reason: "Table name is not valid URL".to_string(),
-            })?;
-        Ok(name.to_string())
+    /// Make every write to the table fail with [`Error::ReadOnly`]
+    pub(crate) fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
     }
 
-    fn supported_btree_data_type(dtype: &DataType) -> bool {
-        dtype.is_integer()
-            || dtype.is_floating()
-            || matches!(
-                dtype,
-                DataType::Boolean
-                    | DataType::Utf8
-                    | DataType::Time32(_)
-                    | DataType::Time64(_)
-                    | DataType::Date32
-                    | DataType::Date64
-                    | DataType::Timestamp(_, _)
-            )
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly {
+                message: format!("table {} cannot be modified", self.name),
+            });
+        }
+        Ok(())
     }
 
-    fn supported_vector_data_type(dtype: &DataType) -> bool {
-        match dtype {
-            DataType::FixedSizeList(inner, _) => DataType::is_floating(inner.data_type()),
-            _ => false,
+    /// Append the rows of `data` whose key (the `on` columns) is not in the table
+    async fn add_deduplicated(
+        &self,
+        data: Box<dyn RecordBatchReader + Send>,
+        on: Vec<String>,
+    ) -> Result<()> {
+        self.dataset.ensure_mutable().await?;
+        let dataset = Arc::new(self.dataset.get().await?.clone());
+        let mut builder = LanceMergeInsertBuilder::try_new(dataset, on)?;
+        builder
+            .when_matched(WhenMatched::DoNothing)
+            .when_not_matched(lance::dataset::WhenNotMatched::InsertAll)
+            .when_not_matched_by_source(WhenNotMatchedBySource::Keep);
+        let (new_dataset, _) = builder.try_build()?.execute_reader(data).await?;
+        self.dataset.set_latest(new_dataset.as_ref().clone()).await;
+        Ok(())
+    }
+
+    /// Add the fields of `schema` that the table does not have
+    ///
+    /// The existing rows are backfilled with the value in `fill_values`, cast to
+    /// the type of the field, or with nulls.  Returns the names of the fields that
+    /// were made nullable to hold the nulls.
+    async fn add_missing_columns(
+        &self,
+        schema: &Schema,
+        fill_values: &HashMap<String, ScalarValue>,
+    ) -> Result<Vec<String>> {
+        let table_schema = self.schema().await?;
+        let missing = schema
+            .fields()
+            .iter()
+            .filter(|field| table_schema.field_with_name(field.name()).is_err())
+            .collect::<Vec<_>>();
+        if let Some(column) = fill_values
+            .keys()
+            .find(|column| !missing.iter().any(|field| field.name() == *column))
+        {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "a fill value was given for {} but it is not a new column",
+                    column
+                ),
+            });
+        }
+        if missing.is_empty() {
+            return Ok(vec![]);
+        }
+        // The new column is written in the format of each fragment, and not every
+        // format can store the nulls of every type
+        let legacy = self
+            .dataset
+            .get()
+            .await?
+            .get_fragments()
+            .iter()
+            .any(|fragment| {
+                fragment
+                    .metadata()
+                    .files
+                    .first()
+                    .map_or(false, |file| file.is_legacy_file())
+            });
+        if let Some(field) = missing.iter().find(|field| {
+            !fill_values.contains_key(field.name()) && !stores_nulls(field.data_type(), legacy)
+        }) {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "column {} of type {} cannot be backfilled with nulls in this table, give it a fill value",
+                    field.name(),
+                    field.data_type()
+                ),
+            });
+        }
+
+        let fields = missing
+            .iter()
+            .map(|field| {
+                let nullable = field.is_nullable() || !fill_values.contains_key(field.name());
+                Field::new(field.name(), field.data_type().clone(), nullable)
+            })
+            .collect::<Vec<_>>();
+        // Check the fill values can be cast before anything is written
+        for field in &fields {
+            if let Some(value) = fill_values.get(field.name()) {
+                arrow_cast::cast(&value.to_array()?, field.data_type()).map_err(|e| {
+                    Error::InvalidInput {
+                        message: format!(
+                            "the fill value {} cannot be cast to {} for column {}: {}",
+                            value,
+                            field.data_type(),
+                            field.name(),
+                            e
+                        ),
+                    }
+                })?;
+            }
+        }
+
+        let nullable = missing
+            .iter()
+            .filter(|field| !field.is_nullable() && !fill_values.contains_key(field.name()))
+            .map(|field| field.name().clone())
+            .collect();
+
+        let output_schema = Arc::new(Schema::new(fields));
+        let fill_values = output_schema
+            .fields()
+            .iter()
+            .map(|field| fill_values.get(field.name()).cloned())
+            .collect::<Vec<_>>();
+        let schema = output_schema.clone();
+        let mapper = move |batch: &RecordBatch| -> lance::Result<RecordBatch> {
+            let num_rows = batch.num_rows();
+            let columns = schema
+                .fields()
+                .iter()
+                .zip(fill_values.iter())
+                .map(|(field, value)| match value {
+                    Some(value) => {
+                        let array = value.to_array_of_size(num_rows)?;
+                        Ok(arrow_cast::cast(&array, field.data_type())?)
+                    }
+                    None => Ok(new_null_array(field.data_type(), num_rows)),
+                })
+                .collect::<lance::Result<Vec<_>>>()?;
+            Ok(RecordBatch::try_new(schema.clone(), columns)?)
+        };
+        let transform = NewColumnTransform::BatchUDF(BatchUDF {
+            mapper: Box::new(mapper),
+            output_schema,
+            result_checkpoint: None,
+        });
+        self.add_columns(transform, Some(vec![])).await?;
+        Ok(nullable)
+    }
+
+    /// Mark the `columns` of `data` as nullable, to match the table schema
+    fn make_nullable(
+        data: Box<dyn RecordBatchReader + Send>,
+        columns: &[String],
+    ) -> Box<dyn RecordBatchReader + Send> {
+        if columns.is_empty() {
+            return data;
+        }
+        let schema = Arc::new(Schema::new(
+            data.schema()
+                .fields()
+                .iter()
+                .map(|field| {
+                    let nullable = field.is_nullable() || columns.contains(field.name());
+                    field.as_ref().clone().with_nullable(nullable)
+                })
+                .collect::<Vec<_>>(),
+        ));
+        let batch_schema = schema.clone();
+        Box::new(RecordBatchIterator::new(
+            data.map(move |batch| {
+                RecordBatch::try_new(batch_schema.clone(), batch?.columns().to_vec())
+            }),
+            schema,
+        ))
+    }
+
+    fn get_table_name(uri: &str) -> Result<String> {
+        let path = Path::new(uri);
+        let name = path
+            .file_stem()
+            .ok_or(Error::TableNotFound {
+                name: uri.to_string(),
+            })?
+            .to_str()
+            .ok_or(Error::InvalidTableName {
+                name: uri.to_string(),
+                reason: "Table name is not valid URL".to_string(),
+            })?;
+        Ok(name.to_string())
+    }
+
+    fn supported_btree_data_type(dtype: &DataType) -> bool {
+        dtype.is_integer()
+            || dtype.is_floating()
+            || matches!(
+                dtype,
+                DataType::Boolean
+                    | DataType::Utf8
+                    | DataType::Time32(_)
+                    | DataType::Time64(_)
+                    | DataType::Date32
+                    | DataType::Date64
+                    | DataType::Timestamp(_, _)
+            )
+    }
+
+    fn supported_vector_data_type(dtype: &DataType) -> bool {
+        match dtype {
+            DataType::FixedSizeList(inner, _) => DataType::is_floating(inner.data_type()),
+            _ => false,
         }
     }
 
@@ -1085,6 +2064,7 @@ impl NativeTable {
             .storage_options
             .unwrap_or_default();
 
+        let batches = coerce_view_types(batches);
         let dataset = Dataset::write(batches, uri, Some(params))
             .await
             .map_err(|e| match e {
@@ -1100,6 +2080,7 @@ impl NativeTable {
             store_wrapper: write_store_wrapper,
             storage_options,
             read_consistency_interval,
+            read_only: false,
         })
     }
 
@@ -1318,22 +2299,20 @@ impl NativeTable {
         } else {
             suggested_num_partitions(self.count_rows(None).await?)
         };
-        let num_sub_vectors: u32 = if let Some(n) = index.num_sub_vectors {
-            n
-        } else {
-            match field.data_type() {
-                arrow_schema::DataType::FixedSizeList(_, n) => {
-                    Ok::<u32, Error>(suggested_num_sub_vectors(*n as u32))
-                }
-                _ => Err(Error::Schema {
-                    message: format!("Column '{}' is not a FixedSizeList", field.name()),
-                }),
-            }?
-        };
+        let dim = match field.data_type() {
+            arrow_schema::DataType::FixedSizeList(_, n) => Ok::<u32, Error>(*n as u32),
+            _ => Err(Error::Schema {
+                message: format!("Column '{}' is not a FixedSizeList", field.name()),
+            }),
+        }?;
+        let num_sub_vectors = index
+            .num_sub_vectors
+            .unwrap_or_else(|| suggested_num_sub_vectors(dim));
+        validate_ivf_pq_params(dim, num_partitions, num_sub_vectors, index.num_bits)?;
         let mut dataset = self.dataset.get_mut().await?;
         let lance_idx_params = lance::index::vector::VectorIndexParams::ivf_pq(
             num_partitions as usize,
-            /*num_bits=*/ 8,
+            index.num_bits as u8,
             num_sub_vectors as usize,
             index.distance_type.into(),
             index.max_iterations as usize,
@@ -1371,18 +2350,16 @@ impl NativeTable {
         } else {
             suggested_num_partitions(self.count_rows(None).await?)
         };
-        let num_sub_vectors: u32 = if let Some(n) = index.num_sub_vectors {
-            n
-        } else {
-            match field.data_type() {
-                arrow_schema::DataType::FixedSizeList(_, n) => {
-                    Ok::<u32, Error>(suggested_num_sub_vectors(*n as u32))
-                }
-                _ => Err(Error::Schema {
-                    message: format!("Column '{}' is not a FixedSizeList", field.name()),
-                }),
-            }?
-        };
+        let dim = match field.data_type() {
+            arrow_schema::DataType::FixedSizeList(_, n) => Ok::<u32, Error>(*n as u32),
+            _ => Err(Error::Schema {
+                message: format!("Column '{}' is not a FixedSizeList", field.name()),
+            }),
+        }?;
+        let num_sub_vectors = index
+            .num_sub_vectors
+            .unwrap_or_else(|| suggested_num_sub_vectors(dim));
+        validate_ivf_pq_params(dim, num_partitions, num_sub_vectors, index.num_bits)?;
 
         let mut dataset = self.dataset.get_mut().await?;
         let mut ivf_params = IvfBuildParams::new(num_partitions as usize);
@@ -1393,6 +2370,7 @@ impl NativeTable {
             .ef_construction(index.ef_construction as usize);
         let pq_params = PQBuildParams {
             num_sub_vectors: num_sub_vectors as usize,
+            num_bits: index.num_bits as usize,
             ..Default::default()
         };
         let lance_idx_params = lance::index::vector::VectorIndexParams::with_ivf_hnsw_pq_params(
@@ -1469,7 +2447,7 @@ impl NativeTable {
             self.create_ivf_pq_index(IvfPqIndexBuilder::default(), field, opts.replace)
                 .await
         } else if Self::supported_btree_data_type(field.data_type()) {
-            self.create_btree_index(field, opts).await
+            self.create_btree_index(field, opts.replace).await
         } else {
             Err(Error::InvalidInput {
                 message: format!(
@@ -1481,7 +2459,7 @@ impl NativeTable {
         }
     }
 
-    async fn create_btree_index(&self, field: &Field, opts: IndexBuilder) -> Result<()> {
+    async fn create_btree_index(&self, field: &Field, replace: bool) -> Result<()> {
         if !Self::supported_btree_data_type(field.data_type()) {
             return Err(Error::Schema {
                 message: format!(
@@ -1500,615 +2478,3807 @@ impl NativeTable {
                 IndexType::Scalar,
                 None,
                 &lance_idx_params,
-                opts.replace,
+                replace,
             )
             .await?;
         Ok(())
     }
 
-    async fn generic_query(
-        &self,
-        query: &VectorQuery,
-        options: QueryExecutionOptions,
-    ) -> Result<DatasetRecordBatchStream> {
-        let plan = self.create_plan(query, options).await?;
-        Ok(DatasetRecordBatchStream::new(execute_plan(
-            plan,
-            Default::default(),
-        )?))
-    }
-}
-
-#[async_trait::async_trait]
-impl TableInternal for NativeTable {
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
-
-    fn as_native(&self) -> Option<&NativeTable> {
-        Some(self)
-    }
-
-    fn name(&self) -> &str {
-        self.name.as_str()
-    }
-
-    async fn version(&self) -> Result<u64> {
-        Ok(self.dataset.get().await?.version().version)
-    }
-
-    async fn checkout(&self, version: u64) -> Result<()> {
-        self.dataset.as_time_travel(version).await
-    }
-
-    async fn checkout_latest(&self) -> Result<()> {
-        self.dataset
-            .as_latest(self.read_consistency_interval)
-            .await?;
-        self.dataset.reload().await
-    }
-
-    async fn restore(&self) -> Result<()> {
-        let version =
-            self.dataset
-                .time_travel_version()
-                .await
-                .ok_or_else(|| Error::InvalidInput {
-                    message: "you must run checkout before running restore".to_string(),
-                })?;
-        {
-            // Use get_mut_unchecked as restore is the only "write" operation that is allowed
-            // when the table is in time travel mode.
-            // Also, drop the guard after .restore because as_latest will need it
-            let mut dataset = self.dataset.get_mut_unchecked().await?;
-            debug_assert_eq!(dataset.version().version, version);
-            dataset.restore().await?;
+    /// Read the definitions of the indices of the table so they can be created again
+    ///
+    /// Returns the name, the column and the index to create for each index.  Only the
+    /// parameters that are recorded with the index are kept (the distance type, the
+    /// number of partitions and the PQ parameters), the others use their defaults.
+    async fn index_definitions(&self) -> Result<Vec<(String, String, Index)>> {
+        let dataset = self.dataset.get().await?.clone();
+        let indices = dataset.load_indices().await?;
+        let mut definitions: Vec<(String, String, Index)> = Vec::with_capacity(indices.len());
+        for idx in indices.iter() {
+            // An index that was updated has one entry for each delta
+            if definitions.iter().any(|(name, _, _)| name == &idx.name) {
+                continue;
+            }
+            let [field_id] = idx.fields[..] else {
+                continue;
+            };
+            let Some(field) = dataset.schema().field_by_id(field_id) else {
+                continue;
+            };
+            if !field.data_type().is_nested() {
+                definitions.push((
+                    idx.name.clone(),
+                    field.name.clone(),
+                    Index::BTree(BTreeIndexBuilder::default()),
+                ));
+                continue;
+            }
+            let Some(stats) = self.index_stats(&idx.name).await? else {
+                continue;
+            };
+            let Some(metadata) = stats.indices.first() else {
+                continue;
+            };
+            let distance_type = match metadata.metric_type.as_deref() {
+                Some(metric_type) => DistanceType::try_from(metric_type)?,
+                None => DistanceType::L2,
+            };
+            let num_partitions = metadata.num_partitions.map(|n| n as u32);
+            let sub_index = metadata.sub_index.as_ref();
+            let index = match sub_index.and_then(|s| s.index_type.as_deref()) {
+                Some("HNSW") if self.is_hnsw_sq(&idx.uuid.to_string()).await? => {
+                    Index::IvfHnswSq(IvfHnswSqIndexBuilder {
+                        distance_type,
+                        num_partitions,
+                        ..Default::default()
+                    })
+                }
+                Some("HNSW") => Index::IvfHnswPq(IvfHnswPqIndexBuilder {
+                    distance_type,
+                    num_partitions,
+                    ..Default::default()
+                }),
+                _ => {
+                    let defaults = IvfPqIndexBuilder::default();
+                    Index::IvfPq(IvfPqIndexBuilder {
+                        distance_type,
+                        num_partitions,
+                        num_sub_vectors: sub_index.and_then(|s| s.num_sub_vectors),
+                        num_bits: sub_index.and_then(|s| s.nbits).unwrap_or(defaults.num_bits),
+                        ..defaults
+                    })
+                }
+            };
+            definitions.push((idx.name.clone(), field.name.clone(), index));
         }
-        self.dataset
-            .as_latest(self.read_consistency_interval)
-            .await?;
-        Ok(())
-    }
-
-    async fn schema(&self) -> Result<SchemaRef> {
-        let lance_schema = self.dataset.get().await?.schema().clone();
-        Ok(Arc::new(Schema::from(&lance_schema)))
+        Ok(definitions)
     }
 
-    async fn table_definition(&self) -> Result<TableDefinition> {
+    /// Create the indices read by [`Self::index_definitions`] again
+    ///
+    /// Indices on columns that no longer exist are not created.
+    async fn rebuild_indices(&self, definitions: Vec<(String, String, Index)>) -> Result<()> {
         let schema = self.schema().await?;
-        TableDefinition::try_from_rich_schema(schema)
+        for (name, column, index) in definitions {
+            let Ok(field) = schema.field_with_name(&column) else {
+                continue;
+            };
+            let result = match index {
+                Index::IvfPq(ivf_pq) => self.create_ivf_pq_index(ivf_pq, field, true).await,
+                Index::IvfHnswPq(ivf_hnsw_pq) => {
+                    self.create_ivf_hnsw_pq_index(ivf_hnsw_pq, field, true)
+                        .await
+                }
+                Index::IvfHnswSq(ivf_hnsw_sq) => {
+                    self.create_ivf_hnsw_sq_index(ivf_hnsw_sq, field, true)
+                        .await
+                }
+                Index::Auto | Index::BTree(_) => self.create_btree_index(field, true).await,
+            };
+            result.map_err(|err| Error::Runtime {
+                message: format!(
+                    "the table was overwritten but the index '{}' on column '{}' could not be created again: {}",
+                    name, column, err
+                ),
+            })?;
+        }
+        Ok(())
     }
 
-    async fn count_rows(&self, filter: Option<String>) -> Result<usize> {
-        Ok(self.dataset.get().await?.count_rows(filter).await?)
+    /// Whether the IVF HNSW index with the given uuid compresses its vectors with SQ
+    ///
+    /// The statistics of an HNSW index do not record how the vectors are compressed,
+    /// this is only found in the metadata of the index file.
+    async fn is_hnsw_sq(&self, uuid: &str) -> Result<bool> {
+        let params = ObjectStoreParams {
+            storage_options: Some(self.storage_options.clone()),
+            object_store_wrapper: self.store_wrapper.clone(),
+            ..Default::default()
+        };
+        let (object_store, base) = ObjectStore::from_uri_and_params(&self.uri, &params).await?;
+        let path = base.child(INDICES_DIR).child(uuid).child(INDEX_FILE_NAME);
+        let reader = FileReader::try_new_self_described(&object_store, &path, None).await?;
+        let Some(metadata) = reader.schema().metadata.get(INDEX_METADATA_SCHEMA_KEY) else {
+            return Ok(false);
+        };
+        let metadata: lance_index::IndexMetadata = whatever!(
+            serde_json::from_str(metadata),
+            "error deserializing index metadata {metadata}",
+        );
+        Ok(metadata.index_type == "IVF_HNSW_SQ")
     }
 
-    async fn add(
+    /// Check the vector search parameters against the index (if any) on `column`
+    ///
+    /// Lance silently clamps values that cannot be satisfied by the index and so we
+    /// check them here to give the user a useful error instead.
+    ///
+    /// Returns whether the vector index should be used.  This is false if the query
+    /// bypasses the index or if the index was trained with a different distance type
+    /// than the one requested by the query, in which case the caller should fall back
+    /// to a flat search.
+    async fn validate_vector_search(
         &self,
-        add: AddDataBuilder<NoData>,
-        data: Box<dyn RecordBatchReader + Send>,
-    ) -> Result<()> {
-        let data =
-            MaybeEmbedded::try_new(data, self.table_definition().await?, add.embedding_registry)?;
-
-        let mut lance_params = add.write_options.lance_write_params.unwrap_or(WriteParams {
-            mode: match add.mode {
-                AddDataMode::Append => WriteMode::Append,
-                AddDataMode::Overwrite => WriteMode::Overwrite,
-            },
-            ..Default::default()
+        dataset: &Dataset,
+        column: &str,
+        query: &VectorQuery,
+    ) -> Result<bool> {
+        if query.refine_factor == Some(0) {
+            return Err(Error::InvalidInput {
+                message: "refine_factor must be greater than 0".to_string(),
+            });
+        }
+        if !query.use_index {
+            return Ok(false);
+        }
+        // Only the parameters set explicitly are checked, so the index does not need
+        // to be loaded when none are
+        if query.nprobes.is_none() && query.ef.is_none() && query.distance_type.is_none() {
+            return Ok(true);
+        }
+        let Some(field_id) = dataset.schema().field(column).map(|f| f.id) else {
+            return Ok(true);
+        };
+        let indices = dataset.load_indices().await?;
+        let Some(index) = indices.iter().find(|idx| idx.fields == [field_id]) else {
+            return Ok(true);
+        };
+        let Some(stats) = self.index_stats(&index.name).await? else {
+            return Ok(true);
+        };
+        if let Some(distance_type) = query.distance_type {
+            let trained_with_other_metric = stats
+                .indices
+                .iter()
+                .filter_map(|m| m.metric_type.as_deref())
+                .filter_map(|metric_type| DistanceType::try_from(metric_type).ok())
+                .any(|index_distance_type| index_distance_type != distance_type);
+            if trained_with_other_metric {
+                return Ok(false);
+            }
+        }
+        // An index may consist of several deltas, they all share the same partitions
+        let num_partitions = stats.indices.iter().filter_map(|m| m.num_partitions).max();
+        if let (Some(nprobes), Some(num_partitions)) = (query.nprobes, num_partitions) {
+            if nprobes == 0 || nprobes > num_partitions {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "nprobes must be between 1 and {} (the number of partitions in the index '{}' on column '{}') but was {}",
+                        num_partitions, index.name, column, nprobes
+                    ),
+                });
+            }
+        }
+        let is_hnsw = stats.indices.iter().any(|m| {
+            m.sub_index
+                .as_ref()
+                .and_then(|sub_index| sub_index.index_type.as_deref())
+                == Some("HNSW")
         });
-
-        // Bring storage options from table
-        let storage_options = lance_params
-            .store_params
-            .get_or_insert(Default::default())
-            .storage_options
-            .get_or_insert(Default::default());
-        for (key, value) in self.storage_options.iter() {
-            if !storage_options.contains_key(key) {
-                storage_options.insert(key.clone(), value.clone());
+        if let (Some(ef), true) = (query.ef, is_hnsw) {
+            let num_results =
+                query.base.limit.unwrap_or(DEFAULT_TOP_K) + query.base.offset.unwrap_or(0);
+            if (ef as usize) < num_results {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "ef must be at least the number of results (limit + offset) of {} but was {}",
+                        num_results, ef
+                    ),
+                });
             }
         }
+        Ok(true)
+    }
 
-        // patch the params if we have a write store wrapper
-        let lance_params = match self.store_wrapper.clone() {
-            Some(wrapper) => lance_params.patch_with_store_wrapper(wrapper)?,
-            None => lance_params,
-        };
-
-        self.dataset.ensure_mutable().await?;
-        let dataset = Dataset::write(data, &self.uri, Some(lance_params)).await?;
-
-        self.dataset.set_latest(dataset).await;
-        Ok(())
-    }
-
-    async fn create_index(&self, opts: IndexBuilder) -> Result<()> {
-        if opts.columns.len() != 1 {
-            return Err(Error::Schema {
-                message: "Multi-column (composite) indices are not yet supported".to_string(),
-            });
-        }
-        let schema = self.schema().await?;
-
-        let field = schema.field_with_name(&opts.columns[0])?;
-
-        match opts.index {
-            Index::Auto => self.create_auto_index(field, opts).await,
-            Index::BTree(_) => self.create_btree_index(field, opts).await,
-            Index::IvfPq(ivf_pq) => self.create_ivf_pq_index(ivf_pq, field, opts.replace).await,
-            Index::IvfHnswPq(ivf_hnsw_pq) => {
-                self.create_ivf_hnsw_pq_index(ivf_hnsw_pq, field, opts.replace)
-                    .await
-            }
-            Index::IvfHnswSq(ivf_hnsw_sq) => {
-                self.create_ivf_hnsw_sq_index(ivf_hnsw_sq, field, opts.replace)
-                    .await
-            }
-        }
-    }
-
-    async fn update(&self, update: UpdateBuilder) -> Result<()> {
-        let dataset = self.dataset.get().await?.clone();
-        let mut builder = LanceUpdateBuilder::new(Arc::new(dataset));
-        if let Some(predicate) = update.filter {
-            builder = builder.update_where(&predicate)?;
-        }
-
-        for (column, value) in update.columns {
-            builder = builder.set(column, &value)?;
-        }
-
-        let operation = builder.build()?;
-        let ds = operation.execute().await?;
-        self.dataset.set_latest(ds.as_ref().clone()).await;
-        Ok(())
-    }
-
-    async fn create_plan(
+    /// Create the plan for a search with a binary (u8) query vector
+    ///
+    /// Binary vectors cannot be indexed and are always searched with a flat search
+    /// using the hamming distance.  The filter is always applied before the search.
+    async fn binary_vector_plan(
         &self,
+        dataset: &Dataset,
         query: &VectorQuery,
+        query_vector: &[u8],
         options: QueryExecutionOptions,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        let ds_ref = self.dataset.get().await?;
-        let mut scanner: Scanner = ds_ref.scan();
-
-        if let Some(query_vector) = query.query_vector.as_ref() {
-            // If there is a vector query, default to limit=10 if unspecified
-            let column = if let Some(col) = query.column.as_ref() {
-                col.clone()
-            } else {
-                // Infer a vector column with the same dimension of the query vector.
-                let arrow_schema = Schema::from(ds_ref.schema());
-                default_vector_column(&arrow_schema, Some(query_vector.len() as i32))?
-            };
-            let field = ds_ref.schema().field(&column).ok_or(Error::Schema {
-                message: format!("Column {} not found in dataset schema", column),
-            })?;
-            if let arrow_schema::DataType::FixedSizeList(f, dim) = field.data_type() {
-                if !f.data_type().is_floating() {
+        let dim = query_vector.len() as i32;
+        let column = if let Some(col) = query.column.as_ref() {
+            col.clone()
+        } else {
+            let arrow_schema = Schema::from(dataset.schema());
+            let candidates = arrow_schema
+                .fields()
+                .iter()
+                .filter(|field| {
+                    matches!(field.data_type(), DataType::FixedSizeList(f, d)
+                        if f.data_type() == &DataType::UInt8 && *d == dim)
+                })
+                .map(|field| field.name().clone())
+                .collect::<Vec<_>>();
+            match candidates.len() {
+                0 => {
                     return Err(Error::InvalidInput {
                         message: format!(
-                            "The data type of the vector column '{}' is not a floating point type",
-                            column
+                            "No binary vector column found to match with the query vector dimension: {}",
+                            dim
                         ),
-                    });
+                    })
                 }
-                if dim != query_vector.len() as i32 {
+                1 => candidates[0].clone(),
+                _ => return Err(Error::AmbiguousVectorColumn { candidates }),
+            }
+        };
+        let field = dataset.schema().field(&column).ok_or(Error::Schema {
+            message: format!("Column {} not found in dataset schema", column),
+        })?;
+        match field.data_type() {
+            DataType::FixedSizeList(f, d) if f.data_type() == &DataType::UInt8 => {
+                if d != dim {
                     return Err(Error::InvalidInput {
                         message: format!(
                             "The dimension of the query vector does not match with the dimension of the vector column '{}': \
                                 query dim={}, expected vector dim={}",
-                            column,
-                            query_vector.len(),
-                            dim,
+                            column, dim, d,
                         ),
                     });
                 }
             }
-            let query_vector = query_vector.as_primitive::<Float32Type>();
-            scanner.nearest(
-                &column,
-                query_vector,
-                query.base.limit.unwrap_or(DEFAULT_TOP_K),
-            )?;
-        } else {
-            // If there is no vector query, it's ok to not have a limit
-            scanner.limit(query.base.limit.map(|limit| limit as i64), None)?;
+            DataType::FixedSizeList(f, _) if f.data_type().is_floating() => {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "A binary query vector was provided but the vector column '{}' is a float vector column (type: {}). \
+                            Product quantization codes cannot be used as a query vector, search with the original vector instead",
+                        column,
+                        field.data_type()
+                    ),
+                })
+            }
+            _ => {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "A binary query vector was provided but the vector column '{}' is not a binary vector column (type: {})",
+                        column,
+                        field.data_type()
+                    ),
+                })
+            }
+        }
+        if !matches!(query.distance_type, None | Some(DistanceType::Hamming)) {
+            return Err(Error::InvalidInput {
+                message: "Binary vectors can only be searched with the hamming distance"
+                    .to_string(),
+            });
         }
-        scanner.nprobs(query.nprobes);
-        scanner.use_index(query.use_index);
-        scanner.prefilter(query.prefilter);
-        scanner.batch_size(options.max_batch_length as usize);
 
-        match &query.base.select {
+        let mut scanner = dataset.scan();
+        scanner.batch_size(
+            query
+                .base
+                .batch_size
+                .unwrap_or(options.max_batch_length as usize),
+        );
+        // The vector column is needed to calculate the distance even if it is
+        // not part of the output
+        let keep_column = match &query.base.select {
             Select::Columns(select) => {
+                let keep_column = select.contains(&column);
+                let mut select = select.clone();
+                if !keep_column {
+                    select.push(column.clone());
+                }
                 scanner.project(select.as_slice())?;
+                keep_column
             }
             Select::Dynamic(select_with_transform) => {
-                scanner.project_with_transform(select_with_transform.as_slice())?;
+                let keep_column = select_with_transform
+                    .iter()
+                    .any(|(name, _)| name == &column);
+                let mut select = select_with_transform.clone();
+                if !keep_column {
+                    select.push((column.clone(), column.clone()));
+                }
+                scanner.project_with_transform(select.as_slice())?;
+                keep_column
             }
-            Select::All => { /* Do nothing */ }
-        }
-
+            Select::Except(_) => unreachable!("resolved to Select::Columns in create_plan"),
+            Select::All => true,
+        };
         if let Some(filter) = &query.base.filter {
-            scanner.filter(filter)?;
+            scanner.filter(&rewrite_filter(filter, &Schema::from(dataset.schema())))?;
         }
-
-        if let Some(refine_factor) = query.refine_factor {
-            scanner.refine(refine_factor);
+        if query.base.with_row_id {
+            scanner.with_row_id();
         }
-
-        if let Some(distance_type) = query.distance_type {
-            scanner.distance_metric(distance_type.into());
+        if query.base.with_row_address {
+            scanner.with_row_address();
         }
-        Ok(scanner.create_plan().await?)
+        let plan = scanner.create_plan().await?;
+        hamming::hamming_search_plan(
+            plan,
+            &column,
+            query_vector,
+            query.base.limit.unwrap_or(DEFAULT_TOP_K),
+            query.base.offset.unwrap_or(0),
+            keep_column,
+        )
     }
 
-    async fn plain_query(
-        &self,
-        query: &Query,
-        options: QueryExecutionOptions,
-    ) -> Result<DatasetRecordBatchStream> {
-        self.generic_query(&query.clone().into_vector(), options)
-            .await
+    /// Check that an update expression can be stored in the column without losing data
+    ///
+    /// Lance casts the result of an update expression to the type of the column, and
+    /// the cast silently truncates or wraps values that do not fit.  A literal is
+    /// allowed if its value fits the column, e.g. `1.0` but not `1.5` for an integer
+    /// column.  Any other expression must have the type of the column or one that
+    /// widens to it.
+    fn validate_update_expr(schema: &SchemaRef, column: &str, expr: &str) -> Result<()> {
+        let Ok(field) = schema.field_with_name(column) else {
+            // Lance will report the missing column
+            return Ok(());
+        };
+        let planner = Planner::new(schema.clone());
+        let expr = planner.optimize_expr(planner.parse_expr(expr)?)?;
+        let expr = planner.create_physical_expr(&expr)?;
+        if let Some(literal) = expr.as_any().downcast_ref::<Literal>() {
+            let value = literal.value();
+            if !fits_losslessly(&value.to_array()?, field.data_type()) {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "the value {} does not fit the column '{}' ({})",
+                        value,
+                        column,
+                        field.data_type()
+                    ),
+                });
+            }
+            return Ok(());
+        }
+        let expr_type = expr.data_type(schema)?;
+        if !is_widening(&expr_type, field.data_type()) {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "cannot update the column '{}' ({}) with an expression of type {}, \
+                        use an explicit CAST to convert the expression",
+                    column,
+                    field.data_type(),
+                    expr_type
+                ),
+            });
+        }
+        Ok(())
     }
 
-    async fn merge_insert(
-        &self,
-        params: MergeInsertBuilder,
-        new_data: Box<dyn RecordBatchReader + Send>,
-    ) -> Result<()> {
-        let dataset = Arc::new(self.dataset.get().await?.clone());
-        let mut builder = LanceMergeInsertBuilder::try_new(dataset.clone(), params.on)?;
-        match (
-            params.when_matched_update_all,
-            params.when_matched_update_all_filt,
-        ) {
-            (false, _) => builder.when_matched(WhenMatched::DoNothing),
-            (true, None) => builder.when_matched(WhenMatched::UpdateAll),
-            (true, Some(filt)) => builder.when_matched(WhenMatched::update_if(&dataset, &filt)?),
-        };
-        if params.when_not_matched_insert_all {
-            builder.when_not_matched(lance::dataset::WhenNotMatched::InsertAll);
-        } else {
-            builder.when_not_matched(lance::dataset::WhenNotMatched::DoNothing);
+    /// Remove the flat search over unindexed rows from a vector search plan
+    ///
+    /// When a table has rows that are not covered by the vector index, Lance searches
+    /// the index and also runs a flat search over the unindexed fragments.  The two
+    /// results are combined with a union (repartitioned to a single partition) whose
+    /// last input is the index search.  This replaces that combination with just the
+    /// index search.  Only the nodes above the union are rebuilt.
+    ///
+    /// Other unions, such as the one combining a scalar index lookup with a scan of
+    /// the rows the scalar index does not cover, are left alone.
+    fn skip_unindexed_search(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+        if plan.as_any().is::<RepartitionExec>() {
+            if let Some(union) = plan.children()[0].as_any().downcast_ref::<UnionExec>() {
+                if let [flat, indexed] = union.children().as_slice() {
+                    if Self::contains_node::<KNNFlatExec>(flat)
+                        && !Self::contains_node::<ANNIvfSubIndexExec>(flat)
+                        && Self::contains_node::<ANNIvfSubIndexExec>(indexed)
+                    {
+                        return Ok(indexed.clone());
+                    }
+                }
+            }
         }
-        if params.when_not_matched_by_source_delete {
-            let behavior = if let Some(filter) = params.when_not_matched_by_source_delete_filt {
-                WhenNotMatchedBySource::delete_if(dataset.as_ref(), &filter)?
-            } else {
-                WhenNotMatchedBySource::Delete
-            };
-            builder.when_not_matched_by_source(behavior);
+        let children = plan.children();
+        let new_children = children
+            .iter()
+            .map(|child| Self::skip_unindexed_search(child.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        let changed = children
+            .iter()
+            .zip(new_children.iter())
+            .any(|(old, new)| !Arc::ptr_eq(old, new));
+        if changed {
+            Ok(plan.with_new_children(new_children)?)
         } else {
-            builder.when_not_matched_by_source(WhenNotMatchedBySource::Keep);
+            Ok(plan)
         }
-        let job = builder.try_build()?;
-        let (new_dataset, _stats) = job.execute_reader(new_data).await?;
-        self.dataset.set_latest(new_dataset.as_ref().clone()).await;
-        Ok(())
     }
 
-    /// Delete rows from the table
-    async fn delete(&self, predicate: &str) -> Result<()> {
-        self.dataset.get_mut().await?.delete(predicate).await?;
-        Ok(())
+    /// Whether `plan` or any of its descendants is a `T`
+    fn contains_node<T: 'static>(plan: &Arc<dyn ExecutionPlan>) -> bool {
+        plan.as_any().is::<T>()
+            || plan
+                .children()
+                .iter()
+                .any(|child| Self::contains_node::<T>(child))
     }
 
-    async fn optimize(&self, action: OptimizeAction) -> Result<OptimizeStats> {
-        let mut stats = OptimizeStats {
-            compaction: None,
-            prune: None,
-        };
-        match action {
-            OptimizeAction::All => {
-                stats.compaction = self
-                    .optimize(OptimizeAction::Compact {
-                        options: CompactionOptions::default(),
-                        remap_options: None,
-                    })
-                    .await?
-                    .compaction;
-                stats.prune = self
-                    .optimize(OptimizeAction::Prune {
-                        older_than: None,
-                        delete_unverified: None,
-                    })
-                    .await?
-                    .prune;
-                self.optimize(OptimizeAction::Index(OptimizeOptions::default()))
-                    .await?;
-            }
-            OptimizeAction::Compact {
-                options,
-                remap_options,
-            } => {
-                stats.compaction = Some(self.compact_files(options, remap_options).await?);
-            }
-            OptimizeAction::Prune {
-                older_than,
-                delete_unverified,
-            } => {
-                stats.prune = Some(
-                    self.cleanup_old_versions(
-                        older_than.unwrap_or(Duration::try_days(7).expect("valid delta")),
-                        delete_unverified,
-                    )
-                    .await?,
-                );
+    /// Parse the dynamic projection of a vector search if it references `_distance`
+    ///
+    /// Lance evaluates a dynamic projection on the scanned columns, before the
+    /// distance has been calculated, and so cannot resolve `_distance`.  Returns
+    /// the parsed expressions and the table columns they read, or None if the
+    /// projection can be left to Lance.
+    fn parse_distance_projection(
+        dataset: &Dataset,
+        select: &[(String, String)],
+    ) -> Result<Option<(Projection, Vec<String>)>> {
+        let mut schema = Schema::from(dataset.schema());
+        let mut fields = schema.fields().to_vec();
+        fields.push(Arc::new(Field::new(DIST_COL, DataType::Float32, true)));
+        schema = Schema::new(fields);
+        let planner = Planner::new(Arc::new(schema));
+
+        let mut references_distance = false;
+        let mut columns = Vec::new();
+        let exprs = select
+            .iter()
+            .map(|(name, raw_expr)| {
+                let expr = planner.parse_expr(raw_expr)?;
+                for column in Planner::column_names_in_expr(&expr) {
+                    if column == DIST_COL {
+                        references_distance = true;
+                    } else if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+                Ok((name.clone(), expr))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(references_distance.then_some((exprs, columns)))
+    }
+
+    /// Evaluate a dynamic projection on the output of a vector search
+    ///
+    /// The `_distance` column, and `_rowid` and `_rowaddr` if they were requested, are kept after
+    /// the projected columns, as they are for projections evaluated by Lance.
+    fn project_search_results(
+        plan: Arc<dyn ExecutionPlan>,
+        exprs: Projection,
+        base: &Query,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = plan.schema();
+        let planner = Planner::new(schema.clone());
+        let mut projection = exprs
+            .into_iter()
+            .map(|(name, expr)| {
+                let expr = planner.create_physical_expr(&planner.optimize_expr(expr)?)?;
+                Ok((expr, name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // The query index matches the results of a multi-vector search to their vector
+        let mut extras = vec![DIST_COL, QUERY_INDEX_COL];
+        if base.with_row_id {
+            extras.push(ROW_ID);
+        }
+        if base.with_row_address {
+            extras.push(ROW_ADDR);
+        }
+        for extra in extras {
+            if projection.iter().any(|(_, name)| name == extra) {
+                continue;
             }
-            OptimizeAction::Index(options) => {
-                self.optimize_indices(&options).await?;
+            if let Ok(idx) = schema.index_of(extra) {
+                let expr: Arc<dyn PhysicalExpr> = Arc::new(Column::new(extra, idx));
+                projection.push((expr, extra.to_string()));
             }
         }
-        Ok(stats)
+        Ok(Arc::new(ProjectionExec::try_new(projection, plan)?))
     }
 
-    async fn add_columns(
-        &self,
-        transforms: NewColumnTransform,
-        read_columns: Option<Vec<String>>,
-    ) -> Result<()> {
-        self.dataset
-            .get_mut()
-            .await?
-            .add_columns(transforms, read_columns)
-            .await?;
-        Ok(())
+    /// The top level columns of the dataset without the `excluded` columns
+    fn columns_except(
+        dataset: &Dataset,
+        excluded: &[String],
+        is_vector_search: bool,
+    ) -> Result<Vec<String>> {
+        let schema = dataset.schema();
+        for column in excluded {
+            let is_distance = is_vector_search && column == DIST_COL;
+            if !is_distance && schema.field(column).is_none() {
+                return Err(Error::InvalidInput {
+                    message: format!("cannot exclude column {}, it does not exist", column),
+                });
+            }
+        }
+        let columns = schema
+            .fields
+            .iter()
+            .map(|field| field.name.clone())
+            .filter(|name| !excluded.contains(name))
+            .collect::<Vec<_>>();
+        if columns.is_empty() {
+            return Err(Error::InvalidInput {
+                message: "cannot exclude every column of the table".to_string(),
+            });
+        }
+        Ok(columns)
     }
 
-    async fn alter_columns(&self, alterations: &[ColumnAlteration]) -> Result<()> {
-        self.dataset
-            .get_mut()
-            .await?
-            .alter_columns(alterations)
-            .await?;
-        Ok(())
+    /// Remove the column `name` from the output of `plan`
+    fn drop_column(plan: Arc<dyn ExecutionPlan>, name: &str) -> Result<Arc<dyn ExecutionPlan>> {
+        let projection = plan
+            .schema()
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| field.name() != name)
+            .map(|(idx, field)| {
+                let expr: Arc<dyn PhysicalExpr> = Arc::new(Column::new(field.name(), idx));
+                (expr, field.name().clone())
+            })
+            .collect();
+        Ok(Arc::new(ProjectionExec::try_new(projection, plan)?))
     }
 
-    async fn drop_columns(&self, columns: &[&str]) -> Result<()> {
-        self.dataset.get_mut().await?.drop_columns(columns).await?;
-        Ok(())
+    /// Keep the results of a vector search whose distance is in `lower..upper`
+    ///
+    /// The `offset` and `limit` are applied to the results in the range.
+    fn filter_distance(
+        plan: Arc<dyn ExecutionPlan>,
+        lower: Option<f32>,
+        upper: Option<f32>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let distance = datafusion_expr::col(DIST_COL);
+        let bounds = [
+            lower.map(|lower| distance.clone().gt_eq(datafusion_expr::lit(lower))),
+            upper.map(|upper| distance.lt(datafusion_expr::lit(upper))),
+        ];
+        let expr = bounds
+            .into_iter()
+            .flatten()
+            .reduce(Expr::and)
+            .expect("the range has at least one bound");
+        let planner = Planner::new(plan.schema());
+        let expr = planner.create_physical_expr(&expr)?;
+        let plan = Arc::new(FilterExec::try_new(expr, plan)?);
+        Ok(Arc::new(GlobalLimitExec::new(plan, offset, Some(limit))))
     }
 
-    async fn list_indices(&self) -> Result<Vec<IndexConfig>> {
-        let dataset = self.dataset.get().await?;
-        let indices = dataset.load_indices().await?;
-        indices.iter().map(|idx| {
-            let mut is_vector = false;
-            let mut columns = Vec::with_capacity(idx.fields.len());
-            for field_id in &idx.fields {
-                let field = dataset.schema().field_by_id(*field_id).ok_or_else(|| Error::Runtime { message: format!("The index with name {} and uuid {} referenced a field with id {} which does not exist in the schema", idx.name, idx.uuid, field_id) })?;
-                if field.data_type().is_nested() {
-                    // Temporary hack to determine if an index is scalar or vector
-                    // Should be removed in https://github.com/lancedb/lance/issues/2039
-                    is_vector = true;
+    /// Append a `_rowaddr` column, with the values of the `_rowid` column, to a plan
+    ///
+    /// The `_rowid` column is dropped unless `keep_row_id` is set.
+    fn row_id_as_address(
+        plan: Arc<dyn ExecutionPlan>,
+        keep_row_id: bool,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = plan.schema();
+        let row_id_idx = schema.index_of(ROW_ID)?;
+        let mut projection = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| keep_row_id || *idx != row_id_idx)
+            .map(|(idx, field)| {
+                let expr: Arc<dyn PhysicalExpr> = Arc::new(Column::new(field.name(), idx));
+                (expr, field.name().clone())
+            })
+            .collect::<Vec<_>>();
+        projection.push((
+            Arc::new(Column::new(ROW_ID, row_id_idx)),
+            ROW_ADDR.to_string(),
+        ));
+        Ok(Arc::new(ProjectionExec::try_new(projection, plan)?))
+    }
+
+    /// Combine the plans for several single-vector searches into one plan
+    ///
+    /// Each plan is tagged with a `query_index` column containing the index of
+    /// the plan in `plans`.
+    fn multi_vector_plan(plans: Vec<Arc<dyn ExecutionPlan>>) -> Result<Arc<dyn ExecutionPlan>> {
+        let tagged = plans
+            .into_iter()
+            .enumerate()
+            .map(|(query_index, plan)| {
+                let schema = plan.schema();
+                let mut exprs = schema
+                    .fields()
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, field)| {
+                        let expr: Arc<dyn PhysicalExpr> = Arc::new(Column::new(field.name(), idx));
+                        (expr, field.name().clone())
+                    })
+                    .collect::<Vec<_>>();
+                let index_expr: Arc<dyn PhysicalExpr> =
+                    Arc::new(Literal::new(ScalarValue::UInt32(Some(query_index as u32))));
+                exprs.push((index_expr, QUERY_INDEX_COL.to_string()));
+                Ok(Arc::new(ProjectionExec::try_new(exprs, plan)?) as Arc<dyn ExecutionPlan>)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(CoalescePartitionsExec::new(Arc::new(
+            UnionExec::new(tagged),
+        ))))
+    }
+
+    async fn generic_query(
+        &self,
+        query: &VectorQuery,
+        options: QueryExecutionOptions,
+    ) -> Result<DatasetRecordBatchStream> {
+        let plan = self.create_plan(query, options).await?;
+        Ok(DatasetRecordBatchStream::new(execute_plan(
+            plan,
+            Default::default(),
+        )?))
+    }
+}
+
+#[async_trait::async_trait]
+impl TableInternal for NativeTable {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_native(&self) -> Option<&NativeTable> {
+        Some(self)
+    }
+
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    async fn version(&self) -> Result<u64> {
+        Ok(self.dataset.get().await?.version().version)
+    }
+
+    async fn checkout(&self, version: u64) -> Result<()> {
+        self.dataset.as_time_travel(version).await
+    }
+
+    async fn checkout_latest(&self) -> Result<()> {
+        self.dataset
+            .as_latest(self.read_consistency_interval)
+            .await?;
+        self.dataset.reload().await
+    }
+
+    async fn checkout_version(&self, version: u64) -> Result<Arc<dyn TableInternal>> {
+        let dataset = self.dataset.get().await?.checkout_version(version).await?;
+        Ok(Arc::new(Self {
+            dataset: DatasetConsistencyWrapper::new_time_travel(dataset, version),
+            ..self.clone()
+        }))
+    }
+
+    async fn list_versions(&self) -> Result<Vec<Version>> {
+        Ok(self
+            .dataset
+            .get()
+            .await?
+            .versions()
+            .await?
+            .into_iter()
+            .map(|v| Version {
+                version: v.version,
+                timestamp: v.timestamp,
+                metadata: v.metadata.into_iter().collect(),
+            })
+            .collect())
+    }
+
+    async fn restore_version(&self, version: u64) -> Result<()> {
+        // Checked before the checkout so a read-only table is not left checked out
+        self.ensure_writable()?;
+        self.checkout(version).await?;
+        self.restore().await
+    }
+
+    async fn restore(&self) -> Result<()> {
+        self.ensure_writable()?;
+        let version =
+            self.dataset
+                .time_travel_version()
+                .await
+                .ok_or_else(|| Error::InvalidInput {
+                    message: "you must run checkout before running restore".to_string(),
+                })?;
+        {
+            // Use get_mut_unchecked as restore is the only "write" operation that is allowed
+            // when the table is in time travel mode.
+            // Also, drop the guard after .restore because as_latest will need it
+            let mut dataset = self.dataset.get_mut_unchecked().await?;
+            debug_assert_eq!(dataset.version().version, version);
+            dataset.restore().await?;
+        }
+        self.dataset
+            .as_latest(self.read_consistency_interval)
+            .await?;
+        Ok(())
+    }
+
+    async fn schema(&self) -> Result<SchemaRef> {
+        let lance_schema = self.dataset.get().await?.schema().clone();
+        Ok(Arc::new(Schema::from(&lance_schema)))
+    }
+
+    async fn schema_at_version(&self, version: u64) -> Result<SchemaRef> {
+        let dataset = self.dataset.get().await?.checkout_version(version).await?;
+        Ok(Arc::new(Schema::from(dataset.schema())))
+    }
+
+    async fn table_definition(&self) -> Result<TableDefinition> {
+        let schema = self.schema().await?;
+        TableDefinition::try_from_rich_schema(schema)
+    }
+
+    async fn count_rows(&self, filter: Option<String>) -> Result<usize> {
+        let dataset = self.dataset.get().await?;
+        let schema = Schema::from(dataset.schema());
+        let filter = filter.map(|filter| rewrite_filter(&filter, &schema).into_owned());
+        Ok(dataset.count_rows(filter).await?)
+    }
+
+    async fn stats(&self) -> Result<TableStats> {
+        let dataset = self.dataset.get().await?.clone();
+        let params = ObjectStoreParams {
+            storage_options: Some(self.storage_options.clone()),
+            object_store_wrapper: self.store_wrapper.clone(),
+            ..Default::default()
+        };
+        let (store, base_path) = ObjectStore::from_uri_and_params(&self.uri, &params).await?;
+
+        let fragments = dataset.get_fragments();
+        let data_files = fragments
+            .iter()
+            .flat_map(|fragment| fragment.metadata().files.iter())
+            .map(|file| base_path.child(DATA_DIR).child(file.path.as_str()))
+            .collect::<Vec<_>>();
+        let data_bytes = futures::stream::iter(data_files)
+            .map(|path| {
+                let store = &store;
+                async move { store.size(&path).await.map(|size| size as u64) }
+            })
+            .buffer_unordered(STATS_CONCURRENCY)
+            .try_fold(0, |total, size| async move { Ok(total + size) })
+            .await?;
+
+        let mut index_bytes = 0;
+        for index in dataset.load_indices().await?.iter() {
+            let index_dir = base_path.child(INDICES_DIR).child(index.uuid.to_string());
+            index_bytes += store
+                .inner
+                .list(Some(&index_dir))
+                .try_fold(0, |total, meta| async move { Ok(total + meta.size as u64) })
+                .await?;
+        }
+
+        Ok(TableStats {
+            num_rows: dataset.count_rows(None).await?,
+            num_fragments: fragments.len(),
+            fragment_ids: fragments
+                .iter()
+                .map(|fragment| fragment.id() as u64)
+                .collect(),
+            num_deletion_files: fragments
+                .iter()
+                .filter(|fragment| fragment.metadata().deletion_file.is_some())
+                .count(),
+            data_bytes,
+            index_bytes,
+        })
+    }
+
+    async fn aggregate(&self, params: AggregateBuilder) -> Result<RecordBatch> {
+        let dataset = self.dataset.get().await?;
+        execute_aggregate(&dataset, &params).await
+    }
+
+    async fn take(&self, row_ids: &[u64], columns: Option<Vec<String>>) -> Result<RecordBatch> {
+        let dataset = self.dataset.get().await?;
+        let projection = match columns {
+            Some(columns) => dataset.schema().project(&columns)?,
+            None => dataset.schema().clone(),
+        };
+
+        let missing = |row_id: u64| {
+            Error::InvalidInput {
+            message: format!(
+                "row id {} does not exist in the current version of the table, it may have been deleted",
+                row_id
+            ),
+        }
+        };
+        if dataset.manifest().uses_move_stable_row_ids() {
+            // Lance maps stable row ids to the addresses of the live rows and skips
+            // the ids it cannot find
+            let rows = dataset.take_rows(row_ids, &projection).await?;
+            if rows.num_rows() != row_ids.len() {
+                return Err(Error::InvalidInput {
+                    message: "some of the row ids do not exist in the current version of the table, they may have been deleted".to_string(),
+                });
+            }
+            return Ok(rows);
+        }
+
+        // Otherwise the row ids are the addresses of the rows (the fragment id in
+        // the upper 32 bits and the offset in the lower 32 bits).  Lance does not
+        // apply deletions when taking rows by address, so check that every address
+        // refers to a live row first
+        let mut by_fragment = HashMap::<u64, Vec<u64>>::new();
+        for row_id in row_ids {
+            by_fragment.entry(row_id >> 32).or_default().push(*row_id);
+        }
+        let mut store = None;
+        for (fragment_id, ids) in by_fragment {
+            let fragment = dataset
+                .get_fragment(fragment_id as usize)
+                .ok_or_else(|| missing(ids[0]))?;
+            let physical_rows = fragment.physical_rows().await? as u64;
+            if let Some(id) = ids.iter().find(|id| (*id & 0xFFFF_FFFF) >= physical_rows) {
+                return Err(missing(*id));
+            }
+            if fragment.metadata().deletion_file.is_none() {
+                continue;
+            }
+            if store.is_none() {
+                let params = ObjectStoreParams {
+                    storage_options: Some(self.storage_options.clone()),
+                    object_store_wrapper: self.store_wrapper.clone(),
+                    ..Default::default()
+                };
+                store = Some(ObjectStore::from_uri_and_params(&self.uri, &params).await?);
+            }
+            let (object_store, base) = store.as_ref().unwrap();
+            let deletion_vector =
+                read_deletion_file(base, fragment.metadata(), object_store).await?;
+            if let Some(deletion_vector) = deletion_vector {
+                if let Some(id) = ids.iter().find(|id| deletion_vector.contains(**id as u32)) {
+                    return Err(missing(*id));
                 }
-                columns.push(field.name.clone());
             }
+        }
 
-            let index_type = if is_vector {
-                crate::index::IndexType::IvfPq
+        Ok(dataset.take_rows(row_ids, &projection).await?)
+    }
+
+    async fn add(
+        &self,
+        add: AddDataBuilder<NoData>,
+        data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        if add.deduplicate_on.is_some() {
+            if matches!(add.mode, AddDataMode::Overwrite) {
+                return Err(Error::InvalidInput {
+                    message: "deduplicate_on cannot be used when overwriting the table".to_string(),
+                });
+            }
+            // The merge insert that skips the duplicates cannot be given write parameters
+            if add.write_options.lance_write_params.is_some()
+                || add.max_rows_per_file.is_some()
+                || add.max_bytes_per_file.is_some()
+            {
+                return Err(Error::InvalidInput {
+                    message: "deduplicate_on cannot be used with write_options, max_rows_per_file or max_bytes_per_file".to_string(),
+                });
+            }
+        }
+        let mut data = coerce_view_types(data);
+        if add.add_missing_columns && matches!(add.mode, AddDataMode::Append) {
+            let nullable = self
+                .add_missing_columns(data.schema().as_ref(), &add.fill_values)
+                .await?;
+            data = Self::make_nullable(data, &nullable);
+        }
+        let data =
+            MaybeEmbedded::try_new(data, self.table_definition().await?, add.embedding_registry)?;
+        // The vectors computed by embedding functions are checked too
+        let data: Box<dyn RecordBatchReader + Send> = match add.on_bad_vector {
+            Some(mode) => {
+                let schema = Schema::from(self.dataset.get().await?.schema());
+                handle_bad_vectors(data, &schema, mode)?
+            }
+            None => Box::new(data),
+        };
+
+        if let Some(on) = add.deduplicate_on {
+            return self.add_deduplicated(data, on).await;
+        }
+
+        let mut lance_params = add.write_options.lance_write_params.unwrap_or(WriteParams {
+            mode: match add.mode {
+                AddDataMode::Append => WriteMode::Append,
+                AddDataMode::Overwrite => WriteMode::Overwrite,
+            },
+            ..Default::default()
+        });
+        if let Some(max_rows_per_file) = add.max_rows_per_file {
+            lance_params.max_rows_per_file = max_rows_per_file;
+        }
+        if let Some(max_bytes_per_file) = add.max_bytes_per_file {
+            lance_params.max_bytes_per_file = max_bytes_per_file;
+        }
+
+        // Bring storage options from table
+        let storage_options = lance_params
+            .store_params
+            .get_or_insert(Default::default())
+            .storage_options
+            .get_or_insert(Default::default());
+        for (key, value) in self.storage_options.iter() {
+            if !storage_options.contains_key(key) {
+                storage_options.insert(key.clone(), value.clone());
+            }
+        }
+
+        // patch the params if we have a write store wrapper
+        let lance_params = match self.store_wrapper.clone() {
+            Some(wrapper) => lance_params.patch_with_store_wrapper(wrapper)?,
+            None => lance_params,
+        };
+
+        // The indices of the old data are lost by the overwrite so they are read now
+        // and trained again on the new data
+        let indices = match lance_params.mode {
+            WriteMode::Overwrite => self.index_definitions().await?,
+            _ => Vec::new(),
+        };
+
+        self.dataset.ensure_mutable().await?;
+        let dataset = Dataset::write(data, &self.uri, Some(lance_params)).await?;
+
+        self.dataset.set_latest(dataset).await;
+        self.rebuild_indices(indices).await
+    }
+
+    async fn create_index(&self, opts: IndexBuilder) -> Result<()> {
+        self.ensure_writable()?;
+        if opts.columns.len() != 1 {
+            let message = if matches!(opts.index, Index::BTree(_)) && opts.columns.len() > 1 {
+                "Multi-column (composite) indices are not yet supported. Create a BTree index on \
+                    each column instead, a filter that combines the columns with AND will use all \
+                    of the indices"
+                    .to_string()
             } else {
-                crate::index::IndexType::BTree
+                "Multi-column (composite) indices are not yet supported".to_string()
             };
+            return Err(Error::Schema { message });
+        }
+        let schema = self.schema().await?;
+
+        let field = schema.field_with_name(&opts.columns[0])?;
+
+        let distance_type = match &opts.index {
+            Index::IvfPq(index) => Some(index.distance_type),
+            Index::IvfHnswPq(index) => Some(index.distance_type),
+            Index::IvfHnswSq(index) => Some(index.distance_type),
+            Index::Auto | Index::BTree(_) => None,
+        };
+        if distance_type == Some(DistanceType::Hamming) {
+            return Err(Error::InvalidInput {
+                message: "Vector indices cannot be trained with the hamming distance, \
+                    binary vectors are searched with a flat search"
+                    .to_string(),
+            });
+        }
+
+        match opts.index {
+            Index::Auto => self.create_auto_index(field, opts).await,
+            Index::BTree(_) => self.create_btree_index(field, opts.replace).await,
+            Index::IvfPq(ivf_pq) => self.create_ivf_pq_index(ivf_pq, field, opts.replace).await,
+            Index::IvfHnswPq(ivf_hnsw_pq) => {
+                self.create_ivf_hnsw_pq_index(ivf_hnsw_pq, field, opts.replace)
+                    .await
+            }
+            Index::IvfHnswSq(ivf_hnsw_sq) => {
+                self.create_ivf_hnsw_sq_index(ivf_hnsw_sq, field, opts.replace)
+                    .await
+            }
+        }
+    }
+
+    async fn update(&self, update: UpdateBuilder) -> Result<UpdateResult> {
+        self.ensure_writable()?;
+        let dataset = self.dataset.get().await?.clone();
+        let schema: SchemaRef = Arc::new(Schema::from(dataset.schema()));
+        let old_fragments = dataset
+            .get_fragments()
+            .iter()
+            .map(|fragment| fragment.id())
+            .collect::<HashSet<_>>();
+        let mut builder = LanceUpdateBuilder::new(Arc::new(dataset));
+        if let Some(predicate) = update.filter {
+            builder = builder.update_where(&rewrite_filter(&predicate, &schema))?;
+        }
+
+        for (column, value) in update.columns {
+            Self::validate_update_expr(&schema, &column, &value)?;
+            builder = builder.set(column, &value)?;
+        }
 
-            let name = idx.name.clone();
-            Ok(IndexConfig { index_type, columns, name })
-        }).collect::<Result<Vec<_>>>()
+        let operation = builder.build()?;
+        let ds = operation.execute().await?;
+        // Lance rewrites the updated rows into new fragments, so the number of rows in
+        // the new fragments is the number of rows updated
+        let rows_updated = ds
+            .get_fragments()
+            .iter()
+            .filter(|fragment| !old_fragments.contains(&fragment.id()))
+            .map(|fragment| fragment.metadata().physical_rows.unwrap_or_default() as u64)
+            .sum();
+        let version = ds.version().version;
+        self.dataset.set_latest(ds.as_ref().clone()).await;
+        Ok(UpdateResult {
+            rows_updated,
+            version,
+        })
     }
-}
 
-#[cfg(test)]
-#[allow(deprecated)]
-mod tests {
-    use std::iter;
-    use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::Arc;
-    use std::time::Duration;
+    async fn create_plan(
+        &self,
+        query: &VectorQuery,
+        options: QueryExecutionOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if query.base.batch_size == Some(0) {
+            return Err(Error::InvalidInput {
+                message: "batch_size must be greater than 0".to_string(),
+            });
+        }
+        if query.query_vector.len() > 1 {
+            // Each query vector gets its own search (so the limit applies per vector)
+            // and the results are combined and tagged with the query vector's index
+            let plans =
+                futures::future::try_join_all(query.query_vector.iter().map(|query_vector| {
+                    let mut sub_query = query.clone();
+                    sub_query.query_vector = vec![query_vector.clone()];
+                    let options = options.clone();
+                    async move { self.create_plan(&sub_query, options).await }
+                }))
+                .await?;
+            return Self::multi_vector_plan(plans);
+        }
+
+        let ds_ref = self.dataset.get().await?;
+
+        if query.base.with_row_address && !ds_ref.manifest().uses_move_stable_row_ids() {
+            // Lance fails to take the remaining columns of rows that carry a `_rowaddr`
+            // column (as vector searches and filtered scans do).  Without stable row ids
+            // the row id is the row address so the address is copied from the row id.
+            let mut search = query.clone();
+            search.base.with_row_address = false;
+            search.base.with_row_id = true;
+            drop(ds_ref);
+            let plan = self.create_plan(&search, options).await?;
+            return Self::row_id_as_address(plan, query.base.with_row_id);
+        }
+
+        if let Select::Except(excluded) = &query.base.select {
+            let is_vector_search = !query.query_vector.is_empty();
+            let drop_distance = is_vector_search && excluded.iter().any(|c| c == DIST_COL);
+            let mut search = query.clone();
+            search.base.select =
+                Select::Columns(Self::columns_except(&ds_ref, excluded, is_vector_search)?);
+            drop(ds_ref);
+            let plan = self.create_plan(&search, options).await?;
+            return if drop_distance {
+                Self::drop_column(plan, DIST_COL)
+            } else {
+                Ok(plan)
+            };
+        }
+
+        if let (Select::Dynamic(select), false) =
+            (&query.base.select, query.query_vector.is_empty())
+        {
+            if let Some((exprs, columns)) = Self::parse_distance_projection(&ds_ref, select)? {
+                // Search with the columns the projection reads and project the results.
+                // Lance refuses to scan nothing so fall back to the row ids.
+                let mut search = query.clone();
+                search.base.with_row_id |= columns.is_empty();
+                search.base.select = Select::Columns(columns);
+                drop(ds_ref);
+                let plan = self.create_plan(&search, options).await?;
+                return Self::project_search_results(plan, exprs, &query.base);
+            }
+        }
+
+        if query.distance_range != (None, None) && !query.query_vector.is_empty() {
+            let (lower, upper) = query.distance_range;
+            if lower.is_some_and(f32::is_nan) || upper.is_some_and(f32::is_nan) {
+                return Err(Error::InvalidInput {
+                    message: "the bounds of the distance range cannot be NaN".to_string(),
+                });
+            }
+            if let (Some(lower), Some(upper)) = (lower, upper) {
+                if lower >= upper {
+                    return Err(Error::InvalidInput {
+                        message: format!(
+                            "the lower bound of the distance range ({}) must be less than the upper bound ({})",
+                            lower, upper
+                        ),
+                    });
+                }
+            }
+            // The nearest results are a prefix of the search results, so without a lower
+            // bound the search only needs to find `limit + offset` of them.  Otherwise the
+            // results below the lower bound are skipped and every row is a candidate.
+            let limit = query.base.limit.unwrap_or(DEFAULT_TOP_K);
+            let offset = query.base.offset.unwrap_or(0);
+            let mut search = query.clone();
+            search.distance_range = (None, None);
+            search.base.offset = None;
+            search.base.limit = Some(if lower.is_some() {
+                ds_ref.count_rows(None).await?.max(1)
+            } else {
+                limit + offset
+            });
+            drop(ds_ref);
+            let plan = self.create_plan(&search, options).await?;
+            return Self::filter_distance(plan, lower, upper, limit, offset);
+        }
+
+        let mut scanner: Scanner = ds_ref.scan();
+
+        if let Some(fragment_ids) = &query.base.fragments {
+            if !query.query_vector.is_empty() {
+                return Err(Error::InvalidInput {
+                    message: "with_fragments cannot be combined with a vector search".to_string(),
+                });
+            }
+            let fragments = fragment_ids
+                .iter()
+                .map(|id| {
+                    ds_ref
+                        .get_fragment(*id as usize)
+                        .map(|fragment| fragment.metadata().clone())
+                        .ok_or_else(|| Error::InvalidInput {
+                            message: format!(
+                                "fragment {} does not exist in version {} of the table",
+                                id,
+                                ds_ref.version().version
+                            ),
+                        })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            scanner.with_fragments(fragments);
+        }
+
+        let mut use_index = query.use_index;
+        if let Some(query_vector) = query.query_vector.first() {
+            if query_vector.data_type() == &DataType::UInt8 {
+                let query_vector = query_vector.as_primitive::<UInt8Type>();
+                return self
+                    .binary_vector_plan(&ds_ref, query, query_vector.values(), options)
+                    .await;
+            }
+            if query.distance_type == Some(DistanceType::Hamming) {
+                return Err(Error::InvalidInput {
+                    message: "The hamming distance can only be used with binary (u8) query vectors"
+                        .to_string(),
+                });
+            }
+            // If there is a vector query, default to limit=10 if unspecified
+            let column = if let Some(col) = query.column.as_ref() {
+                col.clone()
+            } else {
+                // Infer a vector column with the same dimension of the query vector.
+                let arrow_schema = Schema::from(ds_ref.schema());
+                default_vector_column(&arrow_schema, Some(query_vector.len() as i32))?
+            };
+            let field = ds_ref.schema().field(&column).ok_or(Error::Schema {
+                message: format!("Column {} not found in dataset schema", column),
+            })?;
+            if let arrow_schema::DataType::FixedSizeList(f, dim) = field.data_type() {
+                if !f.data_type().is_floating() {
+                    return Err(Error::InvalidInput {
+                        message: format!(
+                            "The data type of the vector column '{}' is not a floating point type",
+                            column
+                        ),
+                    });
+                }
+                if dim != query_vector.len() as i32 {
+                    return Err(Error::InvalidInput {
+                        message: format!(
+                            "The dimension of the query vector does not match with the dimension of the vector column '{}': \
+                                query dim={}, expected vector dim={}",
+                            column,
+                            query_vector.len(),
+                            dim,
+                        ),
+                    });
+                }
+            }
+            use_index = self.validate_vector_search(&ds_ref, &column, query).await?;
+            let query_vector = query_vector.as_primitive::<Float32Type>();
+            let limit = query.base.limit.unwrap_or(DEFAULT_TOP_K);
+            let offset = query.base.offset.unwrap_or(0);
+            // The offset is applied after the distance sort so we need to find
+            // enough neighbors to cover the skipped rows as well
+            scanner.nearest(&column, query_vector, limit + offset)?;
+            if offset > 0 {
+                scanner.limit(Some(limit as i64), Some(offset as i64))?;
+            }
+        } else {
+            // If there is no vector query, it's ok to not have a limit
+            scanner.limit(
+                query.base.limit.map(|limit| limit as i64),
+                query.base.offset.map(|offset| offset as i64),
+            )?;
+        }
+        scanner.nprobs(query.nprobes.unwrap_or(DEFAULT_NPROBES));
+        scanner.use_index(use_index);
+        scanner.prefilter(query.prefilter);
+        scanner.batch_size(
+            query
+                .base
+                .batch_size
+                .unwrap_or(options.max_batch_length as usize),
+        );
+
+        match &query.base.select {
+            Select::Columns(select) => {
+                scanner.project(select.as_slice())?;
+            }
+            Select::Dynamic(select_with_transform) => {
+                scanner.project_with_transform(select_with_transform.as_slice())?;
+            }
+            Select::Except(_) => unreachable!("resolved to Select::Columns above"),
+            Select::All => { /* Do nothing */ }
+        }
+
+        if let Some(filter) = &query.base.filter {
+            scanner.filter(&rewrite_filter(filter, &Schema::from(ds_ref.schema())))?;
+        }
+
+        if query.base.with_row_id {
+            scanner.with_row_id();
+        }
+        if query.base.with_row_address {
+            scanner.with_row_address();
+        }
+
+        if let Some(refine_factor) = query.refine_factor {
+            scanner.refine(refine_factor);
+        }
+
+        if let Some(ef) = query.ef {
+            scanner.ef(ef as usize);
+        }
+
+        if let Some(distance_type) = query.distance_type {
+            scanner.distance_metric(distance_type.into());
+        }
+        let plan = scanner.create_plan().await?;
+        if query.fast_search {
+            Self::skip_unindexed_search(plan)
+        } else {
+            Ok(plan)
+        }
+    }
+
+    async fn plain_query(
+        &self,
+        query: &Query,
+        options: QueryExecutionOptions,
+    ) -> Result<DatasetRecordBatchStream> {
+        self.generic_query(&query.clone().into_vector(), options)
+            .await
+    }
+
+    async fn merge_insert(
+        &self,
+        params: MergeInsertBuilder,
+        new_data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<MergeInsertStats> {
+        self.ensure_writable()?;
+        let dataset = Arc::new(self.dataset.get().await?.clone());
+        let mut builder = LanceMergeInsertBuilder::try_new(dataset.clone(), params.on.clone())?;
+        let when_matched = match (
+            params.when_matched_update_all,
+            &params.when_matched_update_all_filt,
+        ) {
+            (false, _) => WhenMatched::DoNothing,
+            (true, None) => WhenMatched::UpdateAll,
+            (true, Some(filt)) => WhenMatched::update_if(&dataset, filt)?,
+        };
+        builder.when_matched(when_matched.clone());
+        if params.when_not_matched_insert_all {
+            builder.when_not_matched(lance::dataset::WhenNotMatched::InsertAll);
+        } else {
+            builder.when_not_matched(lance::dataset::WhenNotMatched::DoNothing);
+        }
+        let when_not_matched_by_source = if params.when_not_matched_by_source_delete {
+            if let Some(filter) = &params.when_not_matched_by_source_delete_filt {
+                WhenNotMatchedBySource::delete_if(dataset.as_ref(), filter)?
+            } else {
+                WhenNotMatchedBySource::Delete
+            }
+        } else {
+            WhenNotMatchedBySource::Keep
+        };
+        builder.when_not_matched_by_source(when_not_matched_by_source.clone());
+        let job = builder.try_build()?;
+        if params.dry_run {
+            return merge::dry_run_stats(
+                &dataset,
+                &params,
+                &when_matched,
+                &when_not_matched_by_source,
+                new_data,
+            )
+            .await;
+        }
+        let (new_dataset, stats) = job.execute_reader(new_data).await?;
+        self.dataset.set_latest(new_dataset.as_ref().clone()).await;
+        Ok(MergeInsertStats {
+            inserted: stats.num_inserted_rows,
+            updated: stats.num_updated_rows,
+            deleted: stats.num_deleted_rows,
+        })
+    }
+
+    /// Delete rows from the table
+    async fn delete(&self, predicate: &str) -> Result<DeleteResult> {
+        self.ensure_writable()?;
+        let mut dataset = self.dataset.get_mut().await?;
+        // The row counts come from the manifest and the deletion files
+        let rows_before = dataset.count_rows(None).await?;
+        let schema = Schema::from(dataset.schema());
+        dataset.delete(&rewrite_filter(predicate, &schema)).await?;
+        let rows_after = dataset.count_rows(None).await?;
+        Ok(DeleteResult {
+            rows_deleted: (rows_before - rows_after) as u64,
+            version: dataset.version().version,
+        })
+    }
+
+    async fn delete_by_keys(
+        &self,
+        column: &str,
+        keys: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<DeleteResult> {
+        self.ensure_writable()?;
+        let mut dataset = self.dataset.get_mut().await?;
+        let data_type = dataset
+            .schema()
+            .field(column)
+            .ok_or_else(|| Error::InvalidInput {
+                message: format!("The table does not have a column named {}", column),
+            })?
+            .data_type();
+
+        let converter = RowConverter::new(vec![SortField::new(data_type.clone())])?;
+        let mut key_rows = HashSet::new();
+        for batch in keys {
+            let batch = batch?;
+            let keys = batch
+                .column_by_name(column)
+                .ok_or_else(|| Error::InvalidInput {
+                    message: format!("The keys do not contain a column named {}", column),
+                })?;
+            let keys = cast_keys(keys, column, &data_type)?;
+            let rows = converter.convert_columns(&[keys.clone()])?;
+            for (idx, row) in rows.iter().enumerate() {
+                if keys.is_valid(idx) {
+                    key_rows.insert(row.as_ref().to_vec());
+                }
+            }
+        }
+
+        // Find the addresses of the rows to delete, grouped by fragment
+        let mut deletions: HashMap<u32, Vec<u32>> = HashMap::new();
+        let mut scanner = dataset.scan();
+        scanner.project(&[column])?.with_row_address();
+        let mut stream = scanner.try_into_stream().await?;
+        while let Some(batch) = stream.try_next().await? {
+            let values = batch[column].clone();
+            let row_addrs = batch[ROW_ADDR].as_primitive::<UInt64Type>();
+            let rows = converter.convert_columns(&[values.clone()])?;
+            for (idx, row) in rows.iter().enumerate() {
+                if values.is_valid(idx) && key_rows.contains(row.as_ref()) {
+                    let row_addr = row_addrs.value(idx);
+                    deletions
+                        .entry((row_addr >> 32) as u32)
+                        .or_default()
+                        .push(row_addr as u32);
+                }
+            }
+        }
+        let rows_deleted = deletions
+            .values()
+            .map(|offsets| offsets.len())
+            .sum::<usize>();
+        if rows_deleted == 0 {
+            return Ok(DeleteResult {
+                rows_deleted: 0,
+                version: dataset.version().version,
+            });
+        }
+
+        // Write the new deletion files and commit them all at once
+        let params = ObjectStoreParams {
+            storage_options: Some(self.storage_options.clone()),
+            object_store_wrapper: self.store_wrapper.clone(),
+            ..Default::default()
+        };
+        let (object_store, base) = ObjectStore::from_uri_and_params(&self.uri, &params).await?;
+        let mut updated_fragments = Vec::new();
+        let mut deleted_fragment_ids = Vec::new();
+        for fragment in dataset.get_fragments() {
+            let Some(offsets) = deletions.remove(&(fragment.id() as u32)) else {
+                continue;
+            };
+            let mut metadata = fragment.metadata().clone();
+            let mut deletion_vector = read_deletion_file(&base, &metadata, &object_store)
+                .await?
+                .unwrap_or_default();
+            deletion_vector.extend(offsets);
+            if deletion_vector.len() == fragment.physical_rows().await? {
+                deleted_fragment_ids.push(metadata.id);
+                continue;
+            }
+            metadata.deletion_file = write_deletion_file(
+                &base,
+                metadata.id,
+                dataset.version().version,
+                &deletion_vector,
+                &object_store,
+            )
+            .await?;
+            updated_fragments.push(metadata);
+        }
+        let operation = Operation::Delete {
+            updated_fragments,
+            deleted_fragment_ids,
+            predicate: format!("{} in the keys given to delete_by_keys", column),
+        };
+        *dataset = Dataset::commit(
+            &self.uri,
+            operation,
+            Some(dataset.version().version),
+            Some(params),
+            None,
+        )
+        .await?;
+        Ok(DeleteResult {
+            rows_deleted: rows_deleted as u64,
+            version: dataset.version().version,
+        })
+    }
+
+    async fn optimize(&self, action: OptimizeAction) -> Result<OptimizeStats> {
+        self.ensure_writable()?;
+        let mut stats = OptimizeStats {
+            compaction: None,
+            prune: None,
+        };
+        match action {
+            OptimizeAction::All => {
+                stats.compaction = self
+                    .optimize(OptimizeAction::Compact {
+                        options: CompactionOptions::default(),
+                        remap_options: None,
+                    })
+                    .await?
+                    .compaction;
+                stats.prune = self
+                    .optimize(OptimizeAction::Prune {
+                        older_than: None,
+                        delete_unverified: None,
+                    })
+                    .await?
+                    .prune;
+                self.optimize(OptimizeAction::Index(OptimizeOptions::default()))
+                    .await?;
+            }
+            OptimizeAction::Compact {
+                options,
+                remap_options,
+            } => {
+                stats.compaction = Some(self.compact_files(options, remap_options).await?);
+            }
+            OptimizeAction::Prune {
+                older_than,
+                delete_unverified,
+            } => {
+                stats.prune = Some(
+                    self.cleanup_old_versions(
+                        older_than.unwrap_or(Duration::try_days(7).expect("valid delta")),
+                        delete_unverified,
+                    )
+                    .await?,
+                );
+            }
+            OptimizeAction::Index(options) => {
+                self.optimize_indices(&options).await?;
+            }
+        }
+        Ok(stats)
+    }
+
+    async fn add_columns(
+        &self,
+        transforms: NewColumnTransform,
+        read_columns: Option<Vec<String>>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        self.dataset
+            .get_mut()
+            .await?
+            .add_columns(transforms, read_columns)
+            .await?;
+        Ok(())
+    }
+
+    async fn merge(
+        &self,
+        other: Box<dyn RecordBatchReader + Send>,
+        left_on: &str,
+        right_on: &str,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let other = coerce_view_types(other);
+        let schema = other.schema();
+        let batches = other.collect::<std::result::Result<Vec<_>, _>>()?;
+
+        // Lance keeps the last row for a duplicated key so check for them first
+        let key_field = schema
+            .field_with_name(right_on)
+            .map_err(|_| Error::InvalidInput {
+                message: format!(
+                    "The data to merge does not contain a column named {}",
+                    right_on
+                ),
+            })?;
+        let converter = RowConverter::new(vec![SortField::new(key_field.data_type().clone())])?;
+        let mut keys = HashSet::new();
+        for batch in &batches {
+            let column = batch[right_on].clone();
+            let rows = converter.convert_columns(&[column.clone()])?;
+            for (idx, row) in rows.iter().enumerate() {
+                if column.is_valid(idx) && !keys.insert(row.owned()) {
+                    return Err(Error::InvalidInput {
+                        message: format!(
+                            "The data to merge contains the key {} more than once",
+                            arrow::util::display::array_value_to_string(&column, idx)?
+                        ),
+                    });
+                }
+            }
+        }
+
+        // Rows of the table without a match get nulls in the new columns
+        let schema = Arc::new(Schema::new(
+            schema
+                .fields()
+                .iter()
+                .map(|field| {
+                    let nullable = field.is_nullable() || field.name() != right_on;
+                    field.as_ref().clone().with_nullable(nullable)
+                })
+                .collect::<Vec<_>>(),
+        ));
+        let batches = batches
+            .into_iter()
+            .map(|batch| RecordBatch::try_new(schema.clone(), batch.columns().to_vec()))
+            .collect::<Vec<_>>();
+        let other = RecordBatchIterator::new(batches, schema);
+        self.dataset
+            .get_mut()
+            .await?
+            .merge(other, left_on, right_on)
+            .await?;
+        Ok(())
+    }
+
+    async fn alter_columns(&self, alterations: &[ColumnAlteration]) -> Result<()> {
+        self.ensure_writable()?;
+        self.dataset
+            .get_mut()
+            .await?
+            .alter_columns(alterations)
+            .await?;
+        Ok(())
+    }
+
+    async fn drop_columns(&self, columns: &[&str]) -> Result<()> {
+        self.ensure_writable()?;
+        for index in self.list_indices().await? {
+            if let Some(column) = index
+                .columns
+                .iter()
+                .find(|column| columns.contains(&column.as_str()))
+            {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "column {} cannot be dropped because the index {} depends on it, \
+                         drop the index first",
+                        column, index.name
+                    ),
+                });
+            }
+        }
+        self.dataset.get_mut().await?.drop_columns(columns).await?;
+        Ok(())
+    }
+
+    async fn list_indices(&self) -> Result<Vec<IndexConfig>> {
+        let dataset = self.dataset.get().await?;
+        let indices = dataset.load_indices().await?;
+        let mut configs = Vec::with_capacity(indices.len());
+        for idx in indices.iter() {
+            let mut is_vector = false;
+            let mut columns = Vec::with_capacity(idx.fields.len());
+            for field_id in &idx.fields {
+                let field = dataset.schema().field_by_id(*field_id).ok_or_else(|| Error::Runtime { message: format!("The index with name {} and uuid {} referenced a field with id {} which does not exist in the schema", idx.name, idx.uuid, field_id) })?;
+                if field.data_type().is_nested() {
+                    // Temporary hack to determine if an index is scalar or vector
+                    // Should be removed in https://github.com/lancedb/lance/issues/2039
+                    is_vector = true;
+                }
+                columns.push(field.name.clone());
+            }
+
+            let index_stats = dataset.index_statistics(&idx.name).await?;
+            let index_stats: IndexStatistics = whatever!(
+                serde_json::from_str(&index_stats),
+                "error deserializing index statistics {index_stats}",
+            );
+
+            let index_type = if is_vector {
+                crate::index::IndexType::IvfPq
+            } else {
+                crate::index::IndexType::BTree
+            };
+
+            configs.push(IndexConfig {
+                index_type,
+                columns,
+                name: idx.name.clone(),
+                num_indexed_rows: index_stats.num_indexed_rows,
+                dataset_version: idx.dataset_version,
+            });
+        }
+        Ok(configs)
+    }
+
+    async fn wait_for_index(
+        &self,
+        index_names: &[&str],
+        _timeout: std::time::Duration,
+    ) -> Result<()> {
+        let indices = self.dataset.get().await?.load_indices().await?;
+        for name in index_names {
+            if !indices.iter().any(|index| index.name == *name) {
+                return Err(Error::IndexNotFound {
+                    name: name.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    async fn drop_index(&self, name: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let mut dataset = self.dataset.get_mut().await?;
+        let removed_indices = dataset.load_indices_by_name(name).await?;
+        if removed_indices.is_empty() {
+            return Err(Error::IndexNotFound {
+                name: name.to_string(),
+            });
+        }
+        let params = ObjectStoreParams {
+            storage_options: Some(self.storage_options.clone()),
+            object_store_wrapper: self.store_wrapper.clone(),
+            ..Default::default()
+        };
+        // Lance has no operation to drop an index, an index operation that only
+        // removes indices commits a version without them
+        let operation = Operation::CreateIndex {
+            new_indices: vec![],
+            removed_indices,
+        };
+        *dataset = Dataset::commit(
+            &self.uri,
+            operation,
+            Some(dataset.version().version),
+            Some(params),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn index_stats(&self, index_name: &str) -> Result<Option<IndexStatistics>> {
+        Self::index_stats(self, index_name).await
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use std::iter;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use arrow_array::types::{Float64Type, Int32Type, Int64Type};
+    use arrow_array::{
+        Array, BinaryViewArray, BooleanArray, Date32Array, FixedSizeListArray, Float32Array,
+        Float64Array, Int32Array, Int64Array, LargeStringArray, ListArray, RecordBatch,
+        RecordBatchIterator, RecordBatchReader, StringArray, StringViewArray,
+        TimestampMillisecondArray, TimestampNanosecondArray, UInt32Array,
+    };
+    use arrow_data::ArrayDataBuilder;
+    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+    use futures::{StreamExt, TryStreamExt};
+    use lance::dataset::{Dataset, WriteMode};
+    use lance::io::{ObjectStoreParams, WrappingObjectStore};
+    use rand::Rng;
+    use tempfile::tempdir;
+
+    use crate::arrow::SimpleRecordBatchStream;
+    use crate::connect;
+    use crate::connection::ConnectBuilder;
+    use crate::query::{ExecutableQuery, QueryBase};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_open() {
+        let tmp_dir = tempdir().unwrap();
+        let dataset_path = tmp_dir.path().join("test.lance");
+
+        let batches = make_test_batches();
+        Dataset::write(batches, dataset_path.to_str().unwrap(), None)
+            .await
+            .unwrap();
+
+        let table = NativeTable::open(dataset_path.to_str().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(table.name, "test")
+    }
+
+    #[tokio::test]
+    async fn test_open_not_found() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let table = NativeTable::open(uri).await;
+        assert!(matches!(table.unwrap_err(), Error::TableNotFound { .. }));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_object_store_path() {
+        use std::path::Path as StdPath;
+        let p = StdPath::new("s3://bucket/path/to/file");
+        let c = p.join("subfile");
+        assert_eq!(c.to_str().unwrap(), "s3://bucket/path/to/file/subfile");
+    }
+
+    #[tokio::test]
+    async fn test_count_rows() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+
+        let batches = make_test_batches();
+        let table = NativeTable::create(uri, "test", batches, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        assert_eq!(
+            table.count_rows(Some("i >= 5".to_string())).await.unwrap(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_count_rows_without_filter_reads_manifest() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("test", make_test_batches())
+            .execute()
+            .await
+            .unwrap();
+        for _ in 0..4 {
+            table.add(make_test_batches()).execute().await.unwrap();
+        }
+        table.delete("i < 3").await.unwrap();
+
+        let scanned = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_fold(
+                0,
+                |count, batch| async move { Ok(count + batch.num_rows()) },
+            )
+            .await
+            .unwrap();
+        assert_eq!(scanned, 35);
+        assert_eq!(table.count_rows(None).await.unwrap(), scanned);
+
+        // Without a filter the count comes from the fragment metadata in the manifest and
+        // so it does not need the data files
+        let data_dir = tmp_dir.path().join("test.lance").join(DATA_DIR);
+        for entry in std::fs::read_dir(data_dir).unwrap() {
+            std::fs::remove_file(entry.unwrap().path()).unwrap();
+        }
+        assert_eq!(table.count_rows(None).await.unwrap(), 35);
+        assert!(table.count_rows(Some("i > 5".to_string())).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decimal_filter() {
+        use arrow::datatypes::i256;
+        use arrow_array::types::{Decimal128Type, Decimal256Type};
+        use arrow_array::{Decimal128Array, Decimal256Array};
+
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("amount", DataType::Decimal128(10, 2), false),
+            Field::new("big", DataType::Decimal256(40, 4), false),
+        ]));
+        let amounts = [10000_i128, 10050, 10051, 99999999];
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(
+                    Decimal128Array::from(amounts.to_vec())
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ),
+                Arc::new(
+                    Decimal256Array::from(
+                        amounts
+                            .iter()
+                            .map(|a| i256::from_i128(*a * 100))
+                            .collect::<Vec<_>>(),
+                    )
+                    .with_precision_and_scale(40, 4)
+                    .unwrap(),
+                ),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table(
+                "decimals",
+                RecordBatchIterator::new(vec![Ok(batch)], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.schema().await.unwrap(), schema);
+
+        let query = |filter: &str| {
+            let table = table.clone();
+            let filter = filter.to_string();
+            async move {
+                let batches = table
+                    .query()
+                    .only_if(filter)
+                    .execute()
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap();
+                batches
+                    .iter()
+                    .flat_map(|b| {
+                        b["amount"]
+                            .as_primitive::<Decimal128Type>()
+                            .values()
+                            .to_vec()
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(query("amount > 100.50").await, vec![10051, 99999999]);
+        assert_eq!(
+            query("amount >= 100.50").await,
+            vec![10050, 10051, 99999999]
+        );
+        assert_eq!(query("amount = 100.5").await, vec![10050]);
+        assert_eq!(query("amount < 100").await, Vec::<i128>::new());
+        assert_eq!(query("amount = 999999.99").await, vec![99999999]);
+        assert_eq!(query("big > 100.505").await, vec![10051, 99999999]);
+        assert_eq!(
+            query("amount IN (100.5, 999999.99)").await,
+            vec![10050, 99999999]
+        );
+        assert_eq!(
+            query("amount BETWEEN 100.5 AND 100.51").await,
+            vec![10050, 10051]
+        );
+        assert_eq!(
+            table
+                .count_rows(Some("amount > 100.50".to_string()))
+                .await
+                .unwrap(),
+            2
+        );
+
+        let batches = table
+            .query()
+            .only_if("amount = 999999.99")
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let big = batches[0]["big"].as_primitive::<Decimal256Type>();
+        assert_eq!(big.value(0), i256::from_i128(9999999900));
+        assert_eq!(big.value_as_string(0), "999999.9900");
+    }
+
+    #[tokio::test]
+    async fn test_multi_column_scalar_filter() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("tenant_id", DataType::Int32, false),
+            Field::new("status", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values((0..100).map(|i| i % 10))),
+                Arc::new(StringArray::from_iter_values((0..100).map(|i| {
+                    if i % 3 == 0 {
+                        "open"
+                    } else {
+                        "closed"
+                    }
+                }))),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table(
+                "test",
+                RecordBatchIterator::new(vec![Ok(batch)], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let err = table
+            .create_index(&["tenant_id", "status"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Create a BTree index on each column instead"),
+            "{}",
+            err
+        );
+
+        for column in ["tenant_id", "status"] {
+            table
+                .create_index(&[column], Index::BTree(Default::default()))
+                .execute()
+                .await
+                .unwrap();
+        }
+        let filter = "tenant_id = 1 AND status = 'open'";
+        let plan = table
+            .query()
+            .only_if(filter)
+            .explain_plan(false)
+            .await
+            .unwrap();
+        // Both columns are looked up in their indices, neither is filtered by a scan
+        assert!(
+            plan.contains("MaterializeIndex: query=AND(tenant_id = 1,status = open)"),
+            "{}",
+            plan
+        );
+        assert_eq!(table.count_rows(Some(filter.to_string())).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_count_rows_with_scalar_index() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("test", make_test_batches())
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["i"], Index::BTree(BTreeIndexBuilder::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        let filter = "i >= 5 AND i < 8";
+        assert_eq!(table.count_rows(Some(filter.to_string())).await.unwrap(), 3);
+
+        // The filter should be answered by the scalar index and not a full scan
+        let plan = table
+            .query()
+            .only_if(filter)
+            .explain_plan(false)
+            .await
+            .unwrap();
+        assert!(plan.contains("MaterializeIndex"), "{}", plan);
+    }
+
+    #[tokio::test]
+    async fn test_take() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("test", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(merge_insert_test_batches(10, 1))
+            .execute()
+            .await
+            .unwrap();
+
+        // Look up the row ids of i=3 (first fragment) and i=12 (second fragment)
+        let batches = table
+            .query()
+            .only_if("i IN (3, 12)")
+            .with_row_id(true)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        let ids = batch[ROW_ID].as_primitive::<UInt64Type>().values();
+        let i = batch["i"].as_primitive::<Int32Type>().values();
+        let (id_3, id_12) = if i[0] == 3 {
+            (ids[0], ids[1])
+        } else {
+            (ids[1], ids[0])
+        };
+
+        // Rows are returned in the order of the ids
+        let rows = table.take(&[id_12, id_3], None).await.unwrap();
+        assert_eq!(rows.schema(), table.schema().await.unwrap());
+        assert_eq!(
+            rows["i"].as_primitive::<Int32Type>().values().as_ref(),
+            &[12, 3]
+        );
+        assert_eq!(
+            rows["age"].as_primitive::<Int32Type>().values().as_ref(),
+            &[1, 0]
+        );
+
+        let rows = table
+            .take(&[id_3], Some(vec!["age".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(rows.num_columns(), 1);
+        assert_eq!(
+            rows["age"].as_primitive::<Int32Type>().values().as_ref(),
+            &[0]
+        );
+
+        // Deleted rows (and ids that never existed) are an error
+        table.delete("i = 3").await.unwrap();
+        let err = table.take(&[id_12, id_3], None).await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&format!("row id {} does not exist", id_3)),
+            "{}",
+            err
+        );
+        assert!(table.take(&[id_12 + 100], None).await.is_err());
+        assert!(table.take(&[10 << 32], None).await.is_err());
+        let rows = table.take(&[id_12], None).await.unwrap();
+        assert_eq!(
+            rows["i"].as_primitive::<Int32Type>().values().as_ref(),
+            &[12]
+        );
+
+        // Compaction moves the rows, so the old ids no longer refer to them
+        table
+            .optimize(OptimizeAction::Compact {
+                options: CompactionOptions::default(),
+                remap_options: None,
+            })
+            .await
+            .unwrap();
+        assert!(table.take(&[id_12], None).await.is_err());
+        let batches = table
+            .query()
+            .only_if("i IN (4, 12)")
+            .with_row_id(true)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        let rows = table
+            .take(batch[ROW_ID].as_primitive::<UInt64Type>().values(), None)
+            .await
+            .unwrap();
+        assert_eq!(&rows["i"], &batch["i"]);
+    }
+
+    #[tokio::test]
+    async fn test_take_stable_row_ids() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let table = conn
+            .create_table("test", merge_insert_test_batches(0, 0))
+            .write_options(WriteOptions {
+                lance_write_params: Some(WriteParams {
+                    enable_move_stable_row_ids: true,
+                    ..Default::default()
+                }),
+            })
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(merge_insert_test_batches(10, 1))
+            .execute()
+            .await
+            .unwrap();
+        table.delete("i < 5").await.unwrap();
+
+        let batches = table
+            .query()
+            .only_if("i IN (7, 12)")
+            .with_row_id(true)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        let ids = batch[ROW_ID].as_primitive::<UInt64Type>().values();
+        let i = batch["i"].as_primitive::<Int32Type>().values();
+        // The row ids are not the addresses of the rows
+        let id_12 = ids[i.iter().position(|i| *i == 12).unwrap()];
+        assert_eq!(id_12 >> 32, 0);
+
+        let rows = table.take(ids, None).await.unwrap();
+        assert_eq!(&rows["i"], &batch["i"]);
+        // The row ids of deleted rows are an error
+        let err = table.take(&[id_12, 3], None).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_add() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let batches = make_test_batches();
+        let schema = batches.schema().clone();
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+
+        let new_batches = RecordBatchIterator::new(
+            vec![RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(100..110))],
+            )
+            .unwrap()]
+            .into_iter()
+            .map(Ok),
+            schema.clone(),
+        );
+
+        table.add(new_batches).execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 20);
+        assert_eq!(table.name(), "test");
+    }
+
+    #[tokio::test]
+    async fn test_merge_insert() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        // Create a dataset with i=0..10
+        let batches = merge_insert_test_batches(0, 0);
+        let table = conn
+            .create_table("my_table", batches)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+
+        // Create new data with i=5..15
+        let new_batches = Box::new(merge_insert_test_batches(5, 1));
+
+        // Perform a "insert if not exists"
+        let mut merge_insert_builder = table.merge_insert(&["i"]);
+        merge_insert_builder.when_not_matched_insert_all();
+        merge_insert_builder.execute(new_batches).await.unwrap();
+        // Only 5 rows should actually be inserted
+        assert_eq!(table.count_rows(None).await.unwrap(), 15);
+
+        // Create new data with i=15..25 (no id matches)
+        let new_batches = Box::new(merge_insert_test_batches(15, 2));
+        // Perform a "bulk update" (should not affect anything)
+        let mut merge_insert_builder = table.merge_insert(&["i"]);
+        merge_insert_builder.when_matched_update_all(None);
+        merge_insert_builder.execute(new_batches).await.unwrap();
+        // No new rows should have been inserted
+        assert_eq!(table.count_rows(None).await.unwrap(), 15);
+        assert_eq!(
+            table.count_rows(Some("age = 2".to_string())).await.unwrap(),
+            0
+        );
+
+        // Conditional update that only replaces the age=0 data
+        let new_batches = Box::new(merge_insert_test_batches(5, 3));
+        let mut merge_insert_builder = table.merge_insert(&["i"]);
+        merge_insert_builder.when_matched_update_all(Some("target.age = 0".to_string()));
+        merge_insert_builder.execute(new_batches).await.unwrap();
+        assert_eq!(
+            table.count_rows(Some("age = 3".to_string())).await.unwrap(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merge_insert_delete_if() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("tenant_id", DataType::Int32, false),
+            Field::new("id", DataType::Int32, false),
+            Field::new("value", DataType::Int32, false),
+        ]));
+        let make_batches = |tenant_ids: Vec<i32>, ids: Vec<i32>, value: i32| {
+            let num_rows = ids.len();
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from(tenant_ids)),
+                    Arc::new(Int32Array::from(ids)),
+                    Arc::new(Int32Array::from(vec![value; num_rows])),
+                ],
+            )
+            .unwrap();
+            Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema.clone()))
+        };
+
+        // Three tenants with the ids 0..5
+        let tenant_ids = (1..=3).flat_map(|t| iter::repeat(t).take(5)).collect();
+        let ids = (1..=3).flat_map(|_| 0..5).collect();
+        let table = conn
+            .create_table("my_table", make_batches(tenant_ids, ids, 0))
+            .execute()
+            .await
+            .unwrap();
+
+        // Sync tenant 2, whose ids are now 3..8
+        let mut merge_insert_builder = table.merge_insert(&["tenant_id", "id"]);
+        merge_insert_builder
+            .when_matched_update_all(None)
+            .when_not_matched_insert_all()
+            .when_not_matched_by_source_delete_if("tenant_id = 2");
+        let stats = merge_insert_builder
+            .execute(make_batches(vec![2; 5], (3..8).collect(), 1))
+            .await
+            .unwrap();
+        assert_eq!(stats.inserted, 3);
+        assert_eq!(stats.updated, 2);
+        assert_eq!(stats.deleted, 3);
+
+        assert_eq!(table.count_rows(None).await.unwrap(), 15);
+        for tenant_id in [1, 3] {
+            let filter = format!("tenant_id = {} AND id < 5 AND value = 0", tenant_id);
+            assert_eq!(table.count_rows(Some(filter)).await.unwrap(), 5);
+        }
+        let mut ids = table
+            .query()
+            .only_if("tenant_id = 2 AND value = 1")
+            .select(Select::columns(&["id"]))
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .iter()
+            .flat_map(|batch| batch["id"].as_primitive::<Int32Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec![3, 4, 5, 6, 7]);
+    }
+
+    #[tokio::test]
+    async fn test_drop_index() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+        for column in ["i", "age"] {
+            table
+                .create_index(&[column], Index::BTree(Default::default()))
+                .execute()
+                .await
+                .unwrap();
+        }
+        let index_names = |table: Table| async move {
+            let mut names = table
+                .list_indices()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|index| index.name)
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(index_names(table.clone()).await, vec!["age_idx", "i_idx"]);
+        let version = table.version().await.unwrap();
+
+        table.drop_index("i_idx").await.unwrap();
+        assert_eq!(index_names(table.clone()).await, vec!["age_idx"]);
+        assert_eq!(table.version().await.unwrap(), version + 1);
+        assert_eq!(
+            table.count_rows(Some("i = 3".to_string())).await.unwrap(),
+            1
+        );
+
+        let err = table.drop_index("i_idx").await.unwrap_err();
+        assert!(matches!(err, Error::IndexNotFound { .. }), "{}", err);
+        let timeout = std::time::Duration::from_secs(1);
+        table.wait_for_index(&["age_idx"], timeout).await.unwrap();
+        let err = table
+            .wait_for_index(&["age_idx", "i_idx"], timeout)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::IndexNotFound { .. }), "{}", err);
+
+        // The index is still part of the older versions and the column can be
+        // indexed again
+        let old = table.checkout_version(version).await.unwrap();
+        assert_eq!(index_names(old).await, vec!["age_idx", "i_idx"]);
+        table
+            .create_index(&["i"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(index_names(table.clone()).await, vec!["age_idx", "i_idx"]);
+    }
+
+    #[tokio::test]
+    async fn test_add_deduplicate_on() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        // Create a dataset with i=0..10
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+
+        // Add i=5..15, only 10..15 are new
+        table
+            .add(merge_insert_test_batches(5, 1))
+            .deduplicate_on(&["i"])
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 15);
+        assert_eq!(
+            table.count_rows(Some("age = 0".to_string())).await.unwrap(),
+            10
+        );
+        assert_eq!(
+            table.count_rows(Some("age = 1".to_string())).await.unwrap(),
+            5
+        );
+
+        // The keys are looked up with the scalar index, including the rows
+        // added after the index was created
+        table
+            .create_index(&["i"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(merge_insert_test_batches(10, 2))
+            .deduplicate_on(&["i"])
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 20);
+        table
+            .add(merge_insert_test_batches(18, 3))
+            .deduplicate_on(&["i"])
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 28);
+        assert_eq!(
+            table.count_rows(Some("age = 3".to_string())).await.unwrap(),
+            8
+        );
+        assert_eq!(
+            table
+                .count_rows(Some("i >= 18 AND i < 20".to_string()))
+                .await
+                .unwrap(),
+            2
+        );
+
+        let err = table
+            .add(merge_insert_test_batches(0, 4))
+            .mode(AddDataMode::Overwrite)
+            .deduplicate_on(&["i"])
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+
+        // The write parameters cannot be given to the merge insert
+        let version = table.version().await.unwrap();
+        let err = table
+            .add(merge_insert_test_batches(30, 4))
+            .deduplicate_on(&["i"])
+            .max_rows_per_file(2)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        let err = table
+            .add(merge_insert_test_batches(30, 4))
+            .deduplicate_on(&["i"])
+            .write_options(WriteOptions {
+                lance_write_params: Some(WriteParams::default()),
+            })
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        assert_eq!(table.version().await.unwrap(), version);
+    }
+
+    #[tokio::test]
+    async fn test_merge_insert_update_if() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("updated_at", DataType::Int64, false),
+            Field::new("value", DataType::Int32, false),
+        ]));
+        let make_batches = |updated_at: Vec<i64>, value: i32| {
+            let num_rows = updated_at.len();
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from_iter_values(0..num_rows as i32)),
+                    Arc::new(Int64Array::from(updated_at)),
+                    Arc::new(Int32Array::from(vec![value; num_rows])),
+                ],
+            )
+            .unwrap();
+            Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema.clone()))
+        };
+        let table = conn
+            .create_table("my_table", make_batches(vec![10; 10], 0))
+            .execute()
+            .await
+            .unwrap();
+
+        // The rows with an even id are older than the rows in the table
+        let updated_at = (0..10).map(|id| if id % 2 == 0 { 5 } else { 20 }).collect();
+        let mut merge_insert_builder = table.merge_insert(&["id"]);
+        merge_insert_builder.when_matched_update_all_if("target.updated_at < source.updated_at");
+        let stats = merge_insert_builder
+            .execute(make_batches(updated_at, 1))
+            .await
+            .unwrap();
+        assert_eq!(stats.updated, 5);
+        assert_eq!(stats.inserted, 0);
+
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        assert_eq!(
+            table
+                .count_rows(Some(
+                    "value = 1 AND updated_at = 20 AND id % 2 = 1".to_string()
+                ))
+                .await
+                .unwrap(),
+            5
+        );
+        assert_eq!(
+            table
+                .count_rows(Some(
+                    "value = 0 AND updated_at = 10 AND id % 2 = 0".to_string()
+                ))
+                .await
+                .unwrap(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_optimize_compaction() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)]));
+        let make_batch = |i: i32| {
+            let batch =
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![i]))])
+                    .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let table = conn
+            .create_table("test", make_batch(0))
+            .execute()
+            .await
+            .unwrap();
+        for i in 1..500 {
+            table.add(make_batch(i)).execute().await.unwrap();
+        }
+        let native = table.as_native().unwrap();
+        assert_eq!(native.count_fragments().await.unwrap(), 500);
+
+        // A reader that opened the table before optimizing keeps its snapshot
+        let reader = conn.open_table("test").execute().await.unwrap();
+
+        let stats = table
+            .optimize(OptimizeAction::Compact {
+                options: CompactionOptions {
+                    target_rows_per_fragment: 200,
+                    ..Default::default()
+                },
+                remap_options: None,
+            })
+            .await
+            .unwrap();
+        let compaction = stats.compaction.unwrap();
+        assert_eq!(compaction.fragments_removed, 500);
+        assert_eq!(compaction.fragments_added, 3);
+        assert!(stats.prune.is_none());
+        assert_eq!(native.count_fragments().await.unwrap(), 3);
+        assert_eq!(table.count_rows(None).await.unwrap(), 500);
+
+        assert_eq!(
+            reader.as_native().unwrap().count_fragments().await.unwrap(),
+            500
+        );
+        assert_eq!(reader.count_rows(None).await.unwrap(), 500);
+    }
+
+    #[tokio::test]
+    async fn test_compact_files() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)]));
+        let make_batch = |range: std::ops::Range<i32>| {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(range))],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let table = conn
+            .create_table("test", make_batch(0..100))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["i"], Index::BTree(BTreeIndexBuilder::default()))
+            .execute()
+            .await
+            .unwrap();
+        for i in 100..150 {
+            table.add(make_batch(i..i + 1)).execute().await.unwrap();
+        }
+        let native = table.as_native().unwrap();
+        assert_eq!(native.count_fragments().await.unwrap(), 51);
+        let indices = native.load_indices().await.unwrap();
+        let index_stats = table.index_stats("i_idx").await.unwrap().unwrap();
+        assert_eq!(index_stats.num_indexed_rows, 100);
+        assert_eq!(index_stats.num_unindexed_rows, 50);
+
+        // The unindexed fragments are merged, the indexed fragment is left alone
+        let metrics = table
+            .compact_files(CompactOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(metrics.fragments_removed, 50);
+        assert_eq!(metrics.fragments_added, 1);
+        assert_eq!(native.count_fragments().await.unwrap(), 2);
+        assert_eq!(table.count_rows(None).await.unwrap(), 150);
+        let uuids = |indices: &[VectorIndex]| {
+            indices
+                .iter()
+                .map(|index| index.index_uuid.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            uuids(&native.load_indices().await.unwrap()),
+            uuids(&indices)
+        );
+        let index_stats = table.index_stats("i_idx").await.unwrap().unwrap();
+        assert_eq!(index_stats.num_indexed_rows, 100);
+        assert_eq!(index_stats.num_unindexed_rows, 50);
+        assert_eq!(
+            table.count_rows(Some("i < 10".to_string())).await.unwrap(),
+            10
+        );
+
+        // Deleted rows are only removed when asked to
+        let table = conn
+            .create_table("deletions", make_batch(0..100))
+            .execute()
+            .await
+            .unwrap();
+        table.delete("i >= 80").await.unwrap();
+        let native = table.as_native().unwrap();
+        let options = CompactOptions {
+            materialize_deletions: false,
+            ..Default::default()
+        };
+        let metrics = table.compact_files(options).await.unwrap();
+        assert_eq!(metrics.fragments_removed, 0);
+        assert_eq!(native.count_deleted_rows().await.unwrap(), 20);
+
+        let metrics = table
+            .compact_files(CompactOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(metrics.fragments_removed, 1);
+        assert_eq!(metrics.fragments_added, 1);
+        assert_eq!(native.count_deleted_rows().await.unwrap(), 0);
+        assert_eq!(table.count_rows(None).await.unwrap(), 80);
+    }
+
+    #[tokio::test]
+    async fn test_merge_insert_dry_run() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        // Create a dataset with i=0..10
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+        let version = table.version().await.unwrap();
+
+        // New data with i=5..15 matches 5 rows and leaves 5 rows unmatched on each side
+        let mut merge_insert_builder = table.merge_insert(&["i"]);
+        merge_insert_builder
+            .when_matched_update_all(None)
+            .when_not_matched_insert_all()
+            .when_not_matched_by_source_delete(Some("i < 3".to_string()))
+            .dry_run(true);
+        let stats = merge_insert_builder
+            .clone()
+            .execute(Box::new(merge_insert_test_batches(5, 1)))
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            MergeInsertStats {
+                inserted: 5,
+                updated: 5,
+                deleted: 3,
+            }
+        );
+        // Nothing should have changed
+        assert_eq!(table.version().await.unwrap(), version);
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+
+        // The real run should report the same stats
+        merge_insert_builder.dry_run(false);
+        let real_stats = merge_insert_builder
+            .execute(Box::new(merge_insert_test_batches(5, 1)))
+            .await
+            .unwrap();
+        assert_eq!(real_stats, stats);
+        assert_eq!(table.count_rows(None).await.unwrap(), 12);
+
+        // The table now has i=3..5 with age=0 and i=5..15 with age=1.  With
+        // conditions only i=3..5 are updated and only i=10..15 are deleted
+        let version = table.version().await.unwrap();
+        let mut merge_insert_builder = table.merge_insert(&["i"]);
+        merge_insert_builder
+            .when_matched_update_all_if("target.age < source.age AND target.age = 0")
+            .when_not_matched_insert_all()
+            .when_not_matched_by_source_delete_if("age = 1 AND i >= 10")
+            .dry_run(true);
+        let stats = merge_insert_builder
+            .clone()
+            .execute(Box::new(merge_insert_test_batches(0, 2)))
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            MergeInsertStats {
+                inserted: 3,
+                updated: 2,
+                deleted: 5,
+            }
+        );
+        assert_eq!(table.version().await.unwrap(), version);
+        assert_eq!(table.count_rows(None).await.unwrap(), 12);
+
+        merge_insert_builder.dry_run(false);
+        let real_stats = merge_insert_builder
+            .execute(Box::new(merge_insert_test_batches(0, 2)))
+            .await
+            .unwrap();
+        assert_eq!(real_stats, stats);
+        assert_eq!(
+            table.count_rows(Some("age = 2".to_string())).await.unwrap(),
+            5
+        );
+
+        // A dry run rejects incompatible data just like a real run
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int64, false),
+            Field::new("age", DataType::Int32, false),
+        ]));
+        let bad_data = || {
+            Box::new(RecordBatchIterator::new(
+                vec![RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from(vec![1])),
+                        Arc::new(Int32Array::from(vec![1])),
+                    ],
+                )],
+                schema.clone(),
+            ))
+        };
+        let mut merge_insert_builder = table.merge_insert(&["i"]);
+        merge_insert_builder.when_not_matched_insert_all();
+        assert!(merge_insert_builder
+            .clone()
+            .execute(bad_data())
+            .await
+            .is_err());
+        merge_insert_builder.dry_run(true);
+        assert!(merge_insert_builder.execute(bad_data()).await.is_err());
+
+        // Rows with a null key are neither matched, inserted nor deleted
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, true),
+            Field::new("age", DataType::Int32, false),
+        ]));
+        let make_batch = |i: Vec<Option<i32>>, age: i32| {
+            let num_rows = i.len();
+            Box::new(RecordBatchIterator::new(
+                vec![RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int32Array::from(i)),
+                        Arc::new(Int32Array::from(vec![age; num_rows])),
+                    ],
+                )],
+                schema.clone(),
+            ))
+        };
+        let table = conn
+            .create_table("null_keys", make_batch(vec![Some(1), Some(2), None], 0))
+            // Legacy files cannot store null integers
+            .use_legacy_format(false)
+            .execute()
+            .await
+            .unwrap();
+        let mut merge_insert_builder = table.merge_insert(&["i"]);
+        merge_insert_builder
+            .when_matched_update_all(None)
+            .when_not_matched_insert_all()
+            .when_not_matched_by_source_delete(None)
+            .dry_run(true);
+        let stats = merge_insert_builder
+            .clone()
+            .execute(make_batch(vec![Some(2), Some(3), None], 1))
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            MergeInsertStats {
+                inserted: 1,
+                updated: 1,
+                deleted: 1,
+            }
+        );
+        merge_insert_builder.dry_run(false);
+        let real_stats = merge_insert_builder
+            .execute(make_batch(vec![Some(2), Some(3), None], 1))
+            .await
+            .unwrap();
+        assert_eq!(real_stats, stats);
+        assert_eq!(table.count_rows(None).await.unwrap(), 3);
+        assert_eq!(
+            table
+                .count_rows(Some("i IS NULL".to_string()))
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_view_types() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("s", DataType::Utf8View, true),
+            Field::new("b", DataType::BinaryView, true),
+        ]));
+        let make_batch = |offset: usize| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringViewArray::from_iter((offset..offset + 10).map(|i| {
+                        (i % 3 != 0).then(|| format!("a string longer than twelve bytes {}", i))
+                    }))),
+                    Arc::new(BinaryViewArray::from_iter_values(
+                        (offset..offset + 10).map(|i| vec![i as u8; i % 5]),
+                    )),
+                ],
+            )
+            .unwrap()
+        };
+        let table = conn
+            .create_table(
+                "test",
+                RecordBatchIterator::new(vec![Ok(make_batch(0))], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // The view types are stored as the equivalent offset based types
+        let expected_schema = Schema::new(vec![
+            Field::new("s", DataType::Utf8, true),
+            Field::new("b", DataType::Binary, true),
+        ]);
+        assert_eq!(table.schema().await.unwrap().as_ref(), &expected_schema);
+
+        table
+            .add(RecordBatchIterator::new(
+                vec![Ok(make_batch(10))],
+                schema.clone(),
+            ))
+            .execute()
+            .await
+            .unwrap();
+
+        let batches = table
+            .query()
+            .only_if("s LIKE '%longer%'")
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        let expected = (0..20).filter(|i| i % 3 != 0).collect::<Vec<_>>();
+        assert_eq!(batch.num_rows(), expected.len());
+        let strings = batch["s"].as_string::<i32>();
+        let binaries = batch["b"].as_binary::<i32>();
+        for (row, i) in expected.into_iter().enumerate() {
+            assert_eq!(
+                strings.value(row),
+                format!("a string longer than twelve bytes {}", i)
+            );
+            assert_eq!(binaries.value(row), vec![i as u8; i % 5].as_slice());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_stream() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let batches = make_test_batches();
+        let schema = batches.schema().clone();
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+
+        let stream_schema = schema.clone();
+        let stream = futures::stream::iter(0..100).map(move |chunk| {
+            Ok(RecordBatch::try_new(
+                stream_schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(
+                    chunk * 100..(chunk + 1) * 100,
+                ))],
+            )
+            .unwrap())
+        });
+        let stream = Box::pin(SimpleRecordBatchStream::new(stream, schema.clone()));
+        assert_eq!(table.add_stream(stream).await.unwrap(), 10_000);
+        assert_eq!(table.count_rows(None).await.unwrap(), 10_010);
+
+        // The stream is validated against the table schema
+        let bad_schema = Arc::new(Schema::new(vec![Field::new("j", DataType::Utf8, false)]));
+        let stream = Box::pin(SimpleRecordBatchStream::new(
+            futures::stream::empty(),
+            bad_schema,
+        ));
+        assert!(table.add_stream(stream).await.is_err());
+        assert_eq!(table.count_rows(None).await.unwrap(), 10_010);
+    }
+
+    #[tokio::test]
+    async fn test_add_file_size_limits() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let table = conn
+            .create_table("wide", make_test_batches())
+            .execute()
+            .await
+            .unwrap();
+        // Rows of about 1KB
+        let wide_rows = || {
+            let batch = RecordBatch::try_from_iter(vec![
+                (
+                    "i",
+                    Arc::new(Int32Array::from_iter_values(0..100)) as Arc<dyn Array>,
+                ),
+                (
+                    "text",
+                    Arc::new(StringArray::from_iter_values(
+                        (0..100).map(|i| format!("{:01000}", i)),
+                    )),
+                ),
+            ])
+            .unwrap();
+            let schema = batch.schema();
+            RecordBatchIterator::new(vec![Ok(batch)], schema)
+        };
+        // The size of a file is checked after each group of rows
+        let write_options = WriteOptions {
+            lance_write_params: Some(WriteParams {
+                mode: WriteMode::Overwrite,
+                max_rows_per_group: 10,
+                ..Default::default()
+            }),
+        };
+        let fragment_rows = |table: &Table| {
+            let table = table.clone();
+            async move {
+                let dataset = table.as_native().unwrap().dataset.get().await.unwrap();
+                dataset
+                    .get_fragments()
+                    .iter()
+                    .map(|f| f.metadata().physical_rows.unwrap())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        // Each file is closed after the group of rows that makes it reach 25KB
+        table
+            .add(wide_rows())
+            .write_options(write_options.clone())
+            .max_bytes_per_file(25 * 1024)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(fragment_rows(&table).await, vec![30, 30, 30, 10]);
+
+        // The row limit is reached first
+        table
+            .add(wide_rows())
+            .write_options(write_options.clone())
+            .max_bytes_per_file(25 * 1024)
+            .max_rows_per_file(20)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(fragment_rows(&table).await, vec![20; 5]);
+
+        // The byte limit is reached first
+        table
+            .add(wide_rows())
+            .write_options(write_options)
+            .max_bytes_per_file(25 * 1024)
+            .max_rows_per_file(50)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(fragment_rows(&table).await, vec![30, 30, 30, 10]);
+        assert_eq!(table.count_rows(None).await.unwrap(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_add_overwrite() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let batches = make_test_batches();
+        let schema = batches.schema().clone();
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+
+        let batches = vec![RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(100..110))],
+        )
+        .unwrap()]
+        .into_iter()
+        .map(Ok);
+
+        let new_batches = RecordBatchIterator::new(batches.clone(), schema.clone());
+
+        // Can overwrite using AddDataOptions::mode
+        table
+            .add(new_batches)
+            .mode(AddDataMode::Overwrite)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        assert_eq!(table.name(), "test");
 
-    use arrow_array::{
-        Array, BooleanArray, Date32Array, FixedSizeListArray, Float32Array, Float64Array,
-        Int32Array, Int64Array, LargeStringArray, RecordBatch, RecordBatchIterator,
-        RecordBatchReader, StringArray, TimestampMillisecondArray, TimestampNanosecondArray,
-        UInt32Array,
-    };
-    use arrow_data::ArrayDataBuilder;
-    use arrow_schema::{DataType, Field, Schema, TimeUnit};
-    use futures::TryStreamExt;
-    use lance::dataset::{Dataset, WriteMode};
-    use lance::io::{ObjectStoreParams, WrappingObjectStore};
-    use rand::Rng;
-    use tempfile::tempdir;
+        // Can overwrite using underlying WriteParams (which
+        // take precedence over AddDataOptions::mode)
 
-    use crate::connect;
-    use crate::connection::ConnectBuilder;
-    use crate::index::scalar::BTreeIndexBuilder;
-    use crate::query::{ExecutableQuery, QueryBase};
+        let param: WriteParams = WriteParams {
+            mode: WriteMode::Overwrite,
+            ..Default::default()
+        };
 
-    use super::*;
+        let new_batches = RecordBatchIterator::new(batches.clone(), schema.clone());
+        table
+            .add(new_batches)
+            .write_options(WriteOptions {
+                lance_write_params: Some(param),
+            })
+            .mode(AddDataMode::Append)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        assert_eq!(table.name(), "test");
+    }
 
     #[tokio::test]
-    async fn test_open() {
+    async fn test_add_mode_versions() {
         let tmp_dir = tempdir().unwrap();
-        let dataset_path = tmp_dir.path().join("test.lance");
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
 
         let batches = make_test_batches();
-        Dataset::write(batches, dataset_path.to_str().unwrap(), None)
-            .await
-            .unwrap();
+        let schema = batches.schema().clone();
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+        assert_eq!(table.version().await.unwrap(), 1);
+
+        let new_batches = || {
+            RecordBatchIterator::new(
+                vec![Ok(RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from_iter_values(100..105))],
+                )
+                .unwrap())],
+                schema.clone(),
+            )
+        };
 
-        let table = NativeTable::open(dataset_path.to_str().unwrap())
+        // Append is the default
+        table.add(new_batches()).execute().await.unwrap();
+        assert_eq!(table.version().await.unwrap(), 2);
+        assert_eq!(table.count_rows(None).await.unwrap(), 15);
+
+        table
+            .add(new_batches())
+            .mode(AddDataMode::Overwrite)
+            .execute()
             .await
             .unwrap();
+        assert_eq!(table.version().await.unwrap(), 3);
+        assert_eq!(table.count_rows(None).await.unwrap(), 5);
+        assert_eq!(table.count_rows(Some("i < 100".into())).await.unwrap(), 0);
 
-        assert_eq!(table.name, "test")
+        // The data from before the overwrite is still available
+        table.checkout(2).await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 15);
+        table.checkout_latest().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 5);
     }
 
     #[tokio::test]
-    async fn test_open_not_found() {
-        let tmp_dir = tempdir().unwrap();
-        let uri = tmp_dir.path().to_str().unwrap();
-        let table = NativeTable::open(uri).await;
-        assert!(matches!(table.unwrap_err(), Error::TableNotFound { .. }));
-    }
+    async fn test_on_bad_vector() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let vector_type =
+            DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), 2);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("vector", vector_type, true),
+        ]));
+        let table = conn
+            .create_empty_table("test", schema.clone())
+            .execute()
+            .await
+            .unwrap();
 
-    #[test]
-    #[cfg(not(windows))]
-    fn test_object_store_path() {
-        use std::path::Path as StdPath;
-        let p = StdPath::new("s3://bucket/path/to/file");
-        let c = p.join("subfile");
-        assert_eq!(c.to_str().unwrap(), "s3://bucket/path/to/file/subfile");
-    }
+        // The embedding model returned a NaN, a vector with three values and
+        // an infinite value
+        let new_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new(
+                "vector",
+                DataType::List(Arc::new(Field::new("item", DataType::Float32, true))),
+                true,
+            ),
+        ]));
+        let new_data = || {
+            let vectors = ListArray::from_iter_primitive::<Float32Type, _, _>(vec![
+                Some(vec![Some(1.0), Some(2.0)]),
+                Some(vec![Some(f32::NAN), Some(1.0)]),
+                Some(vec![Some(1.0), Some(2.0), Some(3.0)]),
+                Some(vec![Some(f32::INFINITY), Some(0.0)]),
+                None,
+            ]);
+            let batch = RecordBatch::try_new(
+                new_schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from_iter_values(0..5)),
+                    Arc::new(vectors),
+                ],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], new_schema.clone())
+        };
+        let vectors = |table: Table| async move {
+            let batches = table
+                .query()
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            let mut rows = Vec::new();
+            for batch in batches {
+                let ids = batch["id"].as_primitive::<Int32Type>();
+                let vectors = batch["vector"].as_fixed_size_list();
+                for (id, vector) in ids.values().iter().zip(vectors.iter()) {
+                    let vector = vector.map(|v| v.as_primitive::<Float32Type>().values().to_vec());
+                    rows.push((*id, vector));
+                }
+            }
+            rows.sort_by_key(|(id, _)| *id);
+            rows
+        };
 
-    #[tokio::test]
-    async fn test_count_rows() {
-        let tmp_dir = tempdir().unwrap();
-        let uri = tmp_dir.path().to_str().unwrap();
+        let err = table
+            .add(new_data())
+            .on_bad_vector(BadVectorMode::Error)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("the vector in row 1 of column vector has NaN, infinite or null values"),
+            "{}",
+            err
+        );
+        assert_eq!(table.count_rows(None).await.unwrap(), 0);
 
-        let batches = make_test_batches();
-        let table = NativeTable::create(uri, "test", batches, None, None, None)
+        table
+            .add(new_data())
+            .on_bad_vector(BadVectorMode::Drop)
+            .execute()
             .await
             .unwrap();
+        assert_eq!(
+            vectors(table.clone()).await,
+            vec![(0, Some(vec![1.0, 2.0])), (4, None)]
+        );
 
-        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        let table = conn
+            .create_empty_table("fill", schema)
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(new_data())
+            .on_bad_vector(BadVectorMode::Fill(0.0))
+            .execute()
+            .await
+            .unwrap();
         assert_eq!(
-            table.count_rows(Some("i >= 5".to_string())).await.unwrap(),
-            5
+            vectors(table).await,
+            vec![
+                (0, Some(vec![1.0, 2.0])),
+                (1, Some(vec![0.0, 1.0])),
+                (2, Some(vec![0.0, 0.0])),
+                (3, Some(vec![0.0, 0.0])),
+                (4, None),
+            ]
         );
     }
 
     #[tokio::test]
-    async fn test_add() {
-        let tmp_dir = tempdir().unwrap();
-        let uri = tmp_dir.path().to_str().unwrap();
-        let conn = connect(uri).execute().await.unwrap();
+    async fn test_add_missing_columns() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let table = conn
+            .create_table("test", make_test_batches())
+            .execute()
+            .await
+            .unwrap();
 
-        let batches = make_test_batches();
-        let schema = batches.schema().clone();
-        let table = conn.create_table("test", batches).execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, false),
+            Field::new("score", DataType::Float64, false),
+            Field::new("label", DataType::Utf8, false),
+        ]));
+        let new_batches = || {
+            RecordBatchIterator::new(
+                vec![Ok(RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int32Array::from_iter_values(100..105)),
+                        Arc::new(Float64Array::from(vec![1.5; 5])),
+                        Arc::new(StringArray::from(vec!["new"; 5])),
+                    ],
+                )
+                .unwrap())],
+                schema.clone(),
+            )
+        };
+
+        // Without the option the extra columns are an error
+        assert!(table.add(new_batches()).execute().await.is_err());
         assert_eq!(table.count_rows(None).await.unwrap(), 10);
 
-        let new_batches = RecordBatchIterator::new(
-            vec![RecordBatch::try_new(
+        let err = table
+            .add(new_batches())
+            .add_missing_columns(true)
+            .fill_value("i", 0)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+
+        table
+            .add(new_batches())
+            .add_missing_columns(true)
+            .fill_value("score", 0)
+            .execute()
+            .await
+            .unwrap();
+        let table_schema = table.schema().await.unwrap();
+        assert_eq!(
+            table_schema.field_with_name("score").unwrap(),
+            &Field::new("score", DataType::Float64, false)
+        );
+        // There is no fill value so the existing rows are null
+        assert_eq!(
+            table_schema.field_with_name("label").unwrap(),
+            &Field::new("label", DataType::Utf8, true)
+        );
+
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(batch.num_rows(), 15);
+        for row in 0..batch.num_rows() {
+            let id = batch["i"].as_primitive::<Int32Type>().value(row);
+            let score = batch["score"].as_primitive::<Float64Type>().value(row);
+            let label = batch["label"].as_string::<i32>();
+            if id < 100 {
+                assert_eq!(score, 0.0);
+                assert!(label.is_null(row));
+            } else {
+                assert_eq!(score, 1.5);
+                assert_eq!(label.value(row), "new");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_missing_columns_nulls() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, false),
+            Field::new("score", DataType::Float64, false),
+        ]));
+        let new_batches = || {
+            RecordBatchIterator::new(
+                vec![Ok(RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int32Array::from_iter_values(100..105)),
+                        Arc::new(Float64Array::from(vec![1.5; 5])),
+                    ],
+                )
+                .unwrap())],
                 schema.clone(),
-                vec![Arc::new(Int32Array::from_iter_values(100..110))],
             )
-            .unwrap()]
-            .into_iter()
-            .map(Ok),
+        };
+
+        // Legacy files would read the nulls back as zeros
+        let legacy = conn
+            .create_table("legacy", make_test_batches())
+            .execute()
+            .await
+            .unwrap();
+        let err = legacy
+            .add(new_batches())
+            .add_missing_columns(true)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        assert!(legacy
+            .schema()
+            .await
+            .unwrap()
+            .field_with_name("score")
+            .is_err());
+
+        let table = conn
+            .create_table("v2", make_test_batches())
+            .use_legacy_format(false)
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(new_batches())
+            .add_missing_columns(true)
+            .execute()
+            .await
+            .unwrap();
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(batch.num_rows(), 15);
+        for row in 0..batch.num_rows() {
+            let id = batch["i"].as_primitive::<Int32Type>().value(row);
+            let score = batch["score"].as_primitive::<Float64Type>();
+            if id < 100 {
+                assert!(score.is_null(row));
+            } else {
+                assert_eq!(score.value(row), 1.5);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
             schema.clone(),
-        );
+            vec![Arc::new(Int32Array::from_iter_values(0..5))],
+        )
+        .unwrap();
+        let table = conn
+            .create_table("test", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
 
-        table.add(new_batches).execute().await.unwrap();
-        assert_eq!(table.count_rows(None).await.unwrap(), 20);
-        assert_eq!(table.name(), "test");
+        let features = |keys: Vec<i32>, column: Arc<dyn Array>| {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("key", DataType::Int32, false),
+                Field::new("feature", column.data_type().clone(), false),
+            ]));
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from(keys)), column],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema)
+        };
+
+        let labels = Arc::new(StringArray::from(vec!["one", "three", "one again"]));
+        let err = table
+            .merge(features(vec![1, 3, 1], labels), "id", "key")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        assert_eq!(table.version().await.unwrap(), 1);
+
+        // Key 10 has no row in the table and is ignored
+        let labels = Arc::new(StringArray::from(vec!["three", "one", "four", "ten"]));
+        table
+            .merge(features(vec![3, 1, 4, 10], labels), "id", "key")
+            .await
+            .unwrap();
+        assert_eq!(table.version().await.unwrap(), 2);
+        table
+            .alter_columns(&[ColumnAlteration::new("feature".into()).rename("label".into())])
+            .await
+            .unwrap();
+
+        let scores = Arc::new(Float64Array::from(vec![0.0, 0.1, 0.2, 0.3, 0.4]));
+        table
+            .merge(features(vec![0, 1, 2, 3, 4], scores), "id", "key")
+            .await
+            .unwrap();
+
+        let schema = table.schema().await.unwrap();
+        let names = schema.fields().iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["id", "label", "feature"]);
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        let labels = batch["label"].as_string::<i32>();
+        assert_eq!(
+            labels.iter().collect::<Vec<_>>(),
+            vec![None, Some("one"), None, Some("three"), Some("four")]
+        );
+        let scores = batch["feature"].as_primitive::<Float64Type>();
+        assert_eq!(scores.values().as_ref(), &[0.0, 0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(table.count_rows(None).await.unwrap(), 5);
     }
 
     #[tokio::test]
-    async fn test_merge_insert() {
+    async fn test_stats() {
         let tmp_dir = tempdir().unwrap();
         let uri = tmp_dir.path().to_str().unwrap();
         let conn = connect(uri).execute().await.unwrap();
 
-        // Create a dataset with i=0..10
-        let batches = merge_insert_test_batches(0, 0);
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batches = |range: std::ops::Range<i32>| {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(range))],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
         let table = conn
-            .create_table("my_table", batches)
+            .create_table("test", batches(0..100))
+            .execute()
+            .await
+            .unwrap();
+        table.add(batches(100..150)).execute().await.unwrap();
+
+        let data_dir = tmp_dir.path().join("test.lance").join("data");
+        let data_bytes = std::fs::read_dir(data_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().metadata().unwrap().len())
+            .sum::<u64>();
+        let stats = table.stats().await.unwrap();
+        assert_eq!(
+            stats,
+            TableStats {
+                num_rows: 150,
+                num_fragments: 2,
+                fragment_ids: vec![0, 1],
+                num_deletion_files: 0,
+                data_bytes,
+                index_bytes: 0,
+            }
+        );
+
+        table.delete("id < 10").await.unwrap();
+        table
+            .create_index(&["id"], Index::BTree(Default::default()))
             .execute()
             .await
             .unwrap();
-        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        let stats = table.stats().await.unwrap();
+        assert_eq!(stats.num_rows, 140);
+        assert_eq!(stats.num_fragments, 2);
+        assert_eq!(stats.num_deletion_files, 1);
+        // Deleted rows stay in the data files until they are compacted
+        assert_eq!(stats.data_bytes, data_bytes);
+        assert!(stats.index_bytes > 0);
+    }
 
-        // Create new data with i=5..15
-        let new_batches = Box::new(merge_insert_test_batches(5, 1));
+    #[tokio::test]
+    async fn test_aggregate() {
+        use crate::table::aggregate::{Count, Max, Sum};
 
-        // Perform a "insert if not exists"
-        let mut merge_insert_builder = table.merge_insert(&["i"]);
-        merge_insert_builder.when_not_matched_insert_all();
-        merge_insert_builder.execute(new_batches).await.unwrap();
-        // Only 5 rows should actually be inserted
-        assert_eq!(table.count_rows(None).await.unwrap(), 15);
+        let conn = connect("memory://").execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("category", DataType::Utf8, false),
+            Field::new("amount", DataType::Int32, false),
+        ]));
+        let categories = ["a", "b", "c"];
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    (0..100).map(|i| categories[i % 3]),
+                )),
+                Arc::new(Int32Array::from_iter_values(0..100)),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table(
+                "test",
+                RecordBatchIterator::new(vec![Ok(batch)], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
 
-        // Create new data with i=15..25 (no id matches)
-        let new_batches = Box::new(merge_insert_test_batches(15, 2));
-        // Perform a "bulk update" (should not affect anything)
-        let mut merge_insert_builder = table.merge_insert(&["i"]);
-        merge_insert_builder.when_matched_update_all(None);
-        merge_insert_builder.execute(new_batches).await.unwrap();
-        // No new rows should have been inserted
-        assert_eq!(table.count_rows(None).await.unwrap(), 15);
+        let results = table
+            .aggregate()
+            .group_by(&["category"])
+            .agg([Count::all(), Sum::col("amount").alias("total")])
+            .execute()
+            .await
+            .unwrap();
+        let names = results
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["category", "count(*)", "total"]);
+        let mut groups = (0..results.num_rows())
+            .map(|row| {
+                (
+                    results["category"]
+                        .as_string::<i32>()
+                        .value(row)
+                        .to_string(),
+                    results["count(*)"].as_primitive::<Int64Type>().value(row),
+                    results["total"].as_primitive::<Int64Type>().value(row),
+                )
+            })
+            .collect::<Vec<_>>();
+        groups.sort();
         assert_eq!(
-            table.count_rows(Some("age = 2".to_string())).await.unwrap(),
-            0
+            groups,
+            vec![
+                ("a".to_string(), 34, 1683),
+                ("b".to_string(), 33, 1617),
+                ("c".to_string(), 33, 1650),
+            ]
         );
 
-        // Conditional update that only replaces the age=0 data
-        let new_batches = Box::new(merge_insert_test_batches(5, 3));
-        let mut merge_insert_builder = table.merge_insert(&["i"]);
-        merge_insert_builder.when_matched_update_all(Some("target.age = 0".to_string()));
-        merge_insert_builder.execute(new_batches).await.unwrap();
+        // Without a group by the whole table is one group
+        let results = table
+            .aggregate()
+            .only_if("amount >= 50")
+            .agg([Count::all(), Max::col("amount")])
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(results.num_rows(), 1);
+        assert_eq!(results["count(*)"].as_primitive::<Int64Type>().value(0), 50);
         assert_eq!(
-            table.count_rows(Some("age = 3".to_string())).await.unwrap(),
-            5
+            results["max(amount)"].as_primitive::<Int32Type>().value(0),
+            99
         );
+
+        let err = table
+            .aggregate()
+            .agg([Sum::col("category")])
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
     }
 
     #[tokio::test]
-    async fn test_add_overwrite() {
+    async fn test_delete_by_keys() {
         let tmp_dir = tempdir().unwrap();
         let uri = tmp_dir.path().to_str().unwrap();
         let conn = connect(uri).execute().await.unwrap();
 
-        let batches = make_test_batches();
-        let schema = batches.schema().clone();
-        let table = conn.create_table("test", batches).execute().await.unwrap();
-        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        // The column names need quoting in SQL
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("Row Id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let make_batch = |ids: std::ops::Range<i64>| {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from_iter_values(ids.clone())),
+                    Arc::new(StringArray::from_iter_values(
+                        ids.map(|i| format!("it's {}", i)),
+                    )),
+                ],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let table = conn
+            .create_table("test", make_batch(0..20_000))
+            .execute()
+            .await
+            .unwrap();
+        let version = table.version().await.unwrap();
 
-        let batches = vec![RecordBatch::try_new(
-            schema.clone(),
-            vec![Arc::new(Int32Array::from_iter_values(100..110))],
+        // Delete every even id, in a single new version
+        let key_schema = Arc::new(Schema::new(vec![Field::new(
+            "Row Id",
+            DataType::Int64,
+            false,
+        )]));
+        let keys = RecordBatch::try_new(
+            key_schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(
+                (0..20_000).step_by(2),
+            ))],
         )
-        .unwrap()]
-        .into_iter()
-        .map(Ok);
-
-        let new_batches = RecordBatchIterator::new(batches.clone(), schema.clone());
+        .unwrap();
+        let result = table
+            .delete_by_keys(
+                "Row Id",
+                RecordBatchIterator::new(vec![Ok(keys)], key_schema),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.rows_deleted, 10_000);
+        assert_eq!(result.version, version + 1);
+        assert_eq!(table.version().await.unwrap(), version + 1);
+        assert_eq!(table.count_rows(None).await.unwrap(), 10_000);
 
-        // Can overwrite using AddDataOptions::mode
-        table
-            .add(new_batches)
-            .mode(AddDataMode::Overwrite)
+        let mut remaining = table
+            .query()
+            .select(Select::columns(&["Row Id"]))
             .execute()
             .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .iter()
+            .flat_map(|batch| {
+                batch["Row Id"]
+                    .as_primitive::<arrow_array::types::Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        remaining.sort();
+        assert_eq!(remaining, (1..20_000).step_by(2).collect::<Vec<_>>());
+
+        // String keys with quotes, null keys are ignored
+        let key_schema = Arc::new(Schema::new(vec![Field::new("name", DataType::Utf8, true)]));
+        let keys = RecordBatch::try_new(
+            key_schema.clone(),
+            vec![Arc::new(StringArray::from(vec![
+                Some("it's 1"),
+                None,
+                Some("it's 3"),
+                Some("it's 2"),
+            ]))],
+        )
+        .unwrap();
+        let result = table
+            .delete_by_keys("name", RecordBatchIterator::new(vec![Ok(keys)], key_schema))
+            .await
             .unwrap();
-        assert_eq!(table.count_rows(None).await.unwrap(), 10);
-        assert_eq!(table.name(), "test");
-
-        // Can overwrite using underlying WriteParams (which
-        // take precedence over AddDataOptions::mode)
-
-        let param: WriteParams = WriteParams {
-            mode: WriteMode::Overwrite,
-            ..Default::default()
-        };
+        assert_eq!(result.rows_deleted, 2);
+        assert_eq!(table.count_rows(None).await.unwrap(), 9_998);
 
-        let new_batches = RecordBatchIterator::new(batches.clone(), schema.clone());
+        // A fragment whose rows are all deleted is removed, integer keys of another
+        // type are cast to the type of the column
         table
-            .add(new_batches)
-            .write_options(WriteOptions {
-                lance_write_params: Some(param),
-            })
-            .mode(AddDataMode::Append)
+            .add(make_batch(20_000..20_010))
             .execute()
             .await
             .unwrap();
-        assert_eq!(table.count_rows(None).await.unwrap(), 10);
-        assert_eq!(table.name(), "test");
+        let native = table.as_native().unwrap();
+        assert_eq!(native.count_fragments().await.unwrap(), 2);
+        let key_schema = Arc::new(Schema::new(vec![Field::new(
+            "Row Id",
+            DataType::Int32,
+            false,
+        )]));
+        let keys = RecordBatch::try_new(
+            key_schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(20_000..20_010))],
+        )
+        .unwrap();
+        let result = table
+            .delete_by_keys(
+                "Row Id",
+                RecordBatchIterator::new(vec![Ok(keys)], key_schema),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.rows_deleted, 10);
+        assert_eq!(native.count_fragments().await.unwrap(), 1);
+        assert_eq!(table.count_rows(None).await.unwrap(), 9_998);
+
+        // Keys of another kind of type are rejected
+        let version = table.version().await.unwrap();
+        let key_schema = Arc::new(Schema::new(vec![Field::new(
+            "Row Id",
+            DataType::Float64,
+            false,
+        )]));
+        let keys = RecordBatch::try_new(
+            key_schema.clone(),
+            vec![Arc::new(Float64Array::from(vec![5.0]))],
+        )
+        .unwrap();
+        let res = table
+            .delete_by_keys(
+                "Row Id",
+                RecordBatchIterator::new(vec![Ok(keys)], key_schema),
+            )
+            .await;
+        assert!(matches!(res, Err(Error::InvalidInput { .. })));
+        assert_eq!(table.count_rows(None).await.unwrap(), 9_998);
+        assert_eq!(table.version().await.unwrap(), version);
     }
 
     #[tokio::test]
@@ -2401,14 +6571,270 @@ mod tests {
             .execute()
             .await
             .unwrap();
-        let tbl = conn
-            .create_table("my_table", make_test_batches())
+        let tbl = conn
+            .create_table("my_table", make_test_batches())
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(1, tbl.count_rows(Some("i == 0".to_string())).await.unwrap());
+        tbl.update().column("i", "i+1").execute().await.unwrap();
+        assert_eq!(0, tbl.count_rows(Some("i == 0".to_string())).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_update_result() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let tbl = conn
+            .create_table("test", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+        tbl.add(merge_insert_test_batches(10, 0))
+            .execute()
+            .await
+            .unwrap();
+
+        // The matching rows are spread over both fragments
+        let result = tbl
+            .update()
+            .only_if("i >= 5 AND i < 13")
+            .column("age", "age + 1")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            UpdateResult {
+                rows_updated: 8,
+                version: 3
+            }
+        );
+        assert_eq!(tbl.version().await.unwrap(), 3);
+        assert_eq!(
+            tbl.count_rows(Some("age = 1".to_string())).await.unwrap(),
+            8
+        );
+
+        let result = tbl
+            .update()
+            .only_if("i > 100")
+            .column("age", "age + 1")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(result.rows_updated, 0);
+
+        let result = tbl.update().column("age", "0").execute().await.unwrap();
+        assert_eq!(result.rows_updated, 20);
+        assert_eq!(result.version, tbl.version().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_result() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let tbl = conn
+            .create_table("test", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+        tbl.add(merge_insert_test_batches(10, 1))
+            .execute()
+            .await
+            .unwrap();
+
+        // The deleted rows are spread over both fragments
+        let predicate = "i % 3 = 0";
+        let matching = tbl.count_rows(Some(predicate.to_string())).await.unwrap();
+        let result = tbl.delete(predicate).await.unwrap();
+        assert_eq!(
+            result,
+            DeleteResult {
+                rows_deleted: matching as u64,
+                version: 3
+            }
+        );
+        assert_eq!(result.rows_deleted, 7);
+        assert_eq!(tbl.count_rows(None).await.unwrap(), 13);
+
+        // Rows that are already deleted are not counted again
+        assert_eq!(tbl.delete(predicate).await.unwrap().rows_deleted, 0);
+
+        // Deleting every remaining row of a fragment removes the fragment
+        let result = tbl.delete("age = 1").await.unwrap();
+        assert_eq!(result.rows_deleted, 7);
+        assert_eq!(result.version, tbl.version().await.unwrap());
+        assert_eq!(tbl.count_rows(None).await.unwrap(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_update_via_expr_other_columns() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("score", DataType::Float64, true),
+            Field::new("bonus", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..4)),
+                Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0, 4.0])),
+                Arc::new(Int32Array::from(vec![10, 20, 30, 40])),
+            ],
+        )
+        .unwrap();
+        let tbl = conn
+            .create_table(
+                "my_table",
+                RecordBatchIterator::new(vec![Ok(batch)], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let scores = || async {
+            let batches = tbl
+                .query()
+                .select(Select::columns(&["id", "score"]))
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            let mut rows = batches
+                .iter()
+                .flat_map(|b| {
+                    let ids = b["id"].as_primitive::<Int32Type>().clone();
+                    let scores = b["score"].as_primitive::<Float64Type>().clone();
+                    (0..b.num_rows())
+                        .map(move |i| (ids.value(i), scores.is_valid(i).then(|| scores.value(i))))
+                })
+                .collect::<Vec<_>>();
+            rows.sort_by_key(|(id, _)| *id);
+            rows.into_iter().map(|(_, score)| score).collect::<Vec<_>>()
+        };
+
+        tbl.update()
+            .column("score", "score * 2 + bonus")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            scores().await,
+            vec![Some(12.0), Some(24.0), Some(36.0), Some(48.0)]
+        );
+
+        // CASE is not supported, conditional updates are made with a filter instead
+        assert!(tbl
+            .update()
+            .column("score", "CASE WHEN score > 40 THEN 40 ELSE score END")
+            .execute()
+            .await
+            .is_err());
+        tbl.update()
+            .column("score", "40")
+            .only_if("score > 40")
+            .execute()
+            .await
+            .unwrap();
+        tbl.update()
+            .column("score", "score - bonus")
+            .only_if("score < 20 OR bonus = 20")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            scores().await,
+            vec![Some(2.0), Some(4.0), Some(36.0), Some(40.0)]
+        );
+
+        // A floating point result would be truncated when stored in an integer column
+        let err = tbl
+            .update()
+            .column("bonus", "bonus * score")
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        tbl.update()
+            .column("bonus", "CAST(bonus * score AS INT)")
+            .only_if("id = 0")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            tbl.count_rows(Some("bonus = 20".to_string()))
+                .await
+                .unwrap(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_lossy_values() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("big", DataType::Int64, false),
+            Field::new("score", DataType::Float32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..4)),
+                Arc::new(Int64Array::from_iter_values(0..4)),
+                Arc::new(Float32Array::from(vec![1.0; 4])),
+            ],
+        )
+        .unwrap();
+        let tbl = conn
+            .create_table(
+                "my_table",
+                RecordBatchIterator::new(vec![Ok(batch)], schema),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        for (column, value) in [
+            // A float that is not a whole number would be truncated
+            ("id", "1.5"),
+            // An Int64 that does not fit in an Int32 would wrap
+            ("id", "3000000000"),
+            ("id", "big"),
+            ("score", "1e300"),
+        ] {
+            let err = tbl
+                .update()
+                .column(column, value)
+                .execute()
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        }
+        assert_eq!(tbl.version().await.unwrap(), 1);
+
+        tbl.update()
+            .column("id", "2.0")
+            .column("score", "0.1")
             .execute()
             .await
             .unwrap();
-        assert_eq!(1, tbl.count_rows(Some("i == 0".to_string())).await.unwrap());
-        tbl.update().column("i", "i+1").execute().await.unwrap();
-        assert_eq!(0, tbl.count_rows(Some("i == 0".to_string())).await.unwrap());
+        // Widening is allowed
+        tbl.update().column("big", "id").execute().await.unwrap();
+        assert_eq!(
+            tbl.count_rows(Some("id = 2 AND big = 2".to_string()))
+                .await
+                .unwrap(),
+            4
+        );
     }
 
     #[derive(Default, Debug)]
@@ -2609,6 +7035,327 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_list_indices() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let dimension = 16;
+        let vector_type = DataType::FixedSizeList(
+            Arc::new(Field::new("item", DataType::Float32, true)),
+            dimension,
+        );
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("embeddings", vector_type, false),
+        ]));
+        let make_batches = |num_rows: usize| {
+            let mut rng = rand::thread_rng();
+            let float_arr = Float32Array::from(
+                iter::repeat_with(|| rng.gen::<f32>())
+                    .take(num_rows * dimension as usize)
+                    .collect::<Vec<f32>>(),
+            );
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from_iter_values(0..num_rows as i32)),
+                    Arc::new(create_fixed_size_list(float_arr, dimension).unwrap()),
+                ],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let table = conn
+            .create_table("test", make_batches(512))
+            .execute()
+            .await
+            .unwrap();
+
+        table
+            .create_index(
+                &["embeddings"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["id"], Index::BTree(BTreeIndexBuilder::default()))
+            .execute()
+            .await
+            .unwrap();
+        let indexed_version = table.version().await.unwrap();
+
+        // New rows are not indexed until the indices are optimized
+        table.add(make_batches(10)).execute().await.unwrap();
+
+        let mut indices = table.list_indices().await.unwrap();
+        indices.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(indices.len(), 2);
+
+        assert_eq!(indices[0].name, "embeddings_idx");
+        assert_eq!(indices[0].index_type, crate::index::IndexType::IvfPq);
+        assert_eq!(indices[0].columns, vec!["embeddings".to_string()]);
+
+        assert_eq!(indices[1].name, "id_idx");
+        assert_eq!(indices[1].index_type, crate::index::IndexType::BTree);
+        assert_eq!(indices[1].columns, vec!["id".to_string()]);
+
+        for index in &indices {
+            assert_eq!(index.num_indexed_rows, 512);
+            assert!(index.dataset_version <= indexed_version);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_stats() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let make_batches = |ids: std::ops::Range<i32>| {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(ids))],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let table = conn
+            .create_table("test", make_batches(0..1000))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["id"], Index::BTree(BTreeIndexBuilder::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        assert!(table.index_stats("missing_idx").await.unwrap().is_none());
+
+        table.add(make_batches(1000..1100)).execute().await.unwrap();
+        let stats = table.index_stats("id_idx").await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 1000);
+        assert_eq!(stats.num_unindexed_rows, 100);
+
+        table
+            .optimize(OptimizeAction::Index(OptimizeOptions::default()))
+            .await
+            .unwrap();
+        let stats = table.index_stats("id_idx").await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 1100);
+        assert_eq!(stats.num_unindexed_rows, 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_index_ivf_pq_params() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let dimension = 16;
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "embeddings",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                dimension,
+            ),
+            false,
+        )]));
+        let mut rng = rand::thread_rng();
+        let float_arr = Float32Array::from(
+            iter::repeat_with(|| rng.gen::<f32>())
+                .take(512 * dimension as usize)
+                .collect::<Vec<f32>>(),
+        );
+        let vectors = Arc::new(create_fixed_size_list(float_arr, dimension).unwrap());
+        let batches = RecordBatchIterator::new(
+            vec![Ok(
+                RecordBatch::try_new(schema.clone(), vec![vectors]).unwrap()
+            )],
+            schema,
+        );
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+
+        // 16 is not divisible by 3
+        let err = table
+            .create_index(
+                &["embeddings"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_sub_vectors(3)),
+            )
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidInput { message } if message.contains("divisible")),
+            "unexpected error: {}",
+            err
+        );
+
+        let err = table
+            .create_index(
+                &["embeddings"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_bits(9)),
+            )
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }));
+        assert!(table.list_indices().await.unwrap().is_empty());
+
+        table
+            .create_index(
+                &["embeddings"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .num_partitions(4)
+                        .num_sub_vectors(4)
+                        .num_bits(8)
+                        .distance_type(DistanceType::Cosine),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let stats = table
+            .as_native()
+            .unwrap()
+            .index_stats("embeddings_idx")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.indices[0].num_partitions, Some(4));
+        assert_eq!(stats.indices[0].metric_type.as_deref(), Some("cosine"));
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_rebuilds_indices() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let dimension = 16;
+        let vector_type = DataType::FixedSizeList(
+            Arc::new(Field::new("item", DataType::Float32, true)),
+            dimension,
+        );
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("embeddings", vector_type.clone(), false),
+            Field::new("embeddings_sq", vector_type, false),
+        ]));
+        let make_batches = |offset: i32| {
+            let mut rng = rand::thread_rng();
+            let mut vectors = || {
+                let float_arr = Float32Array::from(
+                    iter::repeat_with(|| rng.gen::<f32>())
+                        .take(512 * dimension as usize)
+                        .collect::<Vec<f32>>(),
+                );
+                Arc::new(create_fixed_size_list(float_arr, dimension).unwrap())
+            };
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from_iter_values(offset..offset + 512)),
+                    vectors(),
+                    vectors(),
+                ],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let table = conn
+            .create_table("test", make_batches(0))
+            .execute()
+            .await
+            .unwrap();
+
+        table
+            .create_index(&["id"], Index::BTree(BTreeIndexBuilder::default()))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(
+                &["embeddings"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .num_partitions(4)
+                        .num_sub_vectors(4)
+                        .distance_type(DistanceType::Cosine),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(
+                &["embeddings_sq"],
+                Index::IvfHnswSq(
+                    IvfHnswSqIndexBuilder::default()
+                        .num_partitions(2)
+                        .distance_type(DistanceType::Dot),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        table
+            .add(make_batches(1000))
+            .mode(AddDataMode::Overwrite)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            table
+                .count_rows(Some("id < 1000".to_string()))
+                .await
+                .unwrap(),
+            0
+        );
+
+        let mut indices = table.list_indices().await.unwrap();
+        indices.sort_by(|a, b| a.name.cmp(&b.name));
+        let names = indices
+            .iter()
+            .map(|idx| idx.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["embeddings_idx", "embeddings_sq_idx", "id_idx"]);
+        for index in &indices {
+            assert_eq!(index.num_indexed_rows, 512);
+        }
+
+        let native = table.as_native().unwrap();
+        let stats = native.index_stats("embeddings_idx").await.unwrap().unwrap();
+        let metadata = &stats.indices[0];
+        assert_eq!(metadata.metric_type.as_deref(), Some("cosine"));
+        assert_eq!(metadata.num_partitions, Some(4));
+        assert_eq!(
+            metadata.sub_index.as_ref().unwrap().num_sub_vectors,
+            Some(4)
+        );
+
+        let definitions = native.index_definitions().await.unwrap();
+        let (_, _, index) = definitions
+            .iter()
+            .find(|(name, _, _)| name == "embeddings_sq_idx")
+            .unwrap();
+        assert!(matches!(
+            index,
+            Index::IvfHnswSq(IvfHnswSqIndexBuilder {
+                distance_type: DistanceType::Dot,
+                num_partitions: Some(2),
+                ..
+            })
+        ));
+    }
+
     #[tokio::test]
     async fn test_create_index_ivf_hnsw_sq() {
         use arrow_array::RecordBatch;
@@ -2815,16 +7562,140 @@ mod tests {
         Ok(FixedSizeListArray::from(data))
     }
 
-    fn some_sample_data() -> Box<dyn RecordBatchReader + Send> {
+    fn some_sample_data() -> Box<dyn RecordBatchReader + Send> {
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)])),
+            vec![Arc::new(Int32Array::from(vec![1]))],
+        )
+        .unwrap();
+        let schema = batch.schema().clone();
+        let batch = Ok(batch);
+
+        Box::new(RecordBatchIterator::new(vec![batch], schema))
+    }
+
+    #[tokio::test]
+    async fn test_scalar_index_low_cardinality() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("category", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..1000)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..1000).map(|i| format!("category_{}", i % 20)),
+                )),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table("test", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["category"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        for (filter, expected) in [
+            ("category = 'category_3'", vec![3]),
+            ("category IN ('category_3', 'category_17')", vec![3, 17]),
+        ] {
+            let plan = table
+                .query()
+                .only_if(filter)
+                .explain_plan(false)
+                .await
+                .unwrap();
+            assert!(plan.contains("MaterializeIndex"), "{}", plan);
+
+            let mut ids = table
+                .query()
+                .only_if(filter)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>();
+            ids.sort();
+            let expected_ids = (0..1000)
+                .filter(|i| expected.contains(&(i % 20)))
+                .collect::<Vec<_>>();
+            assert_eq!(ids, expected_ids);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_filters() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let mut tags =
+            arrow_array::builder::ListBuilder::new(arrow_array::builder::StringBuilder::new());
+        for row in [
+            vec!["rust", "python"],
+            vec!["python"],
+            vec!["java", "rust"],
+            vec![],
+        ] {
+            tags.append_value(row.into_iter().map(Some));
+        }
+        let tags = tags.finish();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("tags", tags.data_type().clone(), true),
+        ]));
         let batch = RecordBatch::try_new(
-            Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)])),
-            vec![Arc::new(Int32Array::from(vec![1]))],
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(0..4)), Arc::new(tags)],
         )
         .unwrap();
-        let schema = batch.schema().clone();
-        let batch = Ok(batch);
+        let table = conn
+            .create_table("test", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
 
-        Box::new(RecordBatchIterator::new(vec![batch], schema))
+        for (filter, expected) in [
+            ("array_has(tags, 'rust')", vec![0, 2]),
+            ("array_has_any(tags, ['java', 'python'])", vec![0, 1, 2]),
+            ("array_has_all(tags, ['python', 'rust'])", vec![0]),
+        ] {
+            let ids = table
+                .query()
+                .only_if(filter)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>();
+            assert_eq!(ids, expected, "{}", filter);
+        }
+
+        // Scalar indices cannot be created on list columns
+        let err = table
+            .create_index(&["tags"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Schema { .. }), "{}", err);
     }
 
     #[tokio::test]
@@ -2895,6 +7766,285 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_schema_after_add_columns() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", make_test_batches())
+            .execute()
+            .await
+            .unwrap();
+        let version = table.version().await.unwrap();
+
+        // A second handle that checks for updates on every read
+        let conn2 = ConnectBuilder::new(uri)
+            .read_consistency_interval(Duration::from_secs(0))
+            .execute()
+            .await
+            .unwrap();
+        let table2 = conn2.open_table("my_table").execute().await.unwrap();
+
+        table
+            .add_columns(
+                NewColumnTransform::SqlExpressions(vec![("j".to_string(), "i * 2".to_string())]),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let schema = table.schema().await.unwrap();
+        assert_eq!(
+            schema.field_with_name("j").unwrap().data_type(),
+            &DataType::Int32
+        );
+        assert!(table2.schema().await.unwrap().field_with_name("j").is_ok());
+
+        let old_schema = table.schema_at_version(version).await.unwrap();
+        assert!(old_schema.field_with_name("j").is_err());
+        // Looking at an old schema does not change the checked out version
+        assert!(table.schema().await.unwrap().field_with_name("j").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_drop_columns() {
+        let conn = connect("memory://").execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("score", DataType::Float64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..10)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..10).map(|i| format!("name {}", i)),
+                )),
+                Arc::new(Float64Array::from_iter_values((0..10).map(|i| i as f64))),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table("test", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["id"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+        let version = table.version().await.unwrap();
+
+        table.drop_columns(&["name"]).await.unwrap();
+        assert_eq!(table.version().await.unwrap(), version + 1);
+        let schema = table.schema().await.unwrap();
+        assert_eq!(
+            schema.fields().iter().map(|f| f.name()).collect::<Vec<_>>(),
+            vec!["id", "score"]
+        );
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&schema, &batches).unwrap();
+        assert_eq!(batch.num_rows(), 10);
+        assert_eq!(
+            batch["id"].as_primitive::<Int32Type>().values().as_ref(),
+            (0..10).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            batch["score"]
+                .as_primitive::<Float64Type>()
+                .values()
+                .as_ref(),
+            (0..10).map(|i| i as f64).collect::<Vec<_>>()
+        );
+
+        // The index on id must be dropped before the column
+        let err = table.drop_columns(&["score", "id"]).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        assert_eq!(table.version().await.unwrap(), version + 1);
+        assert_eq!(table.list_indices().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_add_columns_from_sql() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("price", DataType::Int64, false),
+            Field::new("quantity", DataType::Int32, false),
+            Field::new("first", DataType::Utf8, true),
+            Field::new("last", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![10, 20, 30])),
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec![Some("Ada"), Some("Alan"), None])),
+                Arc::new(StringArray::from(vec![
+                    Some("Lovelace"),
+                    None,
+                    Some("Hopper"),
+                ])),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table(
+                "my_table",
+                RecordBatchIterator::new(vec![Ok(batch)], schema),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        table
+            .add_columns(
+                NewColumnTransform::SqlExpressions(vec![
+                    ("total".to_string(), "price * quantity".to_string()),
+                    ("full_name".to_string(), "first || ' ' || last".to_string()),
+                    (
+                        "display_name".to_string(),
+                        "coalesce(first, last)".to_string(),
+                    ),
+                    (
+                        "spaced_name".to_string(),
+                        "concat_ws(' ', first, last)".to_string(),
+                    ),
+                ]),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(table
+            .add_columns(
+                NewColumnTransform::SqlExpressions(vec![(
+                    "case_name".to_string(),
+                    "CASE WHEN first IS NULL THEN last ELSE first END".to_string(),
+                )]),
+                None,
+            )
+            .await
+            .is_err());
+
+        let schema = table.schema().await.unwrap();
+        assert_eq!(
+            schema.field_with_name("total").unwrap().data_type(),
+            &DataType::Int64
+        );
+        assert_eq!(
+            schema.field_with_name("full_name").unwrap().data_type(),
+            &DataType::Utf8
+        );
+
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(
+            batch["total"].as_primitive::<arrow_array::types::Int64Type>(),
+            &Int64Array::from(vec![10, 40, 90])
+        );
+        assert_eq!(
+            batch["full_name"].as_string::<i32>(),
+            &StringArray::from(vec!["Ada Lovelace", "Alan ", " Hopper"])
+        );
+        assert_eq!(
+            batch["display_name"].as_string::<i32>(),
+            &StringArray::from(vec!["Ada", "Alan", "Hopper"])
+        );
+        assert_eq!(
+            batch["spaced_name"].as_string::<i32>(),
+            &StringArray::from(vec!["Ada Lovelace", "Alan", "Hopper"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alter_columns() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", make_test_batches())
+            .execute()
+            .await
+            .unwrap();
+        let data_files = |table: &Table| {
+            let table = table.clone();
+            async move {
+                let dataset = table.as_native().unwrap().dataset.get().await.unwrap();
+                dataset
+                    .get_fragments()
+                    .iter()
+                    .flat_map(|f| f.metadata().files.iter().map(|file| file.path.clone()))
+                    .collect::<Vec<_>>()
+            }
+        };
+        let files = data_files(&table).await;
+
+        // A rename only changes the metadata
+        table
+            .alter_columns(&[ColumnAlteration::new("i".to_string())
+                .rename("id".to_string())
+                .set_nullable(true)])
+            .await
+            .unwrap();
+        assert_eq!(table.version().await.unwrap(), 2);
+        assert_eq!(data_files(&table).await, files);
+        let schema = table.schema().await.unwrap();
+        assert!(schema.field_with_name("i").is_err());
+        assert!(schema.field_with_name("id").unwrap().is_nullable());
+        assert_eq!(
+            table.count_rows(Some("id >= 5".to_string())).await.unwrap(),
+            5
+        );
+
+        // A widening cast rewrites the column and keeps the values
+        table
+            .alter_columns(&[ColumnAlteration::new("id".to_string()).cast_to(DataType::Int64)])
+            .await
+            .unwrap();
+        assert_eq!(table.version().await.unwrap(), 3);
+        assert_ne!(data_files(&table).await, files);
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches[0].schema().field(0).data_type(), &DataType::Int64);
+        assert_eq!(
+            batches[0]["id"].as_primitive::<arrow_array::types::Int64Type>(),
+            &Int64Array::from_iter_values(0..10)
+        );
+
+        // Casts to an unrelated type are rejected
+        let res = table
+            .alter_columns(&[ColumnAlteration::new("id".to_string()).cast_to(DataType::Utf8)])
+            .await;
+        assert!(res.is_err());
+        assert_eq!(table.version().await.unwrap(), 3);
+    }
+
     #[tokio::test]
     async fn test_read_consistency_interval() {
         let intervals = vec![
@@ -2948,6 +8098,160 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_checkout_version() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+
+        let conn = ConnectBuilder::new(uri)
+            .read_consistency_interval(Duration::from_secs(0))
+            .execute()
+            .await
+            .unwrap();
+        let table = conn
+            .create_table("my_table", some_sample_data())
+            .execute()
+            .await
+            .unwrap();
+        table.add(some_sample_data()).execute().await.unwrap();
+        table.add(some_sample_data()).execute().await.unwrap();
+        assert_eq!(table.version().await.unwrap(), 3);
+
+        let snapshot = table.checkout_version(1).await.unwrap();
+        assert_eq!(snapshot.version().await.unwrap(), 1);
+        assert_eq!(snapshot.count_rows(None).await.unwrap(), 1);
+
+        // The original handle is unaffected and still sees new data
+        assert_eq!(table.version().await.unwrap(), 3);
+        assert_eq!(table.count_rows(None).await.unwrap(), 3);
+        table.add(some_sample_data()).execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 4);
+        assert_eq!(snapshot.count_rows(None).await.unwrap(), 1);
+
+        let err = snapshot
+            .add(some_sample_data())
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+
+        snapshot.checkout_latest().await.unwrap();
+        assert_eq!(snapshot.version().await.unwrap(), 4);
+        assert_eq!(snapshot.count_rows(None).await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_list_versions() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", some_sample_data())
+            .execute()
+            .await
+            .unwrap();
+        table.add(some_sample_data()).execute().await.unwrap();
+        table.add(some_sample_data()).execute().await.unwrap();
+
+        let versions = table.list_versions().await.unwrap();
+        assert_eq!(
+            versions.iter().map(|v| v.version).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        for pair in versions.windows(2) {
+            assert!(pair[0].timestamp <= pair[1].timestamp);
+        }
+        assert_eq!(
+            versions.last().unwrap().version,
+            table.version().await.unwrap()
+        );
+
+        // A snapshot still lists every version
+        let snapshot = table.checkout_version(1).await.unwrap();
+        assert_eq!(snapshot.list_versions().await.unwrap(), versions);
+    }
+
+    #[tokio::test]
+    async fn test_checkout_at() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", some_sample_data())
+            .execute()
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            // Make sure each version has a distinct timestamp
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            table.add(some_sample_data()).execute().await.unwrap();
+        }
+        let versions = table.list_versions().await.unwrap();
+        let timestamps = versions.iter().map(|v| v.timestamp).collect::<Vec<_>>();
+
+        // A version is visible from the moment it was committed
+        assert_eq!(table.checkout_at(timestamps[1]).await.unwrap(), 2);
+        assert_eq!(table.count_rows(None).await.unwrap(), 2);
+        let between = timestamps[1] + (timestamps[2] - timestamps[1]) / 2;
+        assert_eq!(table.checkout_at(between).await.unwrap(), 2);
+        assert_eq!(table.checkout_at(timestamps[0]).await.unwrap(), 1);
+        assert_eq!(table.count_rows(None).await.unwrap(), 1);
+        assert_eq!(table.checkout_at(Utc::now()).await.unwrap(), 3);
+        assert_eq!(table.count_rows(None).await.unwrap(), 3);
+
+        let before = timestamps[0] - chrono::Duration::seconds(1);
+        assert!(matches!(
+            table.checkout_at(before).await,
+            Err(Error::InvalidInput { .. })
+        ));
+        assert_eq!(table.version().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_restore_version() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", some_sample_data())
+            .execute()
+            .await
+            .unwrap();
+        table.add(some_sample_data()).execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 2);
+
+        assert!(table.restore_version(10).await.is_err());
+        assert_eq!(table.version().await.unwrap(), 2);
+
+        table.restore_version(1).await.unwrap();
+        assert_eq!(table.version().await.unwrap(), 3);
+        assert_eq!(table.count_rows(None).await.unwrap(), 1);
+        let versions = table.list_versions().await.unwrap();
+        assert_eq!(
+            versions.iter().map(|v| v.version).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        // The bad version can still be inspected and the table is writable again
+        let bad = table.checkout_version(2).await.unwrap();
+        assert_eq!(bad.count_rows(None).await.unwrap(), 2);
+        table.add(some_sample_data()).execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 2);
+
+        // A read-only table is not checked out by a failed restore
+        let read_only = connect(uri).read_only(true).execute().await.unwrap();
+        let table = read_only.open_table("my_table").execute().await.unwrap();
+        assert!(matches!(
+            table.restore_version(1).await,
+            Err(Error::ReadOnly { .. })
+        ));
+        assert_eq!(table.version().await.unwrap(), 4);
+        assert_eq!(table.count_rows(None).await.unwrap(), 2);
+    }
+
     #[tokio::test]
     async fn test_time_travel_write() {
         let tmp_dir = tempdir().unwrap();
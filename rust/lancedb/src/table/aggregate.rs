@@ -0,0 +1,294 @@
+// Copyright 2024 LanceDB Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow::compute::concat_batches;
+use arrow_array::RecordBatch;
+use datafusion_common::ScalarValue;
+use datafusion_expr::type_coercion::aggregates::coerce_types;
+use datafusion_expr::AggregateFunction;
+use datafusion_physical_expr::aggregate::build_in::create_aggregate_expr;
+use datafusion_physical_plan::aggregates::{AggregateExec, AggregateMode, PhysicalGroupBy};
+use datafusion_physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use datafusion_physical_plan::expressions::{cast, col, Literal};
+use datafusion_physical_plan::{
+    AggregateExpr, ExecutionPlan, ExecutionPlanProperties, PhysicalExpr,
+};
+use futures::TryStreamExt;
+use lance::dataset::Dataset;
+use lance_datafusion::exec::execute_plan;
+
+use crate::error::{Error, Result};
+use crate::utils::rewrite_filter;
+
+use super::TableInternal;
+
+/// An aggregate function computed for each group of an [`AggregateBuilder`]
+///
+/// Aggregates are created with [`Count`], [`Sum`], [`Avg`], [`Min`], and [`Max`].
+/// The output column is named after the function and its input, for example
+/// `sum(amount)`, unless an [alias](Self::alias) is given.
+#[derive(Debug, Clone)]
+pub struct Aggregate {
+    function: AggregateFunction,
+    /// The input column, or None to count rows
+    column: Option<String>,
+    alias: Option<String>,
+}
+
+impl Aggregate {
+    fn new(function: AggregateFunction, column: Option<String>) -> Self {
+        Self {
+            function,
+            column,
+            alias: None,
+        }
+    }
+
+    /// Set the name of the output column
+    pub fn alias(mut self, alias: impl Into<String>) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    fn output_name(&self) -> String {
+        if let Some(alias) = &self.alias {
+            return alias.clone();
+        }
+        let function = self.function.to_string().to_lowercase();
+        match &self.column {
+            Some(column) => format!("{}({})", function, column),
+            None => format!("{}(*)", function),
+        }
+    }
+}
+
+/// Counts the rows of each group
+pub struct Count;
+
+impl Count {
+    /// Count every row of the group, named `count(*)`
+    pub fn all() -> Aggregate {
+        Aggregate::new(AggregateFunction::Count, None)
+    }
+
+    /// Count the rows of the group where `column` is not null
+    pub fn col(column: impl Into<String>) -> Aggregate {
+        Aggregate::new(AggregateFunction::Count, Some(column.into()))
+    }
+}
+
+/// Sums a numeric column
+///
+/// Integers are summed as 64 bit integers and floats as 64 bit floats.  Null
+/// values are ignored and the sum of a group with only nulls is null.
+pub struct Sum;
+
+impl Sum {
+    /// Sum the non-null values of `column` in the group, named `sum(column)`
+    pub fn col(column: impl Into<String>) -> Aggregate {
+        Aggregate::new(AggregateFunction::Sum, Some(column.into()))
+    }
+}
+
+/// Averages a numeric column
+///
+/// Null values are ignored and the average of a group with only nulls is null.
+pub struct Avg;
+
+impl Avg {
+    /// Average the non-null values of `column` in the group, named `avg(column)`
+    pub fn col(column: impl Into<String>) -> Aggregate {
+        Aggregate::new(AggregateFunction::Avg, Some(column.into()))
+    }
+}
+
+/// The smallest value of a column
+///
+/// Null values are ignored and the minimum of a group with only nulls is null.
+pub struct Min;
+
+impl Min {
+    /// The smallest non-null value of `column` in the group, named `min(column)`
+    pub fn col(column: impl Into<String>) -> Aggregate {
+        Aggregate::new(AggregateFunction::Min, Some(column.into()))
+    }
+}
+
+/// The largest value of a column
+///
+/// Null values are ignored and the maximum of a group with only nulls is null.
+pub struct Max;
+
+impl Max {
+    /// The largest non-null value of `column` in the group, named `max(column)`
+    pub fn col(column: impl Into<String>) -> Aggregate {
+        Aggregate::new(AggregateFunction::Max, Some(column.into()))
+    }
+}
+
+/// A builder used to run an aggregation query
+///
+/// See [`super::Table::aggregate`] for more context
+#[derive(Debug, Clone)]
+pub struct AggregateBuilder {
+    table: Arc<dyn TableInternal>,
+    pub(super) group_by: Vec<String>,
+    pub(super) aggregates: Vec<Aggregate>,
+    pub(super) filter: Option<String>,
+}
+
+impl AggregateBuilder {
+    pub(super) fn new(table: Arc<dyn TableInternal>) -> Self {
+        Self {
+            table,
+            group_by: Vec::new(),
+            aggregates: Vec::new(),
+            filter: None,
+        }
+    }
+
+    /// The columns to group the rows by
+    ///
+    /// Rows with the same values in all of these columns form a group and
+    /// the result has one row per group.  A null value is a group of its own.
+    /// If no columns are given then the whole table is a single group.
+    pub fn group_by(mut self, columns: &[&str]) -> Self {
+        self.group_by = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    /// The aggregates to compute for each group
+    ///
+    /// This can be called multiple times, each call adds to the list.
+    pub fn agg(mut self, aggregates: impl IntoIterator<Item = Aggregate>) -> Self {
+        self.aggregates.extend(aggregates);
+        self
+    }
+
+    /// Only aggregate the rows that satisfy the SQL filter
+    pub fn only_if(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// Run the aggregation
+    ///
+    /// The result has the group by columns followed by one column for each
+    /// aggregate.  The groups are returned in no particular order.
+    pub async fn execute(self) -> Result<RecordBatch> {
+        if self.group_by.is_empty() && self.aggregates.is_empty() {
+            return Err(Error::InvalidInput {
+                message: "an aggregation needs at least one group by column or aggregate"
+                    .to_string(),
+            });
+        }
+        self.table.clone().aggregate(self).await
+    }
+}
+
+/// Lower an aggregation to a DataFusion aggregate over a scan of the dataset
+pub(super) async fn execute_aggregate(
+    dataset: &Dataset,
+    params: &AggregateBuilder,
+) -> Result<RecordBatch> {
+    // Only read the columns that are used
+    let mut columns = params.group_by.clone();
+    for column in params.aggregates.iter().filter_map(|a| a.column.as_ref()) {
+        if !columns.contains(column) {
+            columns.push(column.clone());
+        }
+    }
+    let mut scanner = dataset.scan();
+    if let Some(filter) = &params.filter {
+        let schema = arrow_schema::Schema::from(dataset.schema());
+        scanner.filter(&rewrite_filter(filter, &schema))?;
+    }
+    if columns.is_empty() {
+        // Lance can't scan zero columns so scan the row ids to count the rows
+        scanner.project::<&str>(&[])?;
+        scanner.with_row_id();
+    } else {
+        scanner.project(&columns)?;
+    }
+    let mut input = scanner.create_plan().await?;
+    if input.output_partitioning().partition_count() > 1 {
+        input = Arc::new(CoalescePartitionsExec::new(input));
+    }
+    let schema = input.schema();
+
+    let group_by = params
+        .group_by
+        .iter()
+        .map(|name| Ok((col(name, &schema)?, name.clone())))
+        .collect::<Result<Vec<_>>>()?;
+
+    let aggregates = params
+        .aggregates
+        .iter()
+        .map(|aggregate| {
+            let input: Arc<dyn PhysicalExpr> = match &aggregate.column {
+                Some(column) => col(column, &schema)?,
+                None => Arc::new(Literal::new(ScalarValue::Int64(Some(1)))),
+            };
+            // Cast the input to a type the function supports, e.g. SUM only
+            // supports 64 bit types
+            let input_type = input.data_type(&schema)?;
+            let coerced = coerce_types(
+                &aggregate.function,
+                &[input_type.clone()],
+                &aggregate.function.signature(),
+            )
+            .map_err(|e| Error::InvalidInput {
+                message: format!(
+                    "cannot compute {} of column {} with type {}: {}",
+                    aggregate.function,
+                    aggregate.column.as_deref().unwrap_or("*"),
+                    input_type,
+                    e
+                ),
+            })?;
+            let input = if coerced[0] != input_type {
+                cast(input, &schema, coerced[0].clone())?
+            } else {
+                input
+            };
+            Ok(create_aggregate_expr(
+                &aggregate.function,
+                false,
+                &[input],
+                &[],
+                &schema,
+                aggregate.output_name(),
+                false,
+            )?)
+        })
+        .collect::<Result<Vec<Arc<dyn AggregateExpr>>>>()?;
+
+    let filters = vec![None; aggregates.len()];
+    let plan = Arc::new(AggregateExec::try_new(
+        AggregateMode::Single,
+        PhysicalGroupBy::new_single(group_by),
+        aggregates,
+        filters,
+        input,
+        schema,
+    )?);
+    let output_schema = plan.schema();
+    let batches = execute_plan(plan, Default::default())?
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(concat_batches(&output_schema, &batches)?)
+}
@@ -0,0 +1,169 @@
+// Copyright 2024 LanceDB Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Flat search over binary vectors
+//!
+//! Lance can only search floating point vectors.  Binary vectors (fixed size lists
+//! of u8) are searched here instead by computing the hamming distance to every
+//! row that matches the filter and keeping the closest rows.
+
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use arrow::array::AsArray;
+use arrow::datatypes::UInt8Type;
+use arrow_array::{Array, Float32Array, RecordBatch};
+use arrow_schema::{DataType, Schema, SortOptions};
+use datafusion_common::DataFusionError;
+use datafusion_physical_plan::expressions::{Column, PhysicalSortExpr};
+use datafusion_physical_plan::limit::GlobalLimitExec;
+use datafusion_physical_plan::projection::ProjectionExec;
+use datafusion_physical_plan::sorts::sort::SortExec;
+use datafusion_physical_plan::{ColumnarValue, ExecutionPlan, PhysicalExpr};
+use lance_linalg::distance::hamming::hamming;
+
+use crate::error::Result;
+
+/// Calculates the hamming distance between a binary vector column and a query vector
+#[derive(Debug)]
+struct HammingDistanceExpr {
+    column: Arc<dyn PhysicalExpr>,
+    query: Vec<u8>,
+}
+
+impl Display for HammingDistanceExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "hamming_distance({})", self.column)
+    }
+}
+
+impl PartialEq<dyn Any> for HammingDistanceExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        other
+            .downcast_ref::<Self>()
+            .map(|other| self.column.eq(&other.column) && self.query == other.query)
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for HammingDistanceExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> datafusion_common::Result<DataType> {
+        Ok(DataType::Float32)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> datafusion_common::Result<bool> {
+        self.column.nullable(input_schema)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> datafusion_common::Result<ColumnarValue> {
+        let vectors = self.column.evaluate(batch)?.into_array(batch.num_rows())?;
+        let vectors = vectors.as_fixed_size_list_opt().ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "hamming distance expects a fixed size list but got {}",
+                vectors.data_type()
+            ))
+        })?;
+        let distances = (0..vectors.len())
+            .map(|i| {
+                if vectors.is_null(i) {
+                    None
+                } else {
+                    let vector = vectors.value(i);
+                    Some(hamming(
+                        vector.as_primitive::<UInt8Type>().values(),
+                        &self.query,
+                    ))
+                }
+            })
+            .collect::<Float32Array>();
+        Ok(ColumnarValue::Array(Arc::new(distances)))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.column.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> datafusion_common::Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self {
+            column: children[0].clone(),
+            query: self.query.clone(),
+        }))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.column.dyn_hash(&mut s);
+        self.query.hash(&mut s);
+    }
+}
+
+/// Adds a `_distance` column to the output of `input` and keeps the `limit` closest
+/// rows after skipping the first `offset` rows.
+///
+/// If `keep_column` is false then the vector column is removed from the output.
+pub(super) fn hamming_search_plan(
+    input: Arc<dyn ExecutionPlan>,
+    column: &str,
+    query: &[u8],
+    limit: usize,
+    offset: usize,
+    keep_column: bool,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let schema = input.schema();
+    let vector_idx = schema.index_of(column)?;
+    let mut exprs = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| keep_column || *idx != vector_idx)
+        .map(|(idx, field)| {
+            (
+                Arc::new(Column::new(field.name(), idx)) as Arc<dyn PhysicalExpr>,
+                field.name().clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let distance_idx = exprs.len();
+    exprs.push((
+        Arc::new(HammingDistanceExpr {
+            column: Arc::new(Column::new(column, vector_idx)),
+            query: query.to_vec(),
+        }),
+        "_distance".to_string(),
+    ));
+    let projection = Arc::new(ProjectionExec::try_new(exprs, input)?);
+
+    let sort_expr = PhysicalSortExpr {
+        expr: Arc::new(Column::new("_distance", distance_idx)),
+        options: SortOptions {
+            descending: false,
+            nulls_first: false,
+        },
+    };
+    let sort = SortExec::new(vec![sort_expr], projection).with_fetch(Some(limit + offset));
+    Ok(Arc::new(GlobalLimitExec::new(
+        Arc::new(sort),
+        offset,
+        Some(limit),
+    )))
+}
@@ -14,12 +14,37 @@
 
 use std::sync::Arc;
 
-use arrow_array::RecordBatchReader;
+use arrow::compute::kernels::boolean::{and, not, or};
+use arrow::compute::{filter_record_batch, is_not_null};
+use arrow_array::{cast::AsArray, BooleanArray, RecordBatch, RecordBatchReader, StructArray};
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef};
+use datafusion_common::JoinType;
+use datafusion_expr::Expr;
+use datafusion_physical_expr::{expressions::Column, PhysicalExpr, PhysicalExprRef};
+use datafusion_physical_plan::joins::{HashJoinExec, PartitionMode};
+use datafusion_physical_plan::ExecutionPlan;
+use futures::TryStreamExt;
+use lance::dataset::{Dataset, WhenMatched, WhenNotMatchedBySource};
+use lance::datatypes::{Schema as LanceSchema, SchemaCompareOptions};
+use lance::io::exec::Planner;
+use lance_datafusion::exec::{execute_plan, OneShotExec};
+use lance_datafusion::utils::reader_to_stream;
 
-use crate::Result;
+use crate::error::{Error, Result};
 
 use super::TableInternal;
 
+/// Statistics about a merge insert operation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeInsertStats {
+    /// The number of new rows inserted into the table
+    pub inserted: u64,
+    /// The number of existing rows that were updated
+    pub updated: u64,
+    /// The number of existing rows that were deleted
+    pub deleted: u64,
+}
+
 /// A builder used to create and run a merge insert operation
 ///
 /// See [`super::Table::merge_insert`] for more context
@@ -32,6 +57,7 @@ pub struct MergeInsertBuilder {
     pub(super) when_not_matched_insert_all: bool,
     pub(super) when_not_matched_by_source_delete: bool,
     pub(super) when_not_matched_by_source_delete_filt: Option<String>,
+    pub(super) dry_run: bool,
 }
 
 impl MergeInsertBuilder {
@@ -44,6 +70,7 @@ impl MergeInsertBuilder {
             when_not_matched_insert_all: false,
             when_not_matched_by_source_delete: false,
             when_not_matched_by_source_delete_filt: None,
+            dry_run: false,
         }
     }
 
@@ -73,6 +100,17 @@ impl MergeInsertBuilder {
         self
     }
 
+    /// Rows that exist in both the source table (new data) and the target
+    /// table (old data) will be updated if they satisfy `condition`
+    ///
+    /// This is the same as calling [`Self::when_matched_update_all`] with
+    /// `Some(condition)`.  For example, to only replace rows with newer data:
+    ///
+    /// `when_matched_update_all_if("target.updated_at < source.updated_at")`
+    pub fn when_matched_update_all_if(&mut self, condition: impl Into<String>) -> &mut Self {
+        self.when_matched_update_all(Some(condition.into()))
+    }
+
     /// Rows that exist only in the source table (new data) should
     /// be inserted into the target table.
     pub fn when_not_matched_insert_all(&mut self) -> &mut Self {
@@ -95,10 +133,243 @@ impl MergeInsertBuilder {
         self
     }
 
+    /// Rows that exist only in the target table (old data) will be deleted if
+    /// they satisfy `condition`
+    ///
+    /// This is the same as calling [`Self::when_not_matched_by_source_delete`] with
+    /// `Some(condition)`.  The condition only refers to the target table, as there
+    /// is no source row, and so the columns are not prefixed.  This scopes a full
+    /// sync to part of the table.  For example, to replace the rows of one tenant
+    /// and leave the other tenants untouched:
+    ///
+    /// `when_not_matched_by_source_delete_if("tenant_id = 42")`
+    ///
+    /// The new data should only contain rows that satisfy the condition, otherwise
+    /// the rows for other tenants are inserted or updated as usual.
+    pub fn when_not_matched_by_source_delete_if(
+        &mut self,
+        condition: impl Into<String>,
+    ) -> &mut Self {
+        self.when_not_matched_by_source_delete(Some(condition.into()))
+    }
+
+    /// If true then the operation will calculate what would change without
+    /// modifying the table
+    ///
+    /// The operation is validated (e.g. the new data must have a schema that is
+    /// compatible with the table) in the same way as a real run, and so this can be
+    /// used to catch mistakes (such as the wrong `on` columns) before running a
+    /// large operation.  The returned [`MergeInsertStats`] describe the rows that
+    /// would have been inserted, updated, and deleted.
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = dry_run;
+        self
+    }
+
     /// Executes the merge insert operation
     ///
-    /// Nothing is returned but the [`super::Table`] is updated
-    pub async fn execute(self, new_data: Box<dyn RecordBatchReader + Send>) -> Result<()> {
+    /// Returns statistics about the rows that were inserted, updated, and deleted.
+    /// Unless this is a [dry run](Self::dry_run) the [`super::Table`] is updated.
+    pub async fn execute(
+        self,
+        new_data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<MergeInsertStats> {
         self.table.clone().merge_insert(self, new_data).await
     }
 }
+
+/// Calculate the statistics of a merge insert without running it
+///
+/// The new data is joined with the table in the same way as the real merge insert
+/// (a full outer hash join on the `on` columns) but, instead of writing anything,
+/// the rows on each side of the join are counted as the join streams.  As with a SQL
+/// join, rows with a null key never match.  Rows whose keys are all null are not
+/// counted at all, the real merge insert skips them (the new rows are not inserted
+/// and the rows of the table are not deleted).
+pub(super) async fn dry_run_stats(
+    dataset: &Dataset,
+    params: &MergeInsertBuilder,
+    when_matched: &WhenMatched,
+    when_not_matched_by_source: &WhenNotMatchedBySource,
+    new_data: Box<dyn RecordBatchReader + Send>,
+) -> Result<MergeInsertStats> {
+    // This is the same check the real merge insert performs
+    let source_schema = new_data.schema();
+    LanceSchema::try_from(source_schema.as_ref())?.check_compatible(
+        dataset.schema(),
+        &SchemaCompareOptions {
+            compare_dictionary: true,
+            ..Default::default()
+        },
+    )?;
+
+    // The keys are enough to classify the rows, the other columns are only needed
+    // to evaluate a condition
+    let has_condition = matches!(when_matched, WhenMatched::UpdateIf(_))
+        || matches!(
+            when_not_matched_by_source,
+            WhenNotMatchedBySource::DeleteIf(_)
+        );
+    let target_columns = if has_condition {
+        source_schema
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect::<Vec<_>>()
+    } else {
+        params.on.clone()
+    };
+    let mut scanner = dataset.scan();
+    scanner.project(&target_columns)?;
+    let target = scanner.create_plan().await?;
+    let target_schema = target.schema();
+    let source: Arc<dyn ExecutionPlan> = Arc::new(OneShotExec::new(reader_to_stream(new_data)));
+
+    let join_on = params
+        .on
+        .iter()
+        .map(|name| {
+            let source_key: PhysicalExprRef =
+                Arc::new(Column::new_with_schema(name, source_schema.as_ref())?);
+            let target_key: PhysicalExprRef =
+                Arc::new(Column::new_with_schema(name, target_schema.as_ref())?);
+            Ok((source_key, target_key))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // The new data is the build side, the table is streamed through the join
+    let joined = Arc::new(HashJoinExec::try_new(
+        source,
+        target,
+        join_on,
+        None,
+        &JoinType::Full,
+        None,
+        PartitionMode::CollectLeft,
+        false,
+    )?);
+
+    // The joined batches are the source columns followed by the target columns
+    let right_offset = source_schema.fields().len();
+    let joined_schema = joined.schema();
+    let source_fields = Fields::from(joined_schema.fields()[..right_offset].to_vec());
+    let target_fields = Fields::from(joined_schema.fields()[right_offset..].to_vec());
+    let source_keys = key_indices(&source_schema, &params.on, 0)?;
+    let target_keys = key_indices(&target_schema, &params.on, right_offset)?;
+
+    let update_filter = match when_matched {
+        WhenMatched::UpdateIf(expr) => Some(compile_condition(
+            Schema::new(vec![
+                Field::new("source", DataType::Struct(source_fields.clone()), false),
+                Field::new("target", DataType::Struct(target_fields.clone()), false),
+            ]),
+            expr,
+        )?),
+        _ => None,
+    };
+    let delete_filter = match when_not_matched_by_source {
+        WhenNotMatchedBySource::DeleteIf(expr) => {
+            Some(compile_condition(Schema::new(target_fields.clone()), expr)?)
+        }
+        _ => None,
+    };
+
+    let mut stats = MergeInsertStats::default();
+    let mut stream = execute_plan(joined, Default::default())?;
+    while let Some(batch) = stream.try_next().await? {
+        let in_source = not_all_null(&batch, &source_keys)?;
+        let in_target = not_all_null(&batch, &target_keys)?;
+
+        if params.when_matched_update_all {
+            let matched = and(&in_source, &in_target)?;
+            stats.updated += match &update_filter {
+                Some(filter) => {
+                    let matched = filter_record_batch(&batch, &matched)?;
+                    let combined = RecordBatch::try_new(
+                        filter.schema.clone(),
+                        vec![
+                            Arc::new(StructArray::try_new(
+                                source_fields.clone(),
+                                matched.columns()[..right_offset].to_vec(),
+                                None,
+                            )?),
+                            Arc::new(StructArray::try_new(
+                                target_fields.clone(),
+                                matched.columns()[right_offset..].to_vec(),
+                                None,
+                            )?),
+                        ],
+                    )?;
+                    filter.count_true(&combined)?
+                }
+                None => matched.true_count() as u64,
+            };
+        }
+        if params.when_not_matched_insert_all {
+            stats.inserted += and(&in_source, &not(&in_target)?)?.true_count() as u64;
+        }
+        if params.when_not_matched_by_source_delete {
+            let target_only = and(&not(&in_source)?, &in_target)?;
+            stats.deleted += match &delete_filter {
+                Some(filter) => {
+                    let unmatched = filter_record_batch(&batch, &target_only)?;
+                    let unmatched = RecordBatch::try_new(
+                        filter.schema.clone(),
+                        unmatched.columns()[right_offset..].to_vec(),
+                    )?;
+                    filter.count_true(&unmatched)?
+                }
+                None => target_only.true_count() as u64,
+            };
+        }
+    }
+    Ok(stats)
+}
+
+fn key_indices(schema: &Schema, on: &[String], offset: usize) -> Result<Vec<usize>> {
+    on.iter()
+        .map(|name| Ok(schema.index_of(name)? + offset))
+        .collect()
+}
+
+// A row of the outer join comes from one side if any of that side's keys are valid
+fn not_all_null(batch: &RecordBatch, keys: &[usize]) -> Result<BooleanArray> {
+    let mut valid = BooleanArray::from(vec![false; batch.num_rows()]);
+    for idx in keys {
+        valid = or(&valid, &is_not_null(batch.column(*idx))?)?;
+    }
+    Ok(valid)
+}
+
+/// A merge insert condition compiled against the batches it is evaluated on
+struct Condition {
+    schema: SchemaRef,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl Condition {
+    /// The number of rows for which the condition is true (null counts as false)
+    fn count_true(&self, batch: &RecordBatch) -> Result<u64> {
+        let mask = self.expr.evaluate(batch)?.into_array(batch.num_rows())?;
+        Ok(mask.as_boolean().true_count() as u64)
+    }
+}
+
+fn compile_condition(schema: Schema, expr: &Expr) -> Result<Condition> {
+    let schema = Arc::new(schema);
+    let planner = Planner::new(schema.clone());
+    let expr = planner.optimize_expr(expr.clone())?;
+    let physical_expr = planner.create_physical_expr(&expr)?;
+    let data_type = physical_expr.data_type(schema.as_ref())?;
+    if data_type != DataType::Boolean {
+        return Err(Error::InvalidInput {
+            message: format!(
+                "Merge insert conditions must be expressions that return a boolean value, received expression ({}) which has data type {}",
+                expr, data_type
+            ),
+        });
+    }
+    Ok(Condition {
+        schema,
+        expr: physical_expr,
+    })
+}
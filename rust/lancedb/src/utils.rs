@@ -12,9 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::borrow::Cow;
+use std::ops::ControlFlow;
 use std::sync::Arc;
 
-use arrow_schema::Schema;
+use arrow_schema::{DataType, Schema};
+use datafusion_sql::sqlparser::ast::{
+    visit_expressions_mut, BinaryOperator, DataType as SqlDataType, ExactNumberInfo, Expr, SetExpr,
+    Statement, UnaryOperator, Value,
+};
+use datafusion_sql::sqlparser::dialect::{Dialect, GenericDialect};
+use datafusion_sql::sqlparser::parser::Parser;
 use lance::dataset::{ReadParams, WriteParams};
 use lance::io::{ObjectStoreParams, WrappingObjectStore};
 use lazy_static::lazy_static;
@@ -102,9 +110,12 @@ pub fn validate_table_name(name: &str) -> Result<()> {
 }
 
 /// Find one default column to create index or perform vector query.
+///
+/// Only floating point vector columns with a dimension of `dim` (if given) are
+/// candidates.  Returns [`Error::AmbiguousVectorColumn`] if there is more than one.
 pub(crate) fn default_vector_column(schema: &Schema, dim: Option<i32>) -> Result<String> {
     // Try to find one fixed size list array column.
-    let candidates = schema
+    let mut candidates = schema
         .fields()
         .iter()
         .filter_map(|field| match field.data_type() {
@@ -112,7 +123,7 @@ pub(crate) fn default_vector_column(schema: &Schema, dim: Option<i32>) -> Result
                 if f.data_type().is_floating()
                     && dim.map(|expect| *d == expect).unwrap_or(true) =>
             {
-                Some(field.name())
+                Some(field.name().clone())
             }
             _ => None,
         })
@@ -125,15 +136,208 @@ pub(crate) fn default_vector_column(schema: &Schema, dim: Option<i32>) -> Result
             ),
         })
     } else if candidates.len() != 1 {
-        Err(Error::Schema {
-            message: format!(
-                "More than one vector columns found, \
-                    please specify which column to create index or query: {:?}",
-                candidates
-            ),
+        Err(Error::AmbiguousVectorColumn { candidates })
+    } else {
+        Ok(candidates.swap_remove(0))
+    }
+}
+
+/// The dialect Lance uses to parse filters, which quotes identifiers with backticks
+#[derive(Debug)]
+struct FilterDialect(GenericDialect);
+
+impl Dialect for FilterDialect {
+    fn is_identifier_start(&self, ch: char) -> bool {
+        self.0.is_identifier_start(ch)
+    }
+
+    fn is_identifier_part(&self, ch: char) -> bool {
+        self.0.is_identifier_part(ch)
+    }
+
+    fn is_delimited_identifier_start(&self, ch: char) -> bool {
+        ch == '`'
+    }
+}
+
+/// Rewrite the parts of a SQL filter that Lance cannot parse into equivalent expressions
+///
+/// `x BETWEEN a AND b` is expanded to `(x >= a AND x <= b)`, which also lets a scalar
+/// index on `x` be used to evaluate the filter.
+///
+/// Lance parses numbers as integer or float literals, which it cannot compare with a
+/// decimal column.  Numbers compared with, or in an `IN` list of, a decimal column (or
+/// struct field, e.g. `item.price`) of `schema` are rewritten to decimal literals with
+/// the precision and scale of the number as it is written (`amount > 100.50` becomes
+/// `amount > DECIMAL(5,2) '100.50'`), so the comparison is exact and a literal with
+/// more digits than the column is not rounded.
+///
+/// Filters that do not need to be rewritten, or that cannot be parsed, are returned
+/// unchanged.
+pub(crate) fn rewrite_filter<'a>(filter: &'a str, schema: &Schema) -> Cow<'a, str> {
+    let sql = format!("SELECT 1 FROM t WHERE {}", filter);
+    let Ok(mut statements) = Parser::parse_sql(&FilterDialect(GenericDialect {}), &sql) else {
+        return Cow::Borrowed(filter);
+    };
+    let mut rewritten = false;
+    let _ = visit_expressions_mut(&mut statements, |expr| {
+        if let Expr::Between {
+            expr: value,
+            negated,
+            low,
+            high,
+        } = expr
+        {
+            let (lower_op, upper_op, join_op) = if *negated {
+                (BinaryOperator::Lt, BinaryOperator::Gt, BinaryOperator::Or)
+            } else {
+                (
+                    BinaryOperator::GtEq,
+                    BinaryOperator::LtEq,
+                    BinaryOperator::And,
+                )
+            };
+            let lower = Expr::BinaryOp {
+                left: value.clone(),
+                op: lower_op,
+                right: low.clone(),
+            };
+            let upper = Expr::BinaryOp {
+                left: value.clone(),
+                op: upper_op,
+                right: high.clone(),
+            };
+            *expr = Expr::Nested(Box::new(Expr::BinaryOp {
+                left: Box::new(lower),
+                op: join_op,
+                right: Box::new(upper),
+            }));
+            rewritten = true;
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    // The comparisons created for BETWEEN are not visited by the pass above
+    let _ = visit_expressions_mut(&mut statements, |expr| {
+        match expr {
+            Expr::BinaryOp {
+                left,
+                op:
+                    BinaryOperator::Eq
+                    | BinaryOperator::NotEq
+                    | BinaryOperator::Lt
+                    | BinaryOperator::LtEq
+                    | BinaryOperator::Gt
+                    | BinaryOperator::GtEq,
+                right,
+            } => {
+                if let Some(data_type) = decimal_column(left, schema) {
+                    if let Some(literal) = decimal_literal(data_type, right) {
+                        **right = literal;
+                        rewritten = true;
+                    }
+                } else if let Some(data_type) = decimal_column(right, schema) {
+                    if let Some(literal) = decimal_literal(data_type, left) {
+                        **left = literal;
+                        rewritten = true;
+                    }
+                }
+            }
+            Expr::InList { expr, list, .. } => {
+                if let Some(data_type) = decimal_column(expr, schema) {
+                    for value in list.iter_mut() {
+                        if let Some(literal) = decimal_literal(data_type, value) {
+                            *value = literal;
+                            rewritten = true;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    if !rewritten {
+        return Cow::Borrowed(filter);
+    }
+    match statements.as_slice() {
+        [Statement::Query(query)] => match query.body.as_ref() {
+            SetExpr::Select(select) => match &select.selection {
+                Some(selection) => Cow::Owned(selection.to_string()),
+                None => Cow::Borrowed(filter),
+            },
+            _ => Cow::Borrowed(filter),
+        },
+        _ => Cow::Borrowed(filter),
+    }
+}
+
+/// Returns the type of `column` if it refers to a decimal column (or struct field) of
+/// `schema`
+fn decimal_column<'a>(column: &Expr, schema: &'a Schema) -> Option<&'a DataType> {
+    let idents = match column {
+        Expr::Identifier(ident) => std::slice::from_ref(ident),
+        Expr::CompoundIdentifier(idents) => idents.as_slice(),
+        _ => return None,
+    };
+    let (first, rest) = idents.split_first()?;
+    let mut data_type = schema.field_with_name(&first.value).ok()?.data_type();
+    for ident in rest {
+        let DataType::Struct(fields) = data_type else {
+            return None;
+        };
+        data_type = fields.find(&ident.value)?.1.data_type();
+    }
+    matches!(
+        data_type,
+        DataType::Decimal128(..) | DataType::Decimal256(..)
+    )
+    .then_some(data_type)
+}
+
+/// Returns `value` as a decimal literal if it is a number, for comparing with a column
+/// of type `data_type`
+fn decimal_literal(data_type: &DataType, value: &Expr) -> Option<Expr> {
+    let (sign, number) = match value {
+        Expr::Value(Value::Number(number, _)) => ("", number),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => match expr.as_ref() {
+            Expr::Value(Value::Number(number, _)) => ("-", number),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    // Numbers with an exponent are left to Lance
+    if !integer
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let scale = fraction.len() as u64;
+    let precision = (integer.len() as u64 + scale).max(1);
+    let value = format!("{}{}", sign, number);
+    if matches!(data_type, DataType::Decimal128(..)) && precision <= 38 {
+        Some(Expr::TypedString {
+            data_type: SqlDataType::Decimal(ExactNumberInfo::PrecisionAndScale(precision, scale)),
+            value,
         })
+    } else if precision <= 76 {
+        // DECIMAL literals are always Decimal128, which DataFusion cannot compare with a
+        // Decimal256 column
+        let literal = format!(
+            "arrow_cast('{}', 'Decimal256({}, {})')",
+            value, precision, scale
+        );
+        Parser::new(&FilterDialect(GenericDialect {}))
+            .try_with_sql(&literal)
+            .and_then(|mut parser| parser.parse_expr())
+            .ok()
     } else {
-        Ok(candidates[0].to_string())
+        None
     }
 }
 
@@ -143,6 +347,83 @@ mod tests {
 
     use arrow_schema::{DataType, Field};
 
+    #[test]
+    fn test_rewrite_filter() {
+        let schema = Schema::empty();
+        assert_eq!(
+            rewrite_filter("x BETWEEN 1 AND 10", &schema),
+            "(x >= 1 AND x <= 10)"
+        );
+        assert_eq!(
+            rewrite_filter("`a b` NOT BETWEEN 1 AND 10 OR y = 'BETWEEN'", &schema),
+            "(`a b` < 1 OR `a b` > 10) OR y = 'BETWEEN'"
+        );
+        assert_eq!(
+            rewrite_filter(
+                "ts BETWEEN timestamp '2024-01-01 00:00:00' AND now()",
+                &schema
+            ),
+            "(ts >= TIMESTAMP '2024-01-01 00:00:00' AND ts <= now())"
+        );
+        // Filters without BETWEEN are passed through as they are
+        assert!(matches!(
+            rewrite_filter("x  =  1", &schema),
+            Cow::Borrowed("x  =  1")
+        ));
+        assert!(matches!(
+            rewrite_filter("x =", &schema),
+            Cow::Borrowed("x =")
+        ));
+    }
+
+    #[test]
+    fn test_rewrite_decimal_filter() {
+        let schema = Schema::new(vec![
+            Field::new("amount", DataType::Decimal128(10, 2), false),
+            Field::new("big", DataType::Decimal256(40, 4), false),
+            Field::new("x", DataType::Float64, false),
+            Field::new(
+                "s",
+                DataType::Struct(
+                    vec![
+                        Field::new("price", DataType::Decimal128(6, 2), false),
+                        Field::new("name", DataType::Utf8, false),
+                    ]
+                    .into(),
+                ),
+                false,
+            ),
+        ]);
+        assert_eq!(
+            rewrite_filter("amount > 100.50", &schema),
+            "amount > DECIMAL(5,2) '100.50'"
+        );
+        assert_eq!(
+            rewrite_filter("-0.5 <= `big` AND amount <> 7 AND x < 1.5", &schema),
+            "arrow_cast('-0.5', 'Decimal256(2, 1)') <= `big` AND amount <> DECIMAL(1,0) '7' AND x < 1.5"
+        );
+        assert_eq!(
+            rewrite_filter("amount BETWEEN 1.5 AND 2", &schema),
+            "(amount >= DECIMAL(2,1) '1.5' AND amount <= DECIMAL(1,0) '2')"
+        );
+        assert!(matches!(
+            rewrite_filter("x = 1.5 AND amount = 1e3", &schema),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            rewrite_filter("amount NOT IN (1.5, -2, x) AND x IN (1.5)", &schema),
+            "amount NOT IN (DECIMAL(2,1) '1.5', DECIMAL(1,0) '-2', x) AND x IN (1.5)"
+        );
+        assert_eq!(
+            rewrite_filter("s.price = 9.99 OR s.price BETWEEN 1 AND 2.5", &schema),
+            "s.price = DECIMAL(3,2) '9.99' OR (s.price >= DECIMAL(1,0) '1' AND s.price <= DECIMAL(2,1) '2.5')"
+        );
+        assert!(matches!(
+            rewrite_filter("s.name = 1.5 AND amount.x = 1.5", &schema),
+            Cow::Borrowed(_)
+        ));
+    }
+
     #[test]
     fn test_guess_default_column() {
         let schema_no_vector = Schema::new(vec![
@@ -180,10 +461,18 @@ mod tests {
                 false,
             ),
         ]);
-        assert!(default_vector_column(&multi_vec_col, None)
-            .unwrap_err()
-            .to_string()
-            .contains("More than one"));
+        let err = default_vector_column(&multi_vec_col, None).unwrap_err();
+        assert!(err.to_string().contains("More than one"));
+        assert!(
+            matches!(&err, Error::AmbiguousVectorColumn { candidates } if candidates == &["vec", "vec2"]),
+            "{}",
+            err
+        );
+        // The dimension of the query vector picks one of the columns
+        assert_eq!(
+            default_vector_column(&multi_vec_col, Some(50)).unwrap(),
+            "vec2"
+        );
     }
 
     #[test]
@@ -7,17 +7,18 @@ use std::{
 
 use arrow::buffer::NullBuffer;
 use arrow_array::{
-    Array, FixedSizeListArray, Float32Array, Int32Array, RecordBatch, RecordBatchIterator,
-    StringArray,
+    cast::AsArray, types::Float32Type, Array, FixedSizeListArray, Float32Array, Int32Array,
+    RecordBatch, RecordBatchIterator, StringArray,
 };
 use arrow_schema::{DataType, Field, Schema};
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
 use lancedb::{
     arrow::IntoArrow,
     connect,
     embeddings::{EmbeddingDefinition, EmbeddingFunction, EmbeddingRegistry},
-    query::ExecutableQuery,
-    Error, Result,
+    query::{ExecutableQuery, QueryBase, VectorQuery},
+    table::BadVectorMode,
+    Error, Result, Table,
 };
 
 #[tokio::test]
@@ -209,6 +210,192 @@ async fn test_no_func_in_registry_on_add() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_embeddings_computed_on_insert() -> Result<()> {
+    let tempdir = tempfile::tempdir().unwrap();
+    let tempdir = tempdir.path().to_str().unwrap();
+
+    let db = connect(tempdir).execute().await?;
+    db.embedding_registry()
+        .register("text_stats", Arc::new(TextStatsEmbed))?;
+    let tbl = db
+        .create_table("test", text_records(&["a", "hello world", "lance"]))
+        .add_embedding(EmbeddingDefinition::new(
+            "text",
+            "text_stats",
+            Some("vector"),
+        ))?
+        .execute()
+        .await?;
+    assert_eq!(
+        read_embeddings(&tbl).await?,
+        vec![
+            ("a".to_string(), vec![1.0, 0.0, 97.0]),
+            ("hello world".to_string(), vec![11.0, 1.0, 104.0]),
+            ("lance".to_string(), vec![5.0, 0.0, 108.0]),
+        ]
+    );
+
+    // The embedding is part of the table definition, so a table opened by a new
+    // connection with the same function registered also embeds the new rows
+    let db = connect(tempdir).execute().await?;
+    db.embedding_registry()
+        .register("text_stats", Arc::new(TextStatsEmbed))?;
+    let tbl = db.open_table("test").execute().await?;
+    tbl.add(text_records(&["a b c"])).execute().await?;
+    let embeddings = read_embeddings(&tbl).await?;
+    assert_eq!(embeddings.len(), 4);
+    assert_eq!(embeddings[3], ("a b c".to_string(), vec![5.0, 2.0, 97.0]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nearest_to_text() -> Result<()> {
+    let tempdir = tempfile::tempdir().unwrap();
+    let tempdir = tempdir.path().to_str().unwrap();
+
+    let db = connect(tempdir).execute().await?;
+    db.embedding_registry()
+        .register("text_stats", Arc::new(TextStatsEmbed))?;
+    let tbl = db
+        .create_table(
+            "test",
+            text_records(&["a", "hello world", "lance", "lancedb", "a b c"]),
+        )
+        .add_embedding(EmbeddingDefinition::new(
+            "text",
+            "text_stats",
+            Some("vector"),
+        ))?
+        .execute()
+        .await?;
+
+    let collect = |query: VectorQuery| async move {
+        let batches = query
+            .limit(3)
+            .execute()
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok::<_, Error>(arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap())
+    };
+    let embedded =
+        TextStatsEmbed.compute_query_embeddings(Arc::new(StringArray::from(vec!["lancer"])))?;
+    let vector = embedded.as_fixed_size_list().value(0);
+    let expected = collect(tbl.query().nearest_to(vector)?).await?;
+    let results = collect(tbl.query().nearest_to_text("lancer")).await?;
+    assert_eq!(results, expected);
+    assert_eq!(
+        results["text"].as_string::<i32>().value(0),
+        "lance".to_string()
+    );
+
+    // The function must be registered on the connection
+    let db = connect(tempdir).execute().await?;
+    let tbl = db.open_table("test").execute().await?;
+    let err = tbl
+        .query()
+        .nearest_to_text("lancer")
+        .explain_plan(false)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::EmbeddingFunctionNotFound { .. }),
+        "{}",
+        err
+    );
+
+    // Tables without an embedding cannot be searched with text
+    let tbl = db
+        .create_table("plain", create_some_records()?)
+        .execute()
+        .await?;
+    let err = tbl
+        .query()
+        .nearest_to_text("lancer")
+        .explain_plan(false)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_on_bad_vector_with_embeddings() -> Result<()> {
+    let tempdir = tempfile::tempdir().unwrap();
+    let tempdir = tempdir.path().to_str().unwrap();
+
+    let db = connect(tempdir).execute().await?;
+    db.embedding_registry()
+        .register("mean_byte", Arc::new(MeanByteEmbed))?;
+    let tbl = db
+        .create_table("test", text_records(&["a", "hello world"]))
+        .add_embedding(EmbeddingDefinition::new(
+            "text",
+            "mean_byte",
+            Some("vector"),
+        ))?
+        .execute()
+        .await?;
+
+    // The empty text embeds to a NaN vector
+    let err = tbl
+        .add(text_records(&["lance", ""]))
+        .on_bad_vector(BadVectorMode::Error)
+        .execute()
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("the vector in row 1 of column vector has NaN, infinite or null values"),
+        "{}",
+        err
+    );
+    assert_eq!(tbl.count_rows(None).await?, 2);
+
+    tbl.add(text_records(&["lance", ""]))
+        .on_bad_vector(BadVectorMode::Drop)
+        .execute()
+        .await?;
+    let embeddings = read_embeddings(&tbl).await?;
+    assert_eq!(embeddings.len(), 3);
+    assert_eq!(embeddings[2], ("lance".to_string(), vec![5.0, 103.0]));
+
+    tbl.add(text_records(&[""]))
+        .on_bad_vector(BadVectorMode::Fill(0.0))
+        .execute()
+        .await?;
+    let embeddings = read_embeddings(&tbl).await?;
+    assert_eq!(embeddings[3], ("".to_string(), vec![0.0, 0.0]));
+    Ok(())
+}
+
+fn text_records(texts: &[&str]) -> impl IntoArrow {
+    let schema = Arc::new(Schema::new(vec![Field::new("text", DataType::Utf8, false)]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from_iter_values(texts))],
+    )
+    .unwrap();
+    Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema))
+}
+
+/// The text and embedding of every row, in the order the rows were added
+async fn read_embeddings(tbl: &Table) -> Result<Vec<(String, Vec<f32>)>> {
+    let batches = tbl.query().execute().await?.try_collect::<Vec<_>>().await?;
+    let mut rows = Vec::new();
+    for batch in batches {
+        let texts = batch["text"].as_string::<i32>();
+        let vectors = batch["vector"].as_fixed_size_list();
+        for (text, vector) in texts.iter().zip(vectors.iter()) {
+            let vector = vector.unwrap();
+            let values = vector.as_primitive::<Float32Type>().values().to_vec();
+            rows.push((text.unwrap().to_string(), values));
+        }
+    }
+    Ok(rows)
+}
+
 fn create_some_records() -> Result<impl IntoArrow> {
     const TOTAL: usize = 2;
 
@@ -323,3 +510,92 @@ impl EmbeddingFunction for MockEmbed {
         unimplemented!()
     }
 }
+
+/// A deterministic embedding of text: its length, number of spaces and first byte
+#[derive(Debug)]
+struct TextStatsEmbed;
+
+impl TextStatsEmbed {
+    fn embed(&self, input: &dyn Array) -> Result<Arc<dyn Array>> {
+        let texts = input.as_string::<i32>();
+        let values = texts
+            .iter()
+            .flat_map(|text| {
+                let text = text.unwrap_or_default();
+                let spaces = text.chars().filter(|c| *c == ' ').count();
+                let first = text.bytes().next().unwrap_or_default();
+                [text.len() as f32, spaces as f32, first as f32]
+            })
+            .collect::<Float32Array>();
+        let field = Arc::new(Field::new("item", DataType::Float32, true));
+        Ok(Arc::new(FixedSizeListArray::try_new(
+            field,
+            3,
+            Arc::new(values),
+            None,
+        )?))
+    }
+}
+
+impl EmbeddingFunction for TextStatsEmbed {
+    fn name(&self) -> &str {
+        "text_stats"
+    }
+    fn source_type(&self) -> Result<Cow<DataType>> {
+        Ok(Cow::Owned(DataType::Utf8))
+    }
+    fn dest_type(&self) -> Result<Cow<DataType>> {
+        Ok(Cow::Owned(DataType::new_fixed_size_list(
+            DataType::Float32,
+            3,
+            true,
+        )))
+    }
+    fn compute_source_embeddings(&self, source: Arc<dyn Array>) -> Result<Arc<dyn Array>> {
+        self.embed(source.as_ref())
+    }
+    fn compute_query_embeddings(&self, input: Arc<dyn Array>) -> Result<Arc<dyn Array>> {
+        self.embed(input.as_ref())
+    }
+}
+
+/// An embedding of text as its length and mean byte, which is NaN for no text
+#[derive(Debug)]
+struct MeanByteEmbed;
+
+impl EmbeddingFunction for MeanByteEmbed {
+    fn name(&self) -> &str {
+        "mean_byte"
+    }
+    fn source_type(&self) -> Result<Cow<DataType>> {
+        Ok(Cow::Owned(DataType::Utf8))
+    }
+    fn dest_type(&self) -> Result<Cow<DataType>> {
+        Ok(Cow::Owned(DataType::new_fixed_size_list(
+            DataType::Float32,
+            2,
+            true,
+        )))
+    }
+    fn compute_source_embeddings(&self, source: Arc<dyn Array>) -> Result<Arc<dyn Array>> {
+        let values = source
+            .as_string::<i32>()
+            .iter()
+            .flat_map(|text| {
+                let text = text.unwrap_or_default();
+                let sum = text.bytes().map(f32::from).sum::<f32>();
+                [text.len() as f32, sum / text.len() as f32]
+            })
+            .collect::<Float32Array>();
+        let field = Arc::new(Field::new("item", DataType::Float32, true));
+        Ok(Arc::new(FixedSizeListArray::try_new(
+            field,
+            2,
+            Arc::new(values),
+            None,
+        )?))
+    }
+    fn compute_query_embeddings(&self, input: Arc<dyn Array>) -> Result<Arc<dyn Array>> {
+        self.compute_source_embeddings(input)
+    }
+}
@@ -143,6 +143,37 @@ async fn test_minio_lifecycle() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_minio_rename_table() -> Result<()> {
+    let bucket = S3Bucket::new("test-bucket-rename").await;
+    let uri = format!("s3://{}", bucket.0);
+
+    let db = lancedb::connect(&uri)
+        .storage_options(CONFIG.iter().cloned())
+        .execute()
+        .await?;
+
+    let data = test_data();
+    let data = RecordBatchIterator::new(vec![Ok(data.clone())], data.schema());
+    let table = db.create_table("test_table", data).execute().await?;
+    let data = test_data();
+    let data = RecordBatchIterator::new(vec![Ok(data.clone())], data.schema());
+    table.add(data).execute().await?;
+    let version = table.version().await?;
+
+    // Renaming copies the table files so the history is kept
+    db.rename_table("test_table", "renamed_table").await?;
+    let table_names = db.table_names().execute().await?;
+    assert_eq!(table_names, vec!["renamed_table"]);
+    let table = db.open_table("renamed_table").execute().await?;
+    assert_eq!(table.version().await?, version);
+    assert_eq!(table.count_rows(None).await?, 6);
+
+    db.drop_table("renamed_table").await?;
+
+    Ok(())
+}
+
 struct KMSKey(String);
 
 impl KMSKey {